@@ -48,6 +48,7 @@ TYPE_KIND_FIXED_SIZE_VEC    = 16
 
 ENCODED_ENUM_PREFIX = "RUST$ENCODED$ENUM$"
 ENUM_DISR_FIELD_NAME = "RUST$ENUM$DISR"
+NICHE_ENUM_DISR_TYPE_PREFIX = "RUST$NICHE$ENUM$"
 
 # Slice related constants
 SLICE_FIELD_NAME_DATA_PTR = "data_ptr"
@@ -243,6 +244,14 @@ class Value(object):
         """
         raise NotImplementedError("Override this method")
 
+    def as_enumerator_name(self):
+        """
+        Returns the name of the enumerator matching the wrapped value, which
+        must be of an enumeration type, or None if there is no such
+        enumerator.
+        """
+        raise NotImplementedError("Override this method")
+
     def get_wrapped_value(self):
         """
         Returns the debugger-specific value-object wrapped by this object. This
@@ -288,13 +297,33 @@ class EncodedEnumInfo(object):
         return self.__null_variant_name
 
 
-def get_discriminant_value_as_integer(enum_val):
+def get_variant_index(enum_val):
+    """
+    Returns the index of the variant held by the given enum value. The
+    discriminant of niche-filled enums is not the index of the variant, so
+    it is looked up by the name of its enumerator instead; the type of the
+    discriminant names the variant any other value belongs to.
+    """
     assert enum_val.type.get_dwarf_type_kind() == DWARF_TYPE_CODE_UNION
     # we can take any variant here because the discriminant has to be the same
     # for all of them.
     variant_val = enum_val.get_child_at_index(0)
     disr_val = variant_val.get_child_at_index(0)
-    return disr_val.as_integer()
+    disr_type_name = disr_val.type.get_unqualified_type_name()
+    if (disr_type_name is None or
+        not disr_type_name.startswith(NICHE_ENUM_DISR_TYPE_PREFIX)):
+        return disr_val.as_integer()
+
+    variant_name = disr_val.as_enumerator_name()
+    if variant_name is None:
+        variant_name = disr_type_name[len(NICHE_ENUM_DISR_TYPE_PREFIX):]
+    variant_name = extract_type_name(variant_name)
+
+    for index in range(len(enum_val.type.get_fields())):
+        variant_type = enum_val.get_child_at_index(index).type
+        if variant_type.get_unqualified_type_name() == variant_name:
+            return index
+    return 0
 
 
 def extract_length_ptr_and_cap_from_std_vec(vec_val):
@@ -81,6 +81,13 @@ class GdbValue(rustpp.Value):
             return int(str(self.gdb_val), 0)
         return int(self.gdb_val)
 
+    def as_enumerator_name(self):
+        value = int(self.gdb_val)
+        for enumerator in self.gdb_val.type.fields():
+            if enumerator.enumval == value:
+                return enumerator.name
+        return None
+
     def get_wrapped_value(self):
         return self.gdb_val
 
@@ -150,9 +157,9 @@ def rust_pretty_printer_lookup_function(gdb_val):
         return rust_pretty_printer_lookup_function(gdb_val[variant])
 
     if type_kind == rustpp.TYPE_KIND_REGULAR_ENUM:
-        # This is a regular enum, extract the discriminant
-        discriminant_val = rustpp.get_discriminant_value_as_integer(val)
-        variant = get_field_at_index(gdb_val, discriminant_val)
+        # This is a regular enum, find the variant from the discriminant
+        variant_index = rustpp.get_variant_index(val)
+        variant = get_field_at_index(gdb_val, variant_index)
         return rust_pretty_printer_lookup_function(gdb_val[variant])
 
     if type_kind == rustpp.TYPE_KIND_COMPRESSED_ENUM:
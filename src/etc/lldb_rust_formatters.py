@@ -81,6 +81,13 @@ class LldbValue(rustpp.Value):
     def as_integer(self):
         return self.lldb_val.GetValueAsUnsigned()
 
+    def as_enumerator_name(self):
+        value = self.lldb_val.GetValueAsUnsigned()
+        for enumerator in self.lldb_val.type.GetEnumMembers():
+            if enumerator.GetValueAsUnsigned() == value:
+                return enumerator.GetName()
+        return None
+
     def get_wrapped_value(self):
         return self.lldb_val
 
@@ -150,9 +157,9 @@ def print_val(lldb_val, internal_dict):
         return print_fixed_size_vec_val(val, internal_dict)
 
     if type_kind == rustpp.TYPE_KIND_REGULAR_ENUM:
-        # This is a regular enum, extract the discriminant
-        discriminant_val = rustpp.get_discriminant_value_as_integer(val)
-        return print_val(lldb_val.GetChildAtIndex(discriminant_val), internal_dict)
+        # This is a regular enum, find the variant from the discriminant
+        variant_index = rustpp.get_variant_index(val)
+        return print_val(lldb_val.GetChildAtIndex(variant_index), internal_dict)
 
     if type_kind == rustpp.TYPE_KIND_COMPRESSED_ENUM:
         encoded_enum_info = rustpp.EncodedEnumInfo(val)
@@ -0,0 +1,145 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use syntax::errors::{self, DiagnosticBuilder};
+
+use Span;
+use __internal;
+
+/// The severity of a `Diagnostic`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Level {
+    /// An error, which will cause compilation to fail.
+    Error,
+    /// A warning.
+    Warning,
+    /// A note, giving extra information.
+    Note,
+    /// A help message, suggesting how to fix a problem.
+    Help,
+}
+
+/// A message to the user of a procedural macro, such as an error pointing at
+/// the part of the macro's input that it could not handle.
+///
+/// A diagnostic is built up with the methods below and reported with `emit`.
+/// Reporting an error makes compilation fail once the macro has finished, so a
+/// macro can report several problems before returning.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    level: Level,
+    message: String,
+    span: Option<Span>,
+    children: Vec<Diagnostic>,
+}
+
+impl Diagnostic {
+    /// Creates a new diagnostic, which points at the macro invocation.
+    pub fn new<T: Into<String>>(level: Level, message: T) -> Diagnostic {
+        Diagnostic { level: level, message: message.into(), span: None, children: Vec::new() }
+    }
+
+    /// Creates a new diagnostic pointing at `span`.
+    pub fn spanned<T: Into<String>>(span: Span, level: Level, message: T) -> Diagnostic {
+        Diagnostic { span: Some(span), ..Diagnostic::new(level, message) }
+    }
+
+    /// The severity of this diagnostic.
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// Attaches a warning to this diagnostic.
+    pub fn warning<T: Into<String>>(self, message: T) -> Diagnostic {
+        self.child(None, Level::Warning, message)
+    }
+
+    /// Attaches a warning pointing at `span` to this diagnostic.
+    pub fn span_warning<T: Into<String>>(self, span: Span, message: T) -> Diagnostic {
+        self.child(Some(span), Level::Warning, message)
+    }
+
+    /// Attaches a note to this diagnostic.
+    pub fn note<T: Into<String>>(self, message: T) -> Diagnostic {
+        self.child(None, Level::Note, message)
+    }
+
+    /// Attaches a note pointing at `span` to this diagnostic.
+    pub fn span_note<T: Into<String>>(self, span: Span, message: T) -> Diagnostic {
+        self.child(Some(span), Level::Note, message)
+    }
+
+    /// Attaches a help message to this diagnostic.
+    pub fn help<T: Into<String>>(self, message: T) -> Diagnostic {
+        self.child(None, Level::Help, message)
+    }
+
+    /// Attaches a help message pointing at `span` to this diagnostic.
+    pub fn span_help<T: Into<String>>(self, span: Span, message: T) -> Diagnostic {
+        self.child(Some(span), Level::Help, message)
+    }
+
+    fn child<T: Into<String>>(mut self, span: Option<Span>, level: Level, message: T)
+                              -> Diagnostic {
+        self.children.push(Diagnostic { span: span, ..Diagnostic::new(level, message) });
+        self
+    }
+
+    /// Reports this diagnostic to the user.
+    pub fn emit(self) {
+        __internal::with_sess(|sess, call_site| {
+            let handler = &sess.span_diagnostic;
+            let level = match self.level {
+                Level::Error => errors::Level::Error,
+                Level::Warning => errors::Level::Warning,
+                Level::Note => errors::Level::Note,
+                Level::Help => errors::Level::Help,
+            };
+            let span = self.span.map_or(call_site, |span| span.0);
+            let mut db = DiagnosticBuilder::new(handler, level, &self.message);
+            db.set_span(span);
+            for child in &self.children {
+                let msg = &child.message[..];
+                match (child.level, child.span) {
+                    (Level::Error, _) => unreachable!(),
+                    (Level::Warning, Some(span)) => db.span_warn(span.0, msg),
+                    (Level::Warning, None) => db.warn(msg),
+                    (Level::Note, Some(span)) => db.span_note(span.0, msg),
+                    (Level::Note, None) => db.note(msg),
+                    (Level::Help, Some(span)) => db.span_help(span.0, msg),
+                    (Level::Help, None) => db.help(msg),
+                };
+            }
+            db.emit();
+        })
+    }
+}
+
+impl Span {
+    /// Creates an error pointing at this span.
+    pub fn error<T: Into<String>>(self, message: T) -> Diagnostic {
+        Diagnostic::spanned(self, Level::Error, message)
+    }
+
+    /// Creates a warning pointing at this span.
+    pub fn warning<T: Into<String>>(self, message: T) -> Diagnostic {
+        Diagnostic::spanned(self, Level::Warning, message)
+    }
+
+    /// Creates a note pointing at this span.
+    pub fn note<T: Into<String>>(self, message: T) -> Diagnostic {
+        Diagnostic::spanned(self, Level::Note, message)
+    }
+
+    /// Creates a help message pointing at this span.
+    pub fn help<T: Into<String>>(self, message: T) -> Diagnostic {
+        Diagnostic::spanned(self, Level::Help, message)
+    }
+}
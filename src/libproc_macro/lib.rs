@@ -13,18 +13,26 @@
 //! This library, provided by the standard distribution, provides the types
 //! consumed in the interfaces of procedurally defined macro definitions.
 //! Currently the primary use of this crate is to provide the ability to define
-//! new custom derive modes through `#[proc_macro_derive]`.
+//! new custom derive modes through `#[proc_macro_derive]`, attribute macros
+//! through `#[proc_macro_attribute]` and function-like macros through
+//! `#[proc_macro]`.
 //!
 //! Added recently as part of [RFC 1681] this crate is currently *unstable* and
 //! requires the `#![feature(proc_macro_lib)]` directive to use.
 //!
 //! [RFC 1681]: https://github.com/rust-lang/rfcs/blob/master/text/1681-macros-1.1.md
 //!
-//! Note that this crate is intentionally very bare-bones currently. The main
-//! type, `TokenStream`, only supports `fmt::Display` and `FromStr`
-//! implementations, indicating that it can only go to and come from a string.
-//! This functionality is intended to be expanded over time as more surface
-//! area for macro authors is stabilized.
+//! The main type, `TokenStream`, is a sequence of token trees. Macros can
+//! inspect their input by iterating over its `TokenTree`s and build their
+//! output by collecting `TokenTree`s into a new stream, with the span of every
+//! token carried through to the expanded code. Streams can also still be
+//! converted to and from strings through `fmt::Display` and `FromStr`.
+//!
+//! Macros report problems with their input through `Diagnostic`s, which can
+//! point at the spans of individual input tokens.
+//!
+//! The `quote!` macro builds a `TokenStream` from Rust tokens written inline,
+//! interpolating other streams and tokens into it; see the `quote` module.
 
 #![crate_name = "proc_macro"]
 #![unstable(feature = "proc_macro_lib", issue = "27812")]
@@ -36,28 +44,40 @@
 #![feature(rustc_private)]
 #![feature(staged_api)]
 #![feature(lang_items)]
+#![feature(allow_internal_unstable)]
 
 extern crate syntax;
 
+use std::collections::VecDeque;
 use std::fmt;
+use std::iter::FromIterator;
+use std::rc::Rc;
 use std::str::FromStr;
+use std::vec;
 
 use syntax::ast;
-use syntax::parse;
-use syntax::ptr::P;
+use syntax::codemap;
+use syntax::parse::{self, token};
+use syntax::parse::lexer::comments;
+use syntax::print::pprust;
+use syntax::tokenstream;
+
+pub use diagnostic::{Diagnostic, Level};
+
+mod diagnostic;
+pub mod quote;
 
 /// The main type provided by this crate, representing an abstract stream of
 /// tokens.
 ///
 /// This is both the input and output of `#[proc_macro_derive]` definitions.
-/// Currently it's required to be a list of valid Rust items, but this
-/// restriction may be lifted in the future.
+/// The input is the item the derive is applied to, and the output must be a
+/// list of valid Rust items.
 ///
-/// The API of this type is intentionally bare-bones, but it'll be expanded over
-/// time!
-pub struct TokenStream {
-    inner: Vec<P<ast::Item>>,
-}
+/// A stream is a sequence of `TokenTree`s, which can be walked with
+/// `into_iter` and built up again with `collect`.
+#[derive(Clone, Debug)]
+pub struct TokenStream(tokenstream::TokenStream);
 
 /// Error returned from `TokenStream::from_str`.
 #[derive(Debug)]
@@ -65,6 +85,505 @@ pub struct LexError {
     _inner: (),
 }
 
+impl TokenStream {
+    /// Returns an empty stream of tokens.
+    pub fn empty() -> TokenStream {
+        TokenStream(tokenstream::TokenStream::mk_empty())
+    }
+
+    /// Checks whether this stream contains no tokens.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// A region of source code, along with the macro expansion it came from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Span(codemap::Span);
+
+impl Span {
+    /// The span of the macro invocation currently being expanded.
+    ///
+    /// Tokens created by a macro, rather than taken from its input, are
+    /// given this span by default.
+    pub fn call_site() -> Span {
+        __internal::with_sess(|_, call_site| Span(call_site))
+    }
+}
+
+/// A single token, or a delimited sequence of token trees, along with the
+/// span it covers.
+#[derive(Clone, Debug)]
+pub struct TokenTree {
+    /// The span of this token tree.
+    pub span: Span,
+    /// The description of this token tree.
+    pub kind: TokenNode,
+}
+
+/// The different kinds of token tree.
+#[derive(Clone, Debug)]
+pub enum TokenNode {
+    /// A token stream surrounded by delimiters, e.g. `[1, 2, 3]`.
+    Group(Delimiter, TokenStream),
+    /// An identifier or lifetime, e.g. `foo` or `'a`.
+    Term(Term),
+    /// A single punctuation character, e.g. `+` or `#`.
+    ///
+    /// Multi-character operators such as `+=` are represented as a sequence
+    /// of `Op`s, all but the last of which have `Spacing::Joint`.
+    Op(char, Spacing),
+    /// A literal, e.g. `1`, `'c'` or `"hello"`.
+    Literal(Literal),
+}
+
+/// The delimiters surrounding a `TokenNode::Group`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Delimiter {
+    /// `( ... )`
+    Parenthesis,
+    /// `{ ... }`
+    Brace,
+    /// `[ ... ]`
+    Bracket,
+    /// An invisible delimiter, which groups tokens that came from a single
+    /// macro fragment (such as an `$e:expr`) without changing how they parse.
+    None,
+}
+
+/// Whether an `Op` is immediately followed by another `Op`, with which it
+/// forms a multi-character operator.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Spacing {
+    /// The `Op` stands alone, e.g. the `+` in `+ =`.
+    Alone,
+    /// The `Op` is joined to the next one, e.g. the `+` in `+=`.
+    Joint,
+}
+
+/// An interned identifier or lifetime.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Term(ast::Name);
+
+impl Term {
+    /// Interns `string`, which should be an identifier, a lifetime (starting
+    /// with `'`) or `_`.
+    pub fn intern(string: &str) -> Term {
+        Term(token::intern(string))
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// A literal token, such as an integer, a character or a string.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Literal(token::Token);
+
+impl Literal {
+    /// An unsuffixed integer literal.
+    pub fn integer(n: u64) -> Literal {
+        Literal(token::Literal(token::Integer(token::intern(&n.to_string())), None))
+    }
+
+    /// An unsuffixed floating point literal.
+    ///
+    /// Panics if `n` is negative, infinite or NaN.
+    pub fn float(n: f64) -> Literal {
+        if !n.is_finite() || n < 0.0 {
+            panic!("invalid float literal {}", n);
+        }
+        let mut repr = n.to_string();
+        if !repr.contains('.') {
+            repr.push_str(".0");
+        }
+        Literal(token::Literal(token::Float(token::intern(&repr)), None))
+    }
+
+    /// A string literal.
+    pub fn string(string: &str) -> Literal {
+        let escaped = string.chars().flat_map(char::escape_default).collect::<String>();
+        Literal(token::Literal(token::Str_(token::intern(&escaped)), None))
+    }
+
+    /// A character literal.
+    pub fn character(ch: char) -> Literal {
+        let escaped = ch.escape_default().collect::<String>();
+        Literal(token::Literal(token::Char(token::intern(&escaped)), None))
+    }
+
+    /// A byte string literal.
+    pub fn byte_string(bytes: &[u8]) -> Literal {
+        let escaped = bytes.iter().cloned()
+            .flat_map(::std::ascii::escape_default)
+            .map(|b| b as char)
+            .collect::<String>();
+        Literal(token::Literal(token::ByteStr(token::intern(&escaped)), None))
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&pprust::token_to_string(&self.0))
+    }
+}
+
+/// An iterator over the `TokenTree`s of a `TokenStream`.
+pub struct TokenTreeIter {
+    trees: vec::IntoIter<tokenstream::TokenTree>,
+    pending: VecDeque<TokenTree>,
+}
+
+impl Iterator for TokenTreeIter {
+    type Item = TokenTree;
+
+    fn next(&mut self) -> Option<TokenTree> {
+        loop {
+            if let Some(tree) = self.pending.pop_front() {
+                return Some(tree);
+            }
+            match self.trees.next() {
+                Some(tree) => from_internal(tree, &mut self.pending),
+                None => return None,
+            }
+        }
+    }
+}
+
+impl IntoIterator for TokenStream {
+    type Item = TokenTree;
+    type IntoIter = TokenTreeIter;
+
+    fn into_iter(self) -> TokenTreeIter {
+        TokenTreeIter { trees: self.0.to_tts().into_iter(), pending: VecDeque::new() }
+    }
+}
+
+impl FromIterator<TokenTree> for TokenStream {
+    fn from_iter<I: IntoIterator<Item = TokenTree>>(trees: I) -> TokenStream {
+        let mut builder = StreamBuilder {
+            tts: Vec::new(),
+            joint_ops: String::new(),
+            op_span: None,
+        };
+        for tree in trees {
+            builder.push(tree);
+        }
+        builder.build()
+    }
+}
+
+impl From<TokenTree> for TokenStream {
+    fn from(tree: TokenTree) -> TokenStream {
+        Some(tree).into_iter().collect()
+    }
+}
+
+impl From<TokenNode> for TokenTree {
+    fn from(kind: TokenNode) -> TokenTree {
+        TokenTree { span: Span::call_site(), kind: kind }
+    }
+}
+
+/// Converts a compiler token tree into the `TokenTree`s which represent it.
+///
+/// Most compiler tokens map to a single `TokenTree`, but multi-character
+/// operators are split into one `Op` per character and doc comments expand
+/// to the `#[doc = "..."]` attribute they stand for.
+fn from_internal(tree: tokenstream::TokenTree, out: &mut VecDeque<TokenTree>) {
+    let (span, tok) = match tree {
+        tokenstream::TokenTree::Token(span, tok) => (span, tok),
+        tokenstream::TokenTree::Delimited(span, delimited) => {
+            let delim = match delimited.delim {
+                token::Paren => Delimiter::Parenthesis,
+                token::Brace => Delimiter::Brace,
+                token::Bracket => Delimiter::Bracket,
+                token::NoDelim => Delimiter::None,
+            };
+            let stream = tokenstream::TokenStream::from_tts(delimited.tts.clone());
+            out.push_back(TokenTree {
+                span: Span(span),
+                kind: TokenNode::Group(delim, TokenStream(stream)),
+            });
+            return
+        }
+        tokenstream::TokenTree::Sequence(span, seq) => {
+            // Repetitions only appear in the definitions of `macro_rules!`
+            // macros, so hand the macro their source text instead.
+            let tts = vec![tokenstream::TokenTree::Sequence(span, seq)];
+            let stream = relex(pprust::tts_to_string(&tts), span);
+            out.push_back(TokenTree {
+                span: Span(span),
+                kind: TokenNode::Group(Delimiter::None, TokenStream(stream)),
+            });
+            return
+        }
+    };
+
+    let kind = match tok {
+        token::Ident(ident) | token::Lifetime(ident) => TokenNode::Term(Term(ident.name)),
+        token::Underscore => TokenNode::Term(Term::intern("_")),
+        token::Literal(..) => TokenNode::Literal(Literal(tok)),
+        token::DocComment(name) => {
+            let comment = name.as_str();
+            let style = comments::doc_comment_style(&comment);
+            let stripped = comments::strip_doc_comment_decoration(&comment);
+            let mut push = |kind| out.push_back(TokenTree { span: Span(span), kind: kind });
+            push(TokenNode::Op('#', Spacing::Alone));
+            if style == ast::AttrStyle::Inner {
+                push(TokenNode::Op('!', Spacing::Alone));
+            }
+            let tts = vec![
+                tokenstream::TokenTree::Token(span, token::Ident(token::str_to_ident("doc"))),
+                tokenstream::TokenTree::Token(span, token::Eq),
+                tokenstream::TokenTree::Token(span, raw_str_token(&stripped)),
+            ];
+            let stream = TokenStream(tokenstream::TokenStream::from_tts(tts));
+            push(TokenNode::Group(Delimiter::Bracket, stream));
+            return
+        }
+        token::Interpolated(..) | token::MatchNt(..) | token::SubstNt(..) => {
+            let stream = relex(pprust::token_to_string(&tok), span);
+            TokenNode::Group(Delimiter::None, TokenStream(stream))
+        }
+        token::OpenDelim(..) | token::CloseDelim(..) | token::Whitespace |
+        token::Comment | token::Shebang(..) | token::Eof => {
+            panic!("unexpected token in token tree: {:?}", tok)
+        }
+        _ => {
+            let op = pprust::token_to_string(&tok);
+            let last = op.chars().count() - 1;
+            for (i, ch) in op.chars().enumerate() {
+                let spacing = if i == last { Spacing::Alone } else { Spacing::Joint };
+                out.push_back(TokenTree { span: Span(span), kind: TokenNode::Op(ch, spacing) });
+            }
+            return
+        }
+    };
+    out.push_back(TokenTree { span: Span(span), kind: kind });
+}
+
+/// Builds a compiler token stream from `TokenTree`s, gluing runs of joint
+/// `Op`s back together into multi-character operators.
+struct StreamBuilder {
+    tts: Vec<tokenstream::TokenTree>,
+    joint_ops: String,
+    op_span: Option<codemap::Span>,
+}
+
+impl StreamBuilder {
+    fn push(&mut self, tree: TokenTree) {
+        let span = tree.span.0;
+        let tok = match tree.kind {
+            TokenNode::Op(ch, spacing) => {
+                self.joint_ops.push(ch);
+                self.op_span = Some(match self.op_span {
+                    Some(op_span) => codemap::mk_sp(op_span.lo, span.hi),
+                    None => span,
+                });
+                if spacing == Spacing::Alone {
+                    self.flush_ops();
+                }
+                return
+            }
+            TokenNode::Group(delim, stream) => {
+                self.flush_ops();
+                let delim = match delim {
+                    Delimiter::Parenthesis => token::Paren,
+                    Delimiter::Brace => token::Brace,
+                    Delimiter::Bracket => token::Bracket,
+                    Delimiter::None => {
+                        self.tts.extend(stream.0.to_tts());
+                        return
+                    }
+                };
+                let delimited = tokenstream::Delimited {
+                    delim: delim,
+                    open_span: codemap::Span { hi: span.lo + codemap::BytePos(1), ..span },
+                    tts: stream.0.to_tts(),
+                    close_span: codemap::Span { lo: span.hi - codemap::BytePos(1), ..span },
+                };
+                self.tts.push(tokenstream::TokenTree::Delimited(span, Rc::new(delimited)));
+                return
+            }
+            TokenNode::Term(term) => {
+                let ident = ast::Ident::with_empty_ctxt(term.0);
+                let name = term.0.as_str();
+                if name.starts_with('\'') {
+                    token::Lifetime(ident)
+                } else if &*name == "_" {
+                    token::Underscore
+                } else {
+                    token::Ident(ident)
+                }
+            }
+            TokenNode::Literal(literal) => literal.0,
+        };
+        self.flush_ops();
+        self.tts.push(tokenstream::TokenTree::Token(span, tok));
+    }
+
+    /// Turns the pending joint `Op`s into tokens, taking the longest
+    /// operator each time.
+    fn flush_ops(&mut self) {
+        let span = match self.op_span.take() {
+            Some(span) => span,
+            None => return,
+        };
+        {
+            let mut rest = &self.joint_ops[..];
+            while !rest.is_empty() {
+                let (len, tok) = (1..4).rev()
+                    .filter(|&len| rest.is_char_boundary(len))
+                    .filter_map(|len| op_token(&rest[..len]).map(|tok| (len, tok)))
+                    .next()
+                    .unwrap_or_else(|| {
+                        panic!("`{}` is not a valid operator", rest.chars().next().unwrap())
+                    });
+                self.tts.push(tokenstream::TokenTree::Token(span, tok));
+                rest = &rest[len..];
+            }
+        }
+        self.joint_ops.clear();
+    }
+
+    fn build(mut self) -> TokenStream {
+        self.flush_ops();
+        TokenStream(tokenstream::TokenStream::from_tts(self.tts))
+    }
+}
+
+/// The operator token spelled `op`, if there is one.
+fn op_token(op: &str) -> Option<token::Token> {
+    Some(match op {
+        "=" => token::Eq,
+        "<" => token::Lt,
+        "<=" => token::Le,
+        "==" => token::EqEq,
+        "!=" => token::Ne,
+        ">=" => token::Ge,
+        ">" => token::Gt,
+        "&&" => token::AndAnd,
+        "||" => token::OrOr,
+        "!" => token::Not,
+        "~" => token::Tilde,
+        "+" => token::BinOp(token::Plus),
+        "-" => token::BinOp(token::Minus),
+        "*" => token::BinOp(token::Star),
+        "/" => token::BinOp(token::Slash),
+        "%" => token::BinOp(token::Percent),
+        "^" => token::BinOp(token::Caret),
+        "&" => token::BinOp(token::And),
+        "|" => token::BinOp(token::Or),
+        "<<" => token::BinOp(token::Shl),
+        ">>" => token::BinOp(token::Shr),
+        "+=" => token::BinOpEq(token::Plus),
+        "-=" => token::BinOpEq(token::Minus),
+        "*=" => token::BinOpEq(token::Star),
+        "/=" => token::BinOpEq(token::Slash),
+        "%=" => token::BinOpEq(token::Percent),
+        "^=" => token::BinOpEq(token::Caret),
+        "&=" => token::BinOpEq(token::And),
+        "|=" => token::BinOpEq(token::Or),
+        "<<=" => token::BinOpEq(token::Shl),
+        ">>=" => token::BinOpEq(token::Shr),
+        "@" => token::At,
+        "." => token::Dot,
+        ".." => token::DotDot,
+        "..." => token::DotDotDot,
+        "," => token::Comma,
+        ";" => token::Semi,
+        ":" => token::Colon,
+        "::" => token::ModSep,
+        "->" => token::RArrow,
+        "<-" => token::LArrow,
+        "=>" => token::FatArrow,
+        "#" => token::Pound,
+        "$" => token::Dollar,
+        "?" => token::Question,
+        _ => return None,
+    })
+}
+
+/// A raw string literal token containing `contents`, with enough `#`s that
+/// nothing in `contents` can end it early.
+fn raw_str_token(contents: &str) -> token::Token {
+    let mut hashes = 0;
+    for (i, _) in contents.match_indices('"') {
+        let run = contents[i + 1..].chars().take_while(|&c| c == '#').count();
+        hashes = std::cmp::max(hashes, run + 1);
+    }
+    token::Literal(token::StrRaw(token::intern(contents), hashes), None)
+}
+
+/// Lexes `src` into a token stream, giving every token the span `span`.
+fn relex(src: String, span: codemap::Span) -> tokenstream::TokenStream {
+    match lex(src, span) {
+        Ok(stream) => stream,
+        Err(_) => panic!("failed to relex pretty-printed tokens"),
+    }
+}
+
+fn lex(src: String, span: codemap::Span) -> Result<tokenstream::TokenStream, LexError> {
+    __internal::with_sess(|sess, _| {
+        let name = "<proc-macro source code>".to_string();
+        let mut parser = parse::new_parser_from_source_str(sess, name, src);
+        match parser.parse_all_token_trees() {
+            Ok(tts) => {
+                let tts = tts.into_iter().map(|tt| respan(tt, span)).collect();
+                Ok(tokenstream::TokenStream::from_tts(tts))
+            }
+            Err(mut err) => {
+                err.cancel();
+                Err(LexError { _inner: () })
+            }
+        }
+    })
+}
+
+fn respan(tree: tokenstream::TokenTree, span: codemap::Span) -> tokenstream::TokenTree {
+    match tree {
+        tokenstream::TokenTree::Token(_, tok) => tokenstream::TokenTree::Token(span, tok),
+        tokenstream::TokenTree::Delimited(_, delimited) => {
+            let delimited = tokenstream::Delimited {
+                delim: delimited.delim,
+                open_span: span,
+                tts: delimited.tts.iter().cloned().map(|tt| respan(tt, span)).collect(),
+                close_span: span,
+            };
+            tokenstream::TokenTree::Delimited(span, Rc::new(delimited))
+        }
+        tokenstream::TokenTree::Sequence(..) => tree,
+    }
+}
+
+/// The token trees of the code pretty-printed as `printed`, which was parsed
+/// from the source covered by `span`. These are the tokens found in the source
+/// if they still match the printed code, so that they keep their own spans;
+/// otherwise they are lexed from `printed`, and all carry `span`.
+fn original_tts(printed: String, span: codemap::Span) -> Vec<tokenstream::TokenTree> {
+    let printed = relex(printed, span).to_tts();
+    let source = __internal::with_sess(|sess, _| {
+        if span.expn_id != codemap::NO_EXPANSION ||
+           sess.codemap().span_to_snippet(span).is_err() {
+            return None
+        }
+        Some(parse::span_to_tts(sess, span))
+    });
+    match source {
+        Some(ref source) if !tts_eq_unspanned(source, &printed) => printed,
+        Some(source) => source,
+        None => printed,
+    }
+}
+
+fn tts_eq_unspanned(a: &[tokenstream::TokenTree], b: &[tokenstream::TokenTree]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.eq_unspanned(b))
+}
+
 /// Permanently unstable internal implementation details of this crate. This
 /// should not be used.
 ///
@@ -80,34 +599,65 @@ pub mod __internal {
     use std::cell::Cell;
 
     use syntax::ast;
+    use syntax::codemap::{Span, DUMMY_SP};
     use syntax::ptr::P;
     use syntax::parse::ParseSess;
+    use syntax::print::pprust;
+    use syntax::tokenstream;
     use super::TokenStream;
 
+    /// Converts `item` into the token stream handed to a custom derive.
+    ///
+    /// Each attribute of the item, and the rest of the item, is handed over
+    /// as the tokens it was parsed from, with their original spans. Those
+    /// which were changed since, e.g. by `#[cfg]` stripping, are
+    /// pretty-printed instead.
     pub fn new_token_stream(item: P<ast::Item>) -> TokenStream {
-        TokenStream { inner: vec![item] }
+        let mut tts = Vec::new();
+        for attr in &item.attrs {
+            tts.extend(super::original_tts(pprust::attr_to_string(attr), attr.span));
+        }
+        let bare_item = ast::Item { attrs: Vec::new(), ..(*item).clone() };
+        tts.extend(super::original_tts(pprust::item_to_string(&bare_item), item.span));
+        TokenStream(tokenstream::TokenStream::from_tts(tts))
     }
 
-    pub fn token_stream_items(stream: TokenStream) -> Vec<P<ast::Item>> {
-        stream.inner
+    pub fn token_stream_wrap(inner: tokenstream::TokenStream) -> TokenStream {
+        TokenStream(inner)
+    }
+
+    pub fn token_stream_inner(stream: TokenStream) -> tokenstream::TokenStream {
+        stream.0
     }
 
     pub trait Registry {
         fn register_custom_derive(&mut self,
                                   trait_name: &str,
                                   expand: fn(TokenStream) -> TokenStream,
-                                  attributes: &[&'static str]);
+                                  attributes: &[&'static str],
+                                  keep_attributes: bool);
+
+        fn register_attr_proc_macro(&mut self,
+                                    name: &str,
+                                    expand: fn(TokenStream, TokenStream) -> TokenStream);
+
+        fn register_bang_proc_macro(&mut self,
+                                    name: &str,
+                                    expand: fn(TokenStream) -> TokenStream);
     }
 
     // Emulate scoped_thread_local!() here essentially
     thread_local! {
-        static CURRENT_SESS: Cell<*const ParseSess> = Cell::new(0 as *const _);
+        static CURRENT_SESS: Cell<(*const ParseSess, Span)> =
+            Cell::new((0 as *const _, DUMMY_SP));
     }
 
-    pub fn set_parse_sess<F, R>(sess: &ParseSess, f: F) -> R
+    /// Runs `f` with `sess` as the session used to lex and parse tokens, and
+    /// `call_site` as the span of the macro invocation being expanded.
+    pub fn set_sess<F, R>(sess: &ParseSess, call_site: Span, f: F) -> R
         where F: FnOnce() -> R
     {
-        struct Reset { prev: *const ParseSess }
+        struct Reset { prev: (*const ParseSess, Span) }
 
         impl Drop for Reset {
             fn drop(&mut self) {
@@ -117,17 +667,17 @@ pub mod __internal {
 
         CURRENT_SESS.with(|p| {
             let _reset = Reset { prev: p.get() };
-            p.set(sess);
+            p.set((sess, call_site));
             f()
         })
     }
 
-    pub fn with_parse_sess<F, R>(f: F) -> R
-        where F: FnOnce(&ParseSess) -> R
+    pub fn with_sess<F, R>(f: F) -> R
+        where F: FnOnce(&ParseSess, Span) -> R
     {
-        let p = CURRENT_SESS.with(|p| p.get());
-        assert!(!p.is_null());
-        f(unsafe { &*p })
+        let (sess, call_site) = CURRENT_SESS.with(|p| p.get());
+        assert!(!sess.is_null(), "procedural macro API is used outside of a procedural macro");
+        f(unsafe { &*sess }, call_site)
     }
 }
 
@@ -135,32 +685,20 @@ impl FromStr for TokenStream {
     type Err = LexError;
 
     fn from_str(src: &str) -> Result<TokenStream, LexError> {
-        __internal::with_parse_sess(|sess| {
-            let src = src.to_string();
-            let name = "<proc-macro source code>".to_string();
-            let mut parser = parse::new_parser_from_source_str(sess, name, src);
-            let mut ret = TokenStream { inner: Vec::new() };
-            loop {
-                match parser.parse_item() {
-                    Ok(Some(item)) => ret.inner.push(item),
-                    Ok(None) => return Ok(ret),
-                    Err(mut err) => {
-                        err.cancel();
-                        return Err(LexError { _inner: () })
-                    }
-                }
-            }
-        })
+        let call_site = Span::call_site();
+        lex(src.to_string(), call_site.0).map(TokenStream)
     }
 }
 
 impl fmt::Display for TokenStream {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for item in self.inner.iter() {
-            let item = syntax::print::pprust::item_to_string(item);
-            try!(f.write_str(&item));
-            try!(f.write_str("\n"));
-        }
-        Ok(())
+        f.write_str(&pprust::tts_to_string(&self.0.to_tts()))
+    }
+}
+
+impl fmt::Display for TokenTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let stream: TokenStream = self.clone().into();
+        fmt::Display::fmt(&stream, f)
     }
 }
@@ -0,0 +1,344 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Quasi-quoting with the `quote!` macro.
+//!
+//! `quote!` turns the Rust tokens it is given into a `TokenStream`, splicing
+//! in the values of local variables along the way:
+//!
+//! * `#var` interpolates `var`, which can be anything implementing
+//!   `ToTokens`, such as a `TokenStream`, a `TokenTree` or a `Term`.
+//! * `#(...)*` repeats the tokens in the parentheses once for each item of
+//!   the iterators interpolated inside them, which are stepped through
+//!   together. A separator can be given before the `*`, as in `#(#args),*`.
+//!
+//! Interpolated tokens keep their spans, so errors in them point at the
+//! macro's input, while the quoted tokens are given the span of the macro
+//! invocation being expanded.
+//!
+//! The macro is built out of `macro_rules!` macros, which a procedural macro
+//! crate can import with `#[macro_use] extern crate proc_macro;`.
+//!
+//! ```rust,ignore
+//! #[proc_macro_derive(Named)]
+//! pub fn derive_named(input: TokenStream) -> TokenStream {
+//!     let name = type_name(input);
+//!     let text = TokenNode::Literal(Literal::string(&name.to_string()));
+//!     quote! {
+//!         impl Named for #name {
+//!             fn name() -> &'static str { #text }
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::collections::VecDeque;
+
+use syntax::parse::token;
+use syntax::tokenstream;
+
+use {Delimiter, Literal, Spacing, Span, Term, TokenNode, TokenStream, TokenTree};
+use {from_internal, op_token};
+
+/// Types which can be interpolated into the output of `quote!`.
+pub trait ToTokens {
+    /// Appends the tokens representing `self` to `tokens`.
+    fn to_tokens(&self, tokens: &mut Vec<TokenTree>);
+}
+
+impl<'a, T: ToTokens + ?Sized> ToTokens for &'a T {
+    fn to_tokens(&self, tokens: &mut Vec<TokenTree>) {
+        (**self).to_tokens(tokens)
+    }
+}
+
+impl<T: ToTokens + ?Sized> ToTokens for Box<T> {
+    fn to_tokens(&self, tokens: &mut Vec<TokenTree>) {
+        (**self).to_tokens(tokens)
+    }
+}
+
+impl<T: ToTokens> ToTokens for Option<T> {
+    fn to_tokens(&self, tokens: &mut Vec<TokenTree>) {
+        if let Some(ref t) = *self {
+            t.to_tokens(tokens)
+        }
+    }
+}
+
+impl ToTokens for TokenStream {
+    fn to_tokens(&self, tokens: &mut Vec<TokenTree>) {
+        tokens.extend(self.clone())
+    }
+}
+
+impl ToTokens for TokenTree {
+    fn to_tokens(&self, tokens: &mut Vec<TokenTree>) {
+        tokens.push(self.clone())
+    }
+}
+
+impl ToTokens for TokenNode {
+    fn to_tokens(&self, tokens: &mut Vec<TokenTree>) {
+        tokens.push(self.clone().into())
+    }
+}
+
+impl ToTokens for Term {
+    fn to_tokens(&self, tokens: &mut Vec<TokenTree>) {
+        tokens.push(TokenNode::Term(*self).into())
+    }
+}
+
+impl ToTokens for Literal {
+    fn to_tokens(&self, tokens: &mut Vec<TokenTree>) {
+        tokens.push(TokenNode::Literal(self.clone()).into())
+    }
+}
+
+/// Appends the token spelled `src` to `tokens`. `src` is a single token
+/// tree from a `quote!` invocation, as printed by `stringify!`, other than a
+/// group.
+#[doc(hidden)]
+pub fn __push_token(tokens: &mut Vec<TokenTree>, src: &str) {
+    if op_token(src).is_some() {
+        let last = src.chars().count() - 1;
+        tokens.extend(src.chars().enumerate().map(|(i, ch)| {
+            let spacing = if i == last { Spacing::Alone } else { Spacing::Joint };
+            TokenTree::from(TokenNode::Op(ch, spacing))
+        }));
+        return
+    }
+
+    let tok = if src.starts_with("//") || src.starts_with("/*") {
+        token::DocComment(token::intern(src))
+    } else if let Some(tok) = literal_token(src) {
+        tok
+    } else if src.starts_with('\'') || src.starts_with(|c: char| c == '_' || c.is_alphabetic()) {
+        // An identifier, a keyword, `_` or a lifetime.
+        tokens.push(TokenNode::Term(Term::intern(src)).into());
+        return
+    } else {
+        panic!("`quote!` cannot quote `{}`", src)
+    };
+
+    let mut trees = VecDeque::new();
+    from_internal(tokenstream::TokenTree::Token(Span::call_site().0, tok), &mut trees);
+    tokens.extend(trees);
+}
+
+/// The literal token spelled `src`, if it is one.
+fn literal_token(src: &str) -> Option<token::Token> {
+    let (lit, suffix) = if src.starts_with("br\"") || src.starts_with("br#") {
+        let (body, hashes, suffix) = raw_quoted(&src[2..]);
+        (token::ByteStrRaw(token::intern(body), hashes), suffix)
+    } else if src.starts_with("r\"") || src.starts_with("r#") {
+        let (body, hashes, suffix) = raw_quoted(&src[1..]);
+        (token::StrRaw(token::intern(body), hashes), suffix)
+    } else if src.starts_with("b'") {
+        let (body, suffix) = quoted(&src[1..]);
+        (token::Byte(token::intern(body)), suffix)
+    } else if src.starts_with("b\"") {
+        let (body, suffix) = quoted(&src[1..]);
+        (token::ByteStr(token::intern(body)), suffix)
+    } else if src.starts_with('"') {
+        let (body, suffix) = quoted(src);
+        (token::Str_(token::intern(body)), suffix)
+    } else if src.starts_with('\'') && src[1..].contains('\'') {
+        // Lifetimes start with a quote too, but have no closing one.
+        let (body, suffix) = quoted(src);
+        (token::Char(token::intern(body)), suffix)
+    } else if src.starts_with(|c: char| c.is_digit(10)) {
+        number(src)
+    } else {
+        return None
+    };
+    let suffix = if suffix.is_empty() { None } else { Some(token::intern(suffix)) };
+    Some(token::Literal(lit, suffix))
+}
+
+/// Splits `src`, which starts with a quote, into the text between that quote
+/// and the matching closing one, and the suffix after it.
+fn quoted(src: &str) -> (&str, &str) {
+    let end = src.rfind(&src[..1]).unwrap();
+    (&src[1..end], &src[end + 1..])
+}
+
+/// Splits `src`, a raw string literal without its `r`, into its contents,
+/// the number of `#`s around them, and its suffix.
+fn raw_quoted(src: &str) -> (&str, usize, &str) {
+    let hashes = src.chars().take_while(|&c| c == '#').count();
+    let end = src.rfind('"').unwrap();
+    (&src[hashes + 1..end], hashes, &src[end + 1 + hashes..])
+}
+
+/// Splits `src`, a numeric literal, into the literal and its suffix.
+fn number(src: &str) -> (token::Lit, &str) {
+    let hex = src.starts_with("0x");
+    let radix = hex || src.starts_with("0o") || src.starts_with("0b");
+    let bytes = src.as_bytes();
+    let mut end = if radix { 2 } else { 0 };
+    let mut float = false;
+    while end < bytes.len() {
+        match bytes[end] {
+            b'0'...b'9' | b'_' => {}
+            b'a'...b'f' | b'A'...b'F' if hex => {}
+            b'.' if !radix => float = true,
+            b'e' | b'E' if !radix => {
+                float = true;
+                if bytes.get(end + 1).map_or(false, |&b| b == b'+' || b == b'-') {
+                    end += 1;
+                }
+            }
+            _ => break,
+        }
+        end += 1;
+    }
+    let name = token::intern(&src[..end]);
+    (if float { token::Float(name) } else { token::Integer(name) }, &src[end..])
+}
+
+/// Appends a group of `inner` delimited by `delim` to `tokens`.
+#[doc(hidden)]
+pub fn __push_group(tokens: &mut Vec<TokenTree>, delim: Delimiter, inner: TokenStream) {
+    tokens.push(TokenNode::Group(delim, inner).into())
+}
+
+/// Quasi-quotes the given tokens into a `TokenStream`. See the `quote` module
+/// for the interpolation syntax.
+#[macro_export]
+#[allow_internal_unstable]
+macro_rules! quote {
+    ($($tt:tt)*) => {{
+        let mut tokens = ::std::vec::Vec::new();
+        __quote_each_token!(tokens $($tt)*);
+        tokens.into_iter().collect::<$crate::TokenStream>()
+    }};
+}
+
+/// Appends the tokens of a `quote!` invocation to `$tokens`, one token tree
+/// at a time.
+#[doc(hidden)]
+#[macro_export]
+#[allow_internal_unstable]
+macro_rules! __quote_each_token {
+    ($tokens:ident) => {};
+    ($tokens:ident # ( $($inner:tt)* ) * $($rest:tt)*) => {
+        for __quote_var_names!(__quote_tuple_pat () $($inner)*)
+            in __quote_var_names!(__quote_zip_expr () $($inner)*) {
+            __quote_each_token!($tokens $($inner)*);
+        }
+        __quote_each_token!($tokens $($rest)*);
+    };
+    ($tokens:ident # ( $($inner:tt)* ) $sep:tt * $($rest:tt)*) => {
+        for (i, __quote_var_names!(__quote_tuple_pat () $($inner)*))
+            in __quote_var_names!(__quote_zip_expr () $($inner)*).enumerate() {
+            if i > 0 {
+                $crate::quote::__push_token(&mut $tokens, stringify!($sep));
+            }
+            __quote_each_token!($tokens $($inner)*);
+        }
+        __quote_each_token!($tokens $($rest)*);
+    };
+    // Attributes are quoted as they are.
+    ($tokens:ident # [ $($inner:tt)* ] $($rest:tt)*) => {
+        $crate::quote::__push_token(&mut $tokens, "#");
+        __quote_each_token!($tokens [ $($inner)* ] $($rest)*);
+    };
+    ($tokens:ident # ! $($rest:tt)*) => {
+        $crate::quote::__push_token(&mut $tokens, "#");
+        __quote_each_token!($tokens ! $($rest)*);
+    };
+    ($tokens:ident # $var:ident $($rest:tt)*) => {
+        $crate::quote::ToTokens::to_tokens(&$var, &mut $tokens);
+        __quote_each_token!($tokens $($rest)*);
+    };
+    ($tokens:ident ( $($inner:tt)* ) $($rest:tt)*) => {
+        $crate::quote::__push_group(&mut $tokens, $crate::Delimiter::Parenthesis,
+                                    quote!($($inner)*));
+        __quote_each_token!($tokens $($rest)*);
+    };
+    ($tokens:ident [ $($inner:tt)* ] $($rest:tt)*) => {
+        $crate::quote::__push_group(&mut $tokens, $crate::Delimiter::Bracket,
+                                    quote!($($inner)*));
+        __quote_each_token!($tokens $($rest)*);
+    };
+    ($tokens:ident { $($inner:tt)* } $($rest:tt)*) => {
+        $crate::quote::__push_group(&mut $tokens, $crate::Delimiter::Brace,
+                                    quote!($($inner)*));
+        __quote_each_token!($tokens $($rest)*);
+    };
+    ($tokens:ident $first:tt $($rest:tt)*) => {
+        $crate::quote::__push_token(&mut $tokens, stringify!($first));
+        __quote_each_token!($tokens $($rest)*);
+    };
+}
+
+/// Finds the variables interpolated in a repetition, and passes them on to
+/// `$finish`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __quote_var_names {
+    ($finish:ident ($($found:ident)*)) => {
+        $finish!(() $($found)*)
+    };
+    ($finish:ident ($($found:ident)*) # ( $($inner:tt)* ) $($rest:tt)*) => {
+        __quote_var_names!($finish ($($found)*) $($inner)* $($rest)*)
+    };
+    ($finish:ident ($($found:ident)*) # [ $($inner:tt)* ] $($rest:tt)*) => {
+        __quote_var_names!($finish ($($found)*) $($inner)* $($rest)*)
+    };
+    ($finish:ident ($($found:ident)*) # ! $($rest:tt)*) => {
+        __quote_var_names!($finish ($($found)*) $($rest)*)
+    };
+    ($finish:ident ($($found:ident)*) # $var:ident $($rest:tt)*) => {
+        __quote_var_names!($finish ($($found)* $var) $($rest)*)
+    };
+    ($finish:ident ($($found:ident)*) ( $($inner:tt)* ) $($rest:tt)*) => {
+        __quote_var_names!($finish ($($found)*) $($inner)* $($rest)*)
+    };
+    ($finish:ident ($($found:ident)*) [ $($inner:tt)* ] $($rest:tt)*) => {
+        __quote_var_names!($finish ($($found)*) $($inner)* $($rest)*)
+    };
+    ($finish:ident ($($found:ident)*) { $($inner:tt)* } $($rest:tt)*) => {
+        __quote_var_names!($finish ($($found)*) $($inner)* $($rest)*)
+    };
+    ($finish:ident ($($found:ident)*) $ignore:tt $($rest:tt)*) => {
+        __quote_var_names!($finish ($($found)*) $($rest)*)
+    };
+}
+
+/// The pattern binding one item of each repeated variable, as nested pairs.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __quote_tuple_pat {
+    (()) => { () };
+    (() $first:ident $($rest:ident)*) => {
+        __quote_tuple_pat!(($first) $($rest)*)
+    };
+    (($pat:pat) $first:ident $($rest:ident)*) => {
+        __quote_tuple_pat!((($pat, $first)) $($rest)*)
+    };
+    (($done:pat)) => { $done };
+}
+
+/// The iterator stepping through all the repeated variables together.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __quote_zip_expr {
+    (()) => { ::std::iter::empty::<()>() };
+    (() $first:ident $($rest:ident)*) => {
+        __quote_zip_expr!((::std::iter::IntoIterator::into_iter($first)) $($rest)*)
+    };
+    (($zips:expr) $first:ident $($rest:ident)*) => {
+        __quote_zip_expr!(($zips.zip($first)) $($rest)*)
+    };
+    (($done:expr)) => { $done };
+}
@@ -649,7 +649,7 @@ impl<'a> LoweringContext<'a> {
                                bounds,
                                items)
             }
-            ItemKind::Mac(_) => panic!("Shouldn't still be around"),
+            ItemKind::Mac(_) | ItemKind::MacroDef(..) => panic!("Shouldn't still be around"),
         }
     }
 
@@ -723,6 +723,7 @@ impl<'a> LoweringContext<'a> {
             imported_from: m.imported_from.map(|x| x.name),
             allow_internal_unstable: m.allow_internal_unstable,
             body: m.body.clone().into(),
+            legacy: m.legacy,
         }
     }
 
@@ -459,6 +459,7 @@ pub struct MacroDef {
     pub imported_from: Option<Name>,
     pub allow_internal_unstable: bool,
     pub body: HirVec<TokenTree>,
+    pub legacy: bool,
 }
 
 #[derive(Clone, PartialEq, Eq, RustcEncodable, RustcDecodable, Hash, Debug)]
@@ -22,6 +22,18 @@ declare_lint! {
     "constant evaluation detected erroneous expression"
 }
 
+declare_lint! {
+    pub UNCONDITIONAL_PANIC,
+    Warn,
+    "operations which always panic at run-time"
+}
+
+declare_lint! {
+    pub UNELIMINATED_TAIL_CALLS,
+    Warn,
+    "recursive calls in `#[tail_recursive]` functions which can't be turned into jumps"
+}
+
 declare_lint! {
     pub UNUSED_IMPORTS,
     Warn,
@@ -233,6 +245,8 @@ impl LintPass for HardwiredLints {
             ILLEGAL_FLOATING_POINT_CONSTANT_PATTERN,
             ILLEGAL_STRUCT_OR_ENUM_CONSTANT_PATTERN,
             CONST_ERR,
+            UNCONDITIONAL_PANIC,
+            UNELIMINATED_TAIL_CALLS,
             RAW_POINTER_DERIVE,
             TRANSMUTE_FROM_FN_ITEM_TYPES,
             OVERLAPPING_INHERENT_IMPLS,
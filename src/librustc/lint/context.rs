@@ -35,12 +35,15 @@ use lint::{Default, CommandLine, Node, Allow, Warn, Deny, Forbid};
 use lint::builtin;
 use util::nodemap::FxHashMap;
 
+use serialize::json;
 use std::cmp;
 use std::default::Default as StdDefault;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
 use std::mem;
 use std::fmt;
 use syntax::attr;
-use syntax::parse::token::InternedString;
+use syntax::parse::token::{self, InternedString};
 use syntax::ast;
 use syntax_pos::{MultiSpan, Span};
 use errors::{self, Diagnostic, DiagnosticBuilder};
@@ -66,7 +69,8 @@ pub struct LintStore {
     /// Lints indexed by name.
     by_name: FxHashMap<String, TargetLint>,
 
-    /// Current levels of each lint, and where they were set.
+    /// Current levels of each lint, and where they were set. These are the
+    /// levels as requested, before `lint_cap` is applied.
     levels: FxHashMap<LintId, LevelSource>,
 
     /// Map of registered lint groups to what lints they expand to. The bool
@@ -142,28 +146,65 @@ enum TargetLint {
     Removed(String),
 }
 
+/// The lint levels recorded by one lint pass, as reported by
+/// `-Z dump-lint-levels`.
+#[derive(RustcEncodable)]
+struct LintLevelReport<'a> {
+    /// `early` or `late`.
+    pass: &'a str,
+    items: &'a [ItemLintLevels],
+}
+
+/// The lint levels in effect at one item, as reported by
+/// `-Z dump-lint-levels`.
+#[derive(RustcEncodable)]
+struct ItemLintLevels {
+    item: String,
+    span: String,
+    lints: Vec<LintLevelEntry>,
+}
+
+#[derive(RustcEncodable)]
+struct LintLevelEntry {
+    lint: String,
+    level: String,
+    /// One of `default`, `attribute` or `command-line`.
+    source: String,
+    /// The lint or lint group named by the attribute or flag which set the
+    /// level.
+    set_by: Option<String>,
+    /// Where the attribute which set the level is.
+    span: Option<String>,
+    /// The level which was requested, if `--cap-lints` lowered it.
+    capped_from: Option<String>,
+}
+
 enum FindLintError {
     NotFound,
     Removed
 }
 
 impl LintStore {
+    /// The level of `lint` currently in effect, and where it was set.
     fn get_level_source(&self, lint: LintId) -> LevelSource {
+        let (level, source) = self.get_requested_level_source(lint);
+        match self.lint_cap {
+            Some(cap) => (cmp::min(level, cap), source),
+            None => (level, source),
+        }
+    }
+
+    /// The level requested for `lint`, ignoring `--cap-lints`.
+    fn get_requested_level_source(&self, lint: LintId) -> LevelSource {
         match self.levels.get(&lint) {
             Some(&s) => s,
             None => (Allow, Default),
         }
     }
 
-    fn set_level(&mut self, lint: LintId, mut lvlsrc: LevelSource) {
-        if let Some(cap) = self.lint_cap {
-            lvlsrc.0 = cmp::min(lvlsrc.0, cap);
-        }
-        if lvlsrc.0 == Allow {
-            self.levels.remove(&lint);
-        } else {
-            self.levels.insert(lint, lvlsrc);
-        }
+    fn set_level(&mut self, lint: LintId, lvlsrc: LevelSource) {
+        // `Allow` levels are kept too, so that their source can be reported.
+        self.levels.insert(lint, lvlsrc);
     }
 
     pub fn new() -> LintStore {
@@ -299,8 +340,9 @@ impl LintStore {
             check_lint_name_cmdline(sess, self,
                                     &lint_name[..], level);
 
+            let source = CommandLine(token::intern(&lint_name[..]));
             match self.find_lint(&lint_name[..], sess, None) {
-                Ok(lint_id) => self.set_level(lint_id, (level, CommandLine)),
+                Ok(lint_id) => self.set_level(lint_id, (level, source)),
                 Err(FindLintError::Removed) => { }
                 Err(_) => {
                     match self.lint_groups.iter().map(|(&x, pair)| (x, pair.0.clone()))
@@ -310,7 +352,7 @@ impl LintStore {
                         Some(v) => {
                             v.iter()
                              .map(|lint_id: &LintId|
-                                     self.set_level(*lint_id, (level, CommandLine)))
+                                     self.set_level(*lint_id, (level, source)))
                              .collect::<Vec<()>>();
                         }
                         None => {
@@ -324,11 +366,6 @@ impl LintStore {
         }
 
         self.lint_cap = sess.opts.lint_cap;
-        if let Some(cap) = self.lint_cap {
-            for level in self.levels.iter_mut().map(|p| &mut (p.1).0) {
-                *level = cmp::min(*level, cap);
-            }
-        }
     }
 }
 
@@ -350,6 +387,10 @@ pub struct LateContext<'a, 'tcx: 'a> {
     /// levels, this stack keeps track of the previous lint levels of whatever
     /// was modified.
     level_stack: Vec<(LintId, LevelSource)>,
+
+    /// The lint levels in effect at each item, recorded for
+    /// `-Z dump-lint-levels`.
+    lint_level_report: Vec<ItemLintLevels>,
 }
 
 /// Context for lint checking of the AST, after expansion, before lowering to
@@ -368,6 +409,10 @@ pub struct EarlyContext<'a> {
     /// levels, this stack keeps track of the previous lint levels of whatever
     /// was modified.
     level_stack: Vec<(LintId, LevelSource)>,
+
+    /// The lint levels in effect at each item, recorded for
+    /// `-Z dump-lint-levels`.
+    lint_level_report: Vec<ItemLintLevels>,
 }
 
 /// Convenience macro for calling a `LintPass` method on every pass in the context.
@@ -459,14 +504,14 @@ pub fn raw_struct_lint<'a, S>(sess: &'a Session,
             format!("{}, #[{}({})] on by default", msg,
                     level.as_str(), name)
         },
-        CommandLine => {
+        CommandLine(_) => {
             format!("{} [-{} {}]", msg,
                     match level {
                         Warn => 'W', Deny => 'D', Forbid => 'F',
                         Allow => bug!()
                     }, name.replace("_", "-"))
         },
-        Node(src) => {
+        Node(_, src) => {
             def = Some(src);
             msg.to_string()
         }
@@ -512,22 +557,24 @@ pub trait LintContext: Sized {
     /// Get the level of `lint` at the current position of the lint
     /// traversal.
     fn current_level(&self, lint: &'static Lint) -> Level {
-        self.lints().levels.get(&LintId::of(lint)).map_or(Allow, |&(lvl, _)| lvl)
+        self.lints().get_level_source(LintId::of(lint)).0
     }
 
     fn level_src(&self, lint: &'static Lint) -> Option<LevelSource> {
-        self.lints().levels.get(&LintId::of(lint)).map(|ls| match ls {
-            &(Warn, _) => {
+        let ls = self.lints().get_level_source(LintId::of(lint));
+        match ls {
+            (Allow, _) => None,
+            (Warn, _) => {
                 let lint_id = LintId::of(builtin::WARNINGS);
                 let warn_src = self.lints().get_level_source(lint_id);
                 if warn_src.0 != Warn {
-                    warn_src
+                    Some(warn_src)
                 } else {
-                    *ls
+                    Some(ls)
                 }
             }
-            _ => *ls
-        })
+            _ => Some(ls)
+        }
     }
 
     fn lookup_and_emit<S: Into<MultiSpan>>(&self,
@@ -629,13 +676,14 @@ pub trait LintContext: Sized {
                     continue;
                 }
                 Ok((lint_name, level, span)) => {
+                    let name = token::intern(&lint_name);
                     match self.lints().find_lint(&lint_name, &self.sess(), Some(span)) {
-                        Ok(lint_id) => vec![(lint_id, level, span)],
+                        Ok(lint_id) => vec![(lint_id, level, name, span)],
                         Err(FindLintError::NotFound) => {
                             match self.lints().lint_groups.get(&lint_name[..]) {
                                 Some(&(ref v, _)) => v.iter()
                                                       .map(|lint_id: &LintId|
-                                                           (*lint_id, level, span))
+                                                           (*lint_id, level, name, span))
                                                       .collect(),
                                 None => {
                                     // The lint or lint group doesn't exist.
@@ -650,7 +698,7 @@ pub trait LintContext: Sized {
                 }
             };
 
-            for (lint_id, level, span) in v {
+            for (lint_id, level, name, span) in v {
                 let (now, now_source) = self.lints().get_level_source(lint_id);
                 if now == Forbid && level != Forbid {
                     let lint_name = lint_id.to_string();
@@ -661,19 +709,21 @@ pub trait LintContext: Sized {
                     diag_builder.span_label(span, &format!("overruled by previous forbid"));
                     match now_source {
                         LintSource::Default => &mut diag_builder,
-                        LintSource::Node(forbid_source_span) => {
+                        LintSource::Node(_, forbid_source_span) => {
                             diag_builder.span_label(forbid_source_span,
                                                     &format!("`forbid` level set here"))
                         },
-                        LintSource::CommandLine => {
+                        LintSource::CommandLine(_) => {
                             diag_builder.note("`forbid` lint level was set on command line")
                         }
                     }.emit()
-                } else if now != level {
-                    let src = self.lints().get_level_source(lint_id).1;
-                    self.level_stack().push((lint_id, (now, src)));
-                    pushed += 1;
-                    self.mut_lints().set_level(lint_id, (level, Node(span)));
+                } else {
+                    let requested = self.lints().get_requested_level_source(lint_id);
+                    if requested.0 != level {
+                        self.level_stack().push((lint_id, requested));
+                        pushed += 1;
+                        self.mut_lints().set_level(lint_id, (level, Node(name, span)));
+                    }
                 }
             }
         }
@@ -690,6 +740,79 @@ pub trait LintContext: Sized {
     }
 }
 
+/// The level of every lint at the current position of the traversal of `cx`,
+/// and where each level was set.
+fn lint_levels_at<C: LintContext>(cx: &C, item: String, span: Span) -> ItemLintLevels {
+    let store = cx.lints();
+    let codemap = cx.sess().codemap();
+
+    let mut lints = store.lints.iter().map(|&(lint, _)| {
+        let lint_id = LintId::of(lint);
+        let (requested, _) = store.get_requested_level_source(lint_id);
+        let (level, source) = match cx.level_src(lint) {
+            Some(level_source) => level_source,
+            None => store.get_level_source(lint_id),
+        };
+        let capped_from = if requested > level && store.lint_cap == Some(level) {
+            Some(requested.as_str().to_string())
+        } else {
+            None
+        };
+        let (source, set_by, attr_span) = match source {
+            Default => ("default", None, None),
+            Node(name, span) => {
+                ("attribute", Some(name.to_string()), Some(codemap.span_to_string(span)))
+            }
+            CommandLine(name) => ("command-line", Some(name.to_string()), None),
+        };
+        LintLevelEntry {
+            lint: lint.name_lower(),
+            level: level.as_str().to_string(),
+            source: source.to_string(),
+            set_by: set_by,
+            span: attr_span,
+            capped_from: capped_from,
+        }
+    }).collect::<Vec<_>>();
+    lints.sort_by(|a, b| a.lint.cmp(&b.lint));
+
+    ItemLintLevels {
+        item: item,
+        span: codemap.span_to_string(span),
+        lints: lints,
+    }
+}
+
+/// Writes the lint levels recorded by the `pass` lint pass for
+/// `-Z dump-lint-levels=<file>`, as one JSON document on a line of its own.
+/// `-` stands for standard output. The file is started afresh unless
+/// `append` is set, which the late pass does to follow the early one.
+fn report_lint_levels(sess: &Session, pass: &str, items: &[ItemLintLevels], append: bool) {
+    let dest = match sess.opts.debugging_opts.dump_lint_levels {
+        Some(ref dest) => dest,
+        None => return,
+    };
+
+    let report = LintLevelReport { pass: pass, items: items };
+    let result = if dest == "-" {
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        writeln!(stdout, "{}", json::as_json(&report))
+    } else {
+        let mut options = OpenOptions::new();
+        if append {
+            options.append(true);
+        } else {
+            options.write(true).truncate(true);
+        }
+        options.create(true).open(dest).and_then(|mut file| {
+            writeln!(file, "{}", json::as_json(&report))
+        })
+    };
+    if let Err(err) = result {
+        sess.err(&format!("failed to write lint levels to `{}`: {}", dest, err));
+    }
+}
 
 impl<'a> EarlyContext<'a> {
     fn new(sess: &'a Session,
@@ -703,6 +826,17 @@ impl<'a> EarlyContext<'a> {
             krate: krate,
             lints: lint_store,
             level_stack: vec![],
+            lint_level_report: vec![],
+        }
+    }
+
+    /// Records the level of every lint at the item named `name`, for
+    /// `-Z dump-lint-levels`. Paths aren't resolved yet, so items are only
+    /// known by their own name and span.
+    fn record_lint_levels(&mut self, name: String, span: Span) {
+        if self.sess.opts.debugging_opts.dump_lint_levels.is_some() {
+            let levels = lint_levels_at(&*self, name, span);
+            self.lint_level_report.push(levels);
         }
     }
 }
@@ -721,9 +855,26 @@ impl<'a, 'tcx> LateContext<'a, 'tcx> {
             access_levels: access_levels,
             lints: lint_store,
             level_stack: vec![],
+            lint_level_report: vec![],
         }
     }
 
+    /// Records the level of every lint at the node `id`, for
+    /// `-Z dump-lint-levels`.
+    fn record_lint_levels(&mut self, id: ast::NodeId, span: Span) {
+        if self.tcx.sess.opts.debugging_opts.dump_lint_levels.is_none() {
+            return;
+        }
+
+        let item = if id == ast::CRATE_NODE_ID {
+            "crate".to_string()
+        } else {
+            self.tcx.item_path_str(self.tcx.map.local_def_id(id))
+        };
+        let levels = lint_levels_at(&*self, item, span);
+        self.lint_level_report.push(levels);
+    }
+
     fn visit_ids<F>(&mut self, f: F)
         where F: FnOnce(&mut IdVisitor)
     {
@@ -803,6 +954,7 @@ impl<'a, 'tcx, 'v> hir_visit::Visitor<'v> for LateContext<'a, 'tcx> {
 
     fn visit_item(&mut self, it: &hir::Item) {
         self.with_lint_attrs(&it.attrs, |cx| {
+            cx.record_lint_levels(it.id, it.span);
             run_lints!(cx, check_item, late_passes, it);
             cx.visit_ids(|v| v.visit_item(it));
             hir_visit::walk_item(cx, it);
@@ -825,6 +977,9 @@ impl<'a, 'tcx, 'v> hir_visit::Visitor<'v> for LateContext<'a, 'tcx> {
 
     fn visit_expr(&mut self, e: &hir::Expr) {
         self.with_lint_attrs(&e.attrs, |cx| {
+            if !e.attrs.is_empty() {
+                cx.visit_ids(|v| hir_visit::walk_expr(v, e));
+            }
             run_lints!(cx, check_expr, late_passes, e);
             hir_visit::walk_expr(cx, e);
         })
@@ -890,6 +1045,9 @@ impl<'a, 'tcx, 'v> hir_visit::Visitor<'v> for LateContext<'a, 'tcx> {
 
     fn visit_local(&mut self, l: &hir::Local) {
         self.with_lint_attrs(&l.attrs, |cx| {
+            if !l.attrs.is_empty() {
+                cx.visit_ids(|v| hir_visit::walk_local(v, l));
+            }
             run_lints!(cx, check_local, late_passes, l);
             hir_visit::walk_local(cx, l);
         })
@@ -922,6 +1080,7 @@ impl<'a, 'tcx, 'v> hir_visit::Visitor<'v> for LateContext<'a, 'tcx> {
 
     fn visit_trait_item(&mut self, trait_item: &hir::TraitItem) {
         self.with_lint_attrs(&trait_item.attrs, |cx| {
+            cx.record_lint_levels(trait_item.id, trait_item.span);
             run_lints!(cx, check_trait_item, late_passes, trait_item);
             cx.visit_ids(|v| hir_visit::walk_trait_item(v, trait_item));
             hir_visit::walk_trait_item(cx, trait_item);
@@ -931,6 +1090,7 @@ impl<'a, 'tcx, 'v> hir_visit::Visitor<'v> for LateContext<'a, 'tcx> {
 
     fn visit_impl_item(&mut self, impl_item: &hir::ImplItem) {
         self.with_lint_attrs(&impl_item.attrs, |cx| {
+            cx.record_lint_levels(impl_item.id, impl_item.span);
             run_lints!(cx, check_impl_item, late_passes, impl_item);
             cx.visit_ids(|v| hir_visit::walk_impl_item(v, impl_item));
             hir_visit::walk_impl_item(cx, impl_item);
@@ -965,6 +1125,7 @@ impl<'a, 'tcx, 'v> hir_visit::Visitor<'v> for LateContext<'a, 'tcx> {
 impl<'a> ast_visit::Visitor for EarlyContext<'a> {
     fn visit_item(&mut self, it: &ast::Item) {
         self.with_lint_attrs(&it.attrs, |cx| {
+            cx.record_lint_levels(it.ident.to_string(), it.span);
             run_lints!(cx, check_item, early_passes, it);
             ast_visit::walk_item(cx, it);
             run_lints!(cx, check_item_post, early_passes, it);
@@ -1073,6 +1234,7 @@ impl<'a> ast_visit::Visitor for EarlyContext<'a> {
 
     fn visit_trait_item(&mut self, trait_item: &ast::TraitItem) {
         self.with_lint_attrs(&trait_item.attrs, |cx| {
+            cx.record_lint_levels(trait_item.ident.to_string(), trait_item.span);
             run_lints!(cx, check_trait_item, early_passes, trait_item);
             ast_visit::walk_trait_item(cx, trait_item);
             run_lints!(cx, check_trait_item_post, early_passes, trait_item);
@@ -1081,6 +1243,7 @@ impl<'a> ast_visit::Visitor for EarlyContext<'a> {
 
     fn visit_impl_item(&mut self, impl_item: &ast::ImplItem) {
         self.with_lint_attrs(&impl_item.attrs, |cx| {
+            cx.record_lint_levels(impl_item.ident.to_string(), impl_item.span);
             run_lints!(cx, check_impl_item, early_passes, impl_item);
             ast_visit::walk_impl_item(cx, impl_item);
             run_lints!(cx, check_impl_item_post, early_passes, impl_item);
@@ -1126,6 +1289,21 @@ impl<'a, 'b, 'tcx, 'v> hir_visit::Visitor<'v> for IdVisitor<'a, 'b, 'tcx> {
         }
     }
 
+    fn visit_expr(&mut self, e: &hir::Expr) {
+        // Expressions and locals with attributes output their lints when they
+        // are visited by the LateContext, so that the attributes apply to them.
+        if e.attrs.is_empty() {
+            hir_visit::walk_expr(self, e);
+        }
+    }
+
+    fn visit_local(&mut self, l: &hir::Local) {
+        // See visit_expr()
+        if l.attrs.is_empty() {
+            hir_visit::walk_local(self, l);
+        }
+    }
+
     fn visit_trait_item(&mut self, _ti: &hir::TraitItem) {
         // Do not recurse into trait or impl items automatically. These are
         // processed separately by calling hir_visit::walk_trait_item()
@@ -1250,6 +1428,8 @@ pub fn check_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
 
     // Visit the whole crate.
     cx.with_lint_attrs(&krate.attrs, |cx| {
+        cx.record_lint_levels(ast::CRATE_NODE_ID, krate.span);
+
         cx.visit_ids(|v| {
             hir_visit::walk_crate(v, krate);
         });
@@ -1273,6 +1453,8 @@ pub fn check_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         }
     }
 
+    report_lint_levels(tcx.sess, "late", &cx.lint_level_report, true);
+
     // Put the lint store back in the session.
     mem::replace(&mut *tcx.sess.lint_store.borrow_mut(), cx.lints);
 }
@@ -1282,6 +1464,8 @@ pub fn check_ast_crate(sess: &Session, krate: &ast::Crate) {
 
     // Visit the whole crate.
     cx.with_lint_attrs(&krate.attrs, |cx| {
+        cx.record_lint_levels("crate".to_string(), krate.span);
+
         // Lints may be assigned to the whole crate.
         if let Some(lints) = cx.sess.lints.borrow_mut().remove(&ast::CRATE_NODE_ID) {
             for early_lint in lints {
@@ -1298,6 +1482,8 @@ pub fn check_ast_crate(sess: &Session, krate: &ast::Crate) {
         run_lints!(cx, check_crate_post, early_passes, krate);
     });
 
+    report_lint_levels(sess, "early", &cx.lint_level_report, false);
+
     // Put the lint store back in the session.
     mem::replace(&mut *sess.lint_store.borrow_mut(), cx.lints);
 
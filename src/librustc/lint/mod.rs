@@ -304,11 +304,13 @@ pub enum LintSource {
     /// in rustc or a plugin.
     Default,
 
-    /// Lint level was set by an attribute.
-    Node(Span),
+    /// Lint level was set by an attribute. The name is that of the lint or
+    /// lint group given in the attribute.
+    Node(ast::Name, Span),
 
-    /// Lint level was set by a command-line flag.
-    CommandLine,
+    /// Lint level was set by a command-line flag. The name is that of the
+    /// lint or lint group given in the flag.
+    CommandLine(ast::Name),
 }
 
 pub type LevelSource = (Level, LintSource);
@@ -898,6 +898,15 @@ impl<'tcx> Lvalue<'tcx> {
             elem: elem,
         }))
     }
+
+    /// The local this lvalue is a projection of, if any.
+    pub fn base_local(&self) -> Option<Local> {
+        match *self {
+            Lvalue::Local(local) => Some(local),
+            Lvalue::Static(_) => None,
+            Lvalue::Projection(ref projection) => projection.base.base_local(),
+        }
+    }
 }
 
 impl<'tcx> Debug for Lvalue<'tcx> {
@@ -82,6 +82,9 @@ pub struct Session {
     pub plugin_llvm_passes: RefCell<Vec<String>>,
     pub mir_passes: RefCell<mir_pass::Passes>,
     pub plugin_attributes: RefCell<Vec<(String, AttributeType)>>,
+    /// The helper attributes declared by the custom derives loaded so far,
+    /// each paired with the name of the derive declaring it.
+    pub derive_helper_attributes: RefCell<Vec<(String, String)>>,
     pub crate_types: RefCell<Vec<config::CrateType>>,
     pub dependency_formats: RefCell<dependency_format::Dependencies>,
     // The crate_disambiguator is constructed out of all the `-C metadata`
@@ -302,6 +305,9 @@ impl Session {
     pub fn diagnostic<'a>(&'a self) -> &'a errors::Handler {
         &self.parse_sess.span_diagnostic
     }
+    pub fn diagnostic_message(&self, id: &str, args: &[(&str, &fmt::Display)]) -> String {
+        self.diagnostic().message(id, args)
+    }
 
     /// Analogous to calling `.span_note` on the given DiagnosticBuilder, but
     /// deduplicates on lint ID, span, and message for this `Session` if we're
@@ -528,20 +534,26 @@ pub fn build_session_with_codemap(sopts: config::Options,
         .unwrap_or(true);
     let treat_err_as_bug = sopts.debugging_opts.treat_err_as_bug;
 
+    let macro_backtrace = sopts.debugging_opts.macro_backtrace;
+
     let emitter: Box<Emitter> = match (sopts.error_format, emitter_dest) {
         (config::ErrorOutputType::HumanReadable(color_config), None) => {
             Box::new(EmitterWriter::stderr(color_config,
-                                           Some(codemap.clone())))
+                                           Some(codemap.clone()))
+                         .macro_backtrace(macro_backtrace))
         }
         (config::ErrorOutputType::HumanReadable(_), Some(dst)) => {
             Box::new(EmitterWriter::new(dst,
-                                        Some(codemap.clone())))
+                                        Some(codemap.clone()))
+                         .macro_backtrace(macro_backtrace))
         }
         (config::ErrorOutputType::Json, None) => {
-            Box::new(JsonEmitter::stderr(Some(registry), codemap.clone()))
+            Box::new(JsonEmitter::stderr(Some(registry), codemap.clone())
+                         .macro_backtrace(macro_backtrace))
         }
         (config::ErrorOutputType::Json, Some(dst)) => {
-            Box::new(JsonEmitter::new(dst, Some(registry), codemap.clone()))
+            Box::new(JsonEmitter::new(dst, Some(registry), codemap.clone())
+                         .macro_backtrace(macro_backtrace))
         }
     };
 
@@ -549,6 +561,19 @@ pub fn build_session_with_codemap(sopts: config::Options,
         errors::Handler::with_emitter(can_print_warnings,
                                       treat_err_as_bug,
                                       emitter);
+    diagnostic_handler.set_error_limit(sopts.debugging_opts.error_limit);
+
+    let catalog_path = sopts.debugging_opts.diagnostic_catalog.clone()
+        .or_else(|| env::var("RUST_DIAGNOSTIC_CATALOG").ok());
+    if let Some(path) = catalog_path {
+        match errors::MessageCatalog::from_file(Path::new(&path)) {
+            Ok(catalog) => diagnostic_handler.set_message_catalog(catalog),
+            Err(e) => {
+                diagnostic_handler.warn(&format!("failed to load diagnostic catalog `{}`: {}; \
+                                                  using the built-in messages", path, e));
+            }
+        }
+    }
 
     build_session_(sopts,
                    dep_graph,
@@ -608,6 +633,7 @@ pub fn build_session_(sopts: config::Options,
         plugin_llvm_passes: RefCell::new(Vec::new()),
         mir_passes: RefCell::new(mir_pass::Passes::new()),
         plugin_attributes: RefCell::new(Vec::new()),
+        derive_helper_attributes: RefCell::new(Vec::new()),
         crate_types: RefCell::new(Vec::new()),
         dependency_formats: RefCell::new(FxHashMap()),
         crate_disambiguator: RefCell::new(token::intern("").as_str()),
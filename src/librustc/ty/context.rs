@@ -436,6 +436,12 @@ pub struct GlobalCtxt<'tcx> {
     /// (in the `RefCell` sense) to prevent accidental mutation.
     pub mir_map: RefCell<DepTrackingMap<maps::Mir<'tcx>>>,
 
+    /// Builds the MIR of a local item ahead of the rest of the crate,
+    /// returning whether the item has any. Set by `rustc_mir` once the
+    /// crate is ready for MIR construction, so that constant evaluation
+    /// doesn't depend on the order the MIR of items is built in.
+    pub mir_builder: Cell<Option<for<'a> fn(TyCtxt<'a, 'tcx, 'tcx>, DefId) -> bool>>,
+
     // Records the free variables refrenced by every closure
     // expression. Do not track deps for this, just recompute it from
     // scratch every time.
@@ -809,6 +815,7 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
             fulfilled_predicates: RefCell::new(fulfilled_predicates),
             map: map,
             mir_map: RefCell::new(DepTrackingMap::new(dep_graph.clone())),
+            mir_builder: Cell::new(None),
             freevars: RefCell::new(freevars),
             maybe_unused_trait_imports: maybe_unused_trait_imports,
             item_types: RefCell::new(DepTrackingMap::new(dep_graph.clone())),
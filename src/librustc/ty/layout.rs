@@ -500,6 +500,54 @@ impl Primitive {
 // FIXME(eddyb) use small vector optimization for the common case.
 pub type FieldPath = Vec<u32>;
 
+/// A scalar whose type doesn't use all the bit patterns of its size,
+/// e.g. a `bool`, which is always either 0 or 1. The invalid values
+/// can hold the discriminant of an enclosing enum, see `NicheFilling`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Niche {
+    /// Offset of the scalar from the start of the enclosing type.
+    pub offset: Size,
+    pub value: Integer,
+    /// Inclusive range of the valid values, compared as unsigned.
+    /// If `valid_start > valid_end`, it represents `valid_start...MAX`
+    /// followed by `0...valid_end`, as in `CEnum`.
+    pub valid_start: u64,
+    pub valid_end: u64
+}
+
+impl Niche {
+    fn new(value: Integer, valid_start: u64, valid_end: u64) -> Niche {
+        let mask = !0 >> (64 - value.size().bits());
+        Niche {
+            offset: Size::from_bytes(0),
+            value: value,
+            valid_start: valid_start & mask,
+            valid_end: valid_end & mask
+        }
+    }
+
+    fn mask(&self) -> u64 {
+        !0 >> (64 - self.value.size().bits())
+    }
+
+    /// The number of invalid values.
+    pub fn available(&self) -> u64 {
+        self.valid_start.wrapping_sub(self.valid_end).wrapping_sub(1) & self.mask()
+    }
+
+    /// Take the `count` invalid values right after the valid ones.
+    /// Returns the first value taken, and the niche that remains,
+    /// in which the values taken are considered valid.
+    pub fn reserve(&self, count: u64) -> Option<(u64, Niche)> {
+        if count == 0 || count > self.available() {
+            return None;
+        }
+        let start = self.valid_end.wrapping_add(1) & self.mask();
+        let end = self.valid_end.wrapping_add(count) & self.mask();
+        Some((start, Niche { valid_end: end, ..*self }))
+    }
+}
+
 /// A structure, a product type in ADT terms.
 #[derive(PartialEq, Eq, Hash, Debug)]
 pub struct Struct {
@@ -511,62 +559,131 @@ pub struct Struct {
     /// If true, the size is exact, otherwise it's only a lower bound.
     pub sized: bool,
 
-    /// Offsets for the first byte of each field.
+    /// Offsets for the first byte of each field, ordered to match the source definition order.
+    /// This vector does not go in increasing order.
     /// FIXME(eddyb) use small vector optimization for the common case.
     pub offsets: Vec<Size>,
 
+    /// Maps source order field indices to memory order indices,
+    /// depending how fields were permuted.
+    /// FIXME(eddyb) use small vector optimization for the common case.
+    pub memory_index: Vec<u32>,
+
     pub min_size: Size,
 }
 
+/// What a `Struct` is used for, which determines how its fields may be
+/// reordered.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StructKind {
+    /// A tuple, closure, or univariant which cannot be coerced to unsized.
+    AlwaysSizedUnivariant,
+    /// A univariant, the last field of which may be coerced to unsized.
+    MaybeUnsizedUnivariant,
+    /// A univariant, but part of an enum; the first field is the
+    /// discriminant, if any.
+    EnumVariant,
+}
+
 impl<'a, 'gcx, 'tcx> Struct {
-    pub fn new(dl: &TargetDataLayout, packed: bool) -> Struct {
-        Struct {
+    /// Lay out a structure with the given fields. Unless `reprs` contains
+    /// `#[repr(C)]` or `#[repr(packed)]`, fields are sorted by alignment
+    /// to minimize padding, so their offsets don't necessarily increase
+    /// with their index; see `memory_index`.
+    pub fn new(dl: &TargetDataLayout,
+               fields: &Vec<&'a Layout>,
+               reprs: &[attr::ReprAttr],
+               kind: StructKind,
+               scapegoat: Ty<'gcx>)
+               -> Result<Struct, LayoutError<'gcx>> {
+        let packed = reprs.contains(&attr::ReprPacked);
+        let mut ret = Struct {
             align: if packed { dl.i8_align } else { dl.aggregate_align },
             packed: packed,
             sized: true,
             offsets: vec![],
+            memory_index: vec![],
             min_size: Size::from_bytes(0),
-        }
-    }
-
-    /// Extend the Struct with more fields.
-    pub fn extend<I>(&mut self, dl: &TargetDataLayout,
-                     fields: I,
-                     scapegoat: Ty<'gcx>)
-                     -> Result<(), LayoutError<'gcx>>
-    where I: Iterator<Item=Result<&'a Layout, LayoutError<'gcx>>> {
-        self.offsets.reserve(fields.size_hint().0);
+        };
 
-        let mut offset = self.min_size;
+        // Anything with repr(C) or repr(packed) doesn't optimize.
+        // Neither do structs with fewer than three fields: reordering
+        // can't remove padding from them, and trans relies on two-field
+        // structs keeping their order to pass them as a pair of immediates.
+        let can_optimize = !packed && !reprs.contains(&attr::ReprExtern) && fields.len() > 2;
+
+        // `inverse_memory_index` holds field indices by increasing offset.
+        // That is, if field 5 has offset 0, its first element is 5.
+        let mut inverse_memory_index: Vec<u32> = (0..fields.len() as u32).collect();
+        if can_optimize {
+            // The discriminant of an enum variant must stay first, and
+            // the last field of a struct that can be unsized must stay last.
+            let start = if kind == StructKind::EnumVariant { 1 } else { 0 };
+            let end = if kind == StructKind::MaybeUnsizedUnivariant {
+                fields.len() - 1
+            } else {
+                fields.len()
+            };
+            if end > start {
+                let optimizing = &mut inverse_memory_index[start..end];
+                if kind == StructKind::EnumVariant {
+                    // Put the smallest fields next to the discriminant,
+                    // leaving room for it to be enlarged.
+                    optimizing.sort_by_key(|&x| fields[x as usize].align(dl).abi());
+                } else {
+                    optimizing.sort_by(|&a, &b| {
+                        let a = fields[a as usize].align(dl).abi();
+                        let b = fields[b as usize].align(dl).abi();
+                        b.cmp(&a)
+                    });
+                }
+            }
+        }
 
-        for field in fields {
-            if !self.sized {
-                bug!("Struct::extend: field #{} of `{}` comes after unsized field",
-                     self.offsets.len(), scapegoat);
+        let mut offset = Size::from_bytes(0);
+        ret.offsets = vec![offset; fields.len()];
+        for &i in &inverse_memory_index {
+            let field = fields[i as usize];
+            if !ret.sized {
+                bug!("Struct::new: field #{} of `{}` comes after unsized field",
+                     i, scapegoat);
             }
 
-            let field = field?;
             if field.is_unsized() {
-                self.sized = false;
+                ret.sized = false;
             }
 
             // Invariant: offset < dl.obj_size_bound() <= 1<<61
-            if !self.packed {
+            if !ret.packed {
                 let align = field.align(dl);
-                self.align = self.align.max(align);
+                ret.align = ret.align.max(align);
                 offset = offset.abi_align(align);
             }
 
-            self.offsets.push(offset);
-
+            ret.offsets[i as usize] = offset;
 
             offset = offset.checked_add(field.size(dl), dl)
                            .map_or(Err(LayoutError::SizeOverflow(scapegoat)), Ok)?;
         }
 
-        self.min_size = offset;
+        ret.min_size = offset;
 
-        Ok(())
+        // `memory_index` is the inverse of `inverse_memory_index`.
+        ret.memory_index = vec![0; inverse_memory_index.len()];
+        for (memory_index, &i) in inverse_memory_index.iter().enumerate() {
+            ret.memory_index[i as usize] = memory_index as u32;
+        }
+
+        Ok(ret)
+    }
+
+    /// Get the indices of the fields, by increasing offset.
+    pub fn field_index_by_increasing_offset(&self) -> impl Iterator<Item=usize> {
+        let mut inverse = vec![0; self.memory_index.len()];
+        for (i, &memory_index) in self.memory_index.iter().enumerate() {
+            inverse[memory_index as usize] = i;
+        }
+        inverse.into_iter()
     }
 
     /// Get the size without trailing alignment padding.
@@ -591,16 +708,18 @@ impl<'a, 'gcx, 'tcx> Struct {
 
     /// Find the path leading to a non-zero leaf field, starting from
     /// the given type and recursing through aggregates.
+    /// The tuple is `(path, source_path)`,
+    /// where `path` is in memory order and `source_path` in source order.
     // FIXME(eddyb) track value ranges and traverse already optimized enums.
     pub fn non_zero_field_in_type(infcx: &InferCtxt<'a, 'gcx, 'tcx>,
                                   ty: Ty<'gcx>)
-                                  -> Result<Option<FieldPath>, LayoutError<'gcx>> {
+                                  -> Result<Option<(FieldPath, FieldPath)>, LayoutError<'gcx>> {
         let tcx = infcx.tcx.global_tcx();
         match (ty.layout(infcx)?, &ty.sty) {
             (&Scalar { non_zero: true, .. }, _) |
-            (&CEnum { non_zero: true, .. }, _) => Ok(Some(vec![])),
+            (&CEnum { non_zero: true, .. }, _) => Ok(Some((vec![], vec![]))),
             (&FatPointer { non_zero: true, .. }, _) => {
-                Ok(Some(vec![FAT_PTR_ADDR as u32]))
+                Ok(Some((vec![FAT_PTR_ADDR as u32], vec![FAT_PTR_ADDR as u32])))
             }
 
             // Is this the NonZero lang item wrapping a pointer or integer type?
@@ -611,10 +730,11 @@ impl<'a, 'gcx, 'tcx> Struct {
                     // FIXME(eddyb) also allow floating-point types here.
                     Scalar { value: Int(_), non_zero: false } |
                     Scalar { value: Pointer, non_zero: false } => {
-                        Ok(Some(vec![0]))
+                        Ok(Some((vec![0], vec![0])))
                     }
                     FatPointer { non_zero: false, .. } => {
-                        Ok(Some(vec![FAT_PTR_ADDR as u32, 0]))
+                        let tmp = vec![FAT_PTR_ADDR as u32, 0];
+                        Ok(Some((tmp.clone(), tmp)))
                     }
                     _ => Ok(None)
                 }
@@ -622,25 +742,27 @@ impl<'a, 'gcx, 'tcx> Struct {
 
             // Perhaps one of the fields of this struct is non-zero
             // let's recurse and find out
-            (_, &ty::TyAdt(def, substs)) if def.is_struct() => {
-                Struct::non_zero_field_path(infcx, def.struct_variant().fields
+            (&Univariant { ref variant, .. }, &ty::TyAdt(def, substs)) if def.is_struct() => {
+                Struct::non_zero_field_paths(infcx, def.struct_variant().fields
                                                       .iter().map(|field| {
                     field.ty(tcx, substs)
-                }))
+                }),
+                Some(&variant.memory_index[..]))
             }
 
             // Perhaps one of the upvars of this closure is non-zero
-            // Let's recurse and find out!
-            (_, &ty::TyClosure(_, ty::ClosureSubsts { upvar_tys: tys, .. })) |
+            (&Univariant { ref variant, .. },
+             &ty::TyClosure(_, ty::ClosureSubsts { upvar_tys: tys, .. })) |
             // Can we use one of the fields in this tuple?
-            (_, &ty::TyTuple(tys)) => {
-                Struct::non_zero_field_path(infcx, tys.iter().cloned())
+            (&Univariant { ref variant, .. }, &ty::TyTuple(tys)) => {
+                Struct::non_zero_field_paths(infcx, tys.iter().cloned(),
+                    Some(&variant.memory_index[..]))
             }
 
             // Is this a fixed-size array of something non-zero
             // with at least one element?
             (_, &ty::TyArray(ety, d)) if d > 0 => {
-                Struct::non_zero_field_path(infcx, Some(ety).into_iter())
+                Struct::non_zero_field_paths(infcx, Some(ety).into_iter(), None)
             }
 
             (_, &ty::TyProjection(_)) | (_, &ty::TyAnon(..)) => {
@@ -658,14 +780,121 @@ impl<'a, 'gcx, 'tcx> Struct {
 
     /// Find the path leading to a non-zero leaf field, starting from
     /// the given set of fields and recursing through aggregates.
-    pub fn non_zero_field_path<I>(infcx: &InferCtxt<'a, 'gcx, 'tcx>,
-                                  fields: I)
-                                  -> Result<Option<FieldPath>, LayoutError<'gcx>>
+    /// Returns `Some((path, source_path))` on success.
+    /// `permutation` maps source order field indices to memory order
+    /// indices; it's `None` when fields are laid out in source order.
+    pub fn non_zero_field_paths<I>(infcx: &InferCtxt<'a, 'gcx, 'tcx>,
+                                   fields: I,
+                                   permutation: Option<&[u32]>)
+                                   -> Result<Option<(FieldPath, FieldPath)>, LayoutError<'gcx>>
+    where I: Iterator<Item=Ty<'gcx>> {
+        for (i, ty) in fields.enumerate() {
+            if let Some((mut path, mut source_path)) = Struct::non_zero_field_in_type(infcx, ty)? {
+                source_path.push(i as u32);
+                let index = if let Some(p) = permutation {
+                    p[i] as usize
+                } else {
+                    i
+                };
+                path.push(index as u32);
+                return Ok(Some((path, source_path)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Find a scalar with at least `count` invalid values, starting from
+    /// the given type and recursing through aggregates and niche-filled
+    /// enums. Non-null pointers and integers have a single invalid value,
+    /// zero, so the enums that could use it are usually represented as
+    /// `RawNullablePointer` or `StructWrappedNullablePointer` instead.
+    pub fn niche_in_type(infcx: &InferCtxt<'a, 'gcx, 'tcx>,
+                         ty: Ty<'gcx>,
+                         count: u64)
+                         -> Result<Option<Niche>, LayoutError<'gcx>> {
+        let tcx = infcx.tcx.global_tcx();
+        let dl = &tcx.data_layout;
+        let niche = match (ty.layout(infcx)?, &ty.sty) {
+            // `bool` is an `i1`, but it's stored as a byte.
+            (_, &ty::TyBool) => Niche::new(I8, 0, 1),
+            (_, &ty::TyChar) => Niche::new(I32, 0, ::std::char::MAX as u64),
+            (&CEnum { discr, min, max, .. }, _) => Niche::new(discr, min, max),
+
+            // The address of a fat pointer comes first.
+            (&Scalar { value: Pointer, non_zero: true }, _) |
+            (&FatPointer { non_zero: true, .. }, _) => {
+                Niche::new(dl.ptr_sized_integer(), 1, !0)
+            }
+
+            // The `NonZero` lang item, wrapping a pointer or an integer.
+            (&Univariant { non_zero: true, .. }, &ty::TyAdt(def, substs)) => {
+                let field = def.struct_variant().fields[0].ty(tcx, substs);
+                match *field.layout(infcx)? {
+                    Scalar { value: Int(value), non_zero: false } => {
+                        Niche::new(value, 1, !0)
+                    }
+                    Scalar { value: Pointer, non_zero: false } |
+                    FatPointer { non_zero: false, .. } => {
+                        Niche::new(dl.ptr_sized_integer(), 1, !0)
+                    }
+                    _ => return Ok(None)
+                }
+            }
+
+            // The remaining invalid values of an already niche-filled enum.
+            (&NicheFilling { niche, .. }, _) => niche,
+
+            (&Univariant { ref variant, .. }, &ty::TyAdt(def, substs)) if def.is_struct() => {
+                return Struct::niche_in_fields(infcx, def.struct_variant().fields
+                                                       .iter().map(|field| {
+                    field.ty(tcx, substs)
+                }), variant, count);
+            }
+
+            (&Univariant { ref variant, .. },
+             &ty::TyClosure(_, ty::ClosureSubsts { upvar_tys: tys, .. })) |
+            (&Univariant { ref variant, .. }, &ty::TyTuple(tys)) => {
+                return Struct::niche_in_fields(infcx, tys.iter().cloned(), variant, count);
+            }
+
+            // The first element of an array is at offset 0.
+            (_, &ty::TyArray(ety, d)) if d > 0 => {
+                return Struct::niche_in_type(infcx, ety, count);
+            }
+
+            (_, &ty::TyProjection(_)) | (_, &ty::TyAnon(..)) => {
+                let normalized = normalize_associated_type(infcx, ty);
+                if ty == normalized {
+                    return Ok(None);
+                }
+                return Struct::niche_in_type(infcx, normalized, count);
+            }
+
+            _ => return Ok(None)
+        };
+        if niche.available() >= count {
+            Ok(Some(niche))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Find a scalar with at least `count` invalid values in the first
+    /// of the given fields which has one; `st` is their layout.
+    pub fn niche_in_fields<I>(infcx: &InferCtxt<'a, 'gcx, 'tcx>,
+                              fields: I,
+                              st: &Struct,
+                              count: u64)
+                              -> Result<Option<Niche>, LayoutError<'gcx>>
     where I: Iterator<Item=Ty<'gcx>> {
+        // The fields of packed structs may be misaligned.
+        if st.packed {
+            return Ok(None);
+        }
         for (i, ty) in fields.enumerate() {
-            if let Some(mut path) = Struct::non_zero_field_in_type(infcx, ty)? {
-                path.push(i as u32);
-                return Ok(Some(path));
+            if let Some(mut niche) = Struct::niche_in_type(infcx, ty, count)? {
+                niche.offset = Size::from_bytes(st.offsets[i].bytes() + niche.offset.bytes());
+                return Ok(Some(niche));
             }
         }
         Ok(None)
@@ -824,7 +1053,27 @@ pub enum Layout {
         nndiscr: u64,
         nonnull: Struct,
         // N.B. There is a 0 at the start, for LLVM GEP through a pointer.
-        discrfield: FieldPath
+        discrfield: FieldPath,
+        /// Like `discrfield`, but in source order, without the leading 0.
+        discrfield_source: FieldPath
+    },
+
+    /// Enums where every variant except `dataful_variant` is zero sized,
+    /// represented by the struct `dataful`. The discriminant is stored in
+    /// `niche`, a scalar of `dataful` which has invalid values: variant
+    /// `v`, within the inclusive range `niche_variants`, is represented by
+    /// the niche holding `niche_start + (v - niche_variants.0)`, while any
+    /// valid value indicates `dataful_variant`.
+    ///
+    /// For example, `Option<bool>` is a single byte, with `None` being 2.
+    NicheFilling {
+        dataful_variant: u64,
+        niche_variants: (u64, u64),
+        dataful: Struct,
+        /// The values used by this enum are considered valid, so that
+        /// enclosing enums only use the remaining ones.
+        niche: Niche,
+        niche_start: u64
     }
 }
 
@@ -899,7 +1148,11 @@ impl<'a, 'gcx, 'tcx> Layout {
             ty::TyFnPtr(_) => Scalar { value: Pointer, non_zero: true },
 
             // The never type.
-            ty::TyNever => Univariant { variant: Struct::new(dl, false), non_zero: false },
+            ty::TyNever => Univariant {
+                variant: Struct::new(dl, &vec![], &[],
+                  StructKind::AlwaysSizedUnivariant, ty)?,
+                non_zero: false
+            },
 
             // Potentially-fat pointers.
             ty::TyBox(pointee) |
@@ -950,21 +1203,39 @@ impl<'a, 'gcx, 'tcx> Layout {
             // Odd unit types.
             ty::TyFnDef(..) => {
                 Univariant {
-                    variant: Struct::new(dl, false),
+                    variant: Struct::new(dl, &vec![], &[],
+                      StructKind::AlwaysSizedUnivariant, ty)?,
                     non_zero: false
                 }
             }
             ty::TyTrait(_) => {
-                let mut unit = Struct::new(dl, false);
+                let mut unit = Struct::new(dl, &vec![], &[],
+                  StructKind::AlwaysSizedUnivariant, ty)?;
                 unit.sized = false;
                 Univariant { variant: unit, non_zero: false }
             }
 
             // Tuples and closures.
-            ty::TyClosure(_, ty::ClosureSubsts { upvar_tys: tys, .. }) |
+            ty::TyClosure(_, ty::ClosureSubsts { upvar_tys: tys, .. }) => {
+                let st = Struct::new(dl,
+                    &tys.iter().map(|ty| ty.layout(infcx))
+                      .collect::<Result<Vec<_>, _>>()?,
+                    &[],
+                    StructKind::AlwaysSizedUnivariant, ty)?;
+                Univariant { variant: st, non_zero: false }
+            }
+
             ty::TyTuple(tys) => {
-                let mut st = Struct::new(dl, false);
-                st.extend(dl, tys.iter().map(|ty| ty.layout(infcx)), ty)?;
+                // The last element of a tuple may be unsized.
+                let kind = if tys.is_empty() {
+                    StructKind::AlwaysSizedUnivariant
+                } else {
+                    StructKind::MaybeUnsizedUnivariant
+                };
+                let st = Struct::new(dl,
+                    &tys.iter().map(|ty| ty.layout(infcx))
+                      .collect::<Result<Vec<_>, _>>()?,
+                    &[], kind, ty)?;
                 Univariant { variant: st, non_zero: false }
             }
 
@@ -988,8 +1259,8 @@ impl<'a, 'gcx, 'tcx> Layout {
 
             // ADTs.
             ty::TyAdt(def, substs) => {
-                let hint = *tcx.lookup_repr_hints(def.did).get(0)
-                    .unwrap_or(&attr::ReprAny);
+                let hints = tcx.lookup_repr_hints(def.did);
+                let hint = *hints.get(0).unwrap_or(&attr::ReprAny);
 
                 if def.variants.is_empty() {
                     // Uninhabitable; represent as unit
@@ -997,7 +1268,8 @@ impl<'a, 'gcx, 'tcx> Layout {
                     assert_eq!(hint, attr::ReprAny);
 
                     return success(Univariant {
-                        variant: Struct::new(dl, false),
+                        variant: Struct::new(dl, &vec![], &hints[..],
+                          StructKind::AlwaysSizedUnivariant, ty)?,
                         non_zero: false
                     });
                 }
@@ -1028,15 +1300,29 @@ impl<'a, 'gcx, 'tcx> Layout {
 
                     let fields = def.variants[0].fields.iter().map(|field| {
                         field.ty(tcx, substs).layout(infcx)
-                    });
+                    }).collect::<Result<Vec<_>, _>>()?;
                     let packed = tcx.lookup_packed(def.did);
                     let layout = if def.is_union() {
                         let mut un = Union::new(dl, packed);
-                        un.extend(dl, fields, ty)?;
+                        un.extend(dl, fields.iter().map(|&f| Ok(f)), ty)?;
                         UntaggedUnion { variants: un }
                     } else {
-                        let mut st = Struct::new(dl, packed);
-                        st.extend(dl, fields, ty)?;
+                        // Whether the last field may be unsized must not
+                        // depend on the substitutions, so that all the
+                        // instantiations of a struct which can be unsized
+                        // agree on where that field is.
+                        let kind = match def.variants[0].fields.last() {
+                            Some(field) if def.is_struct() => {
+                                let param_env = tcx.empty_parameter_environment();
+                                if field.unsubst_ty().is_sized(tcx, &param_env, DUMMY_SP) {
+                                    StructKind::AlwaysSizedUnivariant
+                                } else {
+                                    StructKind::MaybeUnsizedUnivariant
+                                }
+                            }
+                            _ => StructKind::AlwaysSizedUnivariant
+                        };
+                        let st = Struct::new(dl, &fields, &hints[..], kind, ty)?;
                         let non_zero = Some(def.did) == tcx.lang_items.non_zero();
                         Univariant { variant: st, non_zero: non_zero }
                     };
@@ -1067,9 +1353,19 @@ impl<'a, 'gcx, 'tcx> Layout {
                         if !Struct::would_be_zero_sized(dl, other_fields)? {
                             continue;
                         }
-                        let path = Struct::non_zero_field_path(infcx,
-                            variants[discr].iter().cloned())?;
-                        let mut path = if let Some(p) = path { p } else { continue };
+                        let fields = variants[discr].iter().map(|ty| {
+                            ty.layout(infcx)
+                        }).collect::<Result<Vec<_>, _>>()?;
+                        let st = Struct::new(dl, &fields, &hints[..],
+                            StructKind::AlwaysSizedUnivariant, ty)?;
+                        let path = Struct::non_zero_field_paths(infcx,
+                            variants[discr].iter().cloned(),
+                            Some(&st.memory_index[..]))?;
+                        let (mut path, mut path_source) = if let Some(p) = path {
+                            p
+                        } else {
+                            continue
+                        };
 
                         // FIXME(eddyb) should take advantage of a newtype.
                         if path == &[0] && variants[discr].len() == 1 {
@@ -1088,16 +1384,59 @@ impl<'a, 'gcx, 'tcx> Layout {
 
                         path.push(0); // For GEP through a pointer.
                         path.reverse();
-                        let mut st = Struct::new(dl, false);
-                        st.extend(dl, variants[discr].iter().map(|ty| ty.layout(infcx)), ty)?;
+                        path_source.reverse();
                         return success(StructWrappedNullablePointer {
                             nndiscr: discr as u64,
                             nonnull: st,
-                            discrfield: path
+                            discrfield: path,
+                            discrfield_source: path_source
                         });
                     }
                 }
 
+                if hint == attr::ReprAny {
+                    // Niche-filling optimization: if there is only one
+                    // variant which isn't zero-sized, the other variants
+                    // can be stored in the invalid values of one of its
+                    // fields, e.g. in `Option<bool>`, `Option<char>`
+                    // or `Option<Option<Ordering>>`.
+                    let mut dataful_variant = None;
+                    for (i, fields) in variants.iter().enumerate() {
+                        let fields = fields.iter().map(|ty| ty.layout(infcx));
+                        if Struct::would_be_zero_sized(dl, fields)? {
+                            continue;
+                        }
+                        if dataful_variant.is_some() {
+                            dataful_variant = None;
+                            break;
+                        }
+                        dataful_variant = Some(i);
+                    }
+
+                    if let Some(i) = dataful_variant {
+                        let first = if i == 0 { 1 } else { 0 };
+                        let last = if i == variants.len() - 1 { i - 1 } else { variants.len() - 1 };
+                        let count = (last - first + 1) as u64;
+
+                        let fields = variants[i].iter().map(|ty| {
+                            ty.layout(infcx)
+                        }).collect::<Result<Vec<_>, _>>()?;
+                        let st = Struct::new(dl, &fields, &hints[..],
+                            StructKind::AlwaysSizedUnivariant, ty)?;
+                        let niche = Struct::niche_in_fields(infcx,
+                            variants[i].iter().cloned(), &st, count)?;
+                        if let Some((niche_start, niche)) = niche.and_then(|n| n.reserve(count)) {
+                            return success(NicheFilling {
+                                dataful_variant: i as u64,
+                                niche_variants: (first as u64, last as u64),
+                                dataful: st,
+                                niche: niche,
+                                niche_start: niche_start
+                            });
+                        }
+                    }
+                }
+
                 // The general case.
                 let discr_max = (variants.len() - 1) as i64;
                 assert!(discr_max >= 0);
@@ -1111,24 +1450,24 @@ impl<'a, 'gcx, 'tcx> Layout {
 
                 // Create the set of structs that represent each variant
                 // Use the minimum integer type we figured out above
-                let discr = Some(Scalar { value: Int(min_ity), non_zero: false });
+                let discr = Scalar { value: Int(min_ity), non_zero: false };
                 let mut variants = variants.into_iter().map(|fields| {
-                    let mut found_start = false;
-                    let fields = fields.into_iter().map(|field| {
-                        let field = field.layout(infcx)?;
-                        if !found_start {
-                            // Find the first field we can't move later
-                            // to make room for a larger discriminant.
-                            let field_align = field.align(dl);
-                            if field.size(dl).bytes() != 0 || field_align.abi() != 1 {
-                                start_align = start_align.min(field_align);
-                                found_start = true;
-                            }
+                    let mut fields = fields.into_iter().map(|field| {
+                        field.layout(infcx)
+                    }).collect::<Result<Vec<_>, _>>()?;
+                    fields.insert(0, &discr);
+                    let st = Struct::new(dl, &fields, &hints[..],
+                        StructKind::EnumVariant, ty)?;
+                    // Find the first field we can't move later
+                    // to make room for a larger discriminant.
+                    for i in st.field_index_by_increasing_offset().skip(1) {
+                        let field = fields[i];
+                        let field_align = field.align(dl);
+                        if field.size(dl).bytes() != 0 || field_align.abi() != 1 {
+                            start_align = start_align.min(field_align);
+                            break;
                         }
-                        Ok(field)
-                    });
-                    let mut st = Struct::new(dl, false);
-                    st.extend(dl, discr.iter().map(Ok).chain(fields), ty)?;
+                    }
                     size = cmp::max(size, st.min_size);
                     align = align.max(st.align);
                     Ok(st)
@@ -1162,11 +1501,13 @@ impl<'a, 'gcx, 'tcx> Layout {
                     let old_ity_size = Int(min_ity).size(dl);
                     let new_ity_size = Int(ity).size(dl);
                     for variant in &mut variants {
+                        // The offsets aren't in memory order, but all the
+                        // fields placed right after the discriminant are
+                        // at `old_ity_size`.
                         for offset in &mut variant.offsets[1..] {
-                            if *offset > old_ity_size {
-                                break;
+                            if *offset <= old_ity_size {
+                                *offset = new_ity_size;
                             }
-                            *offset = new_ity_size;
                         }
                         // We might be making the struct larger.
                         if variant.min_size <= old_ity_size {
@@ -1208,7 +1549,8 @@ impl<'a, 'gcx, 'tcx> Layout {
             Scalar {..} | Vector {..} | FatPointer {..} |
             CEnum {..} | UntaggedUnion {..} | General {..} |
             RawNullablePointer {..} |
-            StructWrappedNullablePointer {..} => false,
+            StructWrappedNullablePointer {..} |
+            NicheFilling {..} => false,
 
             Array { sized, .. } |
             Univariant { variant: Struct { sized, .. }, .. } => !sized
@@ -1243,7 +1585,8 @@ impl<'a, 'gcx, 'tcx> Layout {
             UntaggedUnion { ref variants } => variants.stride(),
 
             Univariant { ref variant, .. } |
-            StructWrappedNullablePointer { nonnull: ref variant, .. } => {
+            StructWrappedNullablePointer { nonnull: ref variant, .. } |
+            NicheFilling { dataful: ref variant, .. } => {
                 variant.stride()
             }
         }
@@ -1283,7 +1626,8 @@ impl<'a, 'gcx, 'tcx> Layout {
             UntaggedUnion { ref variants } => variants.align,
 
             Univariant { ref variant, .. } |
-            StructWrappedNullablePointer { nonnull: ref variant, .. } => {
+            StructWrappedNullablePointer { nonnull: ref variant, .. } |
+            NicheFilling { dataful: ref variant, .. } => {
                 variant.align
             }
         }
@@ -1983,6 +1983,16 @@ impl BorrowKind {
             UniqueImmBorrow => "uniquely immutable",
         }
     }
+
+    /// The key identifying this kind of borrow to the diagnostic message
+    /// catalog.
+    pub fn message_key(&self) -> &'static str {
+        match *self {
+            MutBorrow => "mut",
+            ImmBorrow => "shared",
+            UniqueImmBorrow => "unique",
+        }
+    }
 }
 
 impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
@@ -229,6 +229,12 @@ impl<'a, 'tcx> CheckLoanCtxt<'a, 'tcx> {
         //! are issued for future scopes and thus they may have been
         //! *issued* but not yet be in effect.
 
+        // With `-Z nll`, loans are checked against each other and against
+        // later uses on MIR instead, where they end with their last use.
+        if self.bccx.tcx.sess.opts.debugging_opts.nll {
+            return true;
+        }
+
         self.dfcx_loans.each_bit_on_entry(node, |loan_index| {
             let loan = &self.all_loans[loan_index];
             op(loan)
@@ -349,6 +355,10 @@ impl<'a, 'tcx> CheckLoanCtxt<'a, 'tcx> {
 
         debug!("check_for_conflicting_loans(node={:?})", node);
 
+        if self.bccx.tcx.sess.opts.debugging_opts.nll {
+            return;
+        }
+
         let new_loan_indices = self.loans_generated_by(node);
         debug!("new_loan_indices = {:?}", new_loan_indices);
 
@@ -430,17 +440,13 @@ impl<'a, 'tcx> CheckLoanCtxt<'a, 'tcx> {
             //     borrow ends
 
             let common = new_loan.loan_path.common(&old_loan.loan_path);
-            let (nl, ol, new_loan_msg, old_loan_msg) = {
+            let (nl, ol, new_via, old_via) = {
                 if new_loan.loan_path.has_fork(&old_loan.loan_path) && common.is_some() {
                     let nl = self.bccx.loan_path_to_string(&common.unwrap());
                     let ol = nl.clone();
-                    let new_loan_msg = format!(" (via `{}`)",
-                                               self.bccx.loan_path_to_string(
-                                                   &new_loan.loan_path));
-                    let old_loan_msg = format!(" (via `{}`)",
-                                               self.bccx.loan_path_to_string(
-                                                   &old_loan.loan_path));
-                    (nl, ol, new_loan_msg, old_loan_msg)
+                    (nl, ol,
+                     self.bccx.loan_path_to_string(&new_loan.loan_path),
+                     self.bccx.loan_path_to_string(&old_loan.loan_path))
                 } else {
                     (self.bccx.loan_path_to_string(&new_loan.loan_path),
                      self.bccx.loan_path_to_string(&old_loan.loan_path),
@@ -448,8 +454,13 @@ impl<'a, 'tcx> CheckLoanCtxt<'a, 'tcx> {
                      String::new())
                 }
             };
+            let via_msg = |via: &str| {
+                if via.is_empty() { String::new() } else { format!(" (via `{}`)", via) }
+            };
+            let (new_loan_msg, old_loan_msg) = (via_msg(&new_via), via_msg(&old_via));
 
-            let ol_pronoun = if new_loan.loan_path == old_loan.loan_path {
+            let same_path = new_loan.loan_path == old_loan.loan_path;
+            let ol_pronoun = if same_path {
                 "it".to_string()
             } else {
                 format!("`{}`", ol)
@@ -468,10 +479,10 @@ impl<'a, 'tcx> CheckLoanCtxt<'a, 'tcx> {
 
             let mut err = match (new_loan.kind, old_loan.kind) {
                 (ty::MutBorrow, ty::MutBorrow) => {
-                    let mut err = struct_span_err!(self.bccx, new_loan.span, E0499,
-                                                  "cannot borrow `{}`{} as mutable \
-                                                  more than once at a time",
-                                                  nl, new_loan_msg);
+                    let mut err = struct_span_err_message!(self.bccx, new_loan.span, E0499,
+                                                           "borrowck-mut-borrow-twice",
+                                                           path = nl,
+                                                           via = new_via);
                     err.span_label(
                             old_loan.span,
                             &format!("first mutable borrow occurs here{}", old_loan_msg));
@@ -536,15 +547,15 @@ impl<'a, 'tcx> CheckLoanCtxt<'a, 'tcx> {
                 }
 
                 (..) => {
-                    let mut err = struct_span_err!(self.bccx, new_loan.span, E0502,
-                                                   "cannot borrow `{}`{} as {} because \
-                                                   {} is also borrowed as {}{}",
-                                                   nl,
-                                                   new_loan_msg,
-                                                   new_loan.kind.to_user_str(),
-                                                   ol_pronoun,
-                                                   old_loan.kind.to_user_str(),
-                                                   old_loan_msg);
+                    let mut err = struct_span_err_message!(self.bccx, new_loan.span, E0502,
+                                                           "borrowck-borrow-conflict",
+                                                           path = nl,
+                                                           via = new_via,
+                                                           new_kind = new_loan.kind.message_key(),
+                                                           same_path = same_path,
+                                                           old_path = ol,
+                                                           old_kind = old_loan.kind.message_key(),
+                                                           old_via = old_via);
                     err.span_label(
                             new_loan.span,
                             &format!("{} borrow occurs here{}",
@@ -96,6 +96,15 @@ impl<'a, 'tcx> GuaranteeLifetimeContext<'a, 'tcx> {
         //! Reports an error if `loan_region` is larger than `max_scope`
 
         if !self.bccx.is_subregion_of(self.loan_region, max_scope) {
+            // With `-Z nll`, borrows confined to the function body are
+            // instead checked on MIR against the point where the borrowed
+            // value goes out of scope, so that they only need to outlive
+            // their last use.
+            if let ty::ReScope(_) = *self.loan_region {
+                if self.bccx.tcx.sess.opts.debugging_opts.nll {
+                    return Ok(());
+                }
+            }
             Err(self.report_error(err_out_of_scope(max_scope, self.loan_region, self.cause)))
         } else {
             Ok(())
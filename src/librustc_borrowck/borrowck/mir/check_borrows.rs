@@ -0,0 +1,533 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks each access made by a MIR body against the borrows in effect at
+//! that point, as computed by the `Borrows` dataflow. This is the MIR
+//! counterpart of `check_loans`, and reports errors the same way.
+
+use rustc::mir::{self, BasicBlock, Location, Lvalue, LvalueProjection, Operand};
+use rustc::mir::{ProjectionElem, Rvalue, Statement, StatementKind, Terminator, TerminatorKind};
+use rustc::mir::tcx::LvalueTy;
+use rustc::ty;
+use rustc_data_structures::indexed_set::IdxSet;
+use syntax_pos::Span;
+
+use super::MirBorrowckCtxt;
+use super::nll::BorrowIndex;
+
+/// The ways in which a statement can access an lvalue.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Access {
+    Read,
+    Move,
+    Write,
+    Borrow(mir::BorrowKind),
+    StorageDead,
+    Drop,
+}
+
+impl<'b, 'a: 'b, 'tcx: 'a> MirBorrowckCtxt<'b, 'a, 'tcx> {
+    pub fn check_borrows(&mut self) {
+        for bb in self.mir.basic_blocks().indices() {
+            self.process_basic_block(bb);
+        }
+    }
+
+    fn process_basic_block(&mut self, bb: BasicBlock) {
+        let mir = self.mir;
+        let data = &mir[bb];
+        let mut borrows = self.flow_borrows.sets().on_entry_set_for(bb.index()).to_owned();
+
+        for (statement_index, statement) in data.statements.iter().enumerate() {
+            let location = Location { block: bb, statement_index: statement_index };
+            self.regions.kill_dead_borrows(&mut borrows, location);
+            self.process_statement(&borrows, statement);
+            if let Some(borrow) = self.regions.borrow_at(location) {
+                borrows.add(&borrow);
+            }
+        }
+
+        let location = Location { block: bb, statement_index: data.statements.len() };
+        self.regions.kill_dead_borrows(&mut borrows, location);
+        self.process_terminator(&borrows, bb, data.terminator());
+    }
+
+    fn process_statement(&mut self, borrows: &IdxSet<BorrowIndex>, stmt: &Statement<'tcx>) {
+        debug!("MirBorrowckCtxt::process_statement({:?})", stmt);
+        let span = stmt.source_info.span;
+        match stmt.kind {
+            StatementKind::Assign(ref lvalue, ref rvalue) => {
+                self.consume_rvalue(borrows, rvalue, span);
+                self.check_access(borrows, lvalue, Access::Write, span);
+            }
+            StatementKind::SetDiscriminant { ref lvalue, .. } => {
+                self.check_access(borrows, lvalue, Access::Write, span);
+            }
+            StatementKind::StorageDead(ref lvalue) => {
+                self.check_access(borrows, lvalue, Access::StorageDead, span);
+            }
+            StatementKind::StorageLive(_) |
+            StatementKind::Nop => {}
+        }
+    }
+
+    fn process_terminator(&mut self,
+                          borrows: &IdxSet<BorrowIndex>,
+                          bb: BasicBlock,
+                          term: &Terminator<'tcx>) {
+        debug!("MirBorrowckCtxt::process_terminator({:?}, {:?})", bb, term);
+        let span = term.source_info.span;
+        match term.kind {
+            TerminatorKind::If { ref cond, .. } |
+            TerminatorKind::Assert { ref cond, .. } => {
+                self.consume_operand(borrows, cond, span);
+            }
+            TerminatorKind::Switch { ref discr, .. } |
+            TerminatorKind::SwitchInt { ref discr, .. } => {
+                self.check_access(borrows, discr, Access::Read, span);
+            }
+            TerminatorKind::Drop { ref location, .. } => {
+                if self.regions.drops_value(bb) {
+                    self.check_access(borrows, location, Access::Drop, span);
+                }
+            }
+            TerminatorKind::DropAndReplace { ref location, ref value, .. } => {
+                self.consume_operand(borrows, value, span);
+                self.check_access(borrows, location, Access::Write, span);
+            }
+            TerminatorKind::Call { ref func, ref args, ref destination, .. } => {
+                self.consume_operand(borrows, func, span);
+                for arg in args {
+                    self.consume_operand(borrows, arg, span);
+                }
+                if let Some((ref dest, _)) = *destination {
+                    self.check_access(borrows, dest, Access::Write, span);
+                }
+            }
+            TerminatorKind::Goto { .. } |
+            TerminatorKind::Resume |
+            TerminatorKind::Return |
+            TerminatorKind::Unreachable => {}
+        }
+    }
+
+    fn consume_rvalue(&mut self,
+                      borrows: &IdxSet<BorrowIndex>,
+                      rvalue: &Rvalue<'tcx>,
+                      span: Span) {
+        match *rvalue {
+            Rvalue::Use(ref op) |
+            Rvalue::Repeat(ref op, _) |
+            Rvalue::Cast(_, ref op, _) |
+            Rvalue::UnaryOp(_, ref op) => self.consume_operand(borrows, op, span),
+            Rvalue::BinaryOp(_, ref lhs, ref rhs) |
+            Rvalue::CheckedBinaryOp(_, ref lhs, ref rhs) => {
+                self.consume_operand(borrows, lhs, span);
+                self.consume_operand(borrows, rhs, span);
+            }
+            Rvalue::Ref(_, kind, ref lvalue) => {
+                self.check_access(borrows, lvalue, Access::Borrow(kind), span);
+            }
+            Rvalue::Len(ref lvalue) => {
+                self.check_access(borrows, lvalue, Access::Read, span);
+            }
+            Rvalue::Aggregate(_, ref ops) => {
+                for op in ops {
+                    self.consume_operand(borrows, op, span);
+                }
+            }
+            Rvalue::InlineAsm { ref outputs, ref inputs, .. } => {
+                for input in inputs {
+                    self.consume_operand(borrows, input, span);
+                }
+                for output in outputs {
+                    self.check_access(borrows, output, Access::Write, span);
+                }
+            }
+            Rvalue::Box(_) => {}
+        }
+    }
+
+    fn consume_operand(&mut self,
+                       borrows: &IdxSet<BorrowIndex>,
+                       operand: &Operand<'tcx>,
+                       span: Span) {
+        if let Operand::Consume(ref lvalue) = *operand {
+            let tcx = self.bcx.tcx;
+            let ty = lvalue.ty(self.mir, tcx).to_ty(tcx);
+            let access = if ty.moves_by_default(tcx, &self.param_env, span) {
+                Access::Move
+            } else {
+                Access::Read
+            };
+            self.check_access(borrows, lvalue, access, span);
+        }
+    }
+
+    /// Reports the first borrow in `borrows` which conflicts with `access`
+    /// to `lvalue`.
+    fn check_access(&mut self,
+                    borrows: &IdxSet<BorrowIndex>,
+                    lvalue: &Lvalue<'tcx>,
+                    access: Access,
+                    span: Span) {
+        let conflict = self.regions.borrows.iter_enumerated().find(|&(borrow, data)| {
+            if !borrows.contains(&borrow) {
+                return false;
+            }
+            match (data.kind, access) {
+                (mir::BorrowKind::Shared, Access::Read) |
+                (mir::BorrowKind::Shared, Access::Borrow(mir::BorrowKind::Shared)) => {
+                    return false;
+                }
+                _ => {}
+            }
+            match (access, data.region) {
+                // Borrows of longer-lived regions are checked by
+                // `gather_loans`.
+                (Access::StorageDead, &ty::ReScope(_)) |
+                (Access::Drop, &ty::ReScope(_)) => {}
+                (Access::StorageDead, _) |
+                (Access::Drop, _) => return false,
+                _ => {}
+            }
+            self.lvalues_conflict(&data.lvalue, lvalue, access)
+        }).map(|(borrow, _)| borrow);
+
+        if let Some(borrow) = conflict {
+            self.report_conflict(borrow, lvalue, access, span);
+        }
+    }
+
+    /// Whether `access` to `accessed` invalidates a borrow of `borrowed`.
+    fn lvalues_conflict(&self,
+                        borrowed: &Lvalue<'tcx>,
+                        accessed: &Lvalue<'tcx>,
+                        access: Access) -> bool {
+        let (mut borrowed_projs, mut accessed_projs) = (vec![], vec![]);
+        let borrowed_root = lvalue_components(borrowed, &mut borrowed_projs);
+        let accessed_root = lvalue_components(accessed, &mut accessed_projs);
+        if borrowed_root != accessed_root {
+            return false;
+        }
+
+        let tcx = self.bcx.tcx;
+        for (b, a) in borrowed_projs.iter().zip(&accessed_projs) {
+            match (&b.elem, &a.elem) {
+                (&ProjectionElem::Field(bf, _), &ProjectionElem::Field(af, _)) if bf != af => {
+                    // The fields of a union all overlap.
+                    let base_ty = b.base.ty(self.mir, tcx).to_ty(tcx);
+                    return match base_ty.sty {
+                        ty::TyAdt(def, _) => def.is_union(),
+                        _ => false,
+                    };
+                }
+                (&ProjectionElem::Downcast(_, bv), &ProjectionElem::Downcast(_, av))
+                    if bv != av => return false,
+                // Indexing may or may not pick the same element.
+                _ => {}
+            }
+        }
+
+        if accessed_projs.len() >= borrowed_projs.len() {
+            return true;
+        }
+
+        // Overwriting a reference (or letting it go out of scope) leaves
+        // whatever it points to, and hence the borrow, intact.
+        match access {
+            Access::Write | Access::StorageDead => {
+                !borrowed_projs[accessed_projs.len()..].iter().any(|proj| {
+                    proj.elem == ProjectionElem::Deref && {
+                        let base_ty = proj.base.ty(self.mir, tcx).to_ty(tcx);
+                        match base_ty.sty {
+                            ty::TyRef(..) | ty::TyRawPtr(..) => true,
+                            _ => false,
+                        }
+                    }
+                })
+            }
+            _ => true,
+        }
+    }
+
+    fn report_conflict(&mut self,
+                       borrow: BorrowIndex,
+                       lvalue: &Lvalue<'tcx>,
+                       access: Access,
+                       span: Span) {
+        let (loan_span, loan_kind) = {
+            let data = &self.regions.borrows[borrow];
+            (data.span, data.kind)
+        };
+        let loan_lvalue = self.regions.borrows[borrow].lvalue.clone();
+        let path = self.describe_lvalue(lvalue);
+        let loan_path = self.describe_lvalue(&loan_lvalue);
+
+        match access {
+            Access::Read => {
+                struct_span_err!(self.bcx, span, E0503,
+                                 "cannot use `{}` because it was mutably borrowed",
+                                 path)
+                    .span_label(loan_span,
+                                &format!("borrow of `{}` occurs here", loan_path))
+                    .span_label(span,
+                                &format!("use of borrowed `{}`", loan_path))
+                    .emit();
+            }
+            Access::Move => {
+                struct_span_err!(self.bcx, span, E0505,
+                                 "cannot move out of `{}` because it is borrowed",
+                                 path)
+                    .span_label(loan_span,
+                                &format!("borrow of `{}` occurs here", loan_path))
+                    .span_label(span,
+                                &format!("move out of `{}` occurs here", path))
+                    .emit();
+            }
+            Access::Write => {
+                struct_span_err!(self.bcx, span, E0506,
+                                 "cannot assign to `{}` because it is borrowed",
+                                 path)
+                    .span_label(loan_span,
+                                &format!("borrow of `{}` occurs here", loan_path))
+                    .span_label(span,
+                                &format!("assignment to borrowed `{}` occurs here", path))
+                    .emit();
+            }
+            Access::Borrow(kind) => {
+                let old_path = if *lvalue == loan_lvalue {
+                    None
+                } else {
+                    Some(&loan_path[..])
+                };
+                self.report_borrow_conflict(span, &path, kind,
+                                            loan_span, old_path, loan_kind);
+            }
+            Access::StorageDead | Access::Drop => {
+                if !self.reported_dead.insert(borrow) {
+                    return;
+                }
+                let named = self.is_named(&loan_lvalue);
+                let msg = if named {
+                    format!("`{}` does not live long enough", loan_path)
+                } else {
+                    "borrowed value does not live long enough".to_string()
+                };
+                let mut err = self.bcx.struct_span_err(loan_span, &msg);
+                err.span_label(loan_span, &"borrow occurs here");
+                if named {
+                    err.span_label(span, &format!("`{}` dropped here while still borrowed",
+                                                  path));
+                } else {
+                    err.span_label(span, &"temporary value dropped here while still borrowed");
+                }
+                err.emit();
+            }
+        }
+    }
+
+    fn report_borrow_conflict(&self,
+                              span: Span,
+                              path: &str,
+                              new_kind: mir::BorrowKind,
+                              old_span: Span,
+                              old_path: Option<&str>,
+                              old_kind: mir::BorrowKind) {
+        let mut err = match (new_kind, old_kind) {
+            (mir::BorrowKind::Mut, mir::BorrowKind::Mut) => {
+                let mut err = struct_span_err_message!(self.bcx, span, E0499,
+                                                       "borrowck-mut-borrow-twice",
+                                                       path = path,
+                                                       via = "");
+                err.span_label(old_span, &"first mutable borrow occurs here");
+                err.span_label(span, &"second mutable borrow occurs here");
+                err
+            }
+            (mir::BorrowKind::Unique, mir::BorrowKind::Unique) => {
+                let mut err = struct_span_err!(self.bcx, span, E0524,
+                                               "two closures require unique access to `{}` \
+                                                at the same time",
+                                               path);
+                err.span_label(old_span, &"first closure is constructed here");
+                err.span_label(span, &"second closure is constructed here");
+                err
+            }
+            (mir::BorrowKind::Unique, _) => {
+                let old_pronoun = match old_path {
+                    Some(old_path) => format!("`{}`", old_path),
+                    None => "it".to_string(),
+                };
+                let mut err = struct_span_err!(self.bcx, span, E0500,
+                                               "closure requires unique access to `{}` \
+                                                but {} is already borrowed",
+                                               path, old_pronoun);
+                err.span_label(span, &"closure construction occurs here");
+                err.span_label(old_span, &"borrow occurs here");
+                err
+            }
+            (_, mir::BorrowKind::Unique) => {
+                let mut err = struct_span_err!(self.bcx, span, E0501,
+                                               "cannot borrow `{}` as {} because \
+                                                previous closure requires unique access",
+                                               path, borrow_kind_to_user_str(new_kind));
+                err.span_label(span, &"borrow occurs here");
+                err.span_label(old_span, &"closure construction occurs here");
+                err
+            }
+            (..) => {
+                let mut err = struct_span_err_message!(self.bcx, span, E0502,
+                                                       "borrowck-borrow-conflict",
+                                                       path = path,
+                                                       via = "",
+                                                       new_kind = borrow_kind_message_key(new_kind),
+                                                       same_path = old_path.is_none(),
+                                                       old_path = old_path.unwrap_or(""),
+                                                       old_kind = borrow_kind_message_key(old_kind),
+                                                       old_via = "");
+                err.span_label(span, &format!("{} borrow occurs here",
+                                              borrow_kind_to_user_str(new_kind)));
+                err.span_label(old_span, &format!("{} borrow occurs here",
+                                                  borrow_kind_to_user_str(old_kind)));
+                err
+            }
+        };
+        err.emit();
+    }
+
+    /// Whether `lvalue` is (part of) a user variable, as opposed to a
+    /// temporary.
+    fn is_named(&self, lvalue: &Lvalue<'tcx>) -> bool {
+        match *lvalue {
+            Lvalue::Local(local) => self.mir.local_decls[local].name.is_some(),
+            Lvalue::Static(_) => true,
+            Lvalue::Projection(ref proj) => self.is_named(&proj.base),
+        }
+    }
+
+    fn describe_lvalue(&self, lvalue: &Lvalue<'tcx>) -> String {
+        let mut buf = String::new();
+        self.append_lvalue_to_string(lvalue, &mut buf, false);
+        buf
+    }
+
+    fn append_lvalue_to_string(&self, lvalue: &Lvalue<'tcx>, buf: &mut String, autoderef: bool) {
+        match *lvalue {
+            Lvalue::Local(local) => {
+                match self.mir.local_decls[local].name {
+                    Some(name) => buf.push_str(&name.as_str()),
+                    None => buf.push_str(&format!("{:?}", local)),
+                }
+            }
+            Lvalue::Static(def_id) => {
+                buf.push_str(&self.bcx.tcx.item_path_str(def_id));
+            }
+            Lvalue::Projection(ref proj) => {
+                match proj.elem {
+                    ProjectionElem::Deref => {
+                        if let Some(upvar) = self.upvar_field(&proj.base) {
+                            // Upvars captured by reference are presented as
+                            // the variable itself.
+                            if self.mir.upvar_decls[upvar].by_ref {
+                                buf.push_str(&self.mir.upvar_decls[upvar].debug_name.as_str());
+                                return;
+                            }
+                        }
+                        if !autoderef {
+                            buf.push('*');
+                        }
+                        self.append_lvalue_to_string(&proj.base, buf, autoderef);
+                    }
+                    ProjectionElem::Downcast(..) => {
+                        self.append_lvalue_to_string(&proj.base, buf, autoderef);
+                    }
+                    ProjectionElem::Field(field, _) => {
+                        if let Some(upvar) = self.upvar_field(lvalue) {
+                            buf.push_str(&self.mir.upvar_decls[upvar].debug_name.as_str());
+                            return;
+                        }
+                        self.append_lvalue_to_string(&proj.base, buf, true);
+                        buf.push('.');
+                        buf.push_str(&self.describe_field(&proj.base, field));
+                    }
+                    ProjectionElem::Index(_) |
+                    ProjectionElem::ConstantIndex { .. } |
+                    ProjectionElem::Subslice { .. } => {
+                        self.append_lvalue_to_string(&proj.base, buf, true);
+                        buf.push_str("[..]");
+                    }
+                }
+            }
+        }
+    }
+
+    /// If `lvalue` is the field of a closure environment holding an upvar,
+    /// returns the index of that upvar.
+    fn upvar_field(&self, lvalue: &Lvalue<'tcx>) -> Option<usize> {
+        if let Lvalue::Projection(ref proj) = *lvalue {
+            if let ProjectionElem::Field(field, _) = proj.elem {
+                let tcx = self.bcx.tcx;
+                if let ty::TyClosure(..) = proj.base.ty(self.mir, tcx).to_ty(tcx).sty {
+                    return Some(field.index());
+                }
+            }
+        }
+        None
+    }
+
+    fn describe_field(&self, base: &Lvalue<'tcx>, field: mir::Field) -> String {
+        match base.ty(self.mir, self.bcx.tcx) {
+            LvalueTy::Ty { ty } => {
+                match ty.sty {
+                    ty::TyAdt(def, _) if !def.is_enum() => {
+                        def.struct_variant().fields[field.index()].name.to_string()
+                    }
+                    _ => field.index().to_string(),
+                }
+            }
+            LvalueTy::Downcast { adt_def, variant_index, .. } => {
+                adt_def.variants[variant_index].fields[field.index()].name.to_string()
+            }
+        }
+    }
+}
+
+/// Splits `lvalue` into the local or static it is rooted in and the
+/// projections applied to it, innermost first.
+fn lvalue_components<'c, 'tcx>(lvalue: &'c Lvalue<'tcx>,
+                               projs: &mut Vec<&'c LvalueProjection<'tcx>>)
+                               -> &'c Lvalue<'tcx> {
+    match *lvalue {
+        Lvalue::Projection(ref proj) => {
+            let root = lvalue_components(&proj.base, projs);
+            projs.push(proj);
+            root
+        }
+        _ => lvalue,
+    }
+}
+
+fn borrow_kind_to_user_str(kind: mir::BorrowKind) -> &'static str {
+    match kind {
+        mir::BorrowKind::Shared => "immutable",
+        mir::BorrowKind::Unique => "uniquely immutable",
+        mir::BorrowKind::Mut => "mutable",
+    }
+}
+
+/// The key identifying `kind` to the diagnostic message catalog, matching
+/// `ty::BorrowKind::message_key`.
+fn borrow_kind_message_key(kind: mir::BorrowKind) -> &'static str {
+    match kind {
+        mir::BorrowKind::Shared => "shared",
+        mir::BorrowKind::Unique => "unique",
+        mir::BorrowKind::Mut => "mut",
+    }
+}
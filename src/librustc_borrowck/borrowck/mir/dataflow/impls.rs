@@ -15,7 +15,10 @@ use rustc_data_structures::bitslice::{BitwiseOperator};
 use rustc_data_structures::indexed_set::{IdxSet};
 use rustc_data_structures::indexed_vec::Idx;
 
+use std::marker::PhantomData;
+
 use super::super::gather_moves::{MoveOutIndex, MovePathIndex};
+use super::super::nll::{BorrowIndex, BorrowRegions};
 use super::super::MoveDataParamEnv;
 use super::super::DropFlagState;
 use super::super::drop_flag_effects_for_function_entry;
@@ -191,6 +194,43 @@ pub struct MovingOutStatements<'a, 'tcx: 'a> {
     mir: &'a Mir<'tcx>,
 }
 
+/// `Borrows` tracks the borrows which may still be in effect at a
+/// particular point in the control flow of a function: those which
+/// have been taken on some path reaching that point and whose
+/// reference may still be used, as computed by `BorrowRegions`.
+///
+/// ```rust,ignore
+/// fn foo(v: &mut Vec<u32>) {         // borrows:
+///                                    // {}
+///     let first = &v[0];             // {&v[0]}
+///     let x = *first;                // {}
+///     v.push(x);                     // {&mut *v}
+/// }                                  // {}
+/// ```
+pub struct Borrows<'tcx> {
+    phantom: PhantomData<&'tcx ()>,
+}
+
+impl<'tcx> Borrows<'tcx> {
+    pub fn new() -> Self {
+        Borrows { phantom: PhantomData }
+    }
+
+    fn update_bits(ctxt: &BorrowRegions<'tcx>,
+                   sets: &mut BlockSets<BorrowIndex>,
+                   location: Location)
+    {
+        for borrow in ctxt.borrows.indices() {
+            if !ctxt.is_live_at(borrow, location) {
+                sets.kill(&borrow);
+            }
+        }
+        if let Some(borrow) = ctxt.borrow_at(location) {
+            sets.gen(&borrow);
+        }
+    }
+}
+
 impl<'a, 'tcx> MaybeInitializedLvals<'a, 'tcx> {
     fn update_bits(sets: &mut BlockSets<MovePathIndex>, path: MovePathIndex,
                    state: DropFlagState)
@@ -499,6 +539,47 @@ impl<'a, 'tcx> BitDenotation for MovingOutStatements<'a, 'tcx> {
     }
 }
 
+impl<'tcx> BitDenotation for Borrows<'tcx> {
+    type Idx = BorrowIndex;
+    type Ctxt = BorrowRegions<'tcx>;
+    fn name() -> &'static str { "borrows" }
+    fn bits_per_block(&self, ctxt: &Self::Ctxt) -> usize {
+        ctxt.borrows.len()
+    }
+
+    fn start_block_effect(&self, _ctxt: &Self::Ctxt, _sets: &mut BlockSets<BorrowIndex>) {
+        // no borrows have been taken prior to function execution.
+    }
+
+    fn statement_effect(&self,
+                        ctxt: &Self::Ctxt,
+                        sets: &mut BlockSets<BorrowIndex>,
+                        bb: mir::BasicBlock,
+                        idx: usize)
+    {
+        Self::update_bits(ctxt, sets, Location { block: bb, statement_index: idx })
+    }
+
+    fn terminator_effect(&self,
+                         ctxt: &Self::Ctxt,
+                         sets: &mut BlockSets<BorrowIndex>,
+                         bb: mir::BasicBlock,
+                         statements_len: usize)
+    {
+        Self::update_bits(ctxt, sets, Location { block: bb, statement_index: statements_len })
+    }
+
+    fn propagate_call_return(&self,
+                             _ctxt: &Self::Ctxt,
+                             _in_out: &mut IdxSet<BorrowIndex>,
+                             _call_bb: mir::BasicBlock,
+                             _dest_bb: mir::BasicBlock,
+                             _dest_lval: &mir::Lvalue) {
+        // calls do not take or release borrows; whatever the
+        // destination holds is accounted for by `BorrowRegions`.
+    }
+}
+
 fn zero_to_one(bitvec: &mut [usize], move_index: MoveOutIndex) {
     let retval = bitvec.set_bit(move_index.index());
     assert!(retval);
@@ -511,6 +592,13 @@ impl<'a, 'tcx> BitwiseOperator for MovingOutStatements<'a, 'tcx> {
     }
 }
 
+impl<'tcx> BitwiseOperator for Borrows<'tcx> {
+    #[inline]
+    fn join(&self, pred1: usize, pred2: usize) -> usize {
+        pred1 | pred2 // borrows from either pred may still be in effect
+    }
+}
+
 impl<'a, 'tcx> BitwiseOperator for MaybeInitializedLvals<'a, 'tcx> {
     #[inline]
     fn join(&self, pred1: usize, pred2: usize) -> usize {
@@ -549,6 +637,13 @@ impl<'a, 'tcx> DataflowOperator for MovingOutStatements<'a, 'tcx> {
     }
 }
 
+impl<'tcx> DataflowOperator for Borrows<'tcx> {
+    #[inline]
+    fn bottom_value() -> bool {
+        false // bottom = no borrows in effect
+    }
+}
+
 impl<'a, 'tcx> DataflowOperator for MaybeInitializedLvals<'a, 'tcx> {
     #[inline]
     fn bottom_value() -> bool {
@@ -24,6 +24,7 @@ use rustc::middle::lang_items;
 use rustc::util::nodemap::FxHashMap;
 use rustc_data_structures::indexed_set::IdxSetBuf;
 use rustc_data_structures::indexed_vec::Idx;
+use rustc_mir::graphviz::DataflowRegistry;
 use syntax_pos::Span;
 
 use std::fmt;
@@ -50,12 +51,14 @@ impl<'tcx> MirPass<'tcx> for ElaborateDrops {
                 move_data: move_data,
                 param_env: param_env
             };
+            let mut registry = DataflowRegistry::new(tcx, "elaborate_drops", id);
             let flow_inits =
-                super::do_dataflow(tcx, mir, id, &[], &env,
+                super::do_dataflow(tcx, mir, id, &[], &mut registry, &env,
                                    MaybeInitializedLvals::new(tcx, mir));
             let flow_uninits =
-                super::do_dataflow(tcx, mir, id, &[], &env,
+                super::do_dataflow(tcx, mir, id, &[], &mut registry, &env,
                                    MaybeUninitializedLvals::new(tcx, mir));
+            registry.dump(tcx, mir);
 
             ElaborateDropsCtxt {
                 tcx: tcx,
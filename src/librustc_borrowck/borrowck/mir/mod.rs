@@ -17,14 +17,20 @@ use syntax_pos::{Span, DUMMY_SP};
 use rustc::hir;
 use rustc::hir::intravisit::{FnKind};
 
-use rustc::mir::{self, BasicBlock, BasicBlockData, Mir, Statement, Terminator, Location};
+use rustc::mir::{self, Mir, Location};
 use rustc::session::Session;
 use rustc::ty::{self, TyCtxt};
+use rustc::util::nodemap::FxHashSet;
+use rustc_mir::graphviz::DataflowRegistry;
+
+use std::fmt::Debug;
 
 mod abs_domain;
+mod check_borrows;
 pub mod elaborate_drops;
 mod dataflow;
 mod gather_moves;
+mod nll;
 mod patch;
 // mod graphviz;
 
@@ -32,8 +38,9 @@ use self::dataflow::{BitDenotation};
 use self::dataflow::{DataflowOperator};
 use self::dataflow::{Dataflow, DataflowAnalysis, DataflowResults};
 use self::dataflow::{MaybeInitializedLvals, MaybeUninitializedLvals};
-use self::dataflow::{DefinitelyInitializedLvals};
+use self::dataflow::{DefinitelyInitializedLvals, Borrows};
 use self::gather_moves::{MoveData, MovePathIndex, LookupResult};
+use self::nll::{BorrowIndex, BorrowRegions};
 
 fn has_rustc_mir_with(attrs: &[ast::Attribute], name: &str) -> Option<P<MetaItem>> {
     for attr in attrs {
@@ -79,12 +86,13 @@ pub fn borrowck_mir(bcx: &mut BorrowckCtxt,
 
     let move_data = MoveData::gather_moves(mir, tcx, &param_env);
     let mdpe = MoveDataParamEnv { move_data: move_data, param_env: param_env };
-    let flow_inits =
-        do_dataflow(tcx, mir, id, attributes, &mdpe, MaybeInitializedLvals::new(tcx, mir));
-    let flow_uninits =
-        do_dataflow(tcx, mir, id, attributes, &mdpe, MaybeUninitializedLvals::new(tcx, mir));
-    let flow_def_inits =
-        do_dataflow(tcx, mir, id, attributes, &mdpe, DefinitelyInitializedLvals::new(tcx, mir));
+    let mut registry = DataflowRegistry::new(tcx, "borrowck", id);
+    let flow_inits = do_dataflow(tcx, mir, id, attributes, &mut registry, &mdpe,
+                                 MaybeInitializedLvals::new(tcx, mir));
+    let flow_uninits = do_dataflow(tcx, mir, id, attributes, &mut registry, &mdpe,
+                                   MaybeUninitializedLvals::new(tcx, mir));
+    let flow_def_inits = do_dataflow(tcx, mir, id, attributes, &mut registry, &mdpe,
+                                     DefinitelyInitializedLvals::new(tcx, mir));
 
     if has_rustc_mir_with(attributes, "rustc_peek_maybe_init").is_some() {
         dataflow::sanity_check_via_rustc_peek(bcx.tcx, mir, id, attributes, &mdpe, &flow_inits);
@@ -100,18 +108,34 @@ pub fn borrowck_mir(bcx: &mut BorrowckCtxt,
         bcx.tcx.sess.fatal("stop_after_dataflow ended compilation");
     }
 
+    if !tcx.sess.opts.debugging_opts.nll && !registry.is_enabled() {
+        return;
+    }
+
+    let regions = BorrowRegions::compute(bcx, mir, &mdpe, &flow_inits);
+    registry.register(|| regions.liveness_sets(mir));
+    let flow_borrows = do_dataflow_with(tcx, mir, id, attributes, &mut registry, &regions,
+                                        Borrows::new(), |regions, i| &regions.borrows[i]);
+    registry.dump(tcx, mir);
+
+    if !tcx.sess.opts.debugging_opts.nll {
+        return;
+    }
+
     let mut mbcx = MirBorrowckCtxt {
         bcx: bcx,
         mir: mir,
         node_id: id,
         move_data: mdpe.move_data,
+        param_env: mdpe.param_env,
         flow_inits: flow_inits,
         flow_uninits: flow_uninits,
+        regions: regions,
+        flow_borrows: flow_borrows,
+        reported_dead: FxHashSet(),
     };
 
-    for bb in mir.basic_blocks().indices() {
-        mbcx.process_basic_block(bb);
-    }
+    mbcx.check_borrows();
 
     debug!("borrowck_mir done");
 }
@@ -120,9 +144,27 @@ fn do_dataflow<'a, 'tcx, BD>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                              mir: &Mir<'tcx>,
                              node_id: ast::NodeId,
                              attributes: &[ast::Attribute],
+                             registry: &mut DataflowRegistry,
                              ctxt: &BD::Ctxt,
                              bd: BD) -> DataflowResults<BD>
     where BD: BitDenotation<Idx=MovePathIndex, Ctxt=MoveDataParamEnv<'tcx>> + DataflowOperator
+{
+    do_dataflow_with(tcx, mir, node_id, attributes, registry, ctxt, bd,
+                     |ctxt, i| &ctxt.move_data.move_paths[i])
+}
+
+/// Runs the dataflow analysis `bd` over `mir`, and registers its results
+/// with `registry`, using `p` to render its elements.
+fn do_dataflow_with<'a, 'tcx, BD, P>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     mir: &Mir<'tcx>,
+                                     node_id: ast::NodeId,
+                                     attributes: &[ast::Attribute],
+                                     registry: &mut DataflowRegistry,
+                                     ctxt: &BD::Ctxt,
+                                     bd: BD,
+                                     p: P) -> DataflowResults<BD>
+    where BD: BitDenotation + DataflowOperator,
+          P: for<'b> Fn(&'b BD::Ctxt, BD::Idx) -> &'b Debug
 {
     let name_found = |sess: &Session, attrs: &[ast::Attribute], name| -> Option<String> {
         if let Some(item) = has_rustc_mir_with(attrs, name) {
@@ -144,20 +186,24 @@ fn do_dataflow<'a, 'tcx, BD>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         name_found(tcx.sess, attributes, "borrowck_graphviz_postflow");
 
     let mut mbcx = MirBorrowckCtxtPreDataflow {
+        tcx: tcx,
         node_id: node_id,
         print_preflow_to: print_preflow_to,
         print_postflow_to: print_postflow_to,
         flow_state: DataflowAnalysis::new(tcx, mir, ctxt, bd),
     };
 
-    mbcx.dataflow(|ctxt, i| &ctxt.move_data.move_paths[i]);
-    mbcx.flow_state.results()
+    mbcx.dataflow(&p);
+    let results = mbcx.flow_state.results();
+    registry.register(|| results.block_sets(ctxt, mir, &p));
+    results
 }
 
 
 pub struct MirBorrowckCtxtPreDataflow<'a, 'tcx: 'a, BD>
     where BD: BitDenotation, BD::Ctxt: 'a
 {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
     node_id: ast::NodeId,
     flow_state: DataflowAnalysis<'a, 'tcx, BD>,
     print_preflow_to: Option<String>,
@@ -170,28 +216,14 @@ pub struct MirBorrowckCtxt<'b, 'a: 'b, 'tcx: 'a> {
     mir: &'b Mir<'tcx>,
     node_id: ast::NodeId,
     move_data: MoveData<'tcx>,
+    param_env: ty::ParameterEnvironment<'tcx>,
     flow_inits: DataflowResults<MaybeInitializedLvals<'b, 'tcx>>,
-    flow_uninits: DataflowResults<MaybeUninitializedLvals<'b, 'tcx>>
-}
-
-impl<'b, 'a: 'b, 'tcx: 'a> MirBorrowckCtxt<'b, 'a, 'tcx> {
-    fn process_basic_block(&mut self, bb: BasicBlock) {
-        let BasicBlockData { ref statements, ref terminator, is_cleanup: _ } =
-            self.mir[bb];
-        for stmt in statements {
-            self.process_statement(bb, stmt);
-        }
-
-        self.process_terminator(bb, terminator);
-    }
-
-    fn process_statement(&mut self, bb: BasicBlock, stmt: &Statement<'tcx>) {
-        debug!("MirBorrowckCtxt::process_statement({:?}, {:?}", bb, stmt);
-    }
-
-    fn process_terminator(&mut self, bb: BasicBlock, term: &Option<Terminator<'tcx>>) {
-        debug!("MirBorrowckCtxt::process_terminator({:?}, {:?})", bb, term);
-    }
+    flow_uninits: DataflowResults<MaybeUninitializedLvals<'b, 'tcx>>,
+    regions: BorrowRegions<'tcx>,
+    flow_borrows: DataflowResults<Borrows<'tcx>>,
+    /// Borrows already reported as outliving what they borrow, so that a
+    /// drop followed by `StorageDead` is only reported once.
+    reported_dead: FxHashSet<BorrowIndex>,
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -0,0 +1,480 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Non-lexical lifetimes for the borrows of a MIR body.
+//!
+//! The AST borrow checker keeps each borrow alive for the lexical region
+//! that region inference picked for it. Here a borrow instead lasts for as
+//! long as the reference it creates may still be used: we find every local
+//! which may end up holding that reference (or anything derived from it),
+//! and consider the borrow live at the points where one of those locals is
+//! live.
+//!
+//! Which locals may hold a borrow is worked out without regard to control
+//! flow, by following assignments, reborrows and calls. The result of a call
+//! may hold anything passed to it, and each argument of a call may be
+//! stored into what any argument, itself included, points to. A borrow only
+//! ever flows into a local whose type mentions a region that the (lexical)
+//! region of the borrow outlives, which keeps e.g. the borrow of a key
+//! passed to a map lookup out of the value returned by it.
+//!
+//! The return pointer and the arguments are used when the function returns
+//! or unwinds, so anything which escapes to the caller through them stays
+//! borrowed until then.
+
+use borrowck::BorrowckCtxt;
+
+use rustc::mir::{self, BasicBlock, Local, Location, Lvalue, Mir, Operand};
+use rustc::mir::{ProjectionElem, Rvalue, StatementKind, TerminatorKind};
+use rustc::ty::{self, TyCtxt};
+use rustc::ty::fold::{TypeFoldable, TypeVisitor};
+use rustc::util::nodemap::FxHashMap;
+use rustc_data_structures::indexed_set::{IdxSet, IdxSetBuf};
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
+use rustc_mir::graphviz::DataflowSets;
+use rustc_mir::liveness::LocalLiveness;
+use syntax_pos::Span;
+
+use std::fmt;
+
+use super::{DropFlagState, MoveDataParamEnv};
+use super::{drop_flag_effects_for_location, on_all_children_bits};
+use super::dataflow::{DataflowResults, MaybeInitializedLvals};
+use super::gather_moves::LookupResult;
+
+/// Index into `BorrowRegions::borrows`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BorrowIndex(usize);
+
+impl Idx for BorrowIndex {
+    fn new(idx: usize) -> Self { BorrowIndex(idx) }
+    fn index(self) -> usize { self.0 }
+}
+
+/// A borrow, i.e. an `Rvalue::Ref` in the MIR.
+pub struct BorrowData<'tcx> {
+    pub location: Location,
+    pub kind: mir::BorrowKind,
+    /// The region given to the borrow by region inference.
+    pub region: &'tcx ty::Region,
+    pub lvalue: Lvalue<'tcx>,
+    pub span: Span,
+}
+
+impl<'tcx> fmt::Debug for BorrowData<'tcx> {
+    fn fmt(&self, w: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            mir::BorrowKind::Shared => "",
+            mir::BorrowKind::Unique => "uniq ",
+            mir::BorrowKind::Mut => "mut ",
+        };
+        write!(w, "&{}{:?}", kind, self.lvalue)
+    }
+}
+
+pub struct BorrowRegions<'tcx> {
+    pub borrows: IndexVec<BorrowIndex, BorrowData<'tcx>>,
+    location_map: FxHashMap<Location, BorrowIndex>,
+    /// For each borrow, the locals which may hold the reference it creates.
+    holders: IndexVec<BorrowIndex, IdxSetBuf<Local>>,
+    /// For each statement and terminator, the locals live on entry to it.
+    live: IndexVec<BasicBlock, Vec<IdxSetBuf<Local>>>,
+    /// Blocks ending in a drop of a value which may be initialized.
+    live_drops: IdxSetBuf<BasicBlock>,
+}
+
+impl<'tcx> BorrowRegions<'tcx> {
+    pub fn compute<'a, 'b>(bcx: &BorrowckCtxt<'a, 'tcx>,
+                           mir: &Mir<'tcx>,
+                           env: &MoveDataParamEnv<'tcx>,
+                           flow_inits: &DataflowResults<MaybeInitializedLvals<'b, 'tcx>>)
+                           -> BorrowRegions<'tcx>
+    {
+        let mut borrows = IndexVec::new();
+        let mut location_map = FxHashMap();
+        for (block, data) in mir.basic_blocks().iter_enumerated() {
+            for (statement_index, statement) in data.statements.iter().enumerate() {
+                if let StatementKind::Assign(_, Rvalue::Ref(region, kind, ref lvalue)) =
+                    statement.kind
+                {
+                    let location = Location { block: block, statement_index: statement_index };
+                    let borrow = borrows.push(BorrowData {
+                        location: location,
+                        kind: kind,
+                        region: region,
+                        lvalue: lvalue.clone(),
+                        span: statement.source_info.span,
+                    });
+                    location_map.insert(location, borrow);
+                }
+            }
+        }
+
+        let live_drops = live_drops(bcx.tcx, mir, env, flow_inits);
+        let holders = compute_holders(bcx, mir, &borrows);
+        let liveness = LocalLiveness::compute(mir, |block| live_drops.contains(&block));
+        let live = mir.basic_blocks().indices().map(|block| {
+            liveness.live_on_entry_to_each(mir, block)
+        }).collect();
+
+        BorrowRegions {
+            borrows: borrows,
+            location_map: location_map,
+            holders: holders,
+            live: live,
+            live_drops: live_drops,
+        }
+    }
+
+    /// Returns the borrow created by the statement at `location`, if any.
+    pub fn borrow_at(&self, location: Location) -> Option<BorrowIndex> {
+        self.location_map.get(&location).cloned()
+    }
+
+    /// Whether the reference created by `borrow` may still be used on entry
+    /// to `location`.
+    pub fn is_live_at(&self, borrow: BorrowIndex, location: Location) -> bool {
+        let live = &self.live[location.block][location.statement_index];
+        intersects(&self.holders[borrow], live)
+    }
+
+    /// Removes from `borrows` those which are dead on entry to `location`.
+    pub fn kill_dead_borrows(&self, borrows: &mut IdxSet<BorrowIndex>, location: Location) {
+        for borrow in self.borrows.indices() {
+            if !self.is_live_at(borrow, location) {
+                borrows.remove(&borrow);
+            }
+        }
+    }
+
+    /// Renders the locals live on entry to and on exit from each block, for
+    /// `-Z dump-mir-dataflow`.
+    pub fn liveness_sets(&self, mir: &Mir<'tcx>) -> DataflowSets {
+        let render = |live: &IdxSet<Local>| {
+            mir.local_decls.indices()
+                .filter(|local| live.contains(local))
+                .map(|local| format!("{:?}", Lvalue::Local(local)))
+                .collect::<Vec<_>>()
+        };
+
+        let mut sets = DataflowSets {
+            name: "liveness",
+            on_entry: IndexVec::with_capacity(mir.basic_blocks().len()),
+            on_exit: IndexVec::with_capacity(mir.basic_blocks().len()),
+            gen_kill: None,
+        };
+        for (block, data) in mir.basic_blocks().iter_enumerated() {
+            let mut exit = IdxSetBuf::new_empty(mir.local_decls.len());
+            for succ in data.terminator().successors().iter() {
+                exit.union(&self.live[*succ][0]);
+            }
+            sets.on_entry.push(render(&self.live[block][0]));
+            sets.on_exit.push(render(&exit));
+        }
+        sets
+    }
+
+    /// Whether the drop terminating `block` may drop anything.
+    pub fn drops_value(&self, block: BasicBlock) -> bool {
+        self.live_drops.contains(&block)
+    }
+}
+
+fn intersects<T: Idx>(a: &IdxSet<T>, b: &IdxSet<T>) -> bool {
+    a.words().iter().zip(b.words()).any(|(a, b)| a & b != 0)
+}
+
+/// Whether `lvalue` is reached through a pointer.
+fn is_indirect<'tcx>(lvalue: &Lvalue<'tcx>) -> bool {
+    match *lvalue {
+        Lvalue::Local(_) | Lvalue::Static(_) => false,
+        Lvalue::Projection(ref proj) => {
+            proj.elem == ProjectionElem::Deref || is_indirect(&proj.base)
+        }
+    }
+}
+
+/// Finds the blocks ending in a drop which may actually drop something:
+/// dropping a value which is definitely uninitialized does nothing, so it
+/// does not use the local the value lives in.
+fn live_drops<'a, 'b, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                            mir: &Mir<'tcx>,
+                            env: &MoveDataParamEnv<'tcx>,
+                            flow_inits: &DataflowResults<MaybeInitializedLvals<'b, 'tcx>>)
+                            -> IdxSetBuf<BasicBlock>
+{
+    let mut live_drops = IdxSetBuf::new_empty(mir.basic_blocks().len());
+    for (block, data) in mir.basic_blocks().iter_enumerated() {
+        let location = match data.terminator().kind {
+            TerminatorKind::Drop { ref location, .. } |
+            TerminatorKind::DropAndReplace { ref location, .. } => location,
+            _ => continue
+        };
+
+        let mut inits = flow_inits.sets().on_entry_set_for(block.index()).to_owned();
+        for statement_index in 0..data.statements.len() {
+            let loc = Location { block: block, statement_index: statement_index };
+            drop_flag_effects_for_location(tcx, mir, env, loc, |path, state| {
+                match state {
+                    DropFlagState::Present => { inits.add(&path); }
+                    DropFlagState::Absent => { inits.remove(&path); }
+                }
+            });
+        }
+
+        let maybe_init = match env.move_data.rev_lookup.find(location) {
+            LookupResult::Exact(path) => {
+                let mut maybe_init = false;
+                on_all_children_bits(tcx, mir, &env.move_data, path, |child| {
+                    if inits.contains(&child) {
+                        maybe_init = true;
+                    }
+                });
+                maybe_init
+            }
+            LookupResult::Parent(_) => true,
+        };
+        if maybe_init {
+            live_drops.add(&block);
+        }
+    }
+    live_drops
+}
+
+/// Whether a value of some type may hold a reference whose region is
+/// `region`, i.e. whether the type mentions a region which `region`
+/// outlives.
+struct MayHoldRegion<'b, 'a: 'b, 'tcx: 'a> {
+    bcx: &'b BorrowckCtxt<'a, 'tcx>,
+    region: &'tcx ty::Region,
+}
+
+impl<'b, 'a, 'tcx> TypeVisitor<'tcx> for MayHoldRegion<'b, 'a, 'tcx> {
+    fn visit_region(&mut self, r: &'tcx ty::Region) -> bool {
+        match (r, self.region) {
+            (&ty::ReLateBound(..), _) => false,
+            (&ty::ReScope(_), &ty::ReScope(_)) |
+            (&ty::ReScope(_), &ty::ReFree(_)) |
+            (&ty::ReScope(_), &ty::ReStatic) |
+            (&ty::ReFree(_), &ty::ReScope(_)) |
+            (&ty::ReFree(_), &ty::ReFree(_)) |
+            (&ty::ReFree(_), &ty::ReStatic) |
+            (&ty::ReStatic, _) |
+            (&ty::ReEmpty, _) => self.bcx.is_subregion_of(r, self.region),
+            // We cannot relate any other regions, so stay conservative.
+            _ => true,
+        }
+    }
+}
+
+/// The ways a value can move from one local to another, regardless of
+/// control flow.
+#[derive(Default)]
+struct Flows {
+    /// `(source, target)`: the target may hold what the source holds.
+    into: Vec<(Local, Local)>,
+    /// `(source, target)`: what the source holds may be stored into memory
+    /// reached through the target.
+    through: Vec<(Local, Local)>,
+}
+
+impl Flows {
+    fn store<'tcx>(&mut self, source: Local, target: &Lvalue<'tcx>) {
+        if let Some(target_local) = target.base_local() {
+            if is_indirect(target) {
+                self.through.push((source, target_local));
+            } else {
+                self.into.push((source, target_local));
+            }
+        }
+    }
+
+    fn operand_sources<'tcx>(operand: &Operand<'tcx>, sources: &mut Vec<Local>) {
+        if let Operand::Consume(ref lvalue) = *operand {
+            sources.extend(lvalue.base_local());
+        }
+    }
+
+    fn rvalue_sources<'tcx>(rvalue: &Rvalue<'tcx>, sources: &mut Vec<Local>) {
+        match *rvalue {
+            Rvalue::Use(ref op) |
+            Rvalue::Repeat(ref op, _) |
+            Rvalue::Cast(_, ref op, _) |
+            Rvalue::UnaryOp(_, ref op) => Flows::operand_sources(op, sources),
+            Rvalue::BinaryOp(_, ref lhs, ref rhs) |
+            Rvalue::CheckedBinaryOp(_, ref lhs, ref rhs) => {
+                Flows::operand_sources(lhs, sources);
+                Flows::operand_sources(rhs, sources);
+            }
+            // Reborrowing (or borrowing something which holds references)
+            // gives access to whatever the borrowed lvalue holds.
+            Rvalue::Ref(_, _, ref lvalue) => sources.extend(lvalue.base_local()),
+            Rvalue::Aggregate(_, ref ops) => {
+                for op in ops {
+                    Flows::operand_sources(op, sources);
+                }
+            }
+            Rvalue::InlineAsm { ref inputs, .. } => {
+                for op in inputs {
+                    Flows::operand_sources(op, sources);
+                }
+            }
+            Rvalue::Len(_) | Rvalue::Box(_) => {}
+        }
+    }
+
+    fn collect<'tcx>(mir: &Mir<'tcx>) -> Flows {
+        let mut flows = Flows::default();
+        let mut sources = vec![];
+        for data in mir.basic_blocks() {
+            for statement in &data.statements {
+                if let StatementKind::Assign(ref lvalue, ref rvalue) = statement.kind {
+                    sources.clear();
+                    Flows::rvalue_sources(rvalue, &mut sources);
+                    for &source in &sources {
+                        flows.store(source, lvalue);
+                    }
+                    if let Rvalue::InlineAsm { ref outputs, .. } = *rvalue {
+                        for output in outputs {
+                            for &source in &sources {
+                                flows.store(source, output);
+                            }
+                        }
+                    }
+                }
+            }
+
+            match data.terminator().kind {
+                TerminatorKind::Call { ref func, ref args, ref destination, .. } => {
+                    sources.clear();
+                    Flows::operand_sources(func, &mut sources);
+                    for arg in args {
+                        Flows::operand_sources(arg, &mut sources);
+                    }
+                    // The callee may store what any argument holds through
+                    // any of them, including through the argument itself,
+                    // e.g. a closure holding both a shared and a mutable
+                    // reference can store the former through the latter.
+                    for &source in &sources {
+                        if let Some((ref dest, _)) = *destination {
+                            flows.store(source, dest);
+                        }
+                        for &target in &sources {
+                            flows.through.push((source, target));
+                        }
+                    }
+                }
+                TerminatorKind::DropAndReplace { ref location, ref value, .. } => {
+                    sources.clear();
+                    Flows::operand_sources(value, &mut sources);
+                    for &source in &sources {
+                        flows.store(source, location);
+                    }
+                }
+                _ => {}
+            }
+        }
+        flows
+    }
+}
+
+/// Computes the locals which may hold each borrow.
+fn compute_holders<'a, 'tcx>(bcx: &BorrowckCtxt<'a, 'tcx>,
+                             mir: &Mir<'tcx>,
+                             borrows: &IndexVec<BorrowIndex, BorrowData<'tcx>>)
+                             -> IndexVec<BorrowIndex, IdxSetBuf<Local>>
+{
+    let num_locals = mir.local_decls.len();
+
+    // The locals whose type allows them to hold each borrow. Many borrows
+    // share their region, so compute this once per region.
+    let mut by_region = FxHashMap();
+    let allowed: IndexVec<BorrowIndex, IdxSetBuf<Local>> = borrows.iter().map(|borrow| {
+        by_region.entry(borrow.region).or_insert_with(|| {
+            let mut allowed = IdxSetBuf::new_empty(num_locals);
+            for (local, decl) in mir.local_decls.iter_enumerated() {
+                let mut visitor = MayHoldRegion { bcx: bcx, region: borrow.region };
+                if decl.ty.visit_with(&mut visitor) {
+                    allowed.add(&local);
+                }
+            }
+            allowed
+        }).clone()
+    }).collect();
+
+    let mut holders: IndexVec<BorrowIndex, IdxSetBuf<Local>> =
+        IndexVec::from_elem_n(IdxSetBuf::new_empty(num_locals), borrows.len());
+    // The locals through which each borrow may have been stored.
+    let mut stored_through = holders.clone();
+
+    for (borrow, data) in borrows.iter_enumerated() {
+        let statement = &mir[data.location.block].statements[data.location.statement_index];
+        if let StatementKind::Assign(ref dest, _) = statement.kind {
+            if let Some(dest_local) = dest.base_local() {
+                if is_indirect(dest) {
+                    stored_through[borrow].add(&dest_local);
+                } else {
+                    holders[borrow].add(&dest_local);
+                }
+            }
+        }
+    }
+
+    let flows = Flows::collect(mir);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for borrow in borrows.indices() {
+            for &(source, target) in &flows.into {
+                if holders[borrow].contains(&source) && allowed[borrow].contains(&target) {
+                    changed |= holders[borrow].add(&target);
+                }
+            }
+            for &(source, target) in &flows.through {
+                if holders[borrow].contains(&source) && allowed[borrow].contains(&target) {
+                    changed |= stored_through[borrow].add(&target);
+                }
+            }
+
+            // Whatever was stored through a local can be reached through it,
+            // and ends up held by whatever the local borrowed.
+            for local in mir.local_decls.indices() {
+                if !stored_through[borrow].contains(&local) {
+                    continue;
+                }
+                changed |= holders[borrow].add(&local);
+                for other in borrows.indices() {
+                    // A reference can't be stored into what it borrows.
+                    if other == borrow || !holders[other].contains(&local) {
+                        continue;
+                    }
+                    let lvalue = &borrows[other].lvalue;
+                    if let Some(target) = lvalue.base_local() {
+                        if !allowed[borrow].contains(&target) {
+                            continue;
+                        }
+                        if is_indirect(lvalue) {
+                            changed |= stored_through[borrow].add(&target);
+                        } else {
+                            changed |= holders[borrow].add(&target);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    for (borrow, data) in borrows.iter_enumerated() {
+        debug!("compute_holders: {:?} {:?} held by {:?}",
+               borrow, data, holders[borrow]);
+    }
+
+    holders
+}
@@ -163,7 +163,9 @@ fn borrowck_fn(this: &mut BorrowckCtxt,
                attributes: &[ast::Attribute]) {
     debug!("borrowck_fn(id={})", id);
 
-    if attributes.iter().any(|item| item.check_name("rustc_mir_borrowck")) {
+    if this.tcx.sess.opts.debugging_opts.nll ||
+       this.tcx.sess.opts.debugging_opts.dump_mir_dataflow.is_some() ||
+       attributes.iter().any(|item| item.check_name("rustc_mir_borrowck")) {
         this.with_temp_region_map(id, |this| {
             mir::borrowck_mir(this, fk, decl, body, sp, id, attributes)
         });
@@ -673,13 +675,13 @@ impl<'a, 'tcx> BorrowckCtxt<'a, 'tcx> {
                     };
 
                 let partial = moved_lp.depth() > lp.depth();
-                let msg = if !has_fork && partial { "partially " }
-                          else if has_fork && !has_common { "collaterally "}
-                          else { "" };
-                let err = struct_span_err!(
+                let moved = if !has_fork && partial { "partially" }
+                            else if has_fork && !has_common { "collaterally" }
+                            else { "fully" };
+                let err = struct_span_err_message!(
                     self.tcx.sess, use_span, E0382,
-                    "{} of {}moved value: `{}`",
-                    verb, msg, nl);
+                    "borrowck-use-moved-value",
+                    use_kind = verb, moved = moved, path = nl);
                 (ol, moved_lp_msg, err)}
         };
 
@@ -745,10 +747,10 @@ impl<'a, 'tcx> BorrowckCtxt<'a, 'tcx> {
                                                 lp: &LoanPath<'tcx>,
                                                 assign:
                                                 &move_data::Assignment) {
-        let mut err = struct_span_err!(
+        let mut err = struct_span_err_message!(
             self.tcx.sess, span, E0384,
-            "re-assignment of immutable variable `{}`",
-            self.loan_path_to_string(lp));
+            "borrowck-reassign-immutable",
+            path = self.loan_path_to_string(lp));
         err.span_label(span, &format!("re-assignment of immutable variable"));
         if span != assign.span {
             err.span_label(assign.span, &format!("first assignment to `{}`",
@@ -766,6 +768,10 @@ impl<'a, 'tcx> BorrowckCtxt<'a, 'tcx> {
         self.tcx.sess.struct_span_err(s, m)
     }
 
+    pub fn diagnostic_message(&self, id: &str, args: &[(&str, &fmt::Display)]) -> String {
+        self.tcx.sess.diagnostic_message(id, args)
+    }
+
     pub fn struct_span_err_with_code<S: Into<MultiSpan>>(&self,
                                                          s: S,
                                                          msg: &str,
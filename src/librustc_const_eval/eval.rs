@@ -26,6 +26,7 @@ use rustc::ty::{self, Ty, TyCtxt};
 use rustc::ty::util::IntTypeExt;
 use rustc::ty::subst::Substs;
 use rustc::traits::Reveal;
+use interpret;
 use rustc::util::common::ErrorReported;
 use rustc::util::nodemap::DefIdMap;
 use rustc::lint;
@@ -34,7 +35,7 @@ use graphviz::IntoCow;
 use syntax::ast;
 use rustc::hir::{Expr, PatKind};
 use rustc::hir;
-use rustc::hir::intravisit::FnKind;
+use rustc::hir::intravisit::{self, FnKind, Visitor};
 use syntax::ptr::P;
 use syntax::codemap;
 use syntax::attr::IntType;
@@ -241,6 +242,38 @@ pub fn lookup_const_fn_by_id<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefI
     }
 }
 
+/// Whether the body of a `const fn` uses `let` bindings, assignments,
+/// branches or loops, which only the MIR interpreter can evaluate.
+fn has_control_flow(body: &hir::Expr) -> bool {
+    struct ControlFlowFinder {
+        found: bool,
+    }
+
+    impl<'v> Visitor<'v> for ControlFlowFinder {
+        fn visit_local(&mut self, local: &'v hir::Local) {
+            self.found = true;
+            intravisit::walk_local(self, local);
+        }
+
+        fn visit_expr(&mut self, expr: &'v Expr) {
+            match expr.node {
+                hir::ExprIf(..) |
+                hir::ExprWhile(..) |
+                hir::ExprLoop(..) |
+                hir::ExprMatch(..) |
+                hir::ExprAssign(..) |
+                hir::ExprAssignOp(..) => self.found = true,
+                _ => {}
+            }
+            intravisit::walk_expr(self, expr);
+        }
+    }
+
+    let mut finder = ControlFlowFinder { found: false };
+    finder.visit_expr(body);
+    finder.found
+}
+
 pub fn const_expr_to_pat<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                    expr: &Expr,
                                    pat_id: ast::NodeId,
@@ -462,6 +495,10 @@ pub enum ErrKind {
     BadType(ConstVal),
     ErroneousReferencedConstant(Box<ConstEvalErr>),
     CharCast(ConstInt),
+
+    /// An error raised while interpreting the MIR of a constant or a
+    /// `const fn`.
+    Interpreter(interpret::EvalErrorKind),
 }
 
 impl From<ConstMathErr> for ErrKind {
@@ -485,6 +522,22 @@ impl<'a> ConstEvalErrDescription<'a> {
 }
 
 impl ConstEvalErr {
+    /// Converts an error raised by the MIR interpreter, which is reported
+    /// at `span` unless it points somewhere more precise.
+    pub fn from_interpreter(err: interpret::EvalError, span: Span) -> ConstEvalErr {
+        let kind = match err.kind {
+            interpret::EvalErrorKind::Math(math) => Math(math),
+            interpret::EvalErrorKind::IndexOutOfBounds { len, index } => {
+                IndexOutOfBounds { len: len, index: index }
+            }
+            kind => Interpreter(kind),
+        };
+        ConstEvalErr {
+            span: err.span.unwrap_or(span),
+            kind: kind,
+        }
+    }
+
     pub fn description(&self) -> ConstEvalErrDescription {
         use self::ErrKind::*;
         use self::ConstEvalErrDescription::*;
@@ -544,6 +597,7 @@ impl ConstEvalErr {
             CharCast(ref got) => {
                 simple!("only `u8` can be cast as `char`, not `{}`", got.description())
             },
+            Interpreter(ref err) => simple!("{}", err),
         }
     }
 }
@@ -598,6 +652,11 @@ macro_rules! signal {
 /// guaranteed to be evaluatable. `ty_hint` is usually ExprTypeChecked,
 /// but a few places need to evaluate constants during type-checking, like
 /// computing the length of an array. (See also the FIXME above EvalHint.)
+///
+/// The expression itself is folded here, on HIR. Only the values of the
+/// constants it refers to, and the results of the `const fn`s it calls once
+/// type-checking is done, come from the MIR interpreter in `interpret`; the
+/// HIR is used for those as well when their MIR can't be evaluated.
 pub fn eval_const_expr_partial<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                          e: &Expr,
                                          ty_hint: EvalHint<'tcx>,
@@ -804,7 +863,26 @@ pub fn eval_const_expr_partial<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                   } else {
                       None
                   };
-                  if let Some((expr, ty)) = lookup_const_by_id(tcx, def_id, substs) {
+                  // Constants outside of traits don't depend on their use
+                  // site, so they can be interpreted before type-checking.
+                  let mir_substs = match (resolution.base_def, substs) {
+                      (_, Some(substs)) => Some(substs),
+                      (Def::Const(_), None) => Some(tcx.intern_substs(&[])),
+                      _ => None,
+                  };
+                  let mir_result = mir_substs.and_then(|substs| {
+                      interpret::eval_const(tcx, def_id, substs)
+                  });
+                  if let Some(result) = mir_result {
+                      match result {
+                          Ok(val) => val,
+                          Err(err) => {
+                              let err = ConstEvalErr::from_interpreter(err, e.span);
+                              debug!("bad reference: {:?}, {:?}", err.description(), err.span);
+                              signal!(e, ErroneousReferencedConstant(box err))
+                          },
+                      }
+                  } else if let Some((expr, ty)) = lookup_const_by_id(tcx, def_id, substs) {
                       let item_hint = match ty {
                           Some(ty) => ty_hint.checked_or(ty),
                           None => ty_hint,
@@ -856,28 +934,59 @@ pub fn eval_const_expr_partial<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
               Struct(_) => signal!(e, UnimplementedConstVal("tuple struct constructors")),
               callee => signal!(e, CallOn(callee)),
           };
-          let (decl, result) = if let Some(fn_like) = lookup_const_fn_by_id(tcx, did) {
-              (fn_like.decl(), fn_like.body())
-          } else {
-              signal!(e, NonConstPath)
+          // The MIR of a `const fn` is only available once type-checking
+          // is done, and the interpreter needs the callee's substitutions.
+          let mir_result = match ty_hint {
+              ExprTypeChecked if interpret::has_mir(tcx, did) => {
+                  let mut arg_vals = Vec::with_capacity(args.len());
+                  for arg_expr in args {
+                      arg_vals.push(eval_const_expr_partial(tcx, arg_expr, sub_ty_hint, fn_args)?);
+                  }
+                  let substs = tcx.tables().node_id_item_substs(callee.id)
+                      .unwrap_or_else(|| tcx.intern_substs(&[]));
+                  interpret::eval_const_fn_call(tcx, did, substs, &arg_vals)
+              }
+              _ => None,
           };
-          assert_eq!(decl.inputs.len(), args.len());
-
-          let mut call_args = DefIdMap();
-          for (arg, arg_expr) in decl.inputs.iter().zip(args.iter()) {
-              let arg_hint = ty_hint.erase_hint();
-              let arg_val = eval_const_expr_partial(
-                  tcx,
-                  arg_expr,
-                  arg_hint,
-                  fn_args
-              )?;
-              debug!("const call arg: {:?}", arg);
-              let old = call_args.insert(tcx.expect_def(arg.pat.id).def_id(), arg_val);
-              assert!(old.is_none());
+          if let Some(result) = mir_result {
+              result.map_err(|err| ConstEvalErr::from_interpreter(err, e.span))?
+          } else {
+              let (decl, result) = if let Some(fn_like) = lookup_const_fn_by_id(tcx, did) {
+                  (fn_like.decl(), fn_like.body())
+              } else {
+                  signal!(e, NonConstPath)
+              };
+              // Only the MIR interpreter evaluates `let` bindings and control
+              // flow, so a body using them which got here, where its MIR
+              // couldn't be evaluated, is reported as unimplemented. Without
+              // `const_fn_control_flow`, const qualification rejects such a
+              // body in this crate, so it is folded like any other rather
+              // than adding a second error; a body from another crate may
+              // have been accepted there with the feature enabled.
+              let control_flow_accepted = !did.is_local() ||
+                  tcx.sess.features.borrow().const_fn_control_flow;
+              if control_flow_accepted && has_control_flow(result) {
+                  signal!(e, UnimplementedConstVal("calls to `const fn`s with `let` bindings \
+                                                    or control flow"));
+              }
+              assert_eq!(decl.inputs.len(), args.len());
+
+              let mut call_args = DefIdMap();
+              for (arg, arg_expr) in decl.inputs.iter().zip(args.iter()) {
+                  let arg_hint = ty_hint.erase_hint();
+                  let arg_val = eval_const_expr_partial(
+                      tcx,
+                      arg_expr,
+                      arg_hint,
+                      fn_args
+                  )?;
+                  debug!("const call arg: {:?}", arg);
+                  let old = call_args.insert(tcx.expect_def(arg.pat.id).def_id(), arg_val);
+                  assert!(old.is_none());
+              }
+              debug!("const call({:?})", call_args);
+              eval_const_expr_partial(tcx, &result, ty_hint, Some(&call_args))?
           }
-          debug!("const call({:?})", call_args);
-          eval_const_expr_partial(tcx, &result, ty_hint, Some(&call_args))?
       },
       hir::ExprLit(ref lit) => match lit_to_const(&lit.node, tcx, ety) {
           Ok(val) => val,
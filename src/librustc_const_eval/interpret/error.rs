@@ -0,0 +1,138 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+
+use rustc_const_math::ConstMathErr;
+use syntax_pos::Span;
+
+pub type EvalResult<T> = Result<T, EvalError>;
+
+/// An error raised while interpreting MIR.
+///
+/// The span is filled in by the interpreter with the source location of
+/// the statement or terminator being executed when the error occurred;
+/// it is only `None` for errors raised before execution started.
+#[derive(Clone, Debug)]
+pub struct EvalError {
+    pub span: Option<Span>,
+    pub kind: EvalErrorKind,
+}
+
+#[derive(Clone, Debug)]
+pub enum EvalErrorKind {
+    DanglingPointerDeref,
+    InvalidFunctionPointer,
+    PointerOutOfBounds {
+        offset: u64,
+        size: u64,
+        allocation_size: u64,
+    },
+    ReadPointerAsBytes,
+    ReadBytesAsPointer,
+    ReadUndefBytes,
+    InvalidBool,
+    InvalidChar(u64),
+    InvalidDiscriminant,
+    ModifiedConstantMemory,
+    Math(ConstMathErr),
+    IndexOutOfBounds { len: u64, index: u64 },
+    NonConstFn(String),
+    Panic,
+    Unreachable,
+    StackFrameLimitReached,
+    ExecutionTimeLimitReached,
+    Layout(String),
+    NoMirFor(String),
+    TooGeneric,
+    Unimplemented(String),
+}
+
+impl EvalError {
+    pub fn new(kind: EvalErrorKind) -> EvalError {
+        EvalError {
+            span: None,
+            kind: kind,
+        }
+    }
+
+    /// Attaches `span` to the error, unless it already points somewhere
+    /// more precise.
+    pub fn or_span(mut self, span: Span) -> EvalError {
+        if self.span.is_none() {
+            self.span = Some(span);
+        }
+        self
+    }
+}
+
+impl From<EvalErrorKind> for EvalError {
+    fn from(kind: EvalErrorKind) -> EvalError {
+        EvalError::new(kind)
+    }
+}
+
+impl From<ConstMathErr> for EvalError {
+    fn from(err: ConstMathErr) -> EvalError {
+        EvalError::new(EvalErrorKind::Math(err))
+    }
+}
+
+impl fmt::Display for EvalErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::EvalErrorKind::*;
+        match *self {
+            DanglingPointerDeref =>
+                write!(f, "dangling pointer was dereferenced"),
+            InvalidFunctionPointer =>
+                write!(f, "tried to use a pointer as a function pointer"),
+            PointerOutOfBounds { offset, size, allocation_size } => {
+                write!(f, "memory access of {}..{} outside bounds of allocation of size {}",
+                       offset, offset + size, allocation_size)
+            }
+            ReadPointerAsBytes =>
+                write!(f, "a memory access tried to interpret part of a pointer as raw bytes"),
+            ReadBytesAsPointer =>
+                write!(f, "a memory access tried to interpret some bytes as a pointer"),
+            ReadUndefBytes =>
+                write!(f, "attempted to read undefined bytes"),
+            InvalidBool =>
+                write!(f, "invalid boolean value read"),
+            InvalidChar(c) =>
+                write!(f, "tried to interpret an invalid 32-bit value as a char: {}", c),
+            InvalidDiscriminant =>
+                write!(f, "invalid enum discriminant value read"),
+            ModifiedConstantMemory =>
+                write!(f, "tried to modify constant memory"),
+            Math(ref err) =>
+                write!(f, "{}", err.description()),
+            IndexOutOfBounds { len, index } =>
+                write!(f, "index out of bounds: the len is {} but the index is {}", len, index),
+            NonConstFn(ref path) =>
+                write!(f, "calling non-const fn `{}`", path),
+            Panic =>
+                write!(f, "the evaluated program panicked"),
+            Unreachable =>
+                write!(f, "entered unreachable code"),
+            StackFrameLimitReached =>
+                write!(f, "reached the configured maximum number of stack frames"),
+            ExecutionTimeLimitReached =>
+                write!(f, "reached the maximum number of evaluation steps"),
+            Layout(ref err) =>
+                write!(f, "{}", err),
+            NoMirFor(ref path) =>
+                write!(f, "no MIR available for `{}`", path),
+            TooGeneric =>
+                write!(f, "the value depends on unsubstituted type parameters"),
+            Unimplemented(ref what) =>
+                write!(f, "unimplemented in the MIR evaluator: {}", what),
+        }
+    }
+}
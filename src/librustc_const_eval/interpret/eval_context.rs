@@ -0,0 +1,859 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::cell::Ref;
+use std::str;
+
+use rustc::hir::def_id::DefId;
+use rustc::middle::const_val::ConstVal;
+use rustc::mir::{self, Local, Mir, Promoted, RETURN_POINTER, START_BLOCK};
+use rustc::traits::{self, Reveal};
+use rustc::ty::{self, Ty, TyCtxt, TypeFoldable};
+use rustc::ty::layout::{Layout, Size};
+use rustc::ty::subst::{Subst, Substs};
+use rustc_const_math::{ConstFloat, ConstInt, ConstIsize, ConstUsize};
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::indexed_vec::IndexVec;
+use syntax::ast;
+use syntax::parse::token;
+use syntax_pos::Span;
+
+use super::error::{EvalResult, EvalErrorKind};
+use super::memory::{Memory, Pointer};
+use super::value::{PrimVal, Value};
+use super::has_mir;
+
+/// The maximum number of statements and terminators a single evaluation
+/// may execute, so that evaluating a nonterminating constant fails
+/// instead of hanging the compiler.
+pub const STEP_LIMIT: u64 = 1_000_000;
+
+/// A constant the interpreter evaluates into an allocation of its own:
+/// a `const` or `static` item, or an rvalue promoted out of an item's MIR.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GlobalId<'tcx> {
+    pub def_id: DefId,
+    pub substs: &'tcx Substs<'tcx>,
+
+    /// The index into the `promoted` MIR of `def_id`, for promoted rvalues.
+    pub promoted: Option<Promoted>,
+}
+
+pub struct Frame<'tcx> {
+    pub mir: Ref<'tcx, Mir<'tcx>>,
+    pub def_id: DefId,
+    pub substs: &'tcx Substs<'tcx>,
+
+    /// The span of the body being evaluated.
+    pub span: Span,
+
+    /// The allocation of each local. The return pointer is the
+    /// destination provided by the caller.
+    pub locals: IndexVec<Local, Pointer>,
+
+    pub block: mir::BasicBlock,
+    pub stmt: usize,
+
+    /// The block to continue at in the calling frame when this one returns,
+    /// or `None` if returning ends the current run, as it does for the
+    /// body of a constant.
+    pub return_to: Option<mir::BasicBlock>,
+}
+
+/// An evaluated lvalue.
+#[derive(Copy, Clone, Debug)]
+pub struct LvalueRef {
+    pub ptr: Pointer,
+    pub extra: LvalueExtra,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LvalueExtra {
+    None,
+
+    /// The length of an unsized slice or `str` lvalue.
+    Length(u64),
+
+    /// The variant of an enum lvalue selected by a `Downcast`, which
+    /// determines where its fields are.
+    DowncastVariant(usize),
+}
+
+impl LvalueRef {
+    pub fn from_ptr(ptr: Pointer) -> LvalueRef {
+        LvalueRef {
+            ptr: ptr,
+            extra: LvalueExtra::None,
+        }
+    }
+}
+
+pub struct EvalContext<'a, 'tcx: 'a> {
+    pub tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    pub memory: Memory<'tcx>,
+    pub stack: Vec<Frame<'tcx>>,
+
+    /// The allocations holding the constants evaluated so far.
+    globals: FxHashMap<GlobalId<'tcx>, Pointer>,
+
+    pub steps_remaining: u64,
+}
+
+impl<'a, 'tcx> EvalContext<'a, 'tcx> {
+    pub fn new(tcx: TyCtxt<'a, 'tcx, 'tcx>) -> EvalContext<'a, 'tcx> {
+        EvalContext {
+            tcx: tcx,
+            memory: Memory::new(&tcx.data_layout),
+            stack: vec![],
+            globals: FxHashMap(),
+            steps_remaining: STEP_LIMIT,
+        }
+    }
+
+    pub fn load_mir(&self, def_id: DefId) -> EvalResult<Ref<'tcx, Mir<'tcx>>> {
+        if !has_mir(self.tcx, def_id) {
+            return Err(EvalErrorKind::NoMirFor(self.tcx.item_path_str(def_id)).into());
+        }
+        Ok(self.tcx.item_mir(def_id))
+    }
+
+    pub fn frame(&self) -> &Frame<'tcx> {
+        self.stack.last().expect("no call frames exist")
+    }
+
+    pub fn frame_mut(&mut self) -> &mut Frame<'tcx> {
+        self.stack.last_mut().expect("no call frames exist")
+    }
+
+    /// Substitutes `substs` into `ty` and normalizes it. Fails if the
+    /// result still depends on type parameters.
+    pub fn monomorphize(&self, ty: Ty<'tcx>, substs: &'tcx Substs<'tcx>) -> EvalResult<Ty<'tcx>> {
+        let ty = self.tcx.normalize_associated_type(&ty.subst(self.tcx, substs));
+        if ty.needs_subst() {
+            return Err(EvalErrorKind::TooGeneric.into());
+        }
+        Ok(ty)
+    }
+
+    /// Monomorphizes a type of the MIR of the topmost frame.
+    pub fn frame_ty(&self, ty: Ty<'tcx>) -> EvalResult<Ty<'tcx>> {
+        self.monomorphize(ty, self.frame().substs)
+    }
+
+    pub fn lvalue_ty(&self, lvalue: &mir::Lvalue<'tcx>) -> EvalResult<Ty<'tcx>> {
+        let ty = lvalue.ty(&self.frame().mir, self.tcx).to_ty(self.tcx);
+        self.frame_ty(ty)
+    }
+
+    pub fn operand_ty(&self, operand: &mir::Operand<'tcx>) -> EvalResult<Ty<'tcx>> {
+        let ty = operand.ty(&self.frame().mir, self.tcx);
+        self.frame_ty(ty)
+    }
+
+    pub fn layout(&self, ty: Ty<'tcx>) -> EvalResult<&'tcx Layout> {
+        self.tcx.infer_ctxt(None, None, Reveal::All).enter(|infcx| {
+            ty.layout(&infcx).map_err(|err| EvalErrorKind::Layout(err.to_string()).into())
+        })
+    }
+
+    pub fn type_size(&self, ty: Ty<'tcx>) -> EvalResult<u64> {
+        Ok(self.layout(ty)?.size(&self.tcx.data_layout).bytes())
+    }
+
+    /// Returns the size in bytes and the signedness of the integer-like
+    /// type `ty`, or `None` if it has no integer representation.
+    pub fn int_info(&self, ty: Ty<'tcx>) -> EvalResult<Option<(u64, bool)>> {
+        use syntax::ast::IntTy::*;
+        use syntax::ast::UintTy::*;
+        let pointer_size = self.memory.pointer_size();
+        Ok(Some(match ty.sty {
+            ty::TyBool => (1, false),
+            ty::TyChar => (4, false),
+            ty::TyInt(I8) => (1, true),
+            ty::TyInt(I16) => (2, true),
+            ty::TyInt(I32) => (4, true),
+            ty::TyInt(I64) => (8, true),
+            ty::TyInt(Is) => (pointer_size, true),
+            ty::TyUint(U8) => (1, false),
+            ty::TyUint(U16) => (2, false),
+            ty::TyUint(U32) => (4, false),
+            ty::TyUint(U64) => (8, false),
+            ty::TyUint(Us) => (pointer_size, false),
+            ty::TyRawPtr(_) | ty::TyRef(..) | ty::TyFnPtr(_) => (pointer_size, false),
+            ty::TyAdt(def, _) if def.is_enum() => match *self.layout(ty)? {
+                Layout::CEnum { discr, signed, .. } => (discr.size().bytes(), signed),
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        }))
+    }
+
+    pub fn push_frame(&mut self,
+                      def_id: DefId,
+                      substs: &'tcx Substs<'tcx>,
+                      mir: Ref<'tcx, Mir<'tcx>>,
+                      return_ptr: Pointer,
+                      return_to: Option<mir::BasicBlock>)
+                      -> EvalResult<()> {
+        if self.stack.len() >= self.tcx.sess.recursion_limit.get() {
+            return Err(EvalErrorKind::StackFrameLimitReached.into());
+        }
+
+        let mut locals = IndexVec::with_capacity(mir.local_decls.len());
+        for (local, decl) in mir.local_decls.iter_enumerated() {
+            if local == RETURN_POINTER {
+                locals.push(return_ptr);
+            } else {
+                let ty = self.monomorphize(decl.ty, substs)?;
+                let size = self.type_size(ty)?;
+                locals.push(self.memory.allocate(size));
+            }
+        }
+
+        let span = mir.span;
+        self.stack.push(Frame {
+            mir: mir,
+            def_id: def_id,
+            substs: substs,
+            span: span,
+            locals: locals,
+            block: START_BLOCK,
+            stmt: 0,
+            return_to: return_to,
+        });
+        Ok(())
+    }
+
+    pub fn pop_frame(&mut self) -> EvalResult<()> {
+        let frame = self.stack.pop().expect("no call frames exist");
+        for (local, &ptr) in frame.locals.iter_enumerated() {
+            if local != RETURN_POINTER {
+                self.memory.deallocate(ptr)?;
+            }
+        }
+        if let Some(target) = frame.return_to {
+            self.goto(target);
+        }
+        Ok(())
+    }
+
+    pub fn goto(&mut self, target: mir::BasicBlock) {
+        let frame = self.frame_mut();
+        frame.block = target;
+        frame.stmt = 0;
+    }
+
+    /// Steps through the topmost frames until only `depth` frames remain.
+    pub fn run(&mut self, depth: usize) -> EvalResult<()> {
+        while self.stack.len() > depth {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    /// Finds the `GlobalId` holding the value of the constant `def_id`,
+    /// selecting the impl for constants declared in a trait.
+    pub fn resolve_global(&self, def_id: DefId, substs: &'tcx Substs<'tcx>)
+                          -> EvalResult<GlobalId<'tcx>> {
+        let trait_id = match self.tcx.trait_of_item(def_id) {
+            Some(trait_id) => trait_id,
+            None => {
+                return Ok(GlobalId {
+                    def_id: def_id,
+                    substs: substs,
+                    promoted: None,
+                });
+            }
+        };
+
+        let trait_ref = ty::Binder(ty::TraitRef::new(trait_id, substs));
+        let selected = self.tcx.infer_ctxt(None, None, Reveal::All).enter(|infcx| {
+            let mut selcx = traits::SelectionContext::new(&infcx);
+            let obligation = traits::Obligation::new(traits::ObligationCause::dummy(),
+                                                     trait_ref.to_poly_trait_predicate());
+            let impl_data = match selcx.select(&obligation) {
+                Ok(Some(traits::VtableImpl(impl_data))) => impl_data,
+                _ => return None,
+            };
+
+            // The nested obligations can constrain the impl's parameters.
+            let mut fulfill_cx = traits::FulfillmentContext::new();
+            for obligation in impl_data.nested {
+                fulfill_cx.register_predicate_obligation(&infcx, obligation);
+            }
+            if fulfill_cx.select_all_or_error(&infcx).is_err() {
+                return None;
+            }
+            let substs = infcx.resolve_type_vars_if_possible(&impl_data.substs);
+            let substs = infcx.tcx.erase_regions(&substs);
+            infcx.tcx.lift_to_global(&substs).map(|substs| (impl_data.impl_def_id, substs))
+        });
+        let (impl_def_id, impl_substs) = match selected {
+            Some(selected) => selected,
+            None => return Err(EvalErrorKind::TooGeneric.into()),
+        };
+
+        let name = self.tcx.associated_item(def_id).name;
+        let impl_item = self.tcx.associated_items(impl_def_id).find(|item| {
+            item.kind == ty::AssociatedKind::Const && item.name == name
+        });
+        Ok(match impl_item {
+            Some(item) => GlobalId {
+                def_id: item.def_id,
+                substs: impl_substs,
+                promoted: None,
+            },
+            // The impl relies on the default in the trait.
+            None => GlobalId {
+                def_id: def_id,
+                substs: substs,
+                promoted: None,
+            },
+        })
+    }
+
+    /// Returns the allocation holding the value of `id`, evaluating it
+    /// first if this is the first use.
+    pub fn global_ptr(&mut self, id: GlobalId<'tcx>) -> EvalResult<Pointer> {
+        if let Some(&ptr) = self.globals.get(&id) {
+            return Ok(ptr);
+        }
+
+        let mir = self.load_mir(id.def_id)?;
+        let mir = match id.promoted {
+            Some(index) => Ref::map(mir, |mir| &mir.promoted[index]),
+            None => mir,
+        };
+        let ty = self.monomorphize(mir.return_ty, id.substs)?;
+        let ptr = self.memory.allocate(self.type_size(ty)?);
+
+        // Registered before evaluating the body, so that a static can
+        // refer to its own address.
+        self.globals.insert(id, ptr);
+
+        let depth = self.stack.len();
+        self.push_frame(id.def_id, id.substs, mir, ptr, None)?;
+        self.run(depth)?;
+        self.memory.mark_immutable(ptr.alloc_id)?;
+        Ok(ptr)
+    }
+
+    pub fn eval_lvalue(&mut self, lvalue: &mir::Lvalue<'tcx>) -> EvalResult<LvalueRef> {
+        match *lvalue {
+            mir::Lvalue::Local(local) => Ok(LvalueRef::from_ptr(self.frame().locals[local])),
+            mir::Lvalue::Static(def_id) => {
+                let id = GlobalId {
+                    def_id: def_id,
+                    substs: self.tcx.intern_substs(&[]),
+                    promoted: None,
+                };
+                Ok(LvalueRef::from_ptr(self.global_ptr(id)?))
+            }
+            mir::Lvalue::Projection(ref proj) => {
+                let base = self.eval_lvalue(&proj.base)?;
+                let base_ty = self.lvalue_ty(&proj.base)?;
+                self.eval_projection(base, base_ty, &proj.elem)
+            }
+        }
+    }
+
+    fn eval_projection(&mut self,
+                       base: LvalueRef,
+                       base_ty: Ty<'tcx>,
+                       elem: &mir::LvalueElem<'tcx>)
+                       -> EvalResult<LvalueRef> {
+        use rustc::mir::ProjectionElem::*;
+        match *elem {
+            Deref => {
+                match self.read_value(base.ptr, base_ty)? {
+                    Value::ByVal(ptr) => Ok(LvalueRef::from_ptr(self.deref_ptr(ptr)?)),
+                    Value::ByValPair(ptr, len) => {
+                        Ok(LvalueRef {
+                            ptr: self.deref_ptr(ptr)?,
+                            extra: LvalueExtra::Length(len.to_bytes()?),
+                        })
+                    }
+                    Value::ByRef(_) => bug!("dereferenced a value of type `{}`", base_ty),
+                }
+            }
+            Field(field, field_ty) => {
+                let field_ty = self.frame_ty(field_ty)?;
+                let offset = self.field_offset(base_ty, base.extra, field.index())?;
+                // Only an unsized field inherits the length of its base.
+                let extra = if self.layout(field_ty)?.is_unsized() {
+                    base.extra
+                } else {
+                    LvalueExtra::None
+                };
+                Ok(LvalueRef {
+                    ptr: base.ptr.offset(offset),
+                    extra: extra,
+                })
+            }
+            Downcast(_, variant) => {
+                Ok(LvalueRef {
+                    ptr: base.ptr,
+                    extra: LvalueExtra::DowncastVariant(variant),
+                })
+            }
+            Index(ref operand) => {
+                let index = self.eval_operand_to_primval(operand)?.to_bytes()?;
+                self.index_lvalue(base, base_ty, index)
+            }
+            ConstantIndex { offset, from_end, .. } => {
+                let len = self.lvalue_len(base, base_ty)?;
+                let index = if from_end {
+                    len - offset as u64
+                } else {
+                    offset as u64
+                };
+                self.index_lvalue(base, base_ty, index)
+            }
+            Subslice { from, to } => {
+                let len = self.lvalue_len(base, base_ty)?;
+                let elem_size = self.type_size(base_ty.sequence_element_type(self.tcx))?;
+                let extra = match base_ty.sty {
+                    ty::TyArray(..) => LvalueExtra::None,
+                    _ => LvalueExtra::Length(len - from as u64 - to as u64),
+                };
+                Ok(LvalueRef {
+                    ptr: base.ptr.offset(from as u64 * elem_size),
+                    extra: extra,
+                })
+            }
+        }
+    }
+
+    fn deref_ptr(&self, ptr: PrimVal) -> EvalResult<Pointer> {
+        match ptr {
+            PrimVal::Ptr(ptr) => Ok(ptr),
+            // Null, or an integer cast to a pointer.
+            PrimVal::Bytes(_) => Err(EvalErrorKind::DanglingPointerDeref.into()),
+            PrimVal::Undef => Err(EvalErrorKind::ReadUndefBytes.into()),
+        }
+    }
+
+    pub fn lvalue_len(&self, lvalue: LvalueRef, ty: Ty<'tcx>) -> EvalResult<u64> {
+        match (&ty.sty, lvalue.extra) {
+            (&ty::TyArray(_, len), _) => Ok(len as u64),
+            (_, LvalueExtra::Length(len)) => Ok(len),
+            _ => bug!("took the length of an lvalue of type `{}`", ty),
+        }
+    }
+
+    fn index_lvalue(&self, base: LvalueRef, base_ty: Ty<'tcx>, index: u64)
+                    -> EvalResult<LvalueRef> {
+        let len = self.lvalue_len(base, base_ty)?;
+        if index >= len {
+            return Err(EvalErrorKind::IndexOutOfBounds { len: len, index: index }.into());
+        }
+        let elem_size = self.type_size(base_ty.sequence_element_type(self.tcx))?;
+        Ok(LvalueRef::from_ptr(base.ptr.offset(index * elem_size)))
+    }
+
+    /// Returns the type of field `i` of the struct, tuple, closure or array
+    /// `ty`.
+    fn field_ty(&self, ty: Ty<'tcx>, i: usize) -> Ty<'tcx> {
+        match ty.sty {
+            ty::TyAdt(def, substs) => {
+                self.tcx.normalize_associated_type(&def.struct_variant().fields[i].ty(self.tcx,
+                                                                                     substs))
+            }
+            ty::TyTuple(tys) => tys[i],
+            ty::TyClosure(_, ty::ClosureSubsts { upvar_tys, .. }) => upvar_tys[i],
+            ty::TyArray(elem_ty, _) => elem_ty,
+            _ => bug!("field {} of non-aggregate type `{}`", i, ty),
+        }
+    }
+
+    /// Returns the offset of field `i` in a value of type `ty`; for enums,
+    /// `extra` selects the variant.
+    pub fn field_offset(&self, ty: Ty<'tcx>, extra: LvalueExtra, i: usize) -> EvalResult<u64> {
+        if let ty::TyArray(elem_ty, _) = ty.sty {
+            return Ok(i as u64 * self.type_size(elem_ty)?);
+        }
+        let layout = self.layout(ty)?;
+        let offset = match (layout, extra) {
+            (&Layout::Univariant { ref variant, .. }, _) => variant.offsets[i],
+            (&Layout::General { ref variants, .. }, LvalueExtra::DowncastVariant(v)) => {
+                // The discriminant comes first.
+                variants[v].offsets[i + 1]
+            }
+            (&Layout::StructWrappedNullablePointer { nndiscr, ref nonnull, .. },
+             LvalueExtra::DowncastVariant(v)) if v as u64 == nndiscr => {
+                nonnull.offsets[i]
+            }
+            (&Layout::NicheFilling { dataful_variant, ref dataful, .. },
+             LvalueExtra::DowncastVariant(v)) if v as u64 == dataful_variant => {
+                dataful.offsets[i]
+            }
+            // The other variant of a nullable pointer is zero-sized, and
+            // so are all of its fields.
+            (&Layout::RawNullablePointer { .. }, _) |
+            (&Layout::StructWrappedNullablePointer { .. }, _) |
+            (&Layout::NicheFilling { .. }, _) |
+            (&Layout::UntaggedUnion { .. }, _) => Size::from_bytes(0),
+            _ => {
+                return Err(EvalErrorKind::Unimplemented(
+                    format!("field access on `{}` with layout {:?}", ty, layout)).into());
+            }
+        };
+        Ok(offset.bytes())
+    }
+
+    /// Returns the offset and size of the field that is null in the
+    /// zero-sized variant of a struct-wrapped nullable pointer enum.
+    fn nonnull_field(&self, ty: Ty<'tcx>, nndiscr: u64, discrfield_source: &[u32])
+                     -> EvalResult<(u64, u64)> {
+        let (nonnull, adt_def, substs) = match (self.layout(ty)?, &ty.sty) {
+            (&Layout::StructWrappedNullablePointer { ref nonnull, .. },
+             &ty::TyAdt(adt_def, substs)) => (nonnull, adt_def, substs),
+            _ => bug!("`{}` is not a struct-wrapped nullable pointer", ty),
+        };
+
+        let mut path = discrfield_source.iter().map(|&i| i as usize);
+        let first = path.next().expect("empty path to the non-null field");
+        let mut offset = nonnull.offsets[first].bytes();
+        let field_ty = adt_def.variants[nndiscr as usize].fields[first].ty(self.tcx, substs);
+        let mut ty = self.tcx.normalize_associated_type(&field_ty);
+        for i in path {
+            if let Layout::FatPointer { .. } = *self.layout(ty)? {
+                // Only the data pointer of a fat pointer is non-null.
+                assert_eq!(i, 0);
+                return Ok((offset, self.memory.pointer_size()));
+            }
+            offset += self.field_offset(ty, LvalueExtra::None, i)?;
+            ty = self.field_ty(ty, i);
+        }
+        let size = match *self.layout(ty)? {
+            Layout::FatPointer { .. } => self.memory.pointer_size(),
+            _ => self.type_size(ty)?,
+        };
+        Ok((offset, size))
+    }
+
+    /// Returns the index of the variant of the enum of type `ty` at `ptr`.
+    pub fn read_discriminant(&self, ptr: Pointer, ty: Ty<'tcx>) -> EvalResult<usize> {
+        let adt_def = match ty.sty {
+            ty::TyAdt(adt_def, _) => adt_def,
+            _ => bug!("read the discriminant of non-ADT type `{}`", ty),
+        };
+        let is_null = |val: PrimVal| -> EvalResult<bool> {
+            match val {
+                PrimVal::Bytes(bits) => Ok(bits == 0),
+                PrimVal::Ptr(_) => Ok(false),
+                PrimVal::Undef => Err(EvalErrorKind::ReadUndefBytes.into()),
+            }
+        };
+        let variant = match *self.layout(ty)? {
+            Layout::CEnum { discr, .. } | Layout::General { discr, .. } => {
+                let size = discr.size().bytes();
+                let bits = self.memory.read_uint(ptr, size)?;
+                adt_def.variants.iter().position(|v| {
+                    truncate(v.disr_val.to_u64_unchecked(), size) == bits
+                })
+            }
+            Layout::RawNullablePointer { nndiscr, value } => {
+                let size = value.size(&self.tcx.data_layout).bytes();
+                let null = is_null(self.memory.read_primval(ptr, size)?)?;
+                Some(if null { 1 - nndiscr } else { nndiscr } as usize)
+            }
+            Layout::StructWrappedNullablePointer { nndiscr, ref discrfield_source, .. } => {
+                let (offset, size) = self.nonnull_field(ty, nndiscr, discrfield_source)?;
+                let null = is_null(self.memory.read_primval(ptr.offset(offset), size)?)?;
+                Some(if null { 1 - nndiscr } else { nndiscr } as usize)
+            }
+            Layout::NicheFilling { dataful_variant, niche_variants, niche, niche_start, .. } => {
+                let size = niche.value.size().bytes();
+                // A pointer niche holds either the null pointer, or an actual
+                // pointer, which is never one of the niche's values.
+                let bits = match self.memory.read_primval(ptr.offset(niche.offset.bytes()), size)? {
+                    PrimVal::Bytes(bits) => bits,
+                    PrimVal::Ptr(_) => return Ok(dataful_variant as usize),
+                    PrimVal::Undef => return Err(EvalErrorKind::ReadUndefBytes.into()),
+                };
+                let relative = truncate(bits.wrapping_sub(niche_start), size);
+                if relative <= niche_variants.1 - niche_variants.0 {
+                    Some((niche_variants.0 + relative) as usize)
+                } else {
+                    Some(dataful_variant as usize)
+                }
+            }
+            Layout::Univariant { .. } => Some(0),
+            ref layout => bug!("read the discriminant of `{}` with layout {:?}", ty, layout),
+        };
+        variant.ok_or(EvalErrorKind::InvalidDiscriminant.into())
+    }
+
+    /// Makes the enum of type `ty` at `ptr` hold variant `variant_index`.
+    /// Its fields are left alone.
+    pub fn write_discriminant(&mut self, ptr: Pointer, ty: Ty<'tcx>, variant_index: usize)
+                              -> EvalResult<()> {
+        let adt_def = match ty.sty {
+            ty::TyAdt(adt_def, _) if adt_def.is_enum() => adt_def,
+            _ => return Ok(()),
+        };
+        let discr_val = adt_def.variants[variant_index].disr_val.to_u64_unchecked();
+        match *self.layout(ty)? {
+            Layout::CEnum { discr, .. } | Layout::General { discr, .. } => {
+                self.memory.write_uint(ptr, discr_val, discr.size().bytes())
+            }
+            Layout::RawNullablePointer { nndiscr, value } => {
+                if variant_index as u64 == nndiscr {
+                    return Ok(());
+                }
+                let size = value.size(&self.tcx.data_layout).bytes();
+                self.memory.write_uint(ptr, 0, size)
+            }
+            Layout::StructWrappedNullablePointer { nndiscr, ref discrfield_source, .. } => {
+                if variant_index as u64 == nndiscr {
+                    return Ok(());
+                }
+                let (offset, size) = self.nonnull_field(ty, nndiscr, discrfield_source)?;
+                self.memory.write_uint(ptr.offset(offset), 0, size)
+            }
+            Layout::NicheFilling { dataful_variant, niche_variants, niche, niche_start, .. } => {
+                if variant_index as u64 == dataful_variant {
+                    return Ok(());
+                }
+                let size = niche.value.size().bytes();
+                let value = (variant_index as u64 - niche_variants.0).wrapping_add(niche_start);
+                let ptr = ptr.offset(niche.offset.bytes());
+                self.memory.write_uint(ptr, truncate(value, size), size)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads a value of type `ty` from `ptr`. Only scalars and pointers
+    /// are actually loaded; aggregates are left in memory.
+    pub fn read_value(&self, ptr: Pointer, ty: Ty<'tcx>) -> EvalResult<Value> {
+        let pointee = match ty.sty {
+            ty::TyRef(_, ty::TypeAndMut { ty: pointee, .. }) |
+            ty::TyRawPtr(ty::TypeAndMut { ty: pointee, .. }) |
+            ty::TyBox(pointee) => Some(pointee),
+            _ => None,
+        };
+        if let Some(pointee) = pointee {
+            if !self.layout(pointee)?.is_unsized() {
+                return Ok(Value::ByVal(self.memory.read_ptr(ptr)?));
+            }
+            return match pointee.sty {
+                ty::TyStr | ty::TySlice(_) => {
+                    let data = self.memory.read_ptr(ptr)?;
+                    let len = self.memory.read_ptr(ptr.offset(self.memory.pointer_size()))?;
+                    Ok(Value::ByValPair(data, len))
+                }
+                _ => Err(EvalErrorKind::Unimplemented(format!("pointers to `{}`", pointee)).into()),
+            };
+        }
+
+        let dl = &self.tcx.data_layout;
+        match *self.layout(ty)? {
+            Layout::Scalar { value, .. } => {
+                Ok(Value::ByVal(self.memory.read_primval(ptr, value.size(dl).bytes())?))
+            }
+            Layout::CEnum { discr, .. } => {
+                Ok(Value::ByVal(self.memory.read_primval(ptr, discr.size().bytes())?))
+            }
+            _ => Ok(Value::ByRef(ptr)),
+        }
+    }
+
+    pub fn write_value(&mut self, val: Value, dest: Pointer, ty: Ty<'tcx>) -> EvalResult<()> {
+        match val {
+            Value::ByRef(src) => {
+                let size = self.type_size(ty)?;
+                self.memory.copy(src, dest, size)
+            }
+            Value::ByVal(prim) => {
+                let size = self.type_size(ty)?;
+                self.memory.write_primval(dest, prim, size)
+            }
+            Value::ByValPair(data, len) => {
+                let pointer_size = self.memory.pointer_size();
+                self.memory.write_primval(dest, data, pointer_size)?;
+                self.memory.write_primval(dest.offset(pointer_size), len, pointer_size)
+            }
+        }
+    }
+
+    pub fn eval_operand(&mut self, operand: &mir::Operand<'tcx>) -> EvalResult<Value> {
+        match *operand {
+            mir::Operand::Consume(ref lvalue) => {
+                let ptr = self.eval_lvalue(lvalue)?.ptr;
+                let ty = self.lvalue_ty(lvalue)?;
+                self.read_value(ptr, ty)
+            }
+            mir::Operand::Constant(ref constant) => {
+                let ty = self.frame_ty(constant.ty)?;
+                match constant.literal {
+                    mir::Literal::Value { ref value } => self.const_to_value(value, ty),
+                    mir::Literal::Item { def_id, substs } => {
+                        // Function items are zero-sized; calls find the
+                        // callee from the type.
+                        if let ty::TyFnDef(..) = ty.sty {
+                            return Ok(Value::ByVal(PrimVal::Undef));
+                        }
+                        let substs = self.tcx.normalize_associated_type(
+                            &substs.subst(self.tcx, self.frame().substs));
+                        let id = self.resolve_global(def_id, substs)?;
+                        let ptr = self.global_ptr(id)?;
+                        self.read_value(ptr, ty)
+                    }
+                    mir::Literal::Promoted { index } => {
+                        let id = GlobalId {
+                            def_id: self.frame().def_id,
+                            substs: self.frame().substs,
+                            promoted: Some(index),
+                        };
+                        let ptr = self.global_ptr(id)?;
+                        self.read_value(ptr, ty)
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn eval_operand_to_primval(&mut self, operand: &mir::Operand<'tcx>)
+                                   -> EvalResult<PrimVal> {
+        match self.eval_operand(operand)? {
+            Value::ByVal(prim) => Ok(prim),
+            val => bug!("expected a primitive value for `{:?}`, found {:?}", operand, val),
+        }
+    }
+
+    /// Turns a constant computed by the HIR evaluator (a literal, or an
+    /// argument of a `const fn`) into a value.
+    pub fn const_to_value(&mut self, val: &ConstVal, ty: Ty<'tcx>) -> EvalResult<Value> {
+        use rustc::middle::const_val::ConstVal::*;
+        let prim = match *val {
+            Integral(i) => {
+                let size = self.type_size(ty)?;
+                PrimVal::Bytes(truncate(i.to_u64_unchecked(), size))
+            }
+            Float(ConstFloat::F32(f)) => PrimVal::from_f32(f),
+            Float(ConstFloat::F64(f)) => PrimVal::from_f64(f),
+            Float(ConstFloat::FInfer { f32, f64 }) => match ty.sty {
+                ty::TyFloat(ast::FloatTy::F32) => PrimVal::from_f32(f32),
+                _ => PrimVal::from_f64(f64),
+            },
+            Bool(b) => PrimVal::from_bool(b),
+            Char(c) => PrimVal::from_char(c),
+            Str(ref s) => {
+                let ptr = self.memory.allocate_bytes(s.as_bytes())?;
+                return Ok(Value::ByValPair(PrimVal::Ptr(ptr), PrimVal::Bytes(s.len() as u64)));
+            }
+            ByteStr(ref bytes) => {
+                let ptr = PrimVal::Ptr(self.memory.allocate_bytes(bytes)?);
+                let pointee = ty.builtin_deref(true, ty::NoPreference).map(|mt| mt.ty);
+                return Ok(match pointee {
+                    Some(&ty::TyS { sty: ty::TySlice(_), .. }) => {
+                        Value::ByValPair(ptr, PrimVal::Bytes(bytes.len() as u64))
+                    }
+                    _ => Value::ByVal(ptr),
+                });
+            }
+            // Function items are zero-sized.
+            Function(_) => PrimVal::Undef,
+            Struct(_) | Tuple(_) | Array(..) | Repeat(..) | Dummy => {
+                return Err(EvalErrorKind::Unimplemented(
+                    format!("{} constants", val.description())).into());
+            }
+        };
+        Ok(Value::ByVal(prim))
+    }
+
+    /// Reads the value of type `ty` at `ptr` back into a `ConstVal`.
+    /// Only the types accepted by `is_const_val_ty` are supported.
+    pub fn read_const_val(&self, ptr: Pointer, ty: Ty<'tcx>) -> EvalResult<ConstVal> {
+        use syntax::ast::IntTy::*;
+        use syntax::ast::UintTy::*;
+
+        let val = self.read_value(ptr, ty)?;
+        if let ty::TyFnDef(def_id, ..) = ty.sty {
+            return Ok(ConstVal::Function(def_id));
+        }
+        if let Value::ByValPair(data, len) = val {
+            let bytes = self.memory.read_bytes(data.to_ptr()?, len.to_bytes()?)?;
+            return match str::from_utf8(bytes) {
+                Ok(s) => Ok(ConstVal::Str(token::intern_and_get_ident(s))),
+                Err(_) => Err(EvalErrorKind::Unimplemented("non-UTF-8 `str`".to_string()).into()),
+            };
+        }
+        let prim = match val {
+            Value::ByVal(prim) => prim,
+            _ => bug!("read_const_val: `{}` is not a scalar", ty),
+        };
+
+        Ok(match ty.sty {
+            ty::TyBool => ConstVal::Bool(prim.to_bool()?),
+            ty::TyChar => ConstVal::Char(prim.to_char()?),
+            ty::TyInt(int_ty) => {
+                let size = self.type_size(ty)?;
+                let v = sign_extend(prim.to_bytes()?, size) as i64;
+                ConstVal::Integral(match int_ty {
+                    I8 => ConstInt::I8(v as i8),
+                    I16 => ConstInt::I16(v as i16),
+                    I32 => ConstInt::I32(v as i32),
+                    I64 => ConstInt::I64(v),
+                    Is => {
+                        let int_type = self.tcx.sess.target.int_type;
+                        ConstInt::Isize(ConstIsize::new_truncating(v, int_type))
+                    }
+                })
+            }
+            ty::TyUint(uint_ty) => {
+                let v = prim.to_bytes()?;
+                ConstVal::Integral(match uint_ty {
+                    U8 => ConstInt::U8(v as u8),
+                    U16 => ConstInt::U16(v as u16),
+                    U32 => ConstInt::U32(v as u32),
+                    U64 => ConstInt::U64(v),
+                    Us => {
+                        let uint_type = self.tcx.sess.target.uint_type;
+                        ConstInt::Usize(ConstUsize::new_truncating(v, uint_type))
+                    }
+                })
+            }
+            ty::TyFloat(ast::FloatTy::F32) => ConstVal::Float(ConstFloat::F32(prim.to_f32()?)),
+            ty::TyFloat(ast::FloatTy::F64) => ConstVal::Float(ConstFloat::F64(prim.to_f64()?)),
+            _ => bug!("read_const_val: unsupported type `{}`", ty),
+        })
+    }
+}
+
+/// Whether `read_const_val` can represent values of type `ty`.
+pub fn is_const_val_ty(ty: Ty) -> bool {
+    match ty.sty {
+        ty::TyBool | ty::TyChar | ty::TyInt(_) | ty::TyUint(_) | ty::TyFloat(_) |
+        ty::TyFnDef(..) => true,
+        ty::TyRef(_, ty::TypeAndMut { ty: &ty::TyS { sty: ty::TyStr, .. }, .. }) => true,
+        _ => false,
+    }
+}
+
+/// Truncates `bits` to its lowest `size` bytes.
+pub fn truncate(bits: u64, size: u64) -> u64 {
+    if size >= 8 {
+        bits
+    } else {
+        bits & ((1 << (size * 8)) - 1)
+    }
+}
+
+/// Sign-extends the `size`-byte integer `bits` to 64 bits.
+pub fn sign_extend(bits: u64, size: u64) -> u64 {
+    if size >= 8 {
+        return bits;
+    }
+    let shift = 64 - size * 8;
+    (((bits << shift) as i64) >> shift) as u64
+}
@@ -0,0 +1,453 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The abstract memory the interpreter runs on.
+//!
+//! Memory is a set of disjoint allocations, each a plain byte buffer. A
+//! pointer is an allocation plus an offset, and can never be turned into
+//! an address: when a pointer is written to memory, the offset goes into
+//! the bytes and the allocation is remembered as a *relocation* at that
+//! position. Reading the bytes back as a pointer recovers it; reading
+//! them as an integer, or reading only part of them, is an error. Every
+//! byte also carries a definedness bit, so reads of uninitialized memory
+//! are caught instead of producing garbage.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use rustc::hir::def_id::DefId;
+use rustc::ty::layout::{Endian, TargetDataLayout};
+use rustc::ty::subst::Substs;
+use rustc_data_structures::fx::FxHashMap;
+
+use super::error::{EvalResult, EvalErrorKind};
+use super::value::PrimVal;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AllocId(u64);
+
+impl fmt::Display for AllocId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "alloc{}", self.0)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Pointer {
+    pub alloc_id: AllocId,
+    pub offset: u64,
+}
+
+impl Pointer {
+    pub fn new(alloc_id: AllocId, offset: u64) -> Pointer {
+        Pointer {
+            alloc_id: alloc_id,
+            offset: offset,
+        }
+    }
+
+    pub fn offset(self, bytes: u64) -> Pointer {
+        Pointer::new(self.alloc_id, self.offset + bytes)
+    }
+}
+
+#[derive(Debug)]
+pub struct Allocation {
+    /// The contents of the allocation. Bytes covered by a relocation hold
+    /// the offset of the pointer stored there.
+    pub bytes: Vec<u8>,
+
+    /// Pointers stored in the allocation, keyed by the offset of their
+    /// first byte, mapping to the allocation they point into.
+    pub relocations: BTreeMap<u64, AllocId>,
+
+    /// Which bytes have been initialized.
+    pub undef_mask: UndefMask,
+
+    /// False for the results of constants and promoted rvalues, which
+    /// must not change once evaluated.
+    pub mutable: bool,
+}
+
+/// A bitmask with one bit per byte of an allocation, set for the bytes
+/// that have been written to.
+#[derive(Clone, Debug)]
+pub struct UndefMask {
+    blocks: Vec<u64>,
+    len: u64,
+}
+
+const BLOCK_SIZE: u64 = 64;
+
+impl UndefMask {
+    /// Creates a mask of `len` undefined bytes.
+    pub fn new(len: u64) -> UndefMask {
+        UndefMask {
+            blocks: vec![0; ((len + BLOCK_SIZE - 1) / BLOCK_SIZE) as usize],
+            len: len,
+        }
+    }
+
+    pub fn get(&self, i: u64) -> bool {
+        assert!(i < self.len);
+        self.blocks[(i / BLOCK_SIZE) as usize] & (1 << (i % BLOCK_SIZE)) != 0
+    }
+
+    pub fn set(&mut self, i: u64, defined: bool) {
+        assert!(i < self.len);
+        let block = &mut self.blocks[(i / BLOCK_SIZE) as usize];
+        if defined {
+            *block |= 1 << (i % BLOCK_SIZE);
+        } else {
+            *block &= !(1 << (i % BLOCK_SIZE));
+        }
+    }
+
+    pub fn set_range(&mut self, start: u64, end: u64, defined: bool) {
+        for i in start..end {
+            self.set(i, defined);
+        }
+    }
+
+    pub fn is_range_defined(&self, start: u64, end: u64) -> bool {
+        (start..end).all(|i| self.get(i))
+    }
+}
+
+pub struct Memory<'tcx> {
+    allocs: FxHashMap<AllocId, Allocation>,
+
+    /// Function pointers are allocations of their own, which can't be
+    /// read from or written to.
+    functions: FxHashMap<AllocId, (DefId, &'tcx Substs<'tcx>)>,
+    function_ids: FxHashMap<(DefId, &'tcx Substs<'tcx>), AllocId>,
+
+    next_id: u64,
+    endian: Endian,
+    pointer_size: u64,
+}
+
+impl<'tcx> Memory<'tcx> {
+    pub fn new(dl: &TargetDataLayout) -> Memory<'tcx> {
+        Memory {
+            allocs: FxHashMap(),
+            functions: FxHashMap(),
+            function_ids: FxHashMap(),
+            next_id: 0,
+            endian: dl.endian,
+            pointer_size: dl.pointer_size.bytes(),
+        }
+    }
+
+    pub fn pointer_size(&self) -> u64 {
+        self.pointer_size
+    }
+
+    fn fresh_id(&mut self) -> AllocId {
+        let id = AllocId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    /// Creates a new, undefined allocation of `size` bytes.
+    pub fn allocate(&mut self, size: u64) -> Pointer {
+        let id = self.fresh_id();
+        self.allocs.insert(id, Allocation {
+            bytes: vec![0; size as usize],
+            relocations: BTreeMap::new(),
+            undef_mask: UndefMask::new(size),
+            mutable: true,
+        });
+        Pointer::new(id, 0)
+    }
+
+    /// Creates an immutable allocation holding `bytes`.
+    pub fn allocate_bytes(&mut self, bytes: &[u8]) -> EvalResult<Pointer> {
+        let ptr = self.allocate(bytes.len() as u64);
+        self.write_bytes(ptr, bytes)?;
+        self.mark_immutable(ptr.alloc_id)?;
+        Ok(ptr)
+    }
+
+    /// Frees the allocation `ptr` points to. Pointers into it become
+    /// dangling.
+    pub fn deallocate(&mut self, ptr: Pointer) -> EvalResult<()> {
+        if ptr.offset != 0 || self.allocs.remove(&ptr.alloc_id).is_none() {
+            return Err(EvalErrorKind::DanglingPointerDeref.into());
+        }
+        Ok(())
+    }
+
+    pub fn create_fn_ptr(&mut self, def_id: DefId, substs: &'tcx Substs<'tcx>) -> Pointer {
+        if let Some(&id) = self.function_ids.get(&(def_id, substs)) {
+            return Pointer::new(id, 0);
+        }
+        let id = self.fresh_id();
+        self.functions.insert(id, (def_id, substs));
+        self.function_ids.insert((def_id, substs), id);
+        Pointer::new(id, 0)
+    }
+
+    pub fn get_fn(&self, ptr: Pointer) -> EvalResult<(DefId, &'tcx Substs<'tcx>)> {
+        if ptr.offset != 0 {
+            return Err(EvalErrorKind::InvalidFunctionPointer.into());
+        }
+        match self.functions.get(&ptr.alloc_id) {
+            Some(&f) => Ok(f),
+            None => Err(EvalErrorKind::InvalidFunctionPointer.into()),
+        }
+    }
+
+    pub fn get(&self, id: AllocId) -> EvalResult<&Allocation> {
+        match self.allocs.get(&id) {
+            Some(alloc) => Ok(alloc),
+            None => Err(EvalErrorKind::DanglingPointerDeref.into()),
+        }
+    }
+
+    fn get_mut(&mut self, id: AllocId) -> EvalResult<&mut Allocation> {
+        let alloc = match self.allocs.get_mut(&id) {
+            Some(alloc) => alloc,
+            None => return Err(EvalErrorKind::DanglingPointerDeref.into()),
+        };
+        if !alloc.mutable {
+            return Err(EvalErrorKind::ModifiedConstantMemory.into());
+        }
+        Ok(alloc)
+    }
+
+    pub fn mark_immutable(&mut self, id: AllocId) -> EvalResult<()> {
+        match self.allocs.get_mut(&id) {
+            Some(alloc) => {
+                alloc.mutable = false;
+                Ok(())
+            }
+            None => Err(EvalErrorKind::DanglingPointerDeref.into()),
+        }
+    }
+
+    fn check_bounds(alloc: &Allocation, ptr: Pointer, size: u64) -> EvalResult<()> {
+        let allocation_size = alloc.bytes.len() as u64;
+        if ptr.offset + size > allocation_size {
+            return Err(EvalErrorKind::PointerOutOfBounds {
+                offset: ptr.offset,
+                size: size,
+                allocation_size: allocation_size,
+            }.into());
+        }
+        Ok(())
+    }
+
+    /// Returns the offsets of the relocations overlapping `size` bytes at
+    /// `ptr`, including pointers that start before `ptr`.
+    fn relocations(&self, ptr: Pointer, size: u64) -> EvalResult<Vec<u64>> {
+        let start = ptr.offset.saturating_sub(self.pointer_size - 1);
+        let end = ptr.offset + size;
+        Ok(self.get(ptr.alloc_id)?.relocations.keys()
+            .cloned()
+            .filter(|&offset| offset >= start && offset < end)
+            .collect())
+    }
+
+    /// Removes the relocations about to be overwritten by a write of
+    /// `size` bytes at `ptr`. The bytes of a pointer that stick out of the
+    /// written range are no longer meaningful, so they become undefined.
+    fn clear_relocations(&mut self, ptr: Pointer, size: u64) -> EvalResult<()> {
+        let relocations = self.relocations(ptr, size)?;
+        if relocations.is_empty() {
+            return Ok(());
+        }
+        let pointer_size = self.pointer_size;
+        let alloc = self.get_mut(ptr.alloc_id)?;
+        let first = relocations[0];
+        let last = relocations[relocations.len() - 1] + pointer_size;
+        if first < ptr.offset {
+            alloc.undef_mask.set_range(first, ptr.offset, false);
+        }
+        if last > ptr.offset + size {
+            alloc.undef_mask.set_range(ptr.offset + size, last, false);
+        }
+        for offset in relocations {
+            alloc.relocations.remove(&offset);
+        }
+        Ok(())
+    }
+
+    /// Returns `size` bytes at `ptr`, which must all be defined and must
+    /// not be part of a pointer.
+    pub fn read_bytes(&self, ptr: Pointer, size: u64) -> EvalResult<&[u8]> {
+        if size == 0 {
+            return Ok(&[]);
+        }
+        if !self.relocations(ptr, size)?.is_empty() {
+            return Err(EvalErrorKind::ReadPointerAsBytes.into());
+        }
+        let alloc = self.get(ptr.alloc_id)?;
+        Memory::check_bounds(alloc, ptr, size)?;
+        if !alloc.undef_mask.is_range_defined(ptr.offset, ptr.offset + size) {
+            return Err(EvalErrorKind::ReadUndefBytes.into());
+        }
+        Ok(&alloc.bytes[ptr.offset as usize..(ptr.offset + size) as usize])
+    }
+
+    /// Returns `size` bytes at `ptr` for writing, marking them defined.
+    fn write_bytes_mut(&mut self, ptr: Pointer, size: u64) -> EvalResult<&mut [u8]> {
+        {
+            let alloc = self.get_mut(ptr.alloc_id)?;
+            Memory::check_bounds(alloc, ptr, size)?;
+        }
+        self.clear_relocations(ptr, size)?;
+        let alloc = self.get_mut(ptr.alloc_id)?;
+        alloc.undef_mask.set_range(ptr.offset, ptr.offset + size, true);
+        Ok(&mut alloc.bytes[ptr.offset as usize..(ptr.offset + size) as usize])
+    }
+
+    pub fn write_bytes(&mut self, ptr: Pointer, src: &[u8]) -> EvalResult<()> {
+        if src.is_empty() {
+            return Ok(());
+        }
+        let bytes = self.write_bytes_mut(ptr, src.len() as u64)?;
+        bytes.clone_from_slice(src);
+        Ok(())
+    }
+
+    /// Marks `size` bytes at `ptr` as undefined.
+    pub fn mark_undef(&mut self, ptr: Pointer, size: u64) -> EvalResult<()> {
+        if size == 0 {
+            return Ok(());
+        }
+        self.write_bytes_mut(ptr, size)?;
+        let alloc = self.get_mut(ptr.alloc_id)?;
+        alloc.undef_mask.set_range(ptr.offset, ptr.offset + size, false);
+        Ok(())
+    }
+
+    /// Copies `size` bytes from `src` to `dest`, along with their
+    /// definedness and any pointers stored in them.
+    pub fn copy(&mut self, src: Pointer, dest: Pointer, size: u64) -> EvalResult<()> {
+        if size == 0 {
+            return Ok(());
+        }
+        let (bytes, undef, relocations) = {
+            let alloc = self.get(src.alloc_id)?;
+            Memory::check_bounds(alloc, src, size)?;
+            let range = src.offset as usize..(src.offset + size) as usize;
+            let undef: Vec<bool> = (src.offset..src.offset + size)
+                .map(|i| alloc.undef_mask.get(i))
+                .collect();
+            let relocations: Vec<(u64, AllocId)> = alloc.relocations.iter()
+                .filter(|&(&offset, _)| offset >= src.offset && offset < src.offset + size)
+                .map(|(&offset, &id)| (offset - src.offset, id))
+                .collect();
+            (alloc.bytes[range].to_vec(), undef, relocations)
+        };
+        // Copying only part of a pointer tears it apart.
+        if self.relocations(src, size)?.len() != relocations.len() ||
+           relocations.iter().any(|&(offset, _)| offset + self.pointer_size > size) {
+            return Err(EvalErrorKind::ReadPointerAsBytes.into());
+        }
+        self.write_bytes(dest, &bytes)?;
+        let alloc = self.get_mut(dest.alloc_id)?;
+        for (i, defined) in undef.into_iter().enumerate() {
+            alloc.undef_mask.set(dest.offset + i as u64, defined);
+        }
+        for (offset, id) in relocations {
+            alloc.relocations.insert(dest.offset + offset, id);
+        }
+        Ok(())
+    }
+
+    fn decode(&self, bytes: &[u8]) -> u64 {
+        let mut value = 0;
+        match self.endian {
+            Endian::Little => {
+                for &b in bytes.iter().rev() {
+                    value = (value << 8) | b as u64;
+                }
+            }
+            Endian::Big => {
+                for &b in bytes {
+                    value = (value << 8) | b as u64;
+                }
+            }
+        }
+        value
+    }
+
+    pub fn read_uint(&self, ptr: Pointer, size: u64) -> EvalResult<u64> {
+        self.read_bytes(ptr, size).map(|bytes| self.decode(bytes))
+    }
+
+    pub fn write_uint(&mut self, ptr: Pointer, value: u64, size: u64) -> EvalResult<()> {
+        let endian = self.endian;
+        let bytes = self.write_bytes_mut(ptr, size)?;
+        for i in 0..size as usize {
+            let shift = match endian {
+                Endian::Little => i,
+                Endian::Big => size as usize - 1 - i,
+            };
+            bytes[i] = (value >> (8 * shift)) as u8;
+        }
+        Ok(())
+    }
+
+    pub fn read_ptr(&self, ptr: Pointer) -> EvalResult<PrimVal> {
+        let size = self.pointer_size;
+        let alloc = self.get(ptr.alloc_id)?;
+        Memory::check_bounds(alloc, ptr, size)?;
+        if !alloc.undef_mask.is_range_defined(ptr.offset, ptr.offset + size) {
+            return Ok(PrimVal::Undef);
+        }
+        match alloc.relocations.get(&ptr.offset) {
+            Some(&id) => {
+                // The pointer's offset is stored in the bytes it covers.
+                let bytes = &alloc.bytes[ptr.offset as usize..(ptr.offset + size) as usize];
+                Ok(PrimVal::Ptr(Pointer::new(id, self.decode(bytes))))
+            }
+            None => self.read_uint(ptr, size).map(PrimVal::Bytes),
+        }
+    }
+
+    pub fn write_ptr(&mut self, dest: Pointer, ptr: Pointer) -> EvalResult<()> {
+        let size = self.pointer_size;
+        self.write_uint(dest, ptr.offset, size)?;
+        self.get_mut(dest.alloc_id)?.relocations.insert(dest.offset, ptr.alloc_id);
+        Ok(())
+    }
+
+    /// Reads a primitive value of `size` bytes. Only pointer-sized reads
+    /// can produce pointers.
+    pub fn read_primval(&self, ptr: Pointer, size: u64) -> EvalResult<PrimVal> {
+        if size == self.pointer_size {
+            return self.read_ptr(ptr);
+        }
+        {
+            let alloc = self.get(ptr.alloc_id)?;
+            Memory::check_bounds(alloc, ptr, size)?;
+            if !alloc.undef_mask.is_range_defined(ptr.offset, ptr.offset + size) {
+                return Ok(PrimVal::Undef);
+            }
+        }
+        self.read_uint(ptr, size).map(PrimVal::Bytes)
+    }
+
+    pub fn write_primval(&mut self, dest: Pointer, val: PrimVal, size: u64) -> EvalResult<()> {
+        match val {
+            PrimVal::Bytes(bytes) => self.write_uint(dest, bytes, size),
+            PrimVal::Ptr(ptr) => {
+                if size != self.pointer_size {
+                    return Err(EvalErrorKind::ReadPointerAsBytes.into());
+                }
+                self.write_ptr(dest, ptr)
+            }
+            PrimVal::Undef => self.mark_undef(dest, size),
+        }
+    }
+}
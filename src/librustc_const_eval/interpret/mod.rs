@@ -0,0 +1,171 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Constant evaluation by interpreting MIR.
+//!
+//! Unlike the evaluator in `eval`, which folds HIR expressions, this
+//! executes the MIR of constants and `const fn`s statement by statement,
+//! so it supports `let` bindings, assignments, branches and loops.
+//!
+//! Values live in an abstract `Memory` made of separate allocations, one
+//! for each local of each call frame and one for each evaluated constant.
+//! Pointers are an allocation and an offset into it rather than an
+//! address, so they can't be inspected as integers; the interpreter
+//! reports an error whenever a constant would depend on the address of
+//! something. Reading uninitialized memory is an error as well.
+//!
+//! Errors carry the span of the statement or terminator that raised
+//! them.
+//!
+//! This doesn't replace `eval` yet: constant expressions are still folded
+//! on HIR there, and only the values of the constants they refer to and the
+//! results of the `const fn`s they call are computed here. Translation
+//! evaluates whole constants here.
+//!
+//! The entry points return `None` rather than evaluating when the value
+//! can't be computed on MIR: before the crate is ready for MIR
+//! construction (i.e. during type-checking, which evaluates array lengths
+//! and patterns), when it depends on type parameters, or when its type
+//! has no `ConstVal` representation that doesn't refer to the HIR. The
+//! HIR evaluator in `eval` covers those cases. Once the MIR of local items
+//! can be built, it is built on demand, so which evaluator is used never
+//! depends on the order items are visited in. Translation only translates
+//! the MIR of constants to LLVM constants when their values can't be
+//! represented, e.g. for aggregates.
+
+use rustc::hir;
+use rustc::hir::def_id::DefId;
+use rustc::hir::intravisit::FnKind;
+use rustc::hir::map::blocks::FnLikeNode;
+use rustc::middle::const_val::ConstVal;
+use rustc::ty::{Ty, TyCtxt, TypeFoldable};
+use rustc::ty::subst::Substs;
+use rustc::mir::{Local, Mir};
+use rustc_data_structures::indexed_vec::Idx;
+
+use std::cell::Ref;
+
+mod error;
+mod eval_context;
+mod memory;
+mod operator;
+mod step;
+mod value;
+
+pub use self::error::{EvalError, EvalErrorKind, EvalResult};
+pub use self::eval_context::{EvalContext, GlobalId, STEP_LIMIT, is_const_val_ty};
+
+/// Whether the MIR of `def_id` is available, either because it was encoded
+/// in the metadata of its crate or because it is local and MIR construction
+/// has started, in which case it is built now if it wasn't yet.
+pub fn has_mir(tcx: TyCtxt, def_id: DefId) -> bool {
+    if !def_id.is_local() {
+        return tcx.sess.cstore.is_item_mir_available(def_id);
+    }
+    if tcx.mir_map.borrow().contains_key(&def_id) {
+        return true;
+    }
+    match tcx.mir_builder.get() {
+        Some(build) => build(tcx.global_tcx(), def_id),
+        None => false,
+    }
+}
+
+fn is_const_fn(tcx: TyCtxt, def_id: DefId) -> bool {
+    if let Some(node_id) = tcx.map.as_local_node_id(def_id) {
+        match FnLikeNode::from_node(tcx.map.get(node_id)).map(|f| f.kind()) {
+            Some(FnKind::ItemFn(_, _, _, c, ..)) => {
+                c == hir::Constness::Const
+            }
+            Some(FnKind::Method(_, m, ..)) => {
+                m.constness == hir::Constness::Const
+            }
+            _ => false,
+        }
+    } else {
+        tcx.sess.cstore.is_const_fn(def_id)
+    }
+}
+
+/// Evaluates the constant `def_id` with the given substitutions. If it is
+/// declared in a trait, the value from the selected impl is used. Returns
+/// `None` if it can't be evaluated on MIR, see the module documentation.
+pub fn eval_const<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                            def_id: DefId,
+                            substs: &'tcx Substs<'tcx>)
+                            -> Option<EvalResult<ConstVal>> {
+    let substs = tcx.erase_regions(&substs);
+    if substs.needs_subst() || !has_mir(tcx, def_id) {
+        return None;
+    }
+
+    let mut ecx = EvalContext::new(tcx);
+    let ty = match ecx.monomorphize(tcx.item_type(def_id), substs) {
+        Ok(ty) if is_const_val_ty(ty) => ty,
+        _ => return None,
+    };
+    Some(ecx.resolve_global(def_id, substs).and_then(|id| {
+        let ptr = ecx.global_ptr(id)?;
+        ecx.read_const_val(ptr, ty)
+    }))
+}
+
+/// Calls the `const fn` `def_id` with the already evaluated `args`.
+/// Returns `None` if it can't be evaluated on MIR, see the module
+/// documentation.
+pub fn eval_const_fn_call<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                    def_id: DefId,
+                                    substs: &'tcx Substs<'tcx>,
+                                    args: &[ConstVal])
+                                    -> Option<EvalResult<ConstVal>> {
+    let substs = tcx.erase_regions(&substs);
+    if substs.needs_subst() || !has_mir(tcx, def_id) {
+        return None;
+    }
+    if !is_const_fn(tcx, def_id) {
+        return Some(Err(EvalErrorKind::NonConstFn(tcx.item_path_str(def_id)).into()));
+    }
+
+    let mut ecx = EvalContext::new(tcx);
+    let mir = tcx.item_mir(def_id);
+    let ret_ty = match ecx.monomorphize(mir.return_ty, substs) {
+        Ok(ty) if is_const_val_ty(ty) => ty,
+        _ => return None,
+    };
+    if mir.arg_count != args.len() {
+        bug!("`{}` called with {} arguments instead of {}",
+             tcx.item_path_str(def_id), args.len(), mir.arg_count);
+    }
+    Some(call_const_fn(&mut ecx, def_id, substs, mir, ret_ty, args))
+}
+
+fn call_const_fn<'a, 'tcx>(ecx: &mut EvalContext<'a, 'tcx>,
+                           def_id: DefId,
+                           substs: &'tcx Substs<'tcx>,
+                           mir: Ref<'tcx, Mir<'tcx>>,
+                           ret_ty: Ty<'tcx>,
+                           args: &[ConstVal])
+                           -> EvalResult<ConstVal> {
+    let mut arg_tys = Vec::with_capacity(args.len());
+    for local in mir.args_iter() {
+        arg_tys.push(ecx.monomorphize(mir.local_decls[local].ty, substs)?);
+    }
+
+    let ret_size = ecx.type_size(ret_ty)?;
+    let ret_ptr = ecx.memory.allocate(ret_size);
+    ecx.push_frame(def_id, substs, mir, ret_ptr, None)?;
+    for (i, (arg, &ty)) in args.iter().zip(&arg_tys).enumerate() {
+        let val = ecx.const_to_value(arg, ty)?;
+        let ptr = ecx.frame().locals[Local::new(i + 1)];
+        ecx.write_value(val, ptr, ty)?;
+    }
+    ecx.run(0)?;
+    ecx.read_const_val(ret_ptr, ret_ty)
+}
@@ -0,0 +1,265 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Arithmetic, comparisons and casts on primitive values.
+//!
+//! Integers are computed on 64 bits and truncated to the size of their
+//! type, which is also how overflow is detected: an operation overflows
+//! iff truncating its result changes its value.
+
+use rustc::mir::{BinOp, CastKind, UnOp};
+use rustc::ty::{self, Ty};
+use rustc_const_math::ConstMathErr;
+use syntax::ast::FloatTy;
+
+use super::error::{EvalResult, EvalErrorKind};
+use super::eval_context::{EvalContext, sign_extend, truncate};
+use super::value::PrimVal;
+
+impl<'a, 'tcx> EvalContext<'a, 'tcx> {
+    /// Applies `op`, returning the result and whether it overflowed. The
+    /// result of an overflowing operation is the wrapped-around value.
+    pub fn binary_op(&self,
+                     op: BinOp,
+                     left: PrimVal,
+                     left_ty: Ty<'tcx>,
+                     right: PrimVal,
+                     right_ty: Ty<'tcx>)
+                     -> EvalResult<(PrimVal, bool)> {
+        // Pointers into allocations have no address, so all we can tell
+        // is whether they are the same.
+        if let (PrimVal::Ptr(l), PrimVal::Ptr(r)) = (left, right) {
+            return match op {
+                BinOp::Eq => Ok((PrimVal::from_bool(l == r), false)),
+                BinOp::Ne => Ok((PrimVal::from_bool(l != r), false)),
+                _ => Err(EvalErrorKind::ReadPointerAsBytes.into()),
+            };
+        }
+
+        let l = left.to_bytes()?;
+        let r = right.to_bytes()?;
+
+        if let ty::TyFloat(float_ty) = left_ty.sty {
+            return self.float_binary_op(op, float_ty, l, r).map(|val| (val, false));
+        }
+
+        let (size, signed) = match self.int_info(left_ty)? {
+            Some(info) => info,
+            None => bug!("binary operation on non-primitive type `{}`", left_ty),
+        };
+
+        if op == BinOp::Shl || op == BinOp::Shr {
+            return self.shift_op(op, l, size, signed, r, right_ty);
+        }
+
+        let bool_result = |b: bool| Ok((PrimVal::from_bool(b), false));
+        if signed {
+            let l = sign_extend(l, size) as i64;
+            let r = sign_extend(r, size) as i64;
+            let (result, overflowed) = match op {
+                BinOp::Eq => return bool_result(l == r),
+                BinOp::Ne => return bool_result(l != r),
+                BinOp::Lt => return bool_result(l < r),
+                BinOp::Le => return bool_result(l <= r),
+                BinOp::Gt => return bool_result(l > r),
+                BinOp::Ge => return bool_result(l >= r),
+                BinOp::BitAnd => (l & r, false),
+                BinOp::BitOr => (l | r, false),
+                BinOp::BitXor => (l ^ r, false),
+                BinOp::Add => l.overflowing_add(r),
+                BinOp::Sub => l.overflowing_sub(r),
+                BinOp::Mul => l.overflowing_mul(r),
+                BinOp::Div if r == 0 => return Err(ConstMathErr::DivisionByZero.into()),
+                BinOp::Rem if r == 0 => return Err(ConstMathErr::RemainderByZero.into()),
+                BinOp::Div => l.overflowing_div(r),
+                BinOp::Rem => l.overflowing_rem(r),
+                BinOp::Shl | BinOp::Shr => unreachable!(),
+            };
+            let truncated = truncate(result as u64, size);
+            let overflowed = overflowed || sign_extend(truncated, size) as i64 != result;
+            Ok((PrimVal::Bytes(truncated), overflowed))
+        } else {
+            let (result, overflowed) = match op {
+                BinOp::Eq => return bool_result(l == r),
+                BinOp::Ne => return bool_result(l != r),
+                BinOp::Lt => return bool_result(l < r),
+                BinOp::Le => return bool_result(l <= r),
+                BinOp::Gt => return bool_result(l > r),
+                BinOp::Ge => return bool_result(l >= r),
+                BinOp::BitAnd => (l & r, false),
+                BinOp::BitOr => (l | r, false),
+                BinOp::BitXor => (l ^ r, false),
+                BinOp::Add => l.overflowing_add(r),
+                BinOp::Sub => l.overflowing_sub(r),
+                BinOp::Mul => l.overflowing_mul(r),
+                BinOp::Div if r == 0 => return Err(ConstMathErr::DivisionByZero.into()),
+                BinOp::Rem if r == 0 => return Err(ConstMathErr::RemainderByZero.into()),
+                BinOp::Div => l.overflowing_div(r),
+                BinOp::Rem => l.overflowing_rem(r),
+                BinOp::Shl | BinOp::Shr => unreachable!(),
+            };
+            let truncated = truncate(result, size);
+            Ok((PrimVal::Bytes(truncated), overflowed || truncated != result))
+        }
+    }
+
+    /// Shifts by the amount `r` of type `right_ty`. Shifting by the bit
+    /// width or more, or by a negative amount, overflows; the amount is
+    /// then masked like the hardware would.
+    fn shift_op(&self, op: BinOp, l: u64, size: u64, signed: bool, r: u64, right_ty: Ty<'tcx>)
+                -> EvalResult<(PrimVal, bool)> {
+        let bits = size * 8;
+        let (right_size, right_signed) = match self.int_info(right_ty)? {
+            Some(info) => info,
+            None => bug!("shift by a value of non-integer type `{}`", right_ty),
+        };
+        let negative = right_signed && (sign_extend(r, right_size) as i64) < 0;
+        let overflowed = negative || r >= bits;
+        let amount = (r % bits) as u32;
+
+        let result = match op {
+            BinOp::Shl => l << amount,
+            BinOp::Shr if signed => ((sign_extend(l, size) as i64) >> amount) as u64,
+            BinOp::Shr => l >> amount,
+            _ => bug!("{:?} is not a shift", op),
+        };
+        Ok((PrimVal::Bytes(truncate(result, size)), overflowed))
+    }
+
+    fn float_binary_op(&self, op: BinOp, float_ty: FloatTy, l: u64, r: u64)
+                       -> EvalResult<PrimVal> {
+        macro_rules! float_op {
+            ($l:expr, $r:expr, $from:path) => {{
+                let (l, r) = ($l, $r);
+                match op {
+                    BinOp::Eq => PrimVal::from_bool(l == r),
+                    BinOp::Ne => PrimVal::from_bool(l != r),
+                    BinOp::Lt => PrimVal::from_bool(l < r),
+                    BinOp::Le => PrimVal::from_bool(l <= r),
+                    BinOp::Gt => PrimVal::from_bool(l > r),
+                    BinOp::Ge => PrimVal::from_bool(l >= r),
+                    BinOp::Add => $from(l + r),
+                    BinOp::Sub => $from(l - r),
+                    BinOp::Mul => $from(l * r),
+                    BinOp::Div => $from(l / r),
+                    BinOp::Rem => $from(l % r),
+                    _ => bug!("invalid float operation {:?}", op),
+                }
+            }}
+        }
+        Ok(match float_ty {
+            FloatTy::F32 => {
+                float_op!(PrimVal::Bytes(l).to_f32()?, PrimVal::Bytes(r).to_f32()?,
+                          PrimVal::from_f32)
+            }
+            FloatTy::F64 => {
+                float_op!(PrimVal::Bytes(l).to_f64()?, PrimVal::Bytes(r).to_f64()?,
+                          PrimVal::from_f64)
+            }
+        })
+    }
+
+    /// Applies `op`. Negation wraps around; MIR checks for overflow with a
+    /// separate assertion.
+    pub fn unary_op(&self, op: UnOp, val: PrimVal, ty: Ty<'tcx>) -> EvalResult<PrimVal> {
+        let bits = val.to_bytes()?;
+        match (op, &ty.sty) {
+            (UnOp::Not, &ty::TyBool) => Ok(PrimVal::from_bool(!val.to_bool()?)),
+            (UnOp::Neg, &ty::TyFloat(FloatTy::F32)) => Ok(PrimVal::from_f32(-val.to_f32()?)),
+            (UnOp::Neg, &ty::TyFloat(FloatTy::F64)) => Ok(PrimVal::from_f64(-val.to_f64()?)),
+            _ => {
+                let (size, _) = match self.int_info(ty)? {
+                    Some(info) => info,
+                    None => bug!("unary operation on non-primitive type `{}`", ty),
+                };
+                let result = match op {
+                    UnOp::Not => !bits,
+                    UnOp::Neg => (bits as i64).wrapping_neg() as u64,
+                };
+                Ok(PrimVal::Bytes(truncate(result, size)))
+            }
+        }
+    }
+
+    /// Casts `val` from `src_ty` to `dest_ty`, for the casts that only
+    /// depend on the value itself.
+    pub fn cast_primval(&mut self,
+                        kind: CastKind,
+                        val: PrimVal,
+                        src_ty: Ty<'tcx>,
+                        dest_ty: Ty<'tcx>)
+                        -> EvalResult<PrimVal> {
+        match kind {
+            CastKind::Misc => {}
+            CastKind::ReifyFnPointer => {
+                return match src_ty.sty {
+                    ty::TyFnDef(def_id, substs, _) => {
+                        Ok(PrimVal::Ptr(self.memory.create_fn_ptr(def_id, substs)))
+                    }
+                    _ => bug!("reified a non-function item of type `{}`", src_ty),
+                };
+            }
+            CastKind::UnsafeFnPointer => return Ok(val),
+            CastKind::Unsize => bug!("unsizing casts produce fat pointers"),
+        }
+
+        // Casting a pointer to another pointer type keeps it intact, but
+        // it has no address to be turned into an integer.
+        if let PrimVal::Ptr(_) = val {
+            return match dest_ty.sty {
+                ty::TyRawPtr(_) | ty::TyRef(..) | ty::TyFnPtr(_) => Ok(val),
+                _ => Err(EvalErrorKind::ReadPointerAsBytes.into()),
+            };
+        }
+        let bits = val.to_bytes()?;
+
+        if let ty::TyFloat(src_float) = src_ty.sty {
+            let f = match src_float {
+                FloatTy::F32 => val.to_f32()? as f64,
+                FloatTy::F64 => val.to_f64()?,
+            };
+            return match dest_ty.sty {
+                ty::TyFloat(FloatTy::F32) => Ok(PrimVal::from_f32(f as f32)),
+                ty::TyFloat(FloatTy::F64) => Ok(PrimVal::from_f64(f)),
+                _ => {
+                    let (size, signed) = match self.int_info(dest_ty)? {
+                        Some(info) => info,
+                        None => bug!("invalid cast from `{}` to `{}`", src_ty, dest_ty),
+                    };
+                    let result = if signed { f as i64 as u64 } else { f as u64 };
+                    Ok(PrimVal::Bytes(truncate(result, size)))
+                }
+            };
+        }
+
+        let (src_size, src_signed) = match self.int_info(src_ty)? {
+            Some(info) => info,
+            None => bug!("invalid cast from `{}` to `{}`", src_ty, dest_ty),
+        };
+        let extended = if src_signed { sign_extend(bits, src_size) } else { bits };
+        match dest_ty.sty {
+            ty::TyFloat(FloatTy::F32) if src_signed => {
+                Ok(PrimVal::from_f32(extended as i64 as f32))
+            }
+            ty::TyFloat(FloatTy::F64) if src_signed => {
+                Ok(PrimVal::from_f64(extended as i64 as f64))
+            }
+            ty::TyFloat(FloatTy::F32) => Ok(PrimVal::from_f32(extended as f32)),
+            ty::TyFloat(FloatTy::F64) => Ok(PrimVal::from_f64(extended as f64)),
+            _ => {
+                let (size, _) = match self.int_info(dest_ty)? {
+                    Some(info) => info,
+                    None => bug!("invalid cast from `{}` to `{}`", src_ty, dest_ty),
+                };
+                Ok(PrimVal::Bytes(truncate(extended, size)))
+            }
+        }
+    }
+}
@@ -0,0 +1,371 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Executing statements and terminators.
+
+use rustc::mir::*;
+use rustc::ty::{self, Ty};
+use rustc_data_structures::indexed_vec::Idx;
+
+use super::error::{EvalError, EvalResult, EvalErrorKind};
+use super::eval_context::{EvalContext, LvalueExtra};
+use super::memory::Pointer;
+use super::value::{PrimVal, Value};
+use super::is_const_fn;
+
+impl<'a, 'tcx> EvalContext<'a, 'tcx> {
+    /// Executes the next statement, or the terminator, of the current
+    /// block of the topmost frame. Errors get the span of what was being
+    /// executed, unless they come from a more deeply nested evaluation.
+    pub fn step(&mut self) -> EvalResult<()> {
+        let (statement, terminator) = {
+            let frame = self.frame();
+            let block = &frame.mir[frame.block];
+            match block.statements.get(frame.stmt) {
+                Some(statement) => (Some(statement.clone()), None),
+                None => (None, Some(block.terminator().clone())),
+            }
+        };
+        let span = match (&statement, &terminator) {
+            (&Some(ref statement), _) => statement.source_info.span,
+            (_, &Some(ref terminator)) => terminator.source_info.span,
+            _ => unreachable!(),
+        };
+
+        if self.steps_remaining == 0 {
+            let err = EvalError::from(EvalErrorKind::ExecutionTimeLimitReached);
+            return Err(err.or_span(span));
+        }
+        self.steps_remaining -= 1;
+
+        let result = match (statement, terminator) {
+            (Some(statement), _) => {
+                self.frame_mut().stmt += 1;
+                self.statement(&statement)
+            }
+            (_, Some(terminator)) => self.terminator(&terminator),
+            _ => unreachable!(),
+        };
+        result.map_err(|err| err.or_span(span))
+    }
+
+    fn statement(&mut self, statement: &Statement<'tcx>) -> EvalResult<()> {
+        match statement.kind {
+            StatementKind::Assign(ref lvalue, ref rvalue) => {
+                self.eval_rvalue_into_lvalue(rvalue, lvalue)
+            }
+            StatementKind::SetDiscriminant { ref lvalue, variant_index } => {
+                let dest = self.eval_lvalue(lvalue)?.ptr;
+                let ty = self.lvalue_ty(lvalue)?;
+                self.write_discriminant(dest, ty, variant_index)
+            }
+            // Each local has an allocation for the whole call, so there is
+            // nothing to do when its storage starts or ends.
+            StatementKind::StorageLive(_) |
+            StatementKind::StorageDead(_) |
+            StatementKind::Nop => Ok(()),
+        }
+    }
+
+    fn eval_rvalue_into_lvalue(&mut self, rvalue: &Rvalue<'tcx>, lvalue: &Lvalue<'tcx>)
+                               -> EvalResult<()> {
+        let dest = self.eval_lvalue(lvalue)?.ptr;
+        let dest_ty = self.lvalue_ty(lvalue)?;
+
+        match *rvalue {
+            Rvalue::Use(ref operand) => {
+                let val = self.eval_operand(operand)?;
+                self.write_value(val, dest, dest_ty)
+            }
+
+            Rvalue::Repeat(ref operand, ref count) => {
+                let val = self.eval_operand(operand)?;
+                let elem_ty = dest_ty.sequence_element_type(self.tcx);
+                let elem_size = self.type_size(elem_ty)?;
+                let count = count.value.as_u64(self.tcx.sess.target.uint_type);
+                for i in 0..count {
+                    self.write_value(val, dest.offset(i * elem_size), elem_ty)?;
+                }
+                Ok(())
+            }
+
+            Rvalue::Ref(_, _, ref lvalue) => {
+                let lvalue = self.eval_lvalue(lvalue)?;
+                let ptr = PrimVal::Ptr(lvalue.ptr);
+                let val = match lvalue.extra {
+                    LvalueExtra::None => Value::ByVal(ptr),
+                    LvalueExtra::Length(len) => Value::ByValPair(ptr, PrimVal::Bytes(len)),
+                    LvalueExtra::DowncastVariant(_) => {
+                        bug!("took a reference to an enum downcast lvalue")
+                    }
+                };
+                self.write_value(val, dest, dest_ty)
+            }
+
+            Rvalue::Len(ref lvalue) => {
+                let ty = self.lvalue_ty(lvalue)?;
+                let lvalue = self.eval_lvalue(lvalue)?;
+                let len = self.lvalue_len(lvalue, ty)?;
+                self.write_value(Value::ByVal(PrimVal::Bytes(len)), dest, dest_ty)
+            }
+
+            Rvalue::Cast(kind, ref operand, cast_ty) => {
+                let cast_ty = self.frame_ty(cast_ty)?;
+                let val = self.eval_cast(kind, operand, cast_ty)?;
+                self.write_value(val, dest, dest_ty)
+            }
+
+            Rvalue::BinaryOp(op, ref left, ref right) => {
+                let (val, _) = self.eval_binary_op(op, left, right)?;
+                self.write_value(Value::ByVal(val), dest, dest_ty)
+            }
+
+            Rvalue::CheckedBinaryOp(op, ref left, ref right) => {
+                let (val, overflowed) = self.eval_binary_op(op, left, right)?;
+                // The result is a `(T, bool)` pair.
+                let tys = match dest_ty.sty {
+                    ty::TyTuple(tys) => tys,
+                    _ => bug!("checked binary operation into non-tuple type `{}`", dest_ty),
+                };
+                let val_offset = self.field_offset(dest_ty, LvalueExtra::None, 0)?;
+                let flag_offset = self.field_offset(dest_ty, LvalueExtra::None, 1)?;
+                self.write_value(Value::ByVal(val), dest.offset(val_offset), tys[0])?;
+                self.write_value(Value::ByVal(PrimVal::from_bool(overflowed)),
+                                 dest.offset(flag_offset), tys[1])
+            }
+
+            Rvalue::UnaryOp(op, ref operand) => {
+                let ty = self.operand_ty(operand)?;
+                let val = self.eval_operand_to_primval(operand)?;
+                let val = self.unary_op(op, val, ty)?;
+                self.write_value(Value::ByVal(val), dest, dest_ty)
+            }
+
+            Rvalue::Aggregate(ref kind, ref operands) => {
+                self.write_aggregate(kind, operands, dest, dest_ty)
+            }
+
+            Rvalue::Box(_) => {
+                Err(EvalErrorKind::Unimplemented("heap allocations".to_string()).into())
+            }
+
+            Rvalue::InlineAsm { .. } => {
+                Err(EvalErrorKind::Unimplemented("inline assembly".to_string()).into())
+            }
+        }
+    }
+
+    fn eval_binary_op(&mut self, op: BinOp, left: &Operand<'tcx>, right: &Operand<'tcx>)
+                      -> EvalResult<(PrimVal, bool)> {
+        let left_ty = self.operand_ty(left)?;
+        let right_ty = self.operand_ty(right)?;
+        let left = self.eval_operand_to_primval(left)?;
+        let right = self.eval_operand_to_primval(right)?;
+        self.binary_op(op, left, left_ty, right, right_ty)
+    }
+
+    fn eval_cast(&mut self, kind: CastKind, operand: &Operand<'tcx>, cast_ty: Ty<'tcx>)
+                 -> EvalResult<Value> {
+        let src_ty = self.operand_ty(operand)?;
+        let val = self.eval_operand(operand)?;
+        if kind != CastKind::Unsize {
+            let prim = match val {
+                Value::ByVal(prim) => prim,
+                _ => bug!("cast from a non-primitive value of type `{}`", src_ty),
+            };
+            return self.cast_primval(kind, prim, src_ty, cast_ty).map(Value::ByVal);
+        }
+
+        // The only unsizing supported is from a pointer to an array to a
+        // pointer to a slice, which adds the length of the array.
+        let pointee = |ty: Ty<'tcx>| match ty.sty {
+            ty::TyRef(_, ty::TypeAndMut { ty, .. }) |
+            ty::TyRawPtr(ty::TypeAndMut { ty, .. }) => Some(ty),
+            _ => None,
+        };
+        match (pointee(src_ty).map(|ty| &ty.sty), pointee(cast_ty).map(|ty| &ty.sty), val) {
+            (Some(&ty::TyArray(_, len)), Some(&ty::TySlice(_)), Value::ByVal(ptr)) => {
+                Ok(Value::ByValPair(ptr, PrimVal::Bytes(len as u64)))
+            }
+            _ => {
+                Err(EvalErrorKind::Unimplemented(
+                    format!("unsizing `{}` to `{}`", src_ty, cast_ty)).into())
+            }
+        }
+    }
+
+    fn write_aggregate(&mut self,
+                       kind: &AggregateKind<'tcx>,
+                       operands: &[Operand<'tcx>],
+                       dest: Pointer,
+                       dest_ty: Ty<'tcx>)
+                       -> EvalResult<()> {
+        let extra = match *kind {
+            AggregateKind::Array => {
+                let elem_ty = dest_ty.sequence_element_type(self.tcx);
+                let elem_size = self.type_size(elem_ty)?;
+                for (i, operand) in operands.iter().enumerate() {
+                    let val = self.eval_operand(operand)?;
+                    self.write_value(val, dest.offset(i as u64 * elem_size), elem_ty)?;
+                }
+                return Ok(());
+            }
+            AggregateKind::Tuple | AggregateKind::Closure(..) => LvalueExtra::None,
+            AggregateKind::Adt(adt_def, variant, _, active_field) => {
+                if let Some(field) = active_field {
+                    // Unions are initialized through a single field.
+                    assert_eq!(operands.len(), 1);
+                    let ty = self.operand_ty(&operands[0])?;
+                    let offset = self.field_offset(dest_ty, LvalueExtra::None, field)?;
+                    let val = self.eval_operand(&operands[0])?;
+                    return self.write_value(val, dest.offset(offset), ty);
+                }
+                self.write_discriminant(dest, dest_ty, variant)?;
+                if adt_def.is_enum() {
+                    LvalueExtra::DowncastVariant(variant)
+                } else {
+                    LvalueExtra::None
+                }
+            }
+        };
+
+        for (i, operand) in operands.iter().enumerate() {
+            let ty = self.operand_ty(operand)?;
+            let offset = self.field_offset(dest_ty, extra, i)?;
+            let val = self.eval_operand(operand)?;
+            self.write_value(val, dest.offset(offset), ty)?;
+        }
+        Ok(())
+    }
+
+    fn terminator(&mut self, terminator: &Terminator<'tcx>) -> EvalResult<()> {
+        match terminator.kind {
+            TerminatorKind::Goto { target } => {
+                self.goto(target);
+                Ok(())
+            }
+
+            TerminatorKind::If { ref cond, targets: (then_block, else_block) } => {
+                let cond = self.eval_operand_to_primval(cond)?.to_bool()?;
+                self.goto(if cond { then_block } else { else_block });
+                Ok(())
+            }
+
+            TerminatorKind::Switch { ref discr, ref targets, .. } => {
+                let ty = self.lvalue_ty(discr)?;
+                let ptr = self.eval_lvalue(discr)?.ptr;
+                let variant = self.read_discriminant(ptr, ty)?;
+                self.goto(targets[variant]);
+                Ok(())
+            }
+
+            TerminatorKind::SwitchInt { ref discr, switch_ty, ref values, ref targets } => {
+                let switch_ty = self.frame_ty(switch_ty)?;
+                let ptr = self.eval_lvalue(discr)?.ptr;
+                let discr = match self.read_value(ptr, switch_ty)? {
+                    Value::ByVal(prim) => prim.to_bytes()?,
+                    _ => bug!("switched on a value of non-primitive type `{}`", switch_ty),
+                };
+                let mut target = targets[values.len()];
+                for (value, &value_target) in values.iter().zip(targets) {
+                    if self.const_to_value(value, switch_ty)? ==
+                       Value::ByVal(PrimVal::Bytes(discr)) {
+                        target = value_target;
+                        break;
+                    }
+                }
+                self.goto(target);
+                Ok(())
+            }
+
+            TerminatorKind::Return => self.pop_frame(),
+
+            TerminatorKind::Unreachable => Err(EvalErrorKind::Unreachable.into()),
+
+            // Only reachable while unwinding.
+            TerminatorKind::Resume => Err(EvalErrorKind::Panic.into()),
+
+            // Constants can't hold values with destructors, so dropping is
+            // always a no-op.
+            TerminatorKind::Drop { target, .. } => {
+                self.goto(target);
+                Ok(())
+            }
+
+            TerminatorKind::DropAndReplace { ref location, ref value, target, .. } => {
+                let dest = self.eval_lvalue(location)?.ptr;
+                let ty = self.lvalue_ty(location)?;
+                let val = self.eval_operand(value)?;
+                self.write_value(val, dest, ty)?;
+                self.goto(target);
+                Ok(())
+            }
+
+            TerminatorKind::Call { ref func, ref args, ref destination, .. } => {
+                self.eval_call(func, args, destination)
+            }
+
+            TerminatorKind::Assert { ref cond, expected, ref msg, target, .. } => {
+                let cond = self.eval_operand_to_primval(cond)?.to_bool()?;
+                if cond == expected {
+                    self.goto(target);
+                    return Ok(());
+                }
+                match *msg {
+                    AssertMessage::BoundsCheck { ref len, ref index } => {
+                        let len = self.eval_operand_to_primval(len)?.to_bytes()?;
+                        let index = self.eval_operand_to_primval(index)?.to_bytes()?;
+                        Err(EvalErrorKind::IndexOutOfBounds { len: len, index: index }.into())
+                    }
+                    AssertMessage::Math(ref err) => Err(EvalErrorKind::Math(err.clone()).into()),
+                }
+            }
+        }
+    }
+
+    fn eval_call(&mut self,
+                 func: &Operand<'tcx>,
+                 args: &[Operand<'tcx>],
+                 destination: &Option<(Lvalue<'tcx>, BasicBlock)>)
+                 -> EvalResult<()> {
+        let func_ty = self.operand_ty(func)?;
+        let (def_id, substs) = match func_ty.sty {
+            ty::TyFnDef(def_id, substs, _) => (def_id, substs),
+            ty::TyFnPtr(_) => {
+                let ptr = self.eval_operand_to_primval(func)?.to_ptr()?;
+                self.memory.get_fn(ptr)?
+            }
+            _ => bug!("called a value of non-function type `{}`", func_ty),
+        };
+        if !is_const_fn(self.tcx, def_id) {
+            return Err(EvalErrorKind::NonConstFn(self.tcx.item_path_str(def_id)).into());
+        }
+        let (dest, target) = match *destination {
+            Some((ref lvalue, target)) => (self.eval_lvalue(lvalue)?.ptr, target),
+            None => {
+                return Err(EvalErrorKind::Unimplemented(
+                    "calls to diverging functions".to_string()).into());
+            }
+        };
+
+        let mut arg_vals = Vec::with_capacity(args.len());
+        for arg in args {
+            arg_vals.push((self.eval_operand(arg)?, self.operand_ty(arg)?));
+        }
+
+        let mir = self.load_mir(def_id)?;
+        self.push_frame(def_id, substs, mir, dest, Some(target))?;
+        for (i, (val, ty)) in arg_vals.into_iter().enumerate() {
+            let ptr = self.frame().locals[Local::new(i + 1)];
+            self.write_value(val, ptr, ty)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::mem::transmute;
+
+use super::error::{EvalResult, EvalErrorKind};
+use super::memory::Pointer;
+
+/// A primitive value: the contents of a scalar or of one half of a fat
+/// pointer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PrimVal {
+    /// The bits of an integer, `bool`, `char` or float, zero-extended to 64
+    /// bits. Whether they are signed is a property of the type they are
+    /// used at, not of the value.
+    Bytes(u64),
+
+    /// A pointer into an allocation. Integer-valued pointers, such as the
+    /// null pointer, are represented as `Bytes` instead.
+    Ptr(Pointer),
+
+    /// Uninitialized memory; it is an error to use it in a computation.
+    Undef,
+}
+
+impl PrimVal {
+    pub fn from_bool(b: bool) -> PrimVal {
+        PrimVal::Bytes(b as u64)
+    }
+
+    pub fn from_char(c: char) -> PrimVal {
+        PrimVal::Bytes(c as u64)
+    }
+
+    pub fn from_f32(f: f32) -> PrimVal {
+        PrimVal::Bytes(unsafe { transmute::<f32, u32>(f) } as u64)
+    }
+
+    pub fn from_f64(f: f64) -> PrimVal {
+        PrimVal::Bytes(unsafe { transmute::<f64, u64>(f) })
+    }
+
+    pub fn to_bytes(self) -> EvalResult<u64> {
+        match self {
+            PrimVal::Bytes(b) => Ok(b),
+            PrimVal::Ptr(_) => Err(EvalErrorKind::ReadPointerAsBytes.into()),
+            PrimVal::Undef => Err(EvalErrorKind::ReadUndefBytes.into()),
+        }
+    }
+
+    pub fn to_ptr(self) -> EvalResult<Pointer> {
+        match self {
+            PrimVal::Ptr(p) => Ok(p),
+            PrimVal::Bytes(_) => Err(EvalErrorKind::ReadBytesAsPointer.into()),
+            PrimVal::Undef => Err(EvalErrorKind::ReadUndefBytes.into()),
+        }
+    }
+
+    pub fn to_bool(self) -> EvalResult<bool> {
+        match self.to_bytes()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(EvalErrorKind::InvalidBool.into()),
+        }
+    }
+
+    pub fn to_char(self) -> EvalResult<char> {
+        let bits = self.to_bytes()?;
+        match ::std::char::from_u32(bits as u32) {
+            Some(c) if bits <= ::std::u32::MAX as u64 => Ok(c),
+            _ => Err(EvalErrorKind::InvalidChar(bits).into()),
+        }
+    }
+
+    pub fn to_f32(self) -> EvalResult<f32> {
+        self.to_bytes().map(|b| unsafe { transmute::<u32, f32>(b as u32) })
+    }
+
+    pub fn to_f64(self) -> EvalResult<f64> {
+        self.to_bytes().map(|b| unsafe { transmute::<u64, f64>(b) })
+    }
+}
+
+/// The value of an operand, in the representation that is cheapest to
+/// produce for its type.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    /// The value lives in memory at the given location; used for
+    /// aggregates, which are copied byte-wise.
+    ByRef(Pointer),
+
+    /// A scalar, a thin pointer, or the (undefined) value of a zero-sized
+    /// type.
+    ByVal(PrimVal),
+
+    /// A fat pointer: the data pointer followed by the length.
+    ByValPair(PrimVal, PrimVal),
+}
@@ -40,6 +40,7 @@ use rustc_plugin as plugin;
 use rustc_passes::{ast_validation, no_asm, loops, consts, rvalues,
                    static_recursion, hir_stats};
 use rustc_const_eval::check_match;
+use pretty;
 use super::Compilation;
 
 use serialize::json;
@@ -53,6 +54,7 @@ use std::path::{Path, PathBuf};
 use syntax::{ast, diagnostics, visit};
 use syntax::attr;
 use syntax::ext::base::ExtCtxt;
+use syntax::feature_gate::AttributeType;
 use syntax::parse::{self, PResult, token};
 use syntax::util::node_count::NodeCounter;
 use syntax;
@@ -731,12 +733,24 @@ pub fn phase_2_configure_and_expand<'a, F>(sess: &Session,
         println!("{}", json::as_json(&krate));
     }
 
+    if let Some(ref dir) = sess.opts.debugging_opts.dump_macro_expansions {
+        if let Err(e) = pretty::dump_macro_expansions(sess, &krate, Path::new(dir)) {
+            sess.err(&format!("could not dump macro expansions to `{}`: {}", dir, e));
+        }
+    }
+
     time(time_passes,
          "checking for inline asm in case the target doesn't support it",
          || no_asm::check_crate(sess, &krate));
 
     // Needs to go *after* expansion to be able to check the results of macro expansion.
     time(time_passes, "complete gated feature checking", || {
+        // Derive helper attributes are known to the compiler, but left to the
+        // `unused_attributes` lint wherever no derive has claimed them.
+        let mut attributes = attributes.clone();
+        attributes.extend(sess.derive_helper_attributes.borrow().iter().map(|&(ref name, _)| {
+            (name.clone(), AttributeType::Normal)
+        }));
         sess.track_errors(|| {
             syntax::feature_gate::check_crate(&krate,
                                               &sess.parse_sess,
@@ -945,6 +959,8 @@ pub fn phase_3_run_analysis_passes<'tcx, F, R>(sess: &'tcx Session,
             passes.push_pass(
                 box mir::transform::simplify_branches::SimplifyBranches::new("initial"));
             passes.push_pass(box mir::transform::simplify::SimplifyCfg::new("qualify-consts"));
+            // Reported here, rather than in trans, so the lint levels apply.
+            passes.push_pass(box mir::transform::const_prop::ConstPropLint);
             // And run everything.
             passes.run_passes(tcx);
         });
@@ -982,6 +998,22 @@ pub fn phase_3_run_analysis_passes<'tcx, F, R>(sess: &'tcx Session,
                                                        lib_features_used)
         });
 
+        time(time_passes, "tail call checking", || {
+            // Checks the functions marked `#[tail_recursive]` as they will be
+            // when `TailRecursion` runs in the MIR optimisations below.
+            let mut lint = mir::transform::tail_recursion::TailCallLint::new();
+            lint.push_pass(box mir::transform::no_landing_pads::NoLandingPads);
+            lint.push_pass(box mir::transform::erase_regions::EraseRegions);
+            lint.push_pass(box mir::transform::add_call_guards::AddCallGuards);
+            lint.push_pass(box borrowck::ElaborateDrops);
+            lint.push_pass(box mir::transform::no_landing_pads::NoLandingPads);
+            lint.push_pass(box mir::transform::simplify::SimplifyCfg::new("elaborate-drops"));
+
+            let mut passes = ::rustc::mir::transform::Passes::new();
+            passes.push_pass(box lint);
+            passes.run_passes(tcx);
+        });
+
         time(time_passes,
              "lint checking",
              || lint::check_crate(tcx, &analysis.access_levels));
@@ -1022,10 +1054,24 @@ pub fn phase_4_translate_to_llvm<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         passes.push_pass(box mir::transform::no_landing_pads::NoLandingPads);
         passes.push_pass(box mir::transform::simplify::SimplifyCfg::new("elaborate-drops"));
 
+        // Inlining happens after drop elaboration, which callee bodies from
+        // other crates have already been through.
+        passes.push_pass(box mir::transform::inline::Inline);
+        passes.push_pass(box mir::transform::no_landing_pads::NoLandingPads);
+        passes.push_pass(box mir::transform::simplify::SimplifyCfg::new("inline"));
+
         // No lifetime analysis based on borrowing can be done from here on out.
         passes.push_pass(box mir::transform::instcombine::InstCombine::new());
         passes.push_pass(box mir::transform::deaggregator::Deaggregator);
+        passes.push_pass(box mir::transform::const_prop::ConstProp);
+        passes.push_pass(
+            box mir::transform::simplify_branches::SimplifyBranches::new("const-prop"));
+        passes.push_pass(box mir::transform::simplify::SimplifyCfg::new("const-prop"));
         passes.push_pass(box mir::transform::copy_prop::CopyPropagation);
+        passes.push_pass(box mir::transform::dest_prop::DestinationPropagation);
+        // Assigns the arguments, so it must come after the above.
+        passes.push_pass(box mir::transform::tail_recursion::TailRecursion);
+        passes.push_pass(box mir::transform::simplify::SimplifyCfg::new("tail-recursion"));
 
         passes.push_pass(box mir::transform::simplify::SimplifyLocals);
         passes.push_pass(box mir::transform::add_call_guards::AddCallGuards);
@@ -40,8 +40,9 @@ use syntax_pos;
 
 use graphviz as dot;
 
-use std::fs::File;
-use std::io::{self, Write};
+use std::cell::RefCell;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
 use std::iter;
 use std::option;
 use std::path::Path;
@@ -466,6 +467,64 @@ impl<'ast> pprust::PpAnn for HygieneAnnotation<'ast> {
 }
 
 
+/// Annotates code produced by a macro with a comment naming the macro and its
+/// call site, for `-Z dump-macro-expansions`.
+struct ExpansionAnnotation<'a> {
+    sess: &'a Session,
+    // The expansions of the nodes being printed, innermost last.
+    expansions: RefCell<Vec<syntax_pos::ExpnId>>,
+}
+
+impl<'a> ExpansionAnnotation<'a> {
+    fn enter(&self, s: &mut pprust::State, span: syntax_pos::Span, is_item: bool)
+             -> io::Result<()> {
+        let expn_id = span.expn_id;
+        let is_new = self.expansions.borrow().last() != Some(&expn_id);
+        self.expansions.borrow_mut().push(expn_id);
+        if !is_new || expn_id == syntax_pos::NO_EXPANSION {
+            return Ok(());
+        }
+
+        let codemap = self.sess.codemap();
+        if let Some(trace) = codemap.macro_backtrace(span).into_iter().next() {
+            s.synth_comment(format!("expansion of `{}` at {}",
+                                    trace.macro_decl_name,
+                                    codemap.span_to_string(trace.call_site)))?;
+            if is_item {
+                pp::hardbreak(&mut s.s)?;
+            } else {
+                pp::space(&mut s.s)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> pprust::PpAnn for ExpansionAnnotation<'a> {
+    fn pre(&self, s: &mut pprust::State, node: pprust::AnnNode) -> io::Result<()> {
+        match node {
+            pprust::NodeItem(item) => self.enter(s, item.span, true),
+            pprust::NodeBlock(blk) => self.enter(s, blk.span, false),
+            pprust::NodeExpr(expr) => self.enter(s, expr.span, false),
+            pprust::NodePat(pat) => self.enter(s, pat.span, false),
+            _ => Ok(()),
+        }
+    }
+    fn post(&self, _s: &mut pprust::State, node: pprust::AnnNode) -> io::Result<()> {
+        match node {
+            pprust::NodeItem(_) |
+            pprust::NodeBlock(_) |
+            pprust::NodeExpr(_) |
+            pprust::NodePat(_) => {
+                self.expansions.borrow_mut().pop();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+
 struct TypedAnnotation<'a, 'tcx: 'a> {
     tcx: TyCtxt<'a, 'tcx, 'tcx>,
 }
@@ -779,6 +838,74 @@ fn write_output(out: Vec<u8>, ofile: Option<&Path>) {
     }
 }
 
+/// Writes the expanded source of each module of `krate` to its own file under
+/// `dir`, mirroring the module tree: the crate root goes in a file named after
+/// the crate's source file, and module `a::b` goes in `a/b/mod.rs`. Files whose
+/// contents have not changed are left alone, so that their timestamps survive.
+pub fn dump_macro_expansions(sess: &Session, krate: &ast::Crate, dir: &Path) -> io::Result<()> {
+    let file_name = sess.local_crate_source_file.as_ref()
+        .and_then(|path| path.file_name())
+        .and_then(|name| name.to_str())
+        .unwrap_or("lib.rs")
+        .to_string();
+    dump_expanded_module(sess, &krate.module, &krate.attrs, dir, &file_name)
+}
+
+fn dump_expanded_module(sess: &Session,
+                        module: &ast::Mod,
+                        attrs: &[ast::Attribute],
+                        dir: &Path,
+                        file_name: &str)
+                        -> io::Result<()> {
+    let annotation = ExpansionAnnotation {
+        sess: sess,
+        expansions: RefCell::new(Vec::new()),
+    };
+    let mut out = Vec::new();
+    let mut children = Vec::new();
+    {
+        let mut s = pprust::rust_printer_annotated(Box::new(&mut out), &annotation);
+        s.print_inner_attributes(attrs)?;
+        for item in &module.items {
+            match item.node {
+                // Child modules are written to their own files.
+                ast::ItemKind::Mod(ref child) => {
+                    s.hardbreak_if_not_bol()?;
+                    s.print_outer_attributes(&item.attrs)?;
+                    s.head(&pprust::visibility_qualified(&item.vis, "mod"))?;
+                    s.print_ident(item.ident)?;
+                    pp::word(&mut s.s, ";")?;
+                    s.end()?; // end inner head-block
+                    s.end()?; // end outer head-block
+                    children.push((item.ident, child, &item.attrs));
+                }
+                _ => s.print_item(item)?,
+            }
+        }
+        pp::eof(&mut s.s)?;
+    }
+
+    fs::create_dir_all(dir)?;
+    let path = dir.join(file_name);
+    let unchanged = match File::open(&path) {
+        Ok(mut file) => {
+            let mut old = Vec::new();
+            file.read_to_end(&mut old)?;
+            old == out
+        }
+        Err(_) => false,
+    };
+    if !unchanged {
+        File::create(&path)?.write_all(&out)?;
+    }
+
+    for (ident, child, attrs) in children {
+        let child_dir = dir.join(&*ident.name.as_str());
+        dump_expanded_module(sess, child, attrs, &child_dir, "mod.rs")?;
+    }
+    Ok(())
+}
+
 pub fn print_after_parsing(sess: &Session,
                            input: &Input,
                            krate: &ast::Crate,
@@ -0,0 +1,306 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Catalogs of translated diagnostic messages.
+//!
+//! Diagnostics which use the catalog refer to their message by an
+//! identifier such as `borrowck-use-moved-value`, and supply the values
+//! interpolated into it as named arguments. The message text is looked up in
+//! the catalog loaded with `-Z diagnostic-catalog` (or from the file named by
+//! the `RUST_DIAGNOSTIC_CATALOG` environment variable), falling back to the
+//! built-in English text for any message the catalog does not translate.
+//!
+//! A catalog file holds one message per line, written `id = text`. Within the
+//! text, `{name}` is replaced by the argument called `name`, and `{{` and `}}`
+//! stand for literal braces. Blank lines and lines starting with `#` are
+//! ignored.
+//!
+//! Arguments are data, such as a path, a count or the kind of a borrow, never
+//! fragments of English text. A message which reads differently depending on
+//! an argument chooses its wording with a selector, written
+//! `{name|key:text|key:text}`: the branch whose key is the text of the
+//! argument is used, or else the branch keyed `other`, and an empty key matches
+//! an empty argument. The text of the chosen branch may refer to arguments in
+//! turn. For example, `{count} {count|1:parameter|other:parameters}` picks the
+//! singular or plural noun from a count.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// The English text of every message which can be looked up in a catalog.
+const BUILTIN_MESSAGES: &'static [(&'static str, &'static str)] = &[
+    ("borrowck-use-moved-value",
+     "{use_kind|capture:capture|other:use} of \
+      {moved|partially:partially moved|collaterally:collaterally moved|other:moved} \
+      value: `{path}`"),
+    ("borrowck-reassign-immutable",
+     "re-assignment of immutable variable `{path}`"),
+    ("borrowck-mut-borrow-twice",
+     "cannot borrow `{path}`{via|:|other: (via `{via}`)} as mutable more than once at a time"),
+    ("borrowck-borrow-conflict",
+     "cannot borrow `{path}`{via|:|other: (via `{via}`)} as \
+      {new_kind|mut:mutable|unique:uniquely immutable|other:immutable} because \
+      {same_path|true:it|other:`{old_path}`} is also borrowed as \
+      {old_kind|mut:mutable|unique:uniquely immutable|other:immutable}\
+      {old_via|:|other: (via `{old_via}`)}"),
+    ("typeck-arg-count-mismatch",
+     "this function takes {variadic|true:at least |other:}{expected} \
+      {expected|1:parameter|other:parameters} but {supplied} \
+      {supplied|1:parameter was|other:parameters were} supplied"),
+    ("typeck-no-field",
+     "no field `{field}` on type `{ty}`"),
+];
+
+/// Returns the built-in English text for the message `id`.
+pub fn builtin_message(id: &str) -> Option<&'static str> {
+    BUILTIN_MESSAGES.iter().find(|&&(name, _)| name == id).map(|&(_, text)| text)
+}
+
+/// A set of translated messages, keyed by message identifier.
+#[derive(Clone, Debug, Default)]
+pub struct MessageCatalog {
+    messages: HashMap<String, String>,
+}
+
+impl MessageCatalog {
+    /// An empty catalog, which always uses the built-in English messages.
+    pub fn new() -> MessageCatalog {
+        MessageCatalog { messages: HashMap::new() }
+    }
+
+    pub fn from_file(path: &Path) -> Result<MessageCatalog, String> {
+        let mut src = String::new();
+        File::open(path)
+            .and_then(|mut f| f.read_to_string(&mut src))
+            .map_err(|e| e.to_string())?;
+        MessageCatalog::parse(&src)
+    }
+
+    pub fn parse(src: &str) -> Result<MessageCatalog, String> {
+        let mut messages = HashMap::new();
+        for (i, line) in src.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let eq = match line.find('=') {
+                Some(eq) => eq,
+                None => return Err(format!("line {}: expected `id = message`", i + 1)),
+            };
+            let id = line[..eq].trim();
+            if id.is_empty() {
+                return Err(format!("line {}: missing message identifier", i + 1));
+            }
+            messages.insert(id.to_string(), line[eq + 1..].trim().to_string());
+        }
+        Ok(MessageCatalog { messages: messages })
+    }
+
+    /// Looks up the text of the message `id`, without interpolating anything
+    /// into it.
+    pub fn get(&self, id: &str) -> Option<&str> {
+        self.messages.get(id).map(|text| &text[..]).or_else(|| builtin_message(id))
+    }
+
+    /// Produces the message `id`, with `args` interpolated into it.
+    ///
+    /// Every message the compiler shows must have built-in text, so an unknown
+    /// `id` is a compiler bug. Builds without debug assertions report it by its
+    /// identifier, so the diagnostic still says which message it meant to show.
+    pub fn format(&self, id: &str, args: &[(&str, &fmt::Display)]) -> String {
+        debug_assert!(builtin_message(id).is_some(),
+                      "no built-in diagnostic message with id `{}`", id);
+        match self.get(id) {
+            Some(text) => interpolate(text, args),
+            None => id.to_string(),
+        }
+    }
+}
+
+/// Replaces each `{name}` in `text` with the argument `name`, and each
+/// selector with its chosen branch. References to arguments which were not
+/// supplied are left as they are, so a bad translation still produces a
+/// readable message.
+fn interpolate(text: &str, args: &[(&str, &fmt::Display)]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(|c: char| c == '{' || c == '}') {
+        result.push_str(&rest[..pos]);
+        let (brace, after) = (&rest[pos..pos + 1], &rest[pos + 1..]);
+        if after.starts_with(brace) {
+            result.push_str(brace);
+            rest = &after[1..];
+            continue;
+        }
+        if brace == "{" {
+            if let Some(end) = closing_brace(after) {
+                let placeholder = &after[..end];
+                let (name, branches) = match placeholder.find('|') {
+                    Some(bar) => (&placeholder[..bar], Some(&placeholder[bar + 1..])),
+                    None => (placeholder, None),
+                };
+                if let Some(&(_, value)) = args.iter().find(|&&(n, _)| n == name) {
+                    let value = value.to_string();
+                    match branches {
+                        Some(branches) => {
+                            result.push_str(&interpolate(select(branches, &value), args))
+                        }
+                        None => result.push_str(&value),
+                    }
+                    rest = &after[end + 1..];
+                    continue;
+                }
+            }
+        }
+        result.push_str(brace);
+        rest = after;
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Finds the `}` closing a placeholder whose contents start `text`, skipping
+/// over any placeholders nested within it.
+fn closing_brace(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Picks the text of the branch of a selector keyed by `value`, falling back
+/// to the branch keyed `other`, or to nothing.
+fn select<'a>(branches: &'a str, value: &str) -> &'a str {
+    let mut other = "";
+    let mut depth = 0;
+    let mut start = 0;
+    let mut branch_texts = vec![];
+    for (i, c) in branches.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            '|' if depth == 0 => {
+                branch_texts.push(&branches[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    branch_texts.push(&branches[start..]);
+    for branch in branch_texts {
+        if let Some(colon) = branch.find(':') {
+            let (key, text) = (&branch[..colon], &branch[colon + 1..]);
+            if key == value {
+                return text;
+            } else if key == "other" {
+                other = text;
+            }
+        }
+    }
+    other
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_fallback() {
+        let catalog = MessageCatalog::new();
+        assert_eq!(catalog.format("typeck-no-field", &[("field", &"x"), ("ty", &"Foo")]),
+                   "no field `x` on type `Foo`");
+    }
+
+    #[test]
+    fn translated_message() {
+        let catalog = MessageCatalog::parse("
+            # a comment
+            typeck-no-field = le type `{ty}` n'a pas de champ `{field}`
+        ").unwrap();
+        assert_eq!(catalog.format("typeck-no-field", &[("field", &"x"), ("ty", &"Foo")]),
+                   "le type `Foo` n'a pas de champ `x`");
+        assert_eq!(catalog.format("borrowck-reassign-immutable", &[("path", &"x")]),
+                   "re-assignment of immutable variable `x`");
+    }
+
+    #[test]
+    fn escapes_and_unknown_arguments() {
+        let catalog = MessageCatalog::parse("typeck-no-field = {{{field}}} {ty2}").unwrap();
+        assert_eq!(catalog.format("typeck-no-field", &[("field", &1)]), "{1} {ty2}");
+    }
+
+    #[test]
+    fn selectors() {
+        let catalog = MessageCatalog::new();
+        let format = |variadic: bool, expected: usize, supplied: usize| {
+            catalog.format("typeck-arg-count-mismatch", &[("variadic", &variadic),
+                                                          ("expected", &expected),
+                                                          ("supplied", &supplied)])
+        };
+        assert_eq!(format(false, 1, 2),
+                   "this function takes 1 parameter but 2 parameters were supplied");
+        assert_eq!(format(true, 2, 1),
+                   "this function takes at least 2 parameters but 1 parameter was supplied");
+
+        let format = |via: &str, same_path: bool| {
+            catalog.format("borrowck-borrow-conflict", &[("path", &"x"),
+                                                         ("via", &via),
+                                                         ("new_kind", &"mut"),
+                                                         ("same_path", &same_path),
+                                                         ("old_path", &"x"),
+                                                         ("old_kind", &"shared"),
+                                                         ("old_via", &via)])
+        };
+        assert_eq!(format("", true),
+                   "cannot borrow `x` as mutable because it is also borrowed as immutable");
+        assert_eq!(format("x.a", false),
+                   "cannot borrow `x` (via `x.a`) as mutable because `x` is also borrowed \
+                    as immutable (via `x.a`)");
+    }
+
+    #[test]
+    fn translated_selector() {
+        let catalog = MessageCatalog::parse("
+            borrowck-reassign-immutable = {path|x:la variable `x`|other:`{path}`}
+        ").unwrap();
+        assert_eq!(catalog.format("borrowck-reassign-immutable", &[("path", &"x")]),
+                   "la variable `x`");
+        assert_eq!(catalog.format("borrowck-reassign-immutable", &[("path", &"y")]), "`y`");
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "no built-in diagnostic message")]
+    fn unknown_message() {
+        MessageCatalog::new().format("no-such-message", &[("a", &1)]);
+    }
+
+    #[test]
+    #[cfg(not(debug_assertions))]
+    fn unknown_message() {
+        let catalog = MessageCatalog::new();
+        assert_eq!(catalog.format("no-such-message", &[("a", &1)]), "no-such-message");
+    }
+
+    #[test]
+    fn malformed_catalog() {
+        assert!(MessageCatalog::parse("no equals sign").is_err());
+        assert!(MessageCatalog::parse(" = text").is_err());
+    }
+}
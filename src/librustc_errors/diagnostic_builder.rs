@@ -80,25 +80,15 @@ impl<'a> DiagnosticBuilder<'a> {
             return;
         }
 
-        match self.level {
-            Level::Bug |
-            Level::Fatal |
-            Level::PhaseFatal |
-            Level::Error => {
-                self.handler.bump_err_count();
-            }
-
-            Level::Warning |
-            Level::Note |
-            Level::Help |
-            Level::Cancelled => {
-            }
-        }
-
-        self.handler.emitter.borrow_mut().emit(&self);
+        let is_error = self.level == Level::Error;
+        let emitted = self.handler.emit_db(&self);
         self.cancel();
         self.handler.panic_if_treat_err_as_bug();
 
+        if emitted && is_error {
+            self.handler.abort_if_error_limit_reached();
+        }
+
         // if self.is_fatal() {
         //     panic!(FatalError);
         // }
@@ -32,11 +32,56 @@ impl Emitter for EmitterWriter {
     fn emit(&mut self, db: &DiagnosticBuilder) {
         let mut primary_span = db.span.clone();
         let mut children = db.children.clone();
-        self.fix_multispans_in_std_macros(&mut primary_span, &mut children);
+        if self.macro_backtrace {
+            if let Some(ref cm) = self.cm {
+                children.extend(macro_backtrace_notes(&**cm, &primary_span));
+            }
+        } else {
+            self.fix_multispans_in_std_macros(&mut primary_span, &mut children);
+        }
         self.emit_messages_default(&db.level, &db.message, &db.code, &primary_span, &children);
     }
 }
 
+/// Builds a note for each macro expansion that the primary spans of `span` come
+/// from, innermost first. Each points at the macro invocation, followed by a note
+/// pointing at the macro's definition when it is known.
+pub fn macro_backtrace_notes(cm: &CodeMapper, span: &MultiSpan) -> Vec<SubDiagnostic> {
+    let mut notes = vec![];
+    let mut call_sites = vec![];
+    for sp in span.primary_spans() {
+        if (*sp == COMMAND_LINE_SP) || (*sp == DUMMY_SP) {
+            continue;
+        }
+        for trace in cm.macro_backtrace(*sp) {
+            // Spans from the same expansion share the rest of their backtrace.
+            if call_sites.contains(&trace.call_site) {
+                break;
+            }
+            call_sites.push(trace.call_site);
+
+            notes.push(SubDiagnostic {
+                level: Level::Note,
+                message: format!("in this expansion of `{}`", trace.macro_decl_name),
+                span: MultiSpan::from_span(trace.call_site),
+                render_span: None,
+            });
+            match trace.def_site_span {
+                Some(def_site) if def_site != COMMAND_LINE_SP && def_site != DUMMY_SP => {
+                    notes.push(SubDiagnostic {
+                        level: Level::Note,
+                        message: format!("`{}` is defined here", trace.macro_decl_name),
+                        span: MultiSpan::from_span(def_site),
+                        render_span: None,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+    notes
+}
+
 /// maximum number of lines we will print for each error; arbitrary.
 pub const MAX_HIGHLIGHT_LINES: usize = 6;
 
@@ -60,6 +105,7 @@ impl ColorConfig {
 pub struct EmitterWriter {
     dst: Destination,
     cm: Option<Rc<CodeMapper>>,
+    macro_backtrace: bool,
 }
 
 struct FileWithAnnotatedLines {
@@ -89,11 +135,13 @@ impl EmitterWriter {
             EmitterWriter {
                 dst: dst,
                 cm: code_map,
+                macro_backtrace: false,
             }
         } else {
             EmitterWriter {
                 dst: Raw(Box::new(io::stderr())),
                 cm: code_map,
+                macro_backtrace: false,
             }
         }
     }
@@ -102,9 +150,17 @@ impl EmitterWriter {
         EmitterWriter {
             dst: Raw(dst),
             cm: code_map,
+            macro_backtrace: false,
         }
     }
 
+    /// Whether to show the full macro expansion backtrace of each diagnostic,
+    /// rather than collapsing it to the outermost invocation.
+    pub fn macro_backtrace(mut self, macro_backtrace: bool) -> EmitterWriter {
+        self.macro_backtrace = macro_backtrace;
+        self
+    }
+
     fn preprocess_annotations(&self, msp: &MultiSpan) -> Vec<FileWithAnnotatedLines> {
         fn add_annotation_to_file(file_vec: &mut Vec<FileWithAnnotatedLines>,
                                   file: Rc<FileMap>,
@@ -529,9 +585,10 @@ impl EmitterWriter {
             emit_to_destination(&buffer.render(), level, &mut self.dst)?;
             return Ok(());
         };
-        if let Ok(pos) =
-            annotated_files.binary_search_by(|x| x.file.name.cmp(&primary_lo.file.name)) {
-            annotated_files.swap(0, pos);
+        if let Some(pos) =
+            annotated_files.iter().position(|x| x.file.name == primary_lo.file.name) {
+            let primary_file = annotated_files.remove(pos);
+            annotated_files.insert(0, primary_file);
         }
 
         // Print out the annotate source lines that correspond with the error
@@ -556,12 +613,22 @@ impl EmitterWriter {
                 let buffer_msg_line_offset = buffer.num_lines();
 
                 // Add spacing line
-                draw_col_separator(&mut buffer, buffer_msg_line_offset, max_line_num_len + 1);
-
-                // Then, the secondary file indicator
-                buffer.prepend(buffer_msg_line_offset + 1, "::: ", Style::LineNumber);
+                draw_col_separator_no_space(&mut buffer,
+                                            buffer_msg_line_offset,
+                                            max_line_num_len + 1);
+
+                // Then, the location of the first annotation in this file
+                let first_line = &annotated_file.lines[0];
+                let first_col = first_line.annotations.iter()
+                    .map(|a| a.start_col)
+                    .min()
+                    .unwrap_or(0);
+                buffer.prepend(buffer_msg_line_offset + 1, "--> ", Style::LineNumber);
                 buffer.append(buffer_msg_line_offset + 1,
-                              &annotated_file.file.name,
+                              &format!("{}:{}:{}",
+                                       annotated_file.file.name,
+                                       first_line.line_index,
+                                       first_col + 1),
                               Style::LineAndColumn);
                 for _ in 0..max_line_num_len {
                     buffer.prepend(buffer_msg_line_offset + 1, " ", Style::NoStyle);
@@ -572,6 +639,30 @@ impl EmitterWriter {
             let buffer_msg_line_offset = buffer.num_lines();
             draw_col_separator_no_space(&mut buffer, buffer_msg_line_offset, max_line_num_len + 1);
 
+            // The source of files from other crates isn't available, so just
+            // list the labels which would have been drawn under it.
+            if annotated_file.file.is_imported() {
+                for line in &annotated_file.lines {
+                    for annotation in &line.annotations {
+                        if let Some(ref label) = annotation.label {
+                            let line_offset = buffer.num_lines();
+                            buffer.puts(line_offset,
+                                        0,
+                                        &line.line_index.to_string(),
+                                        Style::LineNumber);
+                            draw_col_separator(&mut buffer, line_offset, max_line_num_len + 1);
+                            let style = if annotation.is_primary {
+                                Style::LabelPrimary
+                            } else {
+                                Style::LabelSecondary
+                            };
+                            buffer.puts(line_offset, 3 + max_line_num_len, label, style);
+                        }
+                    }
+                }
+                continue;
+            }
+
             // Next, output the annotate source for this file
             for line_idx in 0..annotated_file.lines.len() {
                 self.render_source_line(&mut buffer,
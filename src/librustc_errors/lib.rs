@@ -36,6 +36,7 @@ extern crate rustc_unicode;
 extern crate serialize as rustc_serialize; // used by deriving
 extern crate syntax_pos;
 
+pub use catalog::MessageCatalog;
 pub use emitter::ColorConfig;
 
 use self::Level::*;
@@ -43,9 +44,11 @@ use self::Level::*;
 use emitter::{Emitter, EmitterWriter};
 
 use std::cell::{RefCell, Cell};
+use std::collections::HashSet;
 use std::{error, fmt};
 use std::rc::Rc;
 
+pub mod catalog;
 pub mod diagnostic;
 pub mod diagnostic_builder;
 pub mod emitter;
@@ -57,7 +60,7 @@ mod lock;
 use syntax_pos::{BytePos, Loc, FileLinesResult, FileName, MultiSpan, Span, NO_EXPANSION};
 use syntax_pos::MacroBacktrace;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum RenderSpan {
     /// A FullSpan renders with both with an initial line for the
     /// message, prefixed by file:linenum, followed by a summary of
@@ -71,7 +74,7 @@ pub enum RenderSpan {
     Suggestion(CodeSuggestion),
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct CodeSuggestion {
     pub msp: MultiSpan,
     pub substitutes: Vec<String>,
@@ -224,6 +227,18 @@ pub struct Handler {
     treat_err_as_bug: bool,
     continue_after_error: Cell<bool>,
     delayed_span_bug: RefCell<Option<(MultiSpan, String)>>,
+
+    // The maximum number of errors to report before aborting, set with
+    // `-Z error-limit`.
+    error_limit: Cell<Option<usize>>,
+
+    // Diagnostics which have already been emitted, compared on their level,
+    // code, message, spans and children. Later passes regularly rediscover
+    // the same problem, and only the first report of it is useful.
+    emitted_diagnostics: RefCell<HashSet<Diagnostic>>,
+
+    // The messages used for diagnostics which are looked up by identifier.
+    catalog: RefCell<MessageCatalog>,
 }
 
 impl Handler {
@@ -247,6 +262,9 @@ impl Handler {
             treat_err_as_bug: treat_err_as_bug,
             continue_after_error: Cell::new(true),
             delayed_span_bug: RefCell::new(None),
+            error_limit: Cell::new(None),
+            emitted_diagnostics: RefCell::new(HashSet::new()),
+            catalog: RefCell::new(MessageCatalog::new()),
         }
     }
 
@@ -254,6 +272,20 @@ impl Handler {
         self.continue_after_error.set(continue_after_error);
     }
 
+    pub fn set_error_limit(&self, error_limit: Option<usize>) {
+        self.error_limit.set(error_limit);
+    }
+
+    pub fn set_message_catalog(&self, catalog: MessageCatalog) {
+        *self.catalog.borrow_mut() = catalog;
+    }
+
+    /// Produces the text of the diagnostic message `id` from the active
+    /// message catalog, with `args` interpolated into it.
+    pub fn message(&self, id: &str, args: &[(&str, &fmt::Display)]) -> String {
+        self.catalog.borrow().format(id, args)
+    }
+
     pub fn struct_dummy<'a>(&'a self) -> DiagnosticBuilder<'a> {
         DiagnosticBuilder::new(self, Level::Cancelled, "")
     }
@@ -438,6 +470,52 @@ impl Handler {
     pub fn has_errors(&self) -> bool {
         self.err_count.get() > 0
     }
+
+    /// Hands a diagnostic to the emitter, unless an identical one has
+    /// already been emitted. Returns whether the diagnostic was emitted.
+    fn emit_db(&self, db: &DiagnosticBuilder) -> bool {
+        let diagnostic: &Diagnostic = db;
+        if !self.emitted_diagnostics.borrow_mut().insert(diagnostic.clone()) {
+            return false;
+        }
+
+        match db.level {
+            Level::Bug |
+            Level::Fatal |
+            Level::PhaseFatal |
+            Level::Error => {
+                self.bump_err_count();
+            }
+
+            Level::Warning |
+            Level::Note |
+            Level::Help |
+            Level::Cancelled => {
+            }
+        }
+
+        self.emitter.borrow_mut().emit(db);
+        true
+    }
+
+    /// Aborts compilation if the number of reported errors has reached the
+    /// limit given with `-Z error-limit`.
+    fn abort_if_error_limit_reached(&self) {
+        let limit = match self.error_limit.get() {
+            Some(limit) => limit,
+            None => return,
+        };
+        if self.err_count.get() < limit {
+            return;
+        }
+
+        let s = format!("aborting after {} error{} due to `-Z error-limit={}`",
+                        self.err_count.get(),
+                        if self.err_count.get() == 1 { "" } else { "s" },
+                        limit);
+        panic!(self.fatal(&s));
+    }
+
     pub fn abort_if_errors(&self) {
         let s;
         match self.err_count.get() {
@@ -485,7 +563,7 @@ impl Handler {
 }
 
 
-#[derive(Copy, PartialEq, Clone, Debug)]
+#[derive(Copy, PartialEq, Eq, Hash, Clone, Debug)]
 pub enum Level {
     Bug,
     Fatal,
@@ -542,3 +620,49 @@ pub fn expect<T, M>(diag: &Handler, opt: Option<T>, msg: M) -> T
         None => diag.bug(&msg()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use emitter::Emitter;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use syntax_pos::DUMMY_SP;
+
+    struct RecordingEmitter {
+        messages: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Emitter for RecordingEmitter {
+        fn emit(&mut self, db: &DiagnosticBuilder) {
+            self.messages.borrow_mut().push(db.message.clone());
+        }
+    }
+
+    fn recording_handler() -> (Handler, Rc<RefCell<Vec<String>>>) {
+        let messages = Rc::new(RefCell::new(vec![]));
+        let emitter = RecordingEmitter { messages: messages.clone() };
+        (Handler::with_emitter(true, false, Box::new(emitter)), messages)
+    }
+
+    #[test]
+    fn identical_diagnostics_are_emitted_once() {
+        let (handler, messages) = recording_handler();
+        handler.struct_span_err(DUMMY_SP, "mismatched types").note("found `()`").emit();
+        handler.struct_span_err(DUMMY_SP, "mismatched types").note("found `()`").emit();
+        assert_eq!(*messages.borrow(), ["mismatched types"]);
+        assert_eq!(handler.err_count(), 1);
+    }
+
+    #[test]
+    fn codes_and_children_are_compared() {
+        let (handler, messages) = recording_handler();
+        handler.struct_span_err(DUMMY_SP, "mismatched types").note("found `()`").emit();
+        handler.struct_span_err(DUMMY_SP, "mismatched types").note("found `u8`").emit();
+        handler.struct_span_err_with_code(DUMMY_SP, "mismatched types", "E0308")
+               .note("found `()`")
+               .emit();
+        assert_eq!(messages.borrow().len(), 3);
+        assert_eq!(handler.err_count(), 3);
+    }
+}
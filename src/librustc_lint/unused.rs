@@ -8,6 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use rustc::hir::def::Def;
 use rustc::hir::pat_util;
 use rustc::ty;
 use rustc::ty::adjustment;
@@ -111,7 +112,7 @@ impl LateLintPass for UnusedMut {
 declare_lint! {
     pub UNUSED_MUST_USE,
     Warn,
-    "unused result of a type flagged as #[must_use]"
+    "unused result of a type or function flagged as #[must_use]"
 }
 
 declare_lint! {
@@ -140,6 +141,29 @@ impl LateLintPass for UnusedResults {
             return;
         }
 
+        // Functions and methods may be marked #[must_use] too, whatever
+        // type they return.
+        let callee = match expr.node {
+            hir::ExprCall(ref callee, _) => {
+                match cx.tcx.expect_def_or_none(callee.id) {
+                    Some(Def::Fn(def_id)) | Some(Def::Method(def_id)) => Some(def_id),
+                    _ => None,
+                }
+            }
+            hir::ExprMethodCall(..) => {
+                cx.tcx.tables().method_map.get(&ty::MethodCall::expr(expr.id)).map(|m| m.def_id)
+            }
+            _ => None,
+        };
+        if let Some(def_id) = callee {
+            let attrs = cx.tcx.get_attrs(def_id);
+            let msg = format!("unused return value of `{}` which must be used",
+                              cx.tcx.item_path_str(def_id));
+            if check_must_use(cx, &attrs[..], s.span, msg) {
+                return;
+            }
+        }
+
         let t = cx.tcx.tables().expr_ty(&expr);
         let warned = match t.sty {
             ty::TyTuple(ref tys) if tys.is_empty() => return,
@@ -147,7 +171,8 @@ impl LateLintPass for UnusedResults {
             ty::TyBool => return,
             ty::TyAdt(def, _) => {
                 let attrs = cx.tcx.get_attrs(def.did);
-                check_must_use(cx, &attrs[..], s.span)
+                let msg = "unused result which must be used".to_string();
+                check_must_use(cx, &attrs[..], s.span, msg)
             }
             _ => false,
         };
@@ -155,10 +180,13 @@ impl LateLintPass for UnusedResults {
             cx.span_lint(UNUSED_RESULTS, s.span, "unused result");
         }
 
-        fn check_must_use(cx: &LateContext, attrs: &[ast::Attribute], sp: Span) -> bool {
+        fn check_must_use(cx: &LateContext,
+                          attrs: &[ast::Attribute],
+                          sp: Span,
+                          mut msg: String)
+                          -> bool {
             for attr in attrs {
                 if attr.check_name("must_use") {
-                    let mut msg = "unused result which must be used".to_string();
                     // check for #[must_use="..."]
                     if let Some(s) = attr.value_str() {
                         msg.push_str(": ");
@@ -265,7 +293,15 @@ impl LateLintPass for UnusedAttributes {
 
         if !attr::is_used(attr) {
             debug!("Emitting warning for: {:?}", attr);
-            cx.span_lint(UNUSED_ATTRIBUTES, attr.span, "unused attribute");
+            let mut err = cx.struct_span_lint(UNUSED_ATTRIBUTES, attr.span, "unused attribute");
+            // Is it a helper attribute of a custom derive the item doesn't use?
+            let helpers = cx.sess().derive_helper_attributes.borrow();
+            for &(_, ref derive) in helpers.iter().filter(|&&(ref n, _)| *n == *attr.name()) {
+                err.note(&format!("`#[{}]` is a helper attribute of `#[derive({})]`, which \
+                                   is not applied to this item",
+                                  attr.name(), derive));
+            }
+            err.emit();
             // Is it a builtin attribute that must be used at the crate level?
             let known_crate = BUILTIN_ATTRIBUTES.iter()
                 .find(|&&(name, ty, _)| attr.name() == name && ty == AttributeType::CrateLevel)
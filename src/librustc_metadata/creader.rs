@@ -534,7 +534,7 @@ impl<'a> CrateLoader<'a> {
         }
     }
 
-    /// Load custom derive macros.
+    /// Load custom derive, attribute and function-like procedural macros.
     ///
     /// Note that this is intentionally similar to how we load plugins today,
     /// but also intentionally separate. Plugins are likely always going to be
@@ -548,6 +548,7 @@ impl<'a> CrateLoader<'a> {
         use proc_macro::__internal::Registry;
         use rustc_back::dynamic_lib::DynamicLibrary;
         use syntax_ext::deriving::custom::CustomDerive;
+        use syntax_ext::proc_macro_impl::{AttrProcMacro, BangProcMacro};
 
         let path = match dylib {
             Some(dylib) => dylib,
@@ -576,18 +577,48 @@ impl<'a> CrateLoader<'a> {
             fn register_custom_derive(&mut self,
                                       trait_name: &str,
                                       expand: fn(TokenStream) -> TokenStream,
-                                      attributes: &[&'static str]) {
-                let attrs = attributes.iter().map(|s| InternedString::new(s)).collect();
+                                      attributes: &[&'static str],
+                                      keep_attributes: bool) {
+                let attrs = attributes.iter().map(|s| InternedString::new(s)).collect::<Vec<_>>();
                 let derive = SyntaxExtension::CustomDerive(
-                    Box::new(CustomDerive::new(expand, attrs))
+                    Box::new(CustomDerive::new(expand, attrs.clone())), attrs, keep_attributes
                 );
                 self.0.push((intern(trait_name), Rc::new(derive)));
             }
+
+            fn register_attr_proc_macro(&mut self,
+                                        name: &str,
+                                        expand: fn(TokenStream, TokenStream) -> TokenStream) {
+                let expand = SyntaxExtension::AttrProcMacro(
+                    Box::new(AttrProcMacro { inner: expand })
+                );
+                self.0.push((intern(name), Rc::new(expand)));
+            }
+
+            fn register_bang_proc_macro(&mut self,
+                                        name: &str,
+                                        expand: fn(TokenStream) -> TokenStream) {
+                let expand = SyntaxExtension::ProcMacro(
+                    Box::new(BangProcMacro { inner: expand })
+                );
+                self.0.push((intern(name), Rc::new(expand)));
+            }
         }
 
         let mut my_registrar = MyRegistrar(Vec::new());
         registrar(&mut my_registrar);
 
+        // Helper attributes are accepted wherever they appear, but those not
+        // on an item their derive is applied to are reported as unused.
+        {
+            let mut helpers = self.sess.derive_helper_attributes.borrow_mut();
+            for &(name, ref ext) in &my_registrar.0 {
+                if let SyntaxExtension::CustomDerive(_, ref attrs, _) = **ext {
+                    helpers.extend(attrs.iter().map(|attr| (attr.to_string(), name.to_string())));
+                }
+            }
+        }
+
         // Intentionally leak the dynamic library. We can't ever unload it
         // since the library can make things that will live arbitrarily long.
         mem::forget(lib);
@@ -392,6 +392,7 @@ impl<'tcx> CrateStore<'tcx> for cstore::CStore {
             allow_internal_unstable: attr::contains_name(&def.attrs, "allow_internal_unstable"),
             attrs: def.attrs,
             body: body,
+            legacy: def.legacy,
         })
     }
 
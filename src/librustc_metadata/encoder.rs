@@ -838,7 +838,8 @@ impl<'a, 'tcx> EncodeContext<'a, 'tcx> {
             name: macro_def.name,
             attrs: macro_def.attrs.to_vec(),
             span: macro_def.span,
-            body: ::syntax::print::pprust::tts_to_string(&macro_def.body)
+            body: ::syntax::print::pprust::tts_to_string(&macro_def.body),
+            legacy: macro_def.legacy,
         };
         Entry {
             kind: EntryKind::MacroDef(self.lazy(&macro_def)),
@@ -201,6 +201,7 @@ pub struct MacroDef {
     pub attrs: Vec<ast::Attribute>,
     pub span: Span,
     pub body: String,
+    pub legacy: bool,
 }
 
 #[derive(RustcEncodable, RustcDecodable)]
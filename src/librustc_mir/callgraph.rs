@@ -0,0 +1,242 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! MIR-based callgraph.
+//!
+//! This only considers direct calls, i.e. calls through an operand which is a
+//! constant of function item type. Calls through function pointers, trait
+//! objects and closures do not show up as edges. Calls to trait methods go to
+//! the method of the impl selected for them, if their types are all known.
+
+use rustc::hir::def_id::DefId;
+use rustc_data_structures::graph;
+
+use rustc::mir::*;
+use rustc::mir::visit::*;
+
+use rustc::traits::{self, Reveal};
+use rustc::ty::{self, TyCtxt};
+use rustc::ty::subst::Substs;
+use rustc::ty::fold::TypeFoldable;
+
+use rustc::util::nodemap::DefIdMap;
+
+use syntax_pos::DUMMY_SP;
+
+use std::cmp;
+
+pub struct CallGraph {
+    node_map: DefIdMap<graph::NodeIndex>,
+    graph: graph::Graph<DefId, ()>
+}
+
+impl CallGraph {
+    /// Builds the callgraph of all the functions in the local crate which have
+    /// MIR. Functions of other crates only show up as callees.
+    pub fn build<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>) -> CallGraph {
+        let def_ids = tcx.mir_map.borrow().keys();
+
+        let mut callgraph = CallGraph {
+            node_map: DefIdMap(),
+            graph: graph::Graph::new()
+        };
+
+        for def_id in def_ids {
+            if !def_id.is_local() { continue; }
+
+            let idx = callgraph.add_node(def_id);
+
+            let mut call_visitor = CallVisitor {
+                tcx: tcx,
+                caller: idx,
+                graph: &mut callgraph
+            };
+
+            let mir = tcx.item_mir(def_id);
+            call_visitor.visit_mir(&mir);
+        }
+
+        callgraph
+    }
+
+    /// Returns the strongly-connected components of the graph, callees before
+    /// their callers: a component only comes after all the components it
+    /// calls into.
+    pub fn sccs(&self) -> Vec<Vec<DefId>> {
+        Tarjan::new(&self.graph).run().into_iter().map(|scc| {
+            scc.into_iter().map(|node| self.def_id(node)).collect()
+        }).collect()
+    }
+
+    /// Returns the def_id for the given graph node.
+    pub fn def_id(&self, node: graph::NodeIndex) -> DefId {
+        *self.graph.node_data(node)
+    }
+
+    fn add_node(&mut self, id: DefId) -> graph::NodeIndex {
+        let graph = &mut self.graph;
+        *self.node_map.entry(id).or_insert_with(|| graph.add_node(id))
+    }
+}
+
+/// Resolves a call to `def_id` with `substs` to the function which ends up
+/// being called. For a trait method, that is the method of the impl selected
+/// for `substs`, or the trait's default; if `substs` are not all known yet,
+/// the impl can't be selected and `None` is returned.
+pub fn resolve_callee<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                def_id: DefId,
+                                substs: &'tcx Substs<'tcx>)
+                                -> Option<(DefId, &'tcx Substs<'tcx>)> {
+    let trait_id = match tcx.trait_of_item(def_id) {
+        Some(trait_id) => trait_id,
+        None => return Some((def_id, substs))
+    };
+    if substs.needs_subst() || substs.needs_infer() {
+        return None;
+    }
+
+    let trait_ref = ty::TraitRef::from_method(tcx, trait_id, substs);
+    let trait_ref = tcx.normalize_associated_type(&ty::Binder(trait_ref));
+    let vtable = tcx.infer_ctxt(None, None, Reveal::All).enter(|infcx| {
+        let mut selcx = traits::SelectionContext::new(&infcx);
+        let obligation = traits::Obligation::new(traits::ObligationCause::dummy(),
+                                                 trait_ref.to_poly_trait_predicate());
+        let selection = match selcx.select(&obligation) {
+            Ok(Some(selection)) => selection,
+            _ => return None
+        };
+
+        let mut fulfill_cx = traits::FulfillmentContext::new();
+        let vtable = selection.map(|predicate| {
+            fulfill_cx.register_predicate_obligation(&infcx, predicate);
+        });
+        Some(infcx.drain_fulfillment_cx_or_panic(DUMMY_SP, &mut fulfill_cx, &vtable))
+    });
+
+    match vtable {
+        Some(traits::VtableImpl(vtable_impl)) => {
+            let name = tcx.item_name(def_id);
+            Some(traits::find_method(tcx, name, substs, &vtable_impl))
+        }
+        _ => None
+    }
+}
+
+struct CallVisitor<'a, 'g, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    caller: graph::NodeIndex,
+    graph: &'g mut CallGraph
+}
+
+impl<'a, 'g, 'tcx> Visitor<'tcx> for CallVisitor<'a, 'g, 'tcx> {
+    fn visit_terminator_kind(&mut self, _block: BasicBlock,
+                             kind: &TerminatorKind<'tcx>, _loc: Location) {
+        if let TerminatorKind::Call {
+            func: Operand::Constant(ref f), .. } = *kind {
+            if let ty::TyFnDef(def_id, substs, _) = f.ty.sty {
+                let def_id = resolve_callee(self.tcx, def_id, substs)
+                    .map_or(def_id, |(def_id, _)| def_id);
+                let callee = self.graph.add_node(def_id);
+                self.graph.graph.add_edge(self.caller, callee, ());
+            }
+        }
+    }
+}
+
+/// Tarjan's strongly-connected components algorithm, using an explicit stack
+/// so that deep call chains cannot overflow ours.
+struct Tarjan<'g> {
+    graph: &'g graph::Graph<DefId, ()>,
+    /// The order in which each node was first reached, if it has been.
+    index: Vec<Option<usize>>,
+    /// The smallest index reachable from each node through the nodes which
+    /// are still on `stack`.
+    lowlink: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<graph::NodeIndex>,
+    next_index: usize,
+    sccs: Vec<Vec<graph::NodeIndex>>,
+}
+
+impl<'g> Tarjan<'g> {
+    fn new(graph: &'g graph::Graph<DefId, ()>) -> Tarjan<'g> {
+        let len = graph.len_nodes();
+        Tarjan {
+            graph: graph,
+            index: vec![None; len],
+            lowlink: vec![0; len],
+            on_stack: vec![false; len],
+            stack: Vec::new(),
+            next_index: 0,
+            sccs: Vec::new(),
+        }
+    }
+
+    fn run(mut self) -> Vec<Vec<graph::NodeIndex>> {
+        for node in 0..self.graph.len_nodes() {
+            if self.index[node].is_none() {
+                self.visit(graph::NodeIndex(node));
+            }
+        }
+        self.sccs
+    }
+
+    fn visit(&mut self, root: graph::NodeIndex) {
+        let graph = self.graph;
+        let mut visit_stack = vec![(root, graph.successor_nodes(root))];
+        self.start(root);
+
+        loop {
+            let (node, next) = match visit_stack.last_mut() {
+                Some(&mut (node, ref mut successors)) => (node, successors.next()),
+                None => break
+            };
+
+            if let Some(succ) = next {
+                match self.index[succ.0] {
+                    None => {
+                        self.start(succ);
+                        visit_stack.push((succ, graph.successor_nodes(succ)));
+                    }
+                    Some(index) if self.on_stack[succ.0] => {
+                        self.lowlink[node.0] = cmp::min(self.lowlink[node.0], index);
+                    }
+                    Some(_) => {}
+                }
+                continue;
+            }
+
+            visit_stack.pop();
+            if let Some(&(parent, _)) = visit_stack.last() {
+                self.lowlink[parent.0] = cmp::min(self.lowlink[parent.0], self.lowlink[node.0]);
+            }
+
+            if Some(self.lowlink[node.0]) == self.index[node.0] {
+                let mut scc = Vec::new();
+                loop {
+                    let n = self.stack.pop().unwrap();
+                    self.on_stack[n.0] = false;
+                    scc.push(n);
+                    if n == node { break; }
+                }
+                debug!("Tarjan: found SCC {:?}", scc);
+                self.sccs.push(scc);
+            }
+        }
+    }
+
+    fn start(&mut self, node: graph::NodeIndex) {
+        self.index[node.0] = Some(self.next_index);
+        self.lowlink[node.0] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(node);
+        self.on_stack[node.0] = true;
+    }
+}
@@ -9,14 +9,105 @@
 // except according to those terms.
 
 use dot;
+use pretty::dump_filter_matches;
 use rustc::hir::def_id::DefId;
 use rustc::mir::*;
 use rustc::ty::TyCtxt;
 use std::fmt::Debug;
+use std::fs::File;
 use std::io::{self, Write};
+use std::path::PathBuf;
 use syntax::ast::NodeId;
 
-use rustc_data_structures::indexed_vec::Idx;
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
+
+/// The sets computed by a dataflow analysis for each block of a MIR, with
+/// their elements already rendered to text.
+pub struct DataflowSets {
+    /// The name of the analysis, e.g. `maybe_init`.
+    pub name: &'static str,
+    pub on_entry: IndexVec<BasicBlock, Vec<String>>,
+    pub on_exit: IndexVec<BasicBlock, Vec<String>>,
+    /// The gen and kill sets of each block, for the graphs of analyses
+    /// whose transfer functions are worth seeing too.
+    pub gen_kill: Option<(IndexVec<BasicBlock, Vec<String>>, IndexVec<BasicBlock, Vec<String>>)>,
+}
+
+/// Collects the results of the dataflow analyses a pass runs over a MIR,
+/// so that `-Z dump-mir-dataflow` can draw all of them on one graph.
+///
+/// Analyses are registered as the pass computes them; nothing is rendered
+/// unless `-Z dump-mir-dataflow=<filter>` selects the pass and the item,
+/// with the same forms of filter as `-Z dump-mir`.
+pub struct DataflowRegistry {
+    pass_name: &'static str,
+    nodeid: NodeId,
+    analyses: Option<Vec<DataflowSets>>,
+}
+
+impl DataflowRegistry {
+    pub fn new<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                         pass_name: &'static str,
+                         nodeid: NodeId)
+                         -> DataflowRegistry {
+        let enabled = match tcx.sess.opts.debugging_opts.dump_mir_dataflow {
+            None => false,
+            Some(ref filters) => {
+                let node_path = tcx.item_path_str(tcx.map.local_def_id(nodeid));
+                dump_filter_matches(filters, pass_name, &node_path)
+            }
+        };
+        DataflowRegistry {
+            pass_name: pass_name,
+            nodeid: nodeid,
+            analyses: if enabled { Some(vec![]) } else { None },
+        }
+    }
+
+    /// Whether the registered analyses will be dumped.
+    pub fn is_enabled(&self) -> bool {
+        self.analyses.is_some()
+    }
+
+    /// Registers the results of an analysis; `render` is only called if
+    /// they will be dumped.
+    pub fn register<F>(&mut self, render: F)
+        where F: FnOnce() -> DataflowSets
+    {
+        if let Some(ref mut analyses) = self.analyses {
+            analyses.push(render());
+        }
+    }
+
+    /// Writes a graphviz DOT graph of `mir`, annotated with the registered
+    /// analyses, into:
+    ///
+    /// ```text
+    /// rustc.node<node_id>.<pass_name>.dataflow.dot
+    /// ```
+    ///
+    /// Each block shows the sets of each analysis on entry to it above its
+    /// statements, and the sets on exit from it below its terminator.
+    pub fn dump<'a, 'tcx>(&self, tcx: TyCtxt<'a, 'tcx, 'tcx>, mir: &Mir<'tcx>) {
+        let analyses = match self.analyses {
+            Some(ref analyses) => analyses,
+            None => return,
+        };
+
+        let mut file_path = PathBuf::new();
+        if let Some(ref file_dir) = tcx.sess.opts.debugging_opts.dump_mir_dir {
+            file_path.push(file_dir);
+        }
+        file_path.push(format!("rustc.node{}.{}.dataflow.dot", self.nodeid, self.pass_name));
+        let result = File::create(&file_path).and_then(|mut file| {
+            write_mir_fn_graphviz(tcx, self.nodeid, mir, analyses, &mut file)
+        });
+        if let Err(err) = result {
+            tcx.sess.err(&format!("failed to write MIR dataflow graph to `{}`: {}",
+                                  file_path.display(), err));
+        }
+    }
+}
 
 /// Write a graphviz DOT graph of a list of MIRs.
 pub fn write_mir_graphviz<'a, 'b, 'tcx, W, I>(tcx: TyCtxt<'b, 'tcx, 'tcx>,
@@ -28,29 +119,39 @@ pub fn write_mir_graphviz<'a, 'b, 'tcx, W, I>(tcx: TyCtxt<'b, 'tcx, 'tcx>,
     for def_id in iter {
         let nodeid = tcx.map.as_local_node_id(def_id).unwrap();
         let mir = &tcx.item_mir(def_id);
+        write_mir_fn_graphviz(tcx, nodeid, mir, &[], w)?;
+    }
+    Ok(())
+}
 
-        writeln!(w, "digraph Mir_{} {{", nodeid)?;
+/// Write a graphviz DOT graph of a single MIR, with the sets of `analyses`
+/// shown in each block.
+pub fn write_mir_fn_graphviz<'a, 'tcx, W: Write>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                                 nodeid: NodeId,
+                                                 mir: &Mir,
+                                                 analyses: &[DataflowSets],
+                                                 w: &mut W)
+                                                 -> io::Result<()> {
+    writeln!(w, "digraph Mir_{} {{", nodeid)?;
 
-        // Global graph properties
-        writeln!(w, r#"    graph [fontname="monospace"];"#)?;
-        writeln!(w, r#"    node [fontname="monospace"];"#)?;
-        writeln!(w, r#"    edge [fontname="monospace"];"#)?;
+    // Global graph properties
+    writeln!(w, r#"    graph [fontname="monospace"];"#)?;
+    writeln!(w, r#"    node [fontname="monospace"];"#)?;
+    writeln!(w, r#"    edge [fontname="monospace"];"#)?;
 
-        // Graph label
-        write_graph_label(tcx, nodeid, mir, w)?;
+    // Graph label
+    write_graph_label(tcx, nodeid, mir, w)?;
 
-        // Nodes
-        for (block, _) in mir.basic_blocks().iter_enumerated() {
-            write_node(block, mir, w)?;
-        }
+    // Nodes
+    for (block, _) in mir.basic_blocks().iter_enumerated() {
+        write_node(block, mir, analyses, w)?;
+    }
 
-        // Edges
-        for (source, _) in mir.basic_blocks().iter_enumerated() {
-            write_edges(source, mir, w)?;
-        }
-        writeln!(w, "}}")?
+    // Edges
+    for (source, _) in mir.basic_blocks().iter_enumerated() {
+        write_edges(source, mir, w)?;
     }
-    Ok(())
+    writeln!(w, "}}")
 }
 
 /// Write a graphviz HTML-styled label for the given basic block, with
@@ -103,14 +204,65 @@ pub fn write_node_label<W: Write, INIT, FINI>(block: BasicBlock,
 }
 
 /// Write a graphviz DOT node for the given basic block.
-fn write_node<W: Write>(block: BasicBlock, mir: &Mir, w: &mut W) -> io::Result<()> {
+fn write_node<W: Write>(block: BasicBlock,
+                        mir: &Mir,
+                        analyses: &[DataflowSets],
+                        w: &mut W)
+                        -> io::Result<()> {
     // Start a new node with the label to follow, in one of DOT's pseudo-HTML tables.
     write!(w, r#"    {} [shape="none", label=<"#, node(block))?;
-    write_node_label(block, mir, w, 1, |_| Ok(()), |_| Ok(()))?;
+    write_node_label(block, mir, w, 1,
+                     |w| {
+                         write_dataflow_rows(w, analyses, "entry", |sets| {
+                             Some(&sets.on_entry[block])
+                         })
+                     },
+                     |w| {
+                         write_dataflow_rows(w, analyses, "gen", |sets| {
+                             sets.gen_kill.as_ref().map(|&(ref gen, _)| &gen[block])
+                         })?;
+                         write_dataflow_rows(w, analyses, "kill", |sets| {
+                             sets.gen_kill.as_ref().map(|&(_, ref kill)| &kill[block])
+                         })?;
+                         write_dataflow_rows(w, analyses, "exit", |sets| {
+                             Some(&sets.on_exit[block])
+                         })
+                     })?;
     // Close the node label and the node itself.
     writeln!(w, ">];")
 }
 
+/// Write a row of a node label for each analysis that has the set, listing
+/// the elements of that set for the block.
+fn write_dataflow_rows<W: Write, F>(w: &mut W,
+                                    analyses: &[DataflowSets],
+                                    which: &str,
+                                    set: F)
+                                    -> io::Result<()>
+    where F: for<'s> Fn(&'s DataflowSets) -> Option<&'s Vec<String>>
+{
+    for sets in analyses {
+        let set = match set(sets) {
+            Some(set) => set,
+            None => continue,
+        };
+        write!(w, r#"<tr><td align="left" balign="left" bgcolor="pink">{} {}: ["#,
+               sets.name, which)?;
+        for (i, elem) in set.iter().enumerate() {
+            if i > 0 {
+                write!(w, ", ")?;
+                // Keep wide sets readable.
+                if i % 5 == 0 {
+                    write!(w, "<br/>")?;
+                }
+            }
+            write!(w, "{}", dot::escape_html(elem))?;
+        }
+        write!(w, "]</td></tr>")?;
+    }
+    Ok(())
+}
+
 /// Write graphviz DOT edges with labels between the given basic block and all of its successors.
 fn write_edges<W: Write>(source: BasicBlock, mir: &Mir, w: &mut W) -> io::Result<()> {
     let terminator = mir[source].terminator();
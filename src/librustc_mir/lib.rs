@@ -46,9 +46,11 @@ extern crate rustc_const_eval;
 pub mod diagnostics;
 
 pub mod build;
+pub mod callgraph;
 pub mod def_use;
 pub mod graphviz;
 mod hair;
+pub mod liveness;
 pub mod mir_map;
 pub mod pretty;
 pub mod transform;
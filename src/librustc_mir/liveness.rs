@@ -0,0 +1,278 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Liveness analysis of the locals of a MIR.
+//!
+//! A local is live at a point if the value it holds there may be read
+//! later on. Writing to a part of a local reads it, as the rest of its
+//! value is kept, and a write through a pointer reads the pointer.
+//! Dropping a local reads it, unless the drop is known to do nothing. The
+//! arguments are read when the function returns or unwinds, as a borrow
+//! stored through one of them outlives the call. `StorageLive` and
+//! `StorageDead` don't affect liveness.
+//!
+//! Liveness can't follow a local through the references to it, so clients
+//! usually leave the locals `borrowed_locals` finds alone.
+
+use rustc::mir::*;
+use rustc::mir::visit::{LvalueContext, Visitor};
+use rustc_data_structures::indexed_set::{IdxSet, IdxSetBuf};
+use rustc_data_structures::indexed_vec::IndexVec;
+
+/// Finds the locals whose address is taken, directly or through a
+/// projection.
+pub fn borrowed_locals<'tcx>(mir: &Mir<'tcx>) -> IndexVec<Local, bool> {
+    let mut finder = BorrowedLocals {
+        borrowed: IndexVec::from_elem(false, &mir.local_decls),
+    };
+    finder.visit_mir(mir);
+    finder.borrowed
+}
+
+struct BorrowedLocals {
+    borrowed: IndexVec<Local, bool>,
+}
+
+impl<'tcx> Visitor<'tcx> for BorrowedLocals {
+    fn visit_rvalue(&mut self, rvalue: &Rvalue<'tcx>, location: Location) {
+        if let Rvalue::Ref(_, _, ref lvalue) = *rvalue {
+            if let Some(local) = lvalue.base_local() {
+                self.borrowed[local] = true;
+            }
+        }
+        self.super_rvalue(rvalue, location);
+    }
+}
+
+/// The locals defined and used by a statement or terminator.
+#[derive(Default, Debug)]
+pub struct DefsUses {
+    /// Locals overwritten as a whole.
+    pub defs: Vec<Local>,
+    /// Locals of which only a part is overwritten.
+    pub partial_defs: Vec<Local>,
+    /// Locals read.
+    pub uses: Vec<Local>,
+}
+
+impl DefsUses {
+    pub fn for_statement<'tcx>(block: BasicBlock,
+                               statement: &Statement<'tcx>,
+                               location: Location)
+                               -> DefsUses {
+        let mut visitor = DefsUsesVisitor {
+            defs_uses: DefsUses::default(),
+            drop_is_use: true,
+        };
+        visitor.visit_statement(block, statement, location);
+        visitor.defs_uses
+    }
+
+    /// `drops_value` tells whether a drop terminator may drop anything;
+    /// if it doesn't, it doesn't read the dropped local.
+    pub fn for_terminator<'tcx>(mir: &Mir<'tcx>,
+                                block: BasicBlock,
+                                terminator: &Terminator<'tcx>,
+                                location: Location,
+                                drops_value: bool)
+                                -> DefsUses {
+        let mut visitor = DefsUsesVisitor {
+            defs_uses: DefsUses::default(),
+            drop_is_use: drops_value,
+        };
+        visitor.visit_terminator(block, terminator, location);
+        match terminator.kind {
+            TerminatorKind::Return => {
+                visitor.defs_uses.uses.push(RETURN_POINTER);
+                visitor.defs_uses.uses.extend(mir.args_iter());
+            }
+            TerminatorKind::Resume => {
+                visitor.defs_uses.uses.extend(mir.args_iter());
+            }
+            TerminatorKind::DropAndReplace { location: Lvalue::Local(local), .. } => {
+                // The old value is dropped before the new one is written.
+                visitor.defs_uses.defs.push(local);
+            }
+            _ => {}
+        }
+        visitor.defs_uses
+    }
+
+    /// Whether `local` is written by the statement, as a whole or in part.
+    pub fn writes(&self, local: Local) -> bool {
+        self.defs.contains(&local) || self.partial_defs.contains(&local)
+    }
+
+    /// Whether `local` is read or written by the statement.
+    pub fn mentions(&self, local: Local) -> bool {
+        self.writes(local) || self.uses.contains(&local)
+    }
+
+    /// Turns the locals live after the statement into those live before it.
+    pub fn apply(&self, live: &mut IdxSet<Local>) {
+        for local in &self.defs {
+            live.remove(local);
+        }
+        for local in &self.uses {
+            live.add(local);
+        }
+    }
+}
+
+struct DefsUsesVisitor {
+    defs_uses: DefsUses,
+    drop_is_use: bool,
+}
+
+impl<'tcx> Visitor<'tcx> for DefsUsesVisitor {
+    fn visit_statement(&mut self,
+                       block: BasicBlock,
+                       statement: &Statement<'tcx>,
+                       location: Location) {
+        if let StatementKind::SetDiscriminant { lvalue: Lvalue::Local(local), .. } =
+            statement.kind {
+            // Only the discriminant is written; the fields are kept.
+            self.defs_uses.partial_defs.push(local);
+            self.defs_uses.uses.push(local);
+        } else {
+            self.super_statement(block, statement, location);
+        }
+    }
+
+    fn visit_lvalue(&mut self,
+                    lvalue: &Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        if let LvalueContext::Drop = context {
+            if !self.drop_is_use {
+                return;
+            }
+        }
+        if let Lvalue::Local(local) = *lvalue {
+            match context {
+                LvalueContext::Store |
+                LvalueContext::Call => self.defs_uses.defs.push(local),
+                LvalueContext::Projection(Mutability::Mut) => {
+                    self.defs_uses.partial_defs.push(local);
+                    self.defs_uses.uses.push(local);
+                }
+                LvalueContext::StorageLive |
+                LvalueContext::StorageDead => {}
+                LvalueContext::Drop |
+                LvalueContext::Inspect |
+                LvalueContext::Borrow { .. } |
+                LvalueContext::Projection(Mutability::Not) |
+                LvalueContext::Consume => self.defs_uses.uses.push(local),
+            }
+        }
+        self.super_lvalue(lvalue, context, location)
+    }
+
+    fn visit_projection(&mut self,
+                        projection: &LvalueProjection<'tcx>,
+                        context: LvalueContext<'tcx>,
+                        location: Location) {
+        if let ProjectionElem::Deref = projection.elem {
+            // Only the pointer is read, whatever is done with what it
+            // points to.
+            self.visit_lvalue(&projection.base, LvalueContext::Consume, location);
+        } else {
+            self.super_projection(projection, context, location);
+        }
+    }
+}
+
+/// The results of the liveness analysis.
+pub struct LocalLiveness {
+    defs_uses: IndexVec<BasicBlock, Vec<DefsUses>>,
+    /// The locals live on entry to each block.
+    ins: IndexVec<BasicBlock, IdxSetBuf<Local>>,
+}
+
+impl LocalLiveness {
+    /// `drops_value` tells whether the drop terminating a block may drop
+    /// anything.
+    pub fn compute<'tcx, F>(mir: &Mir<'tcx>, drops_value: F) -> LocalLiveness
+        where F: Fn(BasicBlock) -> bool
+    {
+        let defs_uses: IndexVec<BasicBlock, Vec<DefsUses>> =
+            mir.basic_blocks().iter_enumerated().map(|(block, data)| {
+                let mut block_defs_uses: Vec<_> =
+                    data.statements.iter().enumerate().map(|(statement_index, statement)| {
+                        let location = Location {
+                            block: block,
+                            statement_index: statement_index,
+                        };
+                        DefsUses::for_statement(block, statement, location)
+                    }).collect();
+                let location = Location { block: block, statement_index: data.statements.len() };
+                block_defs_uses.push(DefsUses::for_terminator(mir, block, data.terminator(),
+                                                              location, drops_value(block)));
+                block_defs_uses
+            }).collect();
+
+        let mut liveness = LocalLiveness {
+            defs_uses: defs_uses,
+            ins: IndexVec::from_elem_n(IdxSetBuf::new_empty(mir.local_decls.len()),
+                                       mir.basic_blocks().len()),
+        };
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in mir.basic_blocks().indices().rev() {
+                let mut live = liveness.live_on_exit(mir, block);
+                for defs_uses in liveness.defs_uses[block].iter().rev() {
+                    defs_uses.apply(&mut live);
+                }
+                if live.words() != liveness.ins[block].words() {
+                    liveness.ins[block] = live;
+                    changed = true;
+                }
+            }
+        }
+
+        liveness
+    }
+
+    /// The locals live on entry to `block`.
+    pub fn live_on_entry(&self, block: BasicBlock) -> &IdxSet<Local> {
+        &self.ins[block]
+    }
+
+    /// The locals live on exit from `block`, i.e. on entry to any of its
+    /// successors.
+    pub fn live_on_exit<'tcx>(&self, mir: &Mir<'tcx>, block: BasicBlock) -> IdxSetBuf<Local> {
+        let mut live = IdxSetBuf::new_empty(mir.local_decls.len());
+        for succ in mir[block].terminator().successors().iter() {
+            live.union(&self.ins[*succ]);
+        }
+        live
+    }
+
+    /// The locals live on entry to each of the statements of `block`,
+    /// followed by those live on entry to its terminator.
+    pub fn live_on_entry_to_each<'tcx>(&self, mir: &Mir<'tcx>, block: BasicBlock)
+                                       -> Vec<IdxSetBuf<Local>> {
+        let mut live = self.live_on_exit(mir, block);
+        let mut block_live: Vec<_> = self.defs_uses[block].iter().rev().map(|defs_uses| {
+            defs_uses.apply(&mut live);
+            live.clone()
+        }).collect();
+        block_live.reverse();
+        block_live
+    }
+
+    /// The locals defined and used by the statements of `block`, followed
+    /// by those of its terminator.
+    pub fn defs_uses(&self, block: BasicBlock) -> &[DefsUses] {
+        &self.defs_uses[block]
+    }
+}
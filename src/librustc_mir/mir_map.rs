@@ -30,6 +30,7 @@ use rustc::traits::Reveal;
 use rustc::ty::{self, Ty, TyCtxt};
 use rustc::ty::subst::Substs;
 use rustc::hir;
+use rustc::hir::map as hir_map;
 use rustc::hir::intravisit::{self, FnKind, Visitor};
 use syntax::abi::Abi;
 use syntax::ast;
@@ -38,11 +39,34 @@ use syntax_pos::Span;
 use std::mem;
 
 pub fn build_mir_for_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>) {
+    tcx.mir_builder.set(Some(build_mir_on_demand));
     tcx.visit_all_items_in_krate(DepNode::Mir, &mut BuildMir {
         tcx: tcx
     });
 }
 
+/// Builds the MIR of the local constant or function `def_id` if it wasn't
+/// built yet, e.g. because constant evaluation needs it while building the
+/// MIR of an item visited earlier.
+fn build_mir_on_demand<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> bool {
+    let node_id = match tcx.map.as_local_node_id(def_id) {
+        Some(node_id) => node_id,
+        None => return false,
+    };
+
+    let _task = tcx.dep_graph.in_task(DepNode::Mir(def_id));
+    let mut builder = BuildMir {
+        tcx: tcx
+    };
+    match tcx.map.get(node_id) {
+        hir_map::NodeItem(item) => builder.visit_item(item),
+        hir_map::NodeTraitItem(item) => builder.visit_trait_item(item),
+        hir_map::NodeImplItem(item) => builder.visit_impl_item(item),
+        _ => return false,
+    }
+    tcx.mir_map.borrow().contains_key(&def_id)
+}
+
 /// A pass to lift all the types and substitutions in a Mir
 /// to the global tcx. Sadly, we don't have a "folder" that
 /// can change 'tcx so we have to transmute afterwards.
@@ -107,6 +131,11 @@ impl<'a, 'gcx, 'tcx> CxBuilder<'a, 'gcx, 'tcx> {
     {
         let (src, def_id) = (self.src, self.def_id);
         self.infcx.enter(|infcx| {
+            // The MIR may have been built on demand already.
+            if infcx.tcx.mir_map.borrow().contains_key(&def_id) {
+                return;
+            }
+
             let (mut mir, scope_auxiliary) = f(Cx::new(&infcx, src));
 
             // Convert the Mir to global types.
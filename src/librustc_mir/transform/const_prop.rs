@@ -0,0 +1,676 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Sparse conditional constant propagation.
+//!
+//! This is a forward dataflow analysis which computes, for each local whose
+//! address is never taken, whether it holds the same integer, boolean or
+//! character at the start of each basic block. Only the edges which can be
+//! taken given the values known so far are followed, so a branch on a
+//! constant condition doesn't let the values of the other side flow into
+//! the blocks after it.
+//!
+//! Two passes use the analysis:
+//!
+//! - `ConstProp` replaces the uses of constant locals with the constants
+//!   themselves, folds arithmetic and comparisons and turns switches on a
+//!   known value into gotos. `SimplifyBranches` and `SimplifyCfg` then
+//!   remove the blocks which became unreachable.
+//! - `ConstPropLint` reports the overflow and bounds checks which always
+//!   fail. It runs before lint checking, on the MIR as it was built.
+
+use liveness::borrowed_locals;
+use rustc::hir::def_id::DefId;
+use rustc::lint::builtin::UNCONDITIONAL_PANIC;
+use rustc::middle::const_val::ConstVal;
+use rustc::mir::*;
+use rustc::mir::transform::{MirPass, MirSource, Pass};
+use rustc::mir::visit::MutVisitor;
+use rustc::ty::{self, Ty, TyCtxt};
+use rustc::ty::subst::Substs;
+use rustc_const_eval::{ConstEvalErr, ErrKind, note_const_eval_err};
+use rustc_const_eval::interpret;
+use rustc_const_math::{ConstInt, ConstMathErr, ConstUsize};
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
+use syntax::ast::NodeId;
+use syntax_pos::Span;
+use transform::qualify_consts;
+
+use std::cmp::Ordering;
+
+pub struct ConstProp;
+
+impl Pass for ConstProp {}
+
+impl<'tcx> MirPass<'tcx> for ConstProp {
+    fn run_pass<'a>(&mut self,
+                    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                    source: MirSource,
+                    mir: &mut Mir<'tcx>) {
+        match source {
+            MirSource::Const(_) | MirSource::Static(..) | MirSource::Promoted(..) => {
+                // Trans might not be able to evaluate the optimized MIR.
+                return
+            }
+            MirSource::Fn(function_node_id) => {
+                if qualify_consts::is_const_fn(tcx, tcx.map.local_def_id(function_node_id)) {
+                    return
+                }
+            }
+        }
+
+        match tcx.sess.opts.debugging_opts.mir_opt_level {
+            Some(0) | None => return,
+            _ => {}
+        }
+
+        let (statements, terminators) = {
+            let mut analysis = ConstPropagator::new(tcx, mir);
+            analysis.propagate();
+            analysis.optimizations()
+        };
+
+        debug!("const prop: {} statements and {} terminators changed",
+               statements.len(), terminators.len());
+        for (location, statement) in statements {
+            mir[location.block].statements[location.statement_index] = statement;
+        }
+        for (block, terminator) in terminators {
+            *mir[block].terminator_mut() = terminator;
+        }
+    }
+}
+
+pub struct ConstPropLint;
+
+impl Pass for ConstPropLint {}
+
+impl<'tcx> MirPass<'tcx> for ConstPropLint {
+    fn run_pass<'a>(&mut self,
+                    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                    source: MirSource,
+                    mir: &mut Mir<'tcx>) {
+        // Constants report their errors when they are evaluated.
+        let id = match source {
+            MirSource::Fn(id) => id,
+            _ => return
+        };
+
+        let mut analysis = ConstPropagator::new(tcx, mir);
+        analysis.propagate();
+        analysis.report_panics(id);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    /// Not assigned on any path reaching this point.
+    Undef,
+    /// Always this integer, boolean or character.
+    Const(ConstVal),
+    /// The result of a checked operation: its value, wrapped around if it
+    /// overflowed, and whether it overflowed.
+    Checked(Option<ConstVal>, bool),
+    /// Anything else.
+    Varying,
+}
+
+impl Value {
+    fn join(&self, other: &Value) -> Value {
+        match (self, other) {
+            (&Value::Undef, value) | (value, &Value::Undef) => value.clone(),
+            (a, b) if a == b => a.clone(),
+            _ => Value::Varying,
+        }
+    }
+}
+
+type State = IndexVec<Local, Value>;
+
+struct ConstPropagator<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    mir: &'a Mir<'tcx>,
+    /// Whether the value of each local is tracked, i.e. it is never
+    /// borrowed, so all its writes appear in the MIR.
+    tracked: IndexVec<Local, bool>,
+    /// The values at the start of each block, or `None` if the block
+    /// isn't known to be reachable.
+    entry: IndexVec<BasicBlock, Option<State>>,
+}
+
+impl<'a, 'tcx> ConstPropagator<'a, 'tcx> {
+    fn new(tcx: TyCtxt<'a, 'tcx, 'tcx>, mir: &'a Mir<'tcx>) -> Self {
+        ConstPropagator {
+            tcx: tcx,
+            mir: mir,
+            tracked: borrowed_locals(mir).into_iter().map(|borrowed| !borrowed).collect(),
+            entry: IndexVec::from_elem(None, mir.basic_blocks()),
+        }
+    }
+
+    /// Computes the values at the start of each reachable block.
+    fn propagate(&mut self) {
+        let mut start = IndexVec::from_elem(Value::Undef, &self.mir.local_decls);
+        for arg in self.mir.args_iter() {
+            start[arg] = Value::Varying;
+        }
+        self.entry[START_BLOCK] = Some(start);
+
+        let mir = self.mir;
+        let mut worklist = vec![START_BLOCK];
+        let mut queued = IndexVec::from_elem(false, mir.basic_blocks());
+        queued[START_BLOCK] = true;
+
+        while let Some(block) = worklist.pop() {
+            queued[block] = false;
+
+            let mut state = self.entry[block].clone().unwrap();
+            let data = &mir[block];
+            for statement in &data.statements {
+                self.apply_statement(statement, &mut state);
+            }
+            let terminator = data.terminator();
+            self.apply_terminator(terminator, &mut state);
+
+            for target in self.feasible_targets(terminator, &state) {
+                let changed = match self.entry[target] {
+                    Some(ref mut entry) => {
+                        let mut changed = false;
+                        for (old, new) in entry.iter_mut().zip(&state) {
+                            let joined = old.join(new);
+                            if joined != *old {
+                                *old = joined;
+                                changed = true;
+                            }
+                        }
+                        changed
+                    }
+                    None => {
+                        self.entry[target] = Some(state.clone());
+                        true
+                    }
+                };
+                if changed && !queued[target] {
+                    queued[target] = true;
+                    worklist.push(target);
+                }
+            }
+        }
+    }
+
+    /// Returns the statements and terminators of the reachable blocks,
+    /// rewritten to use the constants computed by `propagate`.
+    fn optimizations(&self) -> (Vec<(Location, Statement<'tcx>)>,
+                                Vec<(BasicBlock, Terminator<'tcx>)>) {
+        let mut statements = vec![];
+        let mut terminators = vec![];
+
+        for (block, data) in self.mir.basic_blocks().iter_enumerated() {
+            let mut state = match self.entry[block] {
+                Some(ref entry) => entry.clone(),
+                None => continue
+            };
+
+            for (statement_index, statement) in data.statements.iter().enumerate() {
+                let location = Location { block: block, statement_index: statement_index };
+                let mut new_statement = statement.clone();
+                let changed = match new_statement.kind {
+                    StatementKind::Assign(ref lvalue, ref mut rvalue) => {
+                        self.fold_rvalue(lvalue, rvalue, &state, location)
+                    }
+                    _ => false
+                };
+                if changed {
+                    statements.push((location, new_statement));
+                }
+                self.apply_statement(statement, &mut state);
+            }
+
+            let terminator = data.terminator();
+            let location = Location { block: block, statement_index: data.statements.len() };
+            let mut new_terminator = terminator.clone();
+            let mut changed = {
+                let mut replacer = OperandReplacer {
+                    propagator: self,
+                    state: &state,
+                    changed: false,
+                };
+                replacer.visit_terminator(block, &mut new_terminator, location);
+                replacer.changed
+            };
+            let known_target = match terminator.kind {
+                TerminatorKind::SwitchInt { .. } => {
+                    let targets = self.feasible_targets(terminator, &state);
+                    if targets.len() == 1 { Some(targets[0]) } else { None }
+                }
+                _ => None
+            };
+            if let Some(target) = known_target {
+                new_terminator.kind = TerminatorKind::Goto { target: target };
+                changed = true;
+            }
+            if changed {
+                terminators.push((block, new_terminator));
+            }
+        }
+
+        (statements, terminators)
+    }
+
+    /// Emits an `unconditional_panic` lint for each reachable assertion
+    /// which always fails.
+    ///
+    /// The lint is attached to `id`, the function or closure the MIR belongs
+    /// to, as the MIR doesn't record which node each statement came from. So
+    /// the lint level is the one in effect at that function or closure:
+    /// attributes on an inner function, or on a closure or the `let` which
+    /// binds it, apply, but those on other statements of the same body don't.
+    fn report_panics(&self, id: NodeId) {
+        for (block, data) in self.mir.basic_blocks().iter_enumerated() {
+            let mut state = match self.entry[block] {
+                Some(ref entry) => entry.clone(),
+                None => continue
+            };
+            for statement in &data.statements {
+                self.apply_statement(statement, &mut state);
+            }
+
+            let terminator = data.terminator();
+            let (cond, expected, msg) = match terminator.kind {
+                TerminatorKind::Assert { ref cond, expected, ref msg, .. } => {
+                    (cond, expected, msg)
+                }
+                _ => continue
+            };
+            match self.eval_operand(cond, &state) {
+                Value::Const(ConstVal::Bool(cond)) if cond != expected => {}
+                _ => continue
+            }
+
+            let kind = match *msg {
+                AssertMessage::BoundsCheck { ref len, ref index } => {
+                    let len = self.eval_operand(len, &state);
+                    let index = self.eval_operand(index, &state);
+                    match (len, index) {
+                        (Value::Const(ConstVal::Integral(len)),
+                         Value::Const(ConstVal::Integral(index))) => {
+                            match (len.to_u64(), index.to_u64()) {
+                                (Some(len), Some(index)) => {
+                                    ErrKind::IndexOutOfBounds { len: len, index: index }
+                                }
+                                _ => continue
+                            }
+                        }
+                        _ => continue
+                    }
+                }
+                AssertMessage::Math(ref err) => ErrKind::Math(err.clone()),
+            };
+
+            let span = terminator.source_info.span;
+            let err = ConstEvalErr { span: span, kind: kind };
+            let mut diag = self.tcx.sess.struct_span_warn(
+                span, "this expression will panic at run-time");
+            note_const_eval_err(self.tcx, &err, span, "expression", &mut diag);
+            self.tcx.sess.add_lint_diagnostic(UNCONDITIONAL_PANIC, id, (*diag).clone());
+            diag.cancel();
+        }
+    }
+
+    /// Replaces `rvalue` by a constant or its operands by the constants they
+    /// evaluate to. Returns whether anything changed.
+    fn fold_rvalue(&self,
+                   lvalue: &Lvalue<'tcx>,
+                   rvalue: &mut Rvalue<'tcx>,
+                   state: &State,
+                   location: Location)
+                   -> bool {
+        if let Rvalue::Use(Operand::Constant(_)) = *rvalue {
+            return false;
+        }
+        if let Value::Const(value) = self.eval_rvalue(rvalue, state) {
+            let ty = lvalue.ty(self.mir, self.tcx).to_ty(self.tcx);
+            let span = span_at(self.mir, location);
+            *rvalue = Rvalue::Use(Operand::Constant(constant(span, ty, value)));
+            return true;
+        }
+
+        let mut replacer = OperandReplacer {
+            propagator: self,
+            state: state,
+            changed: false,
+        };
+        replacer.visit_rvalue(rvalue, location);
+        replacer.changed
+    }
+
+    fn apply_statement(&self, statement: &Statement<'tcx>, state: &mut State) {
+        match statement.kind {
+            StatementKind::Assign(ref lvalue, ref rvalue) => {
+                let value = self.eval_rvalue(rvalue, state);
+                self.assign(lvalue, value, state);
+                if let Rvalue::InlineAsm { ref outputs, .. } = *rvalue {
+                    for output in outputs {
+                        self.assign(output, Value::Varying, state);
+                    }
+                }
+            }
+            StatementKind::SetDiscriminant { ref lvalue, .. } => {
+                self.assign(lvalue, Value::Varying, state);
+            }
+            StatementKind::StorageLive(_) |
+            StatementKind::StorageDead(_) |
+            StatementKind::Nop => {}
+        }
+    }
+
+    fn apply_terminator(&self, terminator: &Terminator<'tcx>, state: &mut State) {
+        match terminator.kind {
+            TerminatorKind::Call { destination: Some((ref lvalue, _)), .. } |
+            TerminatorKind::DropAndReplace { location: ref lvalue, .. } => {
+                self.assign(lvalue, Value::Varying, state);
+            }
+            _ => {}
+        }
+    }
+
+    fn assign(&self, lvalue: &Lvalue<'tcx>, value: Value, state: &mut State) {
+        match *lvalue {
+            Lvalue::Local(local) if self.tracked[local] => {
+                state[local] = value;
+            }
+            Lvalue::Projection(_) => {
+                // Writing to a part of a local makes all of it unknown.
+                if let Some(local) = lvalue.base_local() {
+                    if self.tracked[local] {
+                        state[local] = Value::Varying;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The successors of `terminator` which can be reached given `state`.
+    fn feasible_targets(&self, terminator: &Terminator<'tcx>, state: &State) -> Vec<BasicBlock> {
+        match terminator.kind {
+            TerminatorKind::If { ref cond, targets: (if_true, if_false) } => {
+                match self.eval_operand(cond, state) {
+                    Value::Const(ConstVal::Bool(true)) => vec![if_true],
+                    Value::Const(ConstVal::Bool(false)) => vec![if_false],
+                    _ => vec![if_true, if_false],
+                }
+            }
+            TerminatorKind::SwitchInt { ref discr, ref values, ref targets, .. } => {
+                match self.eval_lvalue(discr, state) {
+                    Value::Const(ref value) => {
+                        // The last target is taken when no value matches.
+                        let index = values.iter().position(|v| v == value)
+                                          .unwrap_or(values.len());
+                        vec![targets[index]]
+                    }
+                    _ => targets.clone(),
+                }
+            }
+            TerminatorKind::Assert { ref cond, expected, target, cleanup, .. } => {
+                // The code after a failing assertion is still analyzed, so
+                // that the other assertions in it are checked as well.
+                match (self.eval_operand(cond, state), cleanup) {
+                    (Value::Const(ConstVal::Bool(cond)), _) if cond == expected => vec![target],
+                    (_, Some(cleanup)) => vec![target, cleanup],
+                    (_, None) => vec![target],
+                }
+            }
+            _ => terminator.successors().into_owned(),
+        }
+    }
+
+    fn eval_rvalue(&self, rvalue: &Rvalue<'tcx>, state: &State) -> Value {
+        match *rvalue {
+            Rvalue::Use(ref operand) => self.eval_operand(operand, state),
+            Rvalue::Len(ref lvalue) => {
+                match lvalue.ty(self.mir, self.tcx).to_ty(self.tcx).sty {
+                    ty::TyArray(_, len) => {
+                        match ConstUsize::new(len as u64, self.tcx.sess.target.uint_type) {
+                            Ok(len) => Value::Const(ConstVal::Integral(ConstInt::Usize(len))),
+                            Err(_) => Value::Varying,
+                        }
+                    }
+                    _ => Value::Varying,
+                }
+            }
+            Rvalue::BinaryOp(op, ref left, ref right) => {
+                match (self.eval_operand(left, state), self.eval_operand(right, state)) {
+                    (Value::Const(left), Value::Const(right)) => {
+                        match fold_binop(op, left, right) {
+                            Ok(value) => Value::Const(value),
+                            Err(_) => Value::Varying,
+                        }
+                    }
+                    (Value::Undef, _) | (_, Value::Undef) => Value::Undef,
+                    _ => Value::Varying,
+                }
+            }
+            Rvalue::CheckedBinaryOp(op, ref left, ref right) => {
+                match (self.eval_operand(left, state), self.eval_operand(right, state)) {
+                    (Value::Const(left), Value::Const(right)) => {
+                        match fold_binop(op, left.clone(), right.clone()) {
+                            Ok(value) => Value::Checked(Some(value), false),
+                            Err(ConstMathErr::Overflow(_)) => {
+                                // Keep going with the wrapped value, so that
+                                // the overflows it leads to are reported too.
+                                let wrapped = match (left, right) {
+                                    (ConstVal::Integral(a), ConstVal::Integral(b)) => {
+                                        wrapping_binop(op, a, b).map(ConstVal::Integral)
+                                    }
+                                    _ => None
+                                };
+                                Value::Checked(wrapped, true)
+                            }
+                            Err(_) => Value::Varying,
+                        }
+                    }
+                    (Value::Undef, _) | (_, Value::Undef) => Value::Undef,
+                    _ => Value::Varying,
+                }
+            }
+            Rvalue::UnaryOp(op, ref operand) => {
+                let value = match (op, self.eval_operand(operand, state)) {
+                    (UnOp::Not, Value::Const(ConstVal::Bool(b))) => Ok(ConstVal::Bool(!b)),
+                    (UnOp::Not, Value::Const(ConstVal::Integral(i))) => {
+                        (!i).map(ConstVal::Integral)
+                    }
+                    (UnOp::Neg, Value::Const(ConstVal::Integral(i))) => {
+                        (-i).map(ConstVal::Integral)
+                    }
+                    (_, Value::Undef) => return Value::Undef,
+                    _ => return Value::Varying,
+                };
+                value.map_or(Value::Varying, Value::Const)
+            }
+            _ => Value::Varying,
+        }
+    }
+
+    fn eval_operand(&self, operand: &Operand<'tcx>, state: &State) -> Value {
+        match *operand {
+            Operand::Consume(ref lvalue) => self.eval_lvalue(lvalue, state),
+            Operand::Constant(ref constant) => match constant.literal {
+                Literal::Value { ref value } => match *value {
+                    ConstVal::Integral(_) | ConstVal::Bool(_) | ConstVal::Char(_) => {
+                        Value::Const(value.clone())
+                    }
+                    _ => Value::Varying,
+                },
+                Literal::Item { def_id, substs } => self.eval_item(constant.ty, def_id, substs),
+                Literal::Promoted { .. } => Value::Varying,
+            },
+        }
+    }
+
+    /// Evaluates a path to a constant item, e.g. `std::u8::MAX`.
+    fn eval_item(&self, ty: Ty<'tcx>, def_id: DefId, substs: &'tcx Substs<'tcx>) -> Value {
+        match ty.sty {
+            ty::TyInt(_) | ty::TyUint(_) | ty::TyBool | ty::TyChar => {
+                match interpret::eval_const(self.tcx, def_id, substs) {
+                    Some(Ok(value)) => Value::Const(value),
+                    Some(Err(_)) | None => Value::Varying,
+                }
+            }
+            _ => Value::Varying,
+        }
+    }
+
+    fn eval_lvalue(&self, lvalue: &Lvalue<'tcx>, state: &State) -> Value {
+        match *lvalue {
+            Lvalue::Local(local) if self.tracked[local] => state[local].clone(),
+            Lvalue::Projection(ref projection) => {
+                // The fields of the result of a checked operation.
+                let (local, field) = match (&projection.base, &projection.elem) {
+                    (&Lvalue::Local(local), &ProjectionElem::Field(field, _)) => (local, field),
+                    _ => return Value::Varying,
+                };
+                if !self.tracked[local] {
+                    return Value::Varying;
+                }
+                match state[local] {
+                    Value::Checked(ref value, overflowed) => {
+                        if field.index() == 0 {
+                            value.clone().map_or(Value::Varying, Value::Const)
+                        } else {
+                            Value::Const(ConstVal::Bool(overflowed))
+                        }
+                    }
+                    Value::Undef => Value::Undef,
+                    _ => Value::Varying,
+                }
+            }
+            _ => Value::Varying,
+        }
+    }
+}
+
+/// Replaces the operands which evaluate to a constant.
+struct OperandReplacer<'b, 'a: 'b, 'tcx: 'a> {
+    propagator: &'b ConstPropagator<'a, 'tcx>,
+    state: &'b State,
+    changed: bool,
+}
+
+impl<'b, 'a, 'tcx> MutVisitor<'tcx> for OperandReplacer<'b, 'a, 'tcx> {
+    fn visit_operand(&mut self, operand: &mut Operand<'tcx>, location: Location) {
+        let value = match *operand {
+            Operand::Consume(ref lvalue) => self.propagator.eval_lvalue(lvalue, self.state),
+            Operand::Constant(_) => return,
+        };
+        if let Value::Const(value) = value {
+            let mir = self.propagator.mir;
+            let ty = operand.ty(mir, self.propagator.tcx);
+            let span = span_at(mir, location);
+            *operand = Operand::Constant(constant(span, ty, value));
+            self.changed = true;
+        } else {
+            self.super_operand(operand, location);
+        }
+    }
+}
+
+fn span_at(mir: &Mir, location: Location) -> Span {
+    let data = &mir[location.block];
+    match data.statements.get(location.statement_index) {
+        Some(statement) => statement.source_info.span,
+        None => data.terminator().source_info.span,
+    }
+}
+
+fn constant<'tcx>(span: Span, ty: Ty<'tcx>, value: ConstVal) -> Constant<'tcx> {
+    Constant {
+        span: span,
+        ty: ty,
+        literal: Literal::Value { value: value },
+    }
+}
+
+/// Folds `left op right`, failing with the error the operation would
+/// panic with, or would wrap on, at run-time.
+fn fold_binop(op: BinOp, left: ConstVal, right: ConstVal) -> Result<ConstVal, ConstMathErr> {
+    match (left, right) {
+        (ConstVal::Integral(a), ConstVal::Integral(b)) => {
+            let result = match op {
+                BinOp::Add => a + b,
+                BinOp::Sub => a - b,
+                BinOp::Mul => a * b,
+                BinOp::Div => a / b,
+                BinOp::Rem => a % b,
+                BinOp::BitXor => a ^ b,
+                BinOp::BitAnd => a & b,
+                BinOp::BitOr => a | b,
+                BinOp::Shl => a << b,
+                BinOp::Shr => a >> b,
+                _ => return a.try_cmp(b).map(|ordering| ConstVal::Bool(compare(op, ordering))),
+            };
+            result.map(ConstVal::Integral)
+        }
+        (ConstVal::Bool(a), ConstVal::Bool(b)) => Ok(ConstVal::Bool(match op {
+            BinOp::BitXor => a ^ b,
+            BinOp::BitAnd => a & b,
+            BinOp::BitOr => a | b,
+            _ => compare(op, a.cmp(&b)),
+        })),
+        (ConstVal::Char(a), ConstVal::Char(b)) => Ok(ConstVal::Bool(compare(op, a.cmp(&b)))),
+        (left, right) => bug!("binary operation `{:?}` on {:?} and {:?}", op, left, right),
+    }
+}
+
+/// The value `left op right` wraps around to when it overflows, for the
+/// operations whose result does.
+fn wrapping_binop(op: BinOp, left: ConstInt, right: ConstInt) -> Option<ConstInt> {
+    use rustc_const_math::ConstInt::*;
+    use rustc_const_math::ConstIsize::*;
+    use rustc_const_math::ConstUsize::*;
+
+    // Truncating the sign-extended bits gives the two's complement result.
+    let (a, b) = (left.to_u64_unchecked(), right.to_u64_unchecked());
+    let bits = match op {
+        BinOp::Add => a.wrapping_add(b),
+        BinOp::Sub => a.wrapping_sub(b),
+        BinOp::Mul => a.wrapping_mul(b),
+        _ => return None,
+    };
+    Some(match left {
+        I8(_) => I8(bits as i8),
+        I16(_) => I16(bits as i16),
+        I32(_) => I32(bits as i32),
+        I64(_) => I64(bits as i64),
+        Isize(Is16(_)) => Isize(Is16(bits as i16)),
+        Isize(Is32(_)) => Isize(Is32(bits as i32)),
+        Isize(Is64(_)) => Isize(Is64(bits as i64)),
+        U8(_) => U8(bits as u8),
+        U16(_) => U16(bits as u16),
+        U32(_) => U32(bits as u32),
+        U64(_) => U64(bits),
+        Usize(Us16(_)) => Usize(Us16(bits as u16)),
+        Usize(Us32(_)) => Usize(Us32(bits as u32)),
+        Usize(Us64(_)) => Usize(Us64(bits)),
+        Infer(_) | InferSigned(_) => return None,
+    })
+}
+
+fn compare(op: BinOp, ordering: Ordering) -> bool {
+    match op {
+        BinOp::Eq => ordering == Ordering::Equal,
+        BinOp::Ne => ordering != Ordering::Equal,
+        BinOp::Lt => ordering == Ordering::Less,
+        BinOp::Le => ordering != Ordering::Greater,
+        BinOp::Gt => ordering == Ordering::Greater,
+        BinOp::Ge => ordering != Ordering::Less,
+        _ => bug!("`{:?}` is not a comparison", op),
+    }
+}
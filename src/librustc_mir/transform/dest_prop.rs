@@ -0,0 +1,272 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Destination propagation.
+//!
+//! MIR building puts the result of nearly every expression in a temporary, which is then
+//! moved into its final destination:
+//!
+//!     TEMP = f(...)
+//!     ...
+//!     DEST = TEMP
+//!
+//! When the live ranges of `TEMP` and `DEST` don't interfere, we merge the two locals, so the
+//! value is written directly into `DEST` and the copy goes away:
+//!
+//!     DEST = f(...)
+//!     ...
+//!     NOP
+//!
+//! Two locals interfere if one of them is written while the other is live, other than by a copy
+//! between them, or if a single statement writes one and reads or writes the other. Unlike copy
+//! propagation, neither local needs to have a single def or use.
+//!
+//! Locals which are borrowed anywhere are left alone, as liveness can't follow them through
+//! references.
+
+use liveness::{LocalLiveness, borrowed_locals};
+use rustc::mir::*;
+use rustc::mir::transform::{MirPass, MirSource, Pass};
+use rustc::mir::visit::{LvalueContext, MutVisitor};
+use rustc::ty::TyCtxt;
+use rustc_data_structures::bitvec::BitMatrix;
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
+use transform::qualify_consts;
+
+pub struct DestinationPropagation;
+
+impl Pass for DestinationPropagation {}
+
+impl<'tcx> MirPass<'tcx> for DestinationPropagation {
+    fn run_pass<'a>(&mut self,
+                    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                    source: MirSource,
+                    mir: &mut Mir<'tcx>) {
+        match source {
+            MirSource::Const(_) | MirSource::Static(..) | MirSource::Promoted(..) => {
+                // As with copy propagation, constant qualification and trans might not accept
+                // the optimized IR.
+                return
+            }
+            MirSource::Fn(function_node_id) => {
+                if qualify_consts::is_const_fn(tcx, tcx.map.local_def_id(function_node_id)) {
+                    return
+                }
+            }
+        }
+
+        // Like copy propagation, this only runs when the MIR optimization level is at least 1.
+        match tcx.sess.opts.debugging_opts.mir_opt_level {
+            Some(0) | None => return,
+            _ => {}
+        }
+
+        let candidates = candidates(mir);
+        if candidates.is_empty() {
+            return
+        }
+
+        let borrowed = borrowed_locals(mir);
+        let liveness = LocalLiveness::compute(mir, |_| true);
+        let mut conflicts = Conflicts::compute(mir, &liveness, &candidates);
+
+        // Each local is renamed to the one it was merged into. Merging two locals gives them the
+        // union of their live ranges, so the conflicts of the merged local are the union of
+        // theirs and whole chains of copies can be merged at once.
+        let mut renames: IndexVec<Local, Local> = mir.local_decls.indices().collect();
+        let mut merged = IndexVec::from_elem(false, &mir.local_decls);
+
+        for (src, dest) in candidates {
+            let src = representative(&renames, src);
+            let dest = representative(&renames, dest);
+            if src == dest {
+                continue
+            }
+            // `src` may have been merged into a variable or the return pointer already.
+            if mir.local_kind(src) != LocalKind::Temp {
+                continue
+            }
+            if borrowed[src] || borrowed[dest] {
+                debug!("Can't merge {:?} into {:?}: borrowed", src, dest);
+                continue
+            }
+            if conflicts.interfere(src, dest) {
+                debug!("Can't merge {:?} into {:?}: live ranges interfere", src, dest);
+                continue
+            }
+
+            debug!("Merging {:?} into {:?}", src, dest);
+            conflicts.merge(src, dest);
+            renames[src] = dest;
+            merged[src] = true;
+            merged[dest] = true;
+        }
+
+        if !merged.iter().any(|&merged| merged) {
+            return
+        }
+
+        for local in mir.local_decls.indices() {
+            renames[local] = representative(&renames, local);
+        }
+        RenameLocals { renames: &renames }.visit_mir(mir);
+        remove_redundant_statements(mir, &merged);
+    }
+}
+
+/// Finds the `DEST = TEMP` copies whose locals might be merged.
+fn candidates<'tcx>(mir: &Mir<'tcx>) -> Vec<(Local, Local)> {
+    let mut candidates = vec![];
+    for data in mir.basic_blocks() {
+        for statement in &data.statements {
+            let (dest, src) = match statement.kind {
+                StatementKind::Assign(Lvalue::Local(dest),
+                                      Rvalue::Use(Operand::Consume(Lvalue::Local(src)))) => {
+                    (dest, src)
+                }
+                _ => continue,
+            };
+            if src == dest || mir.local_kind(src) != LocalKind::Temp {
+                continue
+            }
+            // Arguments are already initialized on entry, so their live ranges can't be read
+            // off the body alone.
+            if mir.local_kind(dest) == LocalKind::Arg {
+                continue
+            }
+            if mir.local_decls[src].ty != mir.local_decls[dest].ty {
+                continue
+            }
+            candidates.push((src, dest));
+        }
+    }
+    candidates
+}
+
+/// The local `local` ends up merged into.
+fn representative(renames: &IndexVec<Local, Local>, mut local: Local) -> Local {
+    while renames[local] != local {
+        local = renames[local];
+    }
+    local
+}
+
+/// Which of the locals of the candidate copies have interfering live ranges.
+///
+/// Two locals interfere if one of them is written while the other is live, other than by a copy
+/// between them, or if a single statement writes one and reads or writes the other.
+struct Conflicts {
+    /// Symmetric; only the rows and columns of the locals of the candidates are filled in.
+    matrix: BitMatrix,
+}
+
+impl Conflicts {
+    fn compute<'tcx>(mir: &Mir<'tcx>,
+                     liveness: &LocalLiveness,
+                     candidates: &[(Local, Local)])
+                     -> Conflicts {
+        let num_locals = mir.local_decls.len();
+        let mut relevant = IndexVec::from_elem(false, &mir.local_decls);
+        for &(src, dest) in candidates {
+            relevant[src] = true;
+            relevant[dest] = true;
+        }
+        let relevant: Vec<Local> = relevant.iter_enumerated()
+            .filter(|&(_, &is_relevant)| is_relevant)
+            .map(|(local, _)| local)
+            .collect();
+
+        let mut matrix = BitMatrix::new(num_locals, num_locals);
+        for (block, data) in mir.basic_blocks().iter_enumerated() {
+            let mut live = liveness.live_on_exit(mir, block);
+            for (index, defs_uses) in liveness.defs_uses(block).iter().enumerate().rev() {
+                let copy = data.statements.get(index).and_then(copy_locals);
+                for &written in &relevant {
+                    if !defs_uses.writes(written) {
+                        continue
+                    }
+                    for &other in &relevant {
+                        if other == written || copy == Some((written, other)) {
+                            continue
+                        }
+                        if live.contains(&other) || defs_uses.mentions(other) {
+                            matrix.add(written.index(), other.index());
+                            matrix.add(other.index(), written.index());
+                        }
+                    }
+                }
+                defs_uses.apply(&mut live);
+            }
+        }
+
+        Conflicts { matrix: matrix }
+    }
+
+    fn interfere(&self, a: Local, b: Local) -> bool {
+        self.matrix.contains(a.index(), b.index())
+    }
+
+    /// Gives `dest` the conflicts of `src`, once `src` is merged into it.
+    fn merge(&mut self, src: Local, dest: Local) {
+        self.matrix.merge(src.index(), dest.index());
+        let others: Vec<usize> = self.matrix.iter(src.index()).collect();
+        for other in others {
+            self.matrix.add(other, dest.index());
+        }
+    }
+}
+
+/// The destination and source of a copy between locals.
+fn copy_locals(statement: &Statement) -> Option<(Local, Local)> {
+    match statement.kind {
+        StatementKind::Assign(Lvalue::Local(dest),
+                              Rvalue::Use(Operand::Consume(Lvalue::Local(src)))) => {
+            Some((dest, src))
+        }
+        _ => None,
+    }
+}
+
+/// Turns the copies between merged locals, which are now copies of a local to itself, into
+/// `NOP`s, along with the storage markers of the merged locals, which no longer match.
+fn remove_redundant_statements<'tcx>(mir: &mut Mir<'tcx>, merged: &IndexVec<Local, bool>) {
+    for data in mir.basic_blocks_mut() {
+        for statement in &mut data.statements {
+            let redundant = match statement.kind {
+                StatementKind::Assign(Lvalue::Local(dest),
+                                      Rvalue::Use(Operand::Consume(Lvalue::Local(src)))) => {
+                    dest == src
+                }
+                StatementKind::StorageLive(Lvalue::Local(local)) |
+                StatementKind::StorageDead(Lvalue::Local(local)) => merged[local],
+                _ => false,
+            };
+            if redundant {
+                statement.make_nop();
+            }
+        }
+    }
+}
+
+struct RenameLocals<'a> {
+    renames: &'a IndexVec<Local, Local>,
+}
+
+impl<'a, 'tcx> MutVisitor<'tcx> for RenameLocals<'a> {
+    fn visit_lvalue(&mut self,
+                    lvalue: &mut Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        if let Lvalue::Local(ref mut local) = *lvalue {
+            *local = self.renames[*local];
+        }
+        self.super_lvalue(lvalue, context, location);
+    }
+}
@@ -0,0 +1,670 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Inlining pass for MIR functions.
+//!
+//! Direct calls to small functions, and to functions marked `#[inline]`, are
+//! replaced by a copy of the callee's body, with its types substituted for
+//! those of the call. The functions of the crate are visited bottom-up in the
+//! callgraph, so a callee has already had its own calls inlined when it is
+//! considered. Calls between the functions of a strongly-connected component
+//! of the callgraph, i.e. recursive calls, are never inlined.
+//!
+//! The pass runs after drop elaboration, so the inlined drops are already the
+//! final ones; unwinding out of the inlined body is redirected to the cleanup
+//! block of the call it replaces.
+
+use rustc::dep_graph::DepNode;
+use rustc::hir::def_id::DefId;
+use rustc::hir::map as hir_map;
+use rustc::mir::*;
+use rustc::mir::transform::{MirMapPass, MirPassHook, MirSource, Pass};
+use rustc::mir::visit::*;
+use rustc::traits::Reveal;
+use rustc::ty::{self, Ty, TyCtxt};
+use rustc::ty::subst::{Subst, Substs};
+use rustc::util::nodemap::DefIdSet;
+
+use rustc_data_structures::bitvec::BitVector;
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
+
+use syntax::abi::Abi;
+use syntax::attr;
+
+use std::mem;
+
+use callgraph;
+use transform::qualify_consts;
+
+const DEFAULT_THRESHOLD: usize = 50;
+const HINT_THRESHOLD: usize = 100;
+
+const INSTR_COST: usize = 5;
+const CALL_PENALTY: usize = 25;
+
+const UNKNOWN_SIZE_COST: usize = 10;
+
+pub struct Inline;
+
+impl Pass for Inline {}
+
+impl<'tcx> MirMapPass<'tcx> for Inline {
+    fn run_pass<'a>(
+        &mut self,
+        tcx: TyCtxt<'a, 'tcx, 'tcx>,
+        hooks: &mut [Box<for<'s> MirPassHook<'s>>]) {
+
+        // We only run when the MIR optimization level is at least 2.
+        match tcx.sess.opts.debugging_opts.mir_opt_level {
+            Some(0) |
+            Some(1) |
+            None => { return; },
+            _ => {}
+        };
+
+        let def_ids = tcx.mir_map.borrow().keys();
+        for &def_id in &def_ids {
+            run_hooks(self, tcx, hooks, def_id, false);
+        }
+
+        let callgraph = {
+            let _ignore = tcx.dep_graph.in_ignore();
+            callgraph::CallGraph::build(tcx)
+        };
+
+        let mut inliner = Inliner { tcx: tcx };
+        for scc in callgraph.sccs() {
+            inliner.inline_scc(&scc);
+        }
+
+        for &def_id in &def_ids {
+            run_hooks(self, tcx, hooks, def_id, true);
+        }
+    }
+}
+
+fn run_hooks<'a, 'tcx>(pass: &Inline,
+                       tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                       hooks: &mut [Box<for<'s> MirPassHook<'s>>],
+                       def_id: DefId,
+                       is_after: bool) {
+    if !def_id.is_local() {
+        return;
+    }
+
+    let _task = tcx.dep_graph.in_task(DepNode::Mir(def_id));
+    let mir = tcx.item_mir(def_id);
+    let id = tcx.map.as_local_node_id(def_id).unwrap();
+    let src = MirSource::from_node(tcx, id);
+    for hook in &mut *hooks {
+        hook.on_mir_pass(tcx, src, &mir, pass, is_after);
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct CallSite<'tcx> {
+    caller: DefId,
+    callee: DefId,
+    substs: &'tcx Substs<'tcx>,
+    bb: BasicBlock,
+    location: SourceInfo,
+}
+
+struct Inliner<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+}
+
+impl<'a, 'tcx> Inliner<'a, 'tcx> {
+    fn inline_scc(&mut self, scc: &[DefId]) {
+        let in_scc: DefIdSet = scc.iter().cloned().collect();
+
+        for &caller in scc {
+            if !self.can_inline_into(caller) {
+                continue;
+            }
+
+            let _task = self.tcx.dep_graph.in_task(DepNode::Mir(caller));
+            self.tcx.dep_graph.write(DepNode::Mir(caller));
+
+            for callsite in self.callsites(caller) {
+                if in_scc.contains(&callsite.callee) {
+                    debug!("not inlining {:?} into {:?}: recursive call",
+                           callsite.callee, caller);
+                    continue;
+                }
+
+                // The callee's MIR has to be copied out before the caller's is
+                // borrowed mutably: loading the MIR of another crate's function
+                // inserts it into the MIR map.
+                let callee_mir = match self.callee_mir(callsite) {
+                    Some(callee_mir) => callee_mir,
+                    None => continue
+                };
+
+                let map = self.tcx.mir_map.borrow();
+                let mut caller_mir = map[&caller].borrow_mut();
+                self.inline_call(callsite, &mut caller_mir, callee_mir);
+            }
+        }
+    }
+
+    /// Whether calls can be inlined into the body of `def_id`. Constants,
+    /// statics and `const fn`s are left alone, as constant evaluation might
+    /// not cope with the result.
+    fn can_inline_into(&self, def_id: DefId) -> bool {
+        let id = match self.tcx.map.as_local_node_id(def_id) {
+            Some(id) => id,
+            None => return false
+        };
+        match MirSource::from_node(self.tcx, id) {
+            MirSource::Fn(_) => !qualify_consts::is_const_fn(self.tcx, def_id),
+            _ => false
+        }
+    }
+
+    /// Collects the direct calls to Rust functions in `caller`, with calls to
+    /// trait methods resolved to the method of the selected impl. Calls in
+    /// cleanup blocks are left alone, as they are only reached by unwinding.
+    fn callsites(&self, caller: DefId) -> Vec<CallSite<'tcx>> {
+        let mir = self.tcx.item_mir(caller);
+        mir.basic_blocks().iter_enumerated().filter_map(|(bb, bb_data)| {
+            if bb_data.is_cleanup {
+                return None;
+            }
+
+            let terminator = bb_data.terminator();
+            if let TerminatorKind::Call {
+                func: Operand::Constant(ref f), destination: Some(_), .. } = terminator.kind {
+                if let ty::TyFnDef(callee, substs, fn_ty) = f.ty.sty {
+                    if fn_ty.abi == Abi::Rust {
+                        let resolved = callgraph::resolve_callee(self.tcx, callee, substs);
+                        return resolved.map(|(callee, substs)| CallSite {
+                            caller: caller,
+                            callee: callee,
+                            substs: substs,
+                            bb: bb,
+                            location: terminator.source_info,
+                        });
+                    }
+                }
+            }
+            None
+        }).collect()
+    }
+
+    /// Returns a copy of the body of the callee of `callsite`, with its types
+    /// substituted for those of the call, if it should be inlined.
+    fn callee_mir(&self, callsite: CallSite<'tcx>) -> Option<Mir<'tcx>> {
+        let tcx = self.tcx;
+
+        let has_mir = if callsite.callee.is_local() {
+            tcx.mir_map.borrow().contains_key(&callsite.callee)
+        } else {
+            tcx.sess.cstore.is_item_mir_available(callsite.callee)
+        };
+        if !has_mir {
+            return None;
+        }
+
+        // `box_free` is called with a `Box`, but defined to take a raw pointer.
+        if Some(callsite.callee) == tcx.lang_items.box_free_fn() {
+            return None;
+        }
+
+        let callee_mir = tcx.item_mir(callsite.callee);
+        if !self.should_inline(callsite, &callee_mir) {
+            return None;
+        }
+
+        let mut callee_mir = clone_mir(&callee_mir);
+        let mut subst = SubstVisitor { tcx: tcx, substs: callsite.substs };
+        subst.visit_mir(&mut callee_mir);
+        for promoted in callee_mir.promoted.iter_mut() {
+            subst.visit_mir(promoted);
+        }
+        Some(callee_mir)
+    }
+
+    fn should_inline(&self, callsite: CallSite<'tcx>, callee_mir: &Mir<'tcx>) -> bool {
+        let tcx = self.tcx;
+
+        // Closures which capture their environment, and functions using the
+        // "rust-call" ABI, take their arguments in a different shape.
+        if !callee_mir.upvar_decls.is_empty() || callee_mir.spread_arg.is_some() {
+            return false;
+        }
+
+        let attrs = tcx.get_attrs(callsite.callee);
+        let hint = attr::find_inline_attr(None, &attrs[..]);
+
+        let hinted = match hint {
+            // `#[inline(always)]` still has to be possible, so it is only
+            // treated as a hint which skips the cost check.
+            attr::InlineAttr::Always |
+            attr::InlineAttr::Hint => true,
+            attr::InlineAttr::Never => return false,
+            attr::InlineAttr::None => false,
+        };
+
+        // Other crates instantiate the bodies of generic and `#[inline]`
+        // functions themselves. A local function inlined into such a body
+        // could refer to symbols which are not exported, unless it would have
+        // been instantiated in other crates anyway.
+        if callsite.callee.is_local() && !hinted &&
+           callsite.substs.types().next().is_none() &&
+           is_cross_crate_body(tcx, callsite.caller) {
+            debug!("not inlining {:?} into {:?}: not available cross-crate",
+                   callsite.callee, callsite.caller);
+            return false;
+        }
+
+        let mut threshold = if hinted { HINT_THRESHOLD } else { DEFAULT_THRESHOLD };
+
+        // Significantly lower the threshold for inlining cold functions.
+        if attr::contains_name(&attrs[..], "cold") {
+            threshold /= 5;
+        }
+
+        // Give a bonus to functions with a small number of blocks, even very
+        // small functions normally have two or three.
+        if callee_mir.basic_blocks().len() <= 3 {
+            threshold += threshold / 4;
+        }
+
+        let id = tcx.map.as_local_node_id(callsite.caller).expect("caller not local");
+        let param_env = ty::ParameterEnvironment::for_item(tcx, id);
+
+        let mut first_block = true;
+        let mut cost = 0;
+
+        // Walk the reachable blocks manually, so that drops which will turn
+        // out to be no-ops after substitution don't count their unwind paths.
+        let mut work_list = vec![START_BLOCK];
+        let mut visited = BitVector::new(callee_mir.basic_blocks().len());
+        while let Some(bb) = work_list.pop() {
+            if !visited.insert(bb.index()) {
+                continue;
+            }
+            let blk = &callee_mir.basic_blocks()[bb];
+
+            for stmt in &blk.statements {
+                // Storage markers and nops disappear in translation.
+                match stmt.kind {
+                    StatementKind::StorageLive(_) |
+                    StatementKind::StorageDead(_) |
+                    StatementKind::Nop => {}
+                    _ => cost += INSTR_COST
+                }
+            }
+
+            let term = blk.terminator();
+            let mut is_drop = false;
+            match term.kind {
+                TerminatorKind::Drop { ref location, target, unwind } |
+                TerminatorKind::DropAndReplace { ref location, target, unwind, .. } => {
+                    is_drop = true;
+                    work_list.push(target);
+                    // A drop of a type which turns out not to need dropping is
+                    // just a jump.
+                    let ty = location.ty(callee_mir, tcx).to_ty(tcx).subst(tcx, callsite.substs);
+                    if tcx.type_needs_drop_given_env(ty, &param_env) {
+                        cost += CALL_PENALTY;
+                        if let Some(unwind) = unwind {
+                            work_list.push(unwind);
+                        }
+                    } else {
+                        cost += INSTR_COST;
+                    }
+                }
+
+                TerminatorKind::Unreachable |
+                TerminatorKind::Call { destination: None, .. } if first_block => {
+                    // Don't bother inlining a function which always diverges.
+                    threshold = 0;
+                }
+
+                TerminatorKind::Call { func: Operand::Constant(ref f), .. } => {
+                    if let ty::TyFnDef(.., f) = f.ty.sty {
+                        // Intrinsics don't get the penalty of a real call.
+                        if f.abi == Abi::RustIntrinsic || f.abi == Abi::PlatformIntrinsic {
+                            cost += INSTR_COST;
+                        } else {
+                            cost += CALL_PENALTY;
+                        }
+                    } else {
+                        cost += CALL_PENALTY;
+                    }
+                }
+
+                TerminatorKind::Call { .. } |
+                TerminatorKind::Assert { .. } => cost += CALL_PENALTY,
+
+                _ => cost += INSTR_COST
+            }
+
+            if !is_drop {
+                work_list.extend(term.successors().iter().cloned());
+            }
+
+            first_block = false;
+        }
+
+        // Count the locals by their size in machine words when it is known,
+        // and with a moderately large guess otherwise.
+        let ptr_size = tcx.data_layout.pointer_size.bytes();
+        tcx.infer_ctxt(None, Some(param_env), Reveal::All).enter(|infcx| {
+            for v in callee_mir.vars_and_temps_iter() {
+                let ty = callee_mir.local_decls[v].ty.subst(tcx, callsite.substs);
+                match ty.layout(&infcx) {
+                    Ok(layout) => {
+                        cost += (layout.size(&tcx.data_layout).bytes() / ptr_size) as usize;
+                    }
+                    Err(_) => cost += UNKNOWN_SIZE_COST
+                }
+            }
+        });
+
+        debug!("inline cost for {:?} is {}, threshold {}", callsite.callee, cost, threshold);
+
+        if let attr::InlineAttr::Always = hint {
+            true
+        } else {
+            cost <= threshold
+        }
+    }
+
+    fn inline_call(&self,
+                   callsite: CallSite<'tcx>,
+                   caller_mir: &mut Mir<'tcx>,
+                   mut callee_mir: Mir<'tcx>) -> bool {
+        let tcx = self.tcx;
+
+        match caller_mir[callsite.bb].terminator().kind {
+            TerminatorKind::Call { ref args, .. } if args.len() == callee_mir.arg_count => {}
+            _ => return false
+        }
+
+        let (args, destination, cleanup) = match caller_mir[callsite.bb].terminator.take() {
+            Some(Terminator {
+                kind: TerminatorKind::Call { args, destination: Some(destination), cleanup, .. },
+                ..
+            }) => (args, destination, cleanup),
+            _ => bug!("inlining into a block not ending in a converging call")
+        };
+
+        debug!("inlining {:?} into {:?}", callsite.callee, callsite.caller);
+
+        // The callee's outermost scope becomes a child of the call's scope.
+        // Its spans are replaced by the call's so that debuginfo stays within
+        // the caller's source file.
+        let mut scope_map = IndexVec::with_capacity(callee_mir.visibility_scopes.len());
+        for scope in callee_mir.visibility_scopes.iter() {
+            let parent_scope = match scope.parent_scope {
+                Some(parent) => scope_map[parent],
+                None => callsite.location.scope
+            };
+            scope_map.push(caller_mir.visibility_scopes.push(VisibilityScopeData {
+                span: callsite.location.span,
+                parent_scope: Some(parent_scope),
+            }));
+        }
+
+        // The return pointer is replaced by the call's destination. If that
+        // goes through a deref or an index, which the callee might change
+        // through its arguments, it is borrowed once up front instead.
+        let dest = if dest_needs_borrow(&destination.0) {
+            debug!("creating temp for return destination");
+            let dest_ty = destination.0.ty(caller_mir, tcx).to_ty(tcx);
+            let region = tcx.mk_region(ty::ReErased);
+            let tmp = caller_mir.local_decls.push(
+                LocalDecl::new_temp(tcx.mk_mut_ref(region, dest_ty)));
+            let tmp = Lvalue::Local(tmp);
+            caller_mir[callsite.bb].statements.push(Statement {
+                source_info: callsite.location,
+                kind: StatementKind::Assign(tmp.clone(),
+                                            Rvalue::Ref(region, BorrowKind::Mut, destination.0))
+            });
+            tmp.deref()
+        } else {
+            destination.0
+        };
+
+        // Every other local of the callee gets a fresh local in the caller.
+        // The arguments are moved into theirs at the end of the call's block.
+        let mut local_map = IndexVec::with_capacity(callee_mir.local_decls.len());
+        for (local, decl) in callee_mir.local_decls.iter_enumerated() {
+            if local == RETURN_POINTER {
+                local_map.push(RETURN_POINTER);
+                continue;
+            }
+
+            let mut decl = decl.clone();
+            if let Some(ref mut source_info) = decl.source_info {
+                source_info.span = callsite.location.span;
+                source_info.scope = scope_map[source_info.scope];
+            }
+            local_map.push(caller_mir.local_decls.push(decl));
+        }
+
+        for (i, arg) in args.into_iter().enumerate() {
+            let local = local_map[Local::new(i + 1)];
+            caller_mir[callsite.bb].statements.push(Statement {
+                source_info: callsite.location,
+                kind: StatementKind::Assign(Lvalue::Local(local), Rvalue::Use(arg))
+            });
+        }
+
+        let mut promoted_map = IndexVec::with_capacity(callee_mir.promoted.len());
+        for promoted in mem::replace(&mut callee_mir.promoted, IndexVec::new()) {
+            promoted_map.push(caller_mir.promoted.push(promoted));
+        }
+
+        let start = BasicBlock::new(caller_mir.basic_blocks().len());
+        let mut integrator = Integrator {
+            block_offset: start.index(),
+            local_map: local_map,
+            scope_map: scope_map,
+            promoted_map: promoted_map,
+            destination: dest,
+            return_block: destination.1,
+            cleanup_block: cleanup,
+            in_cleanup_block: false,
+        };
+
+        let blocks = mem::replace(callee_mir.basic_blocks_mut(), IndexVec::new());
+        for (bb, mut block) in blocks.into_iter_enumerated() {
+            integrator.visit_basic_block_data(bb, &mut block);
+            caller_mir.basic_blocks_mut().push(block);
+        }
+
+        caller_mir[callsite.bb].terminator = Some(Terminator {
+            source_info: callsite.location,
+            kind: TerminatorKind::Goto { target: start }
+        });
+
+        true
+    }
+}
+
+/// Whether other crates may instantiate the body of the local function
+/// `def_id` themselves, which is the case for generic, `#[inline]` and
+/// `const` functions, and the closures defined in them.
+fn is_cross_crate_body<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> bool {
+    let id = tcx.map.as_local_node_id(def_id).unwrap();
+    if let hir_map::NodeExpr(_) = tcx.map.get(id) {
+        return true;
+    }
+
+    let generics = tcx.item_generics(def_id);
+    generics.parent_types > 0 || !generics.types.is_empty() ||
+        attr::requests_inline(&tcx.get_attrs(def_id)[..]) ||
+        qualify_consts::is_const_fn(tcx, def_id)
+}
+
+fn dest_needs_borrow(lvalue: &Lvalue) -> bool {
+    match *lvalue {
+        Lvalue::Projection(ref p) => {
+            match p.elem {
+                ProjectionElem::Deref |
+                ProjectionElem::Index(_) => true,
+                _ => dest_needs_borrow(&p.base)
+            }
+        }
+        // The callee might modify the same static.
+        Lvalue::Static(_) => true,
+        Lvalue::Local(_) => false
+    }
+}
+
+/// `Mir` deliberately doesn't implement `Clone`: the inliner is the one place
+/// which needs a copy of a body.
+fn clone_mir<'tcx>(mir: &Mir<'tcx>) -> Mir<'tcx> {
+    let mut clone = Mir::new(mir.basic_blocks().clone(),
+                             mir.visibility_scopes.clone(),
+                             mir.promoted.iter().map(clone_mir).collect(),
+                             mir.return_ty,
+                             mir.local_decls.clone(),
+                             mir.arg_count,
+                             mir.upvar_decls.clone(),
+                             mir.span);
+    clone.spread_arg = mir.spread_arg;
+    clone
+}
+
+/// Substitutes the types of a callee for those of a particular call.
+struct SubstVisitor<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    substs: &'tcx Substs<'tcx>,
+}
+
+impl<'a, 'tcx> MutVisitor<'tcx> for SubstVisitor<'a, 'tcx> {
+    fn visit_ty(&mut self, ty: &mut Ty<'tcx>) {
+        *ty = ty.subst(self.tcx, self.substs);
+    }
+
+    fn visit_substs(&mut self, substs: &mut &'tcx Substs<'tcx>) {
+        *substs = substs.subst(self.tcx, self.substs);
+    }
+
+    fn visit_closure_substs(&mut self, substs: &mut ty::ClosureSubsts<'tcx>) {
+        *substs = substs.subst(self.tcx, self.substs);
+    }
+}
+
+/// Integrates the blocks of an inlined callee into the caller, renumbering
+/// its blocks, locals, scopes and promoted constants.
+struct Integrator<'tcx> {
+    block_offset: usize,
+    local_map: IndexVec<Local, Local>,
+    scope_map: IndexVec<VisibilityScope, VisibilityScope>,
+    promoted_map: IndexVec<Promoted, Promoted>,
+    destination: Lvalue<'tcx>,
+    return_block: BasicBlock,
+    cleanup_block: Option<BasicBlock>,
+    in_cleanup_block: bool,
+}
+
+impl<'tcx> Integrator<'tcx> {
+    fn update_target(&self, target: BasicBlock) -> BasicBlock {
+        BasicBlock::new(target.index() + self.block_offset)
+    }
+
+    /// Unwinding out of a non-cleanup block of the callee now continues in
+    /// the cleanup of the call instead.
+    fn update_unwind(&self, unwind: Option<BasicBlock>) -> Option<BasicBlock> {
+        match unwind {
+            Some(target) => Some(self.update_target(target)),
+            None if !self.in_cleanup_block => self.cleanup_block,
+            None => None
+        }
+    }
+}
+
+impl<'tcx> MutVisitor<'tcx> for Integrator<'tcx> {
+    fn visit_lvalue(&mut self,
+                    lvalue: &mut Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        match *lvalue {
+            Lvalue::Local(local) if local == RETURN_POINTER => {
+                *lvalue = self.destination.clone();
+            }
+            Lvalue::Local(ref mut local) => {
+                *local = self.local_map[*local];
+            }
+            _ => self.super_lvalue(lvalue, context, location)
+        }
+    }
+
+    fn visit_basic_block_data(&mut self, block: BasicBlock, data: &mut BasicBlockData<'tcx>) {
+        self.in_cleanup_block = data.is_cleanup;
+        self.super_basic_block_data(block, data);
+        self.in_cleanup_block = false;
+    }
+
+    fn visit_terminator_kind(&mut self,
+                             block: BasicBlock,
+                             kind: &mut TerminatorKind<'tcx>,
+                             location: Location) {
+        self.super_terminator_kind(block, kind, location);
+
+        match *kind {
+            TerminatorKind::Goto { ref mut target } => {
+                *target = self.update_target(*target);
+            }
+            TerminatorKind::If { ref mut targets, .. } => {
+                targets.0 = self.update_target(targets.0);
+                targets.1 = self.update_target(targets.1);
+            }
+            TerminatorKind::Switch { ref mut targets, .. } |
+            TerminatorKind::SwitchInt { ref mut targets, .. } => {
+                for target in targets {
+                    *target = self.update_target(*target);
+                }
+            }
+            TerminatorKind::Drop { ref mut target, ref mut unwind, .. } |
+            TerminatorKind::DropAndReplace { ref mut target, ref mut unwind, .. } => {
+                *target = self.update_target(*target);
+                *unwind = self.update_unwind(*unwind);
+            }
+            TerminatorKind::Call { ref mut destination, ref mut cleanup, .. } => {
+                if let Some((_, ref mut target)) = *destination {
+                    *target = self.update_target(*target);
+                }
+                *cleanup = self.update_unwind(*cleanup);
+            }
+            TerminatorKind::Assert { ref mut target, ref mut cleanup, .. } => {
+                *target = self.update_target(*target);
+                *cleanup = self.update_unwind(*cleanup);
+            }
+            TerminatorKind::Return => {
+                *kind = TerminatorKind::Goto { target: self.return_block };
+            }
+            TerminatorKind::Resume => {
+                if let Some(cleanup) = self.cleanup_block {
+                    *kind = TerminatorKind::Goto { target: cleanup };
+                }
+            }
+            TerminatorKind::Unreachable => {}
+        }
+    }
+
+    fn visit_visibility_scope(&mut self, scope: &mut VisibilityScope) {
+        *scope = self.scope_map[*scope];
+    }
+
+    fn visit_literal(&mut self, literal: &mut Literal<'tcx>, location: Location) {
+        if let Literal::Promoted { ref mut index } = *literal {
+            *index = self.promoted_map[*index];
+        } else {
+            self.super_literal(literal, location);
+        }
+    }
+}
@@ -20,3 +20,7 @@ pub mod dump_mir;
 pub mod deaggregator;
 pub mod instcombine;
 pub mod copy_prop;
+pub mod inline;
+pub mod const_prop;
+pub mod dest_prop;
+pub mod tail_recursion;
@@ -198,6 +198,12 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
         }
     }
 
+    /// Whether this is a `const fn` that may use `let` bindings,
+    /// assignments, branches and loops.
+    fn allows_control_flow(&self) -> bool {
+        self.mode == Mode::ConstFn && self.tcx.sess.features.borrow().const_fn_control_flow
+    }
+
     /// Add the given qualification to self.qualif.
     fn add(&mut self, qualif: Qualif) {
         self.qualif = self.qualif | qualif;
@@ -318,11 +324,14 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
     fn assign(&mut self, dest: &Lvalue<'tcx>, location: Location) {
         let qualif = self.qualif;
         let span = self.span;
+        let control_flow = self.allows_control_flow();
         let store = |slot: &mut Option<Qualif>| {
-            if slot.is_some() {
-                span_bug!(span, "multiple assignments to {:?}", dest);
+            match *slot {
+                // Each branch can assign its own value.
+                Some(other) if control_flow => *slot = Some(other | qualif),
+                Some(_) => span_bug!(span, "multiple assignments to {:?}", dest),
+                None => *slot = Some(qualif),
             }
-            *slot = Some(qualif);
         };
 
         // Only handle promotable temps in non-const functions.
@@ -359,6 +368,11 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
                 // already for the Box allocation Rvalue.
             }
 
+            Lvalue::Local(index) if self.mir.local_kind(index) == LocalKind::Var
+                                 && control_flow => {
+                debug!("store to var {:?}", index);
+            }
+
             // This must be an explicit assignment.
             _ => {
                 // Catch more errors in the destination.
@@ -372,6 +386,10 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
     fn qualify_const(&mut self) -> Qualif {
         debug!("qualifying {} {}", self.mode, self.tcx.item_path_str(self.def_id));
 
+        if self.allows_control_flow() {
+            return self.qualify_const_fn_with_control_flow();
+        }
+
         let mir = self.mir;
 
         let mut seen_blocks = BitVector::new(mir.basic_blocks().len());
@@ -476,6 +494,29 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
         }
         self.qualif
     }
+
+    /// Qualify a `const fn` whose body can branch and loop, in which case
+    /// all of its blocks are checked rather than a single path.
+    fn qualify_const_fn_with_control_flow(&mut self) -> Qualif {
+        while let Some((bb, data)) = self.rpo.next() {
+            // Constants evaluated outside of the MIR interpreter can't
+            // read the discriminant of an enum.
+            if let TerminatorKind::Switch { .. } = data.terminator().kind {
+                self.span = data.terminator().source_info.span;
+                self.not_const();
+            }
+            self.visit_basic_block_data(bb, data);
+        }
+
+        // Account for errors by using the conservative type qualification.
+        self.qualif = self.return_qualif.unwrap_or(Qualif::NOT_CONST);
+        if self.qualif.intersects(Qualif::CONST_ERROR) {
+            let return_ty = self.mir.return_ty;
+            self.qualif = Qualif::empty();
+            self.add_type(return_ty);
+        }
+        self.qualif
+    }
 }
 
 /// Accumulates an Rvalue or Call's effects in self.qualif.
@@ -0,0 +1,366 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Tail recursion elimination.
+//!
+//! A call of a function to itself whose result is returned right away is turned into a jump
+//! back to the start of the function, after the arguments have been overwritten:
+//!
+//!     bb1: {                                  bb1: {
+//!         _3 = f(_4, _5) -> bb2;                  _1 = _4;
+//!     }                                   =>      _2 = _5;
+//!     bb2: {                                      goto -> bb0;
+//!         _0 = _3;                            }
+//!         return;
+//!     }
+//!
+//! This runs after drop elaboration, so only the drops which really happen after the call keep
+//! it from being in tail position; for a call which diverges, those which would happen if it
+//! unwound do, as the jump would leak those values. It also runs after the other optimizations,
+//! which assume that the arguments are never assigned.
+//!
+//! Functions marked `#[tail_recursive]` go through this pass whatever the MIR optimization level.
+//! `TailCallLint` reports their recursive calls which can't be turned into jumps with the
+//! `uneliminated_tail_calls` lint. It runs before lint checking, so that the lint levels apply,
+//! on a copy of the MIR which has been through the passes pushed onto it: drop elaboration, at
+//! least, as the drops which it removes would otherwise keep calls from being in tail position.
+
+use rustc::hir::def_id::DefId;
+use rustc::lint::builtin::UNELIMINATED_TAIL_CALLS;
+use rustc::mir::*;
+use rustc::mir::transform::{MirPass, MirSource, Pass};
+use rustc::mir::visit::{LvalueContext, Visitor};
+use rustc::ty::{self, TyCtxt};
+use rustc::ty::subst::Substs;
+use syntax::abi::Abi;
+use transform::qualify_consts;
+
+pub struct TailRecursion;
+
+impl Pass for TailRecursion {}
+
+impl<'tcx> MirPass<'tcx> for TailRecursion {
+    fn run_pass<'a>(&mut self,
+                    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                    source: MirSource,
+                    mir: &mut Mir<'tcx>) {
+        let (def_id, fn_substs) = match recursive_fn(tcx, source) {
+            Some(recursive_fn) => recursive_fn,
+            None => return
+        };
+
+        if !tcx.has_attr(def_id, "tail_recursive") {
+            match tcx.sess.opts.debugging_opts.mir_opt_level {
+                Some(0) | None => return,
+                _ => {}
+            }
+        }
+
+        for (block, call) in recursive_calls(mir, def_id, fn_substs) {
+            if let Ok(storage_deads) = call {
+                eliminate_call(mir, block, storage_deads);
+            }
+        }
+    }
+}
+
+pub struct TailCallLint {
+    prepare: Vec<Box<for<'tcx> MirPass<'tcx>>>,
+}
+
+impl TailCallLint {
+    pub fn new() -> TailCallLint {
+        TailCallLint { prepare: vec![] }
+    }
+
+    /// Adds a pass to run on the copy of the MIR before checking it. These should be the
+    /// passes which run before `TailRecursion`.
+    pub fn push_pass(&mut self, pass: Box<for<'tcx> MirPass<'tcx>>) {
+        self.prepare.push(pass);
+    }
+}
+
+impl Pass for TailCallLint {}
+
+impl<'tcx> MirPass<'tcx> for TailCallLint {
+    fn run_pass<'a>(&mut self,
+                    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                    source: MirSource,
+                    mir: &mut Mir<'tcx>) {
+        let (def_id, fn_substs) = match recursive_fn(tcx, source) {
+            Some(recursive_fn) => recursive_fn,
+            None => return
+        };
+        if !tcx.has_attr(def_id, "tail_recursive") {
+            return
+        }
+
+        let mut mir = mir.clone();
+        for pass in &mut self.prepare {
+            pass.run_pass(tcx, source, &mut mir);
+        }
+
+        for (block, call) in recursive_calls(&mir, def_id, fn_substs) {
+            if let Err(reason) = call {
+                let span = mir[block].terminator().source_info.span;
+                let mut diag = tcx.sess.struct_span_warn(
+                    span, "recursive call not turned into a jump");
+                diag.note(&format!("it can't be eliminated because {}", reason));
+                tcx.sess.add_lint_diagnostic(UNELIMINATED_TAIL_CALLS,
+                                             source.item_id(),
+                                             (*diag).clone());
+                diag.cancel();
+            }
+        }
+    }
+}
+
+/// The function whose MIR is `source`, with the substitutions its recursive calls must use to
+/// be eliminated, if any of them could be.
+fn recursive_fn<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          source: MirSource)
+                          -> Option<(DefId, &'tcx Substs<'tcx>)> {
+    let id = match source {
+        MirSource::Fn(id) => id,
+        _ => return None
+    };
+    let def_id = tcx.map.local_def_id(id);
+    if qualify_consts::is_const_fn(tcx, def_id) {
+        return None
+    }
+
+    match tcx.item_type(def_id).sty {
+        ty::TyFnDef(_, substs, fn_ty) if fn_ty.abi != Abi::RustCall => Some((def_id, substs)),
+        // Closures can't call themselves, and the arguments of "rust-call" functions are
+        // untupled on entry.
+        _ => None
+    }
+}
+
+/// Finds the recursive calls in `mir`, with either the `StorageDead`s between each call which
+/// can be eliminated and the return, or the reason why it can't be.
+fn recursive_calls<'tcx>(mir: &Mir<'tcx>,
+                         def_id: DefId,
+                         fn_substs: &'tcx Substs<'tcx>)
+                         -> Vec<(BasicBlock, Result<Vec<Statement<'tcx>>, &'static str>)> {
+    // The callee could be given references to the locals of the caller, which the jump
+    // would overwrite while they are still in use.
+    let borrows_locals = borrows_locals(mir);
+
+    mir.basic_blocks().indices().filter_map(|block| {
+        match classify_call(mir, block, def_id, fn_substs) {
+            Some(Ok(_)) if borrows_locals => {
+                Some((block, Err("the function borrows its own locals")))
+            }
+            Some(call) => Some((block, call)),
+            None => None,
+        }
+    }).collect()
+}
+
+/// Checks whether the terminator of `block` is a recursive call which can be eliminated. If it
+/// can, the `StorageDead`s between the call and the return are returned.
+fn classify_call<'tcx>(mir: &Mir<'tcx>,
+                       block: BasicBlock,
+                       def_id: DefId,
+                       fn_substs: &'tcx Substs<'tcx>)
+                       -> Option<Result<Vec<Statement<'tcx>>, &'static str>> {
+    let (substs, destination, cleanup) = match mir[block].terminator().kind {
+        TerminatorKind::Call {
+            func: Operand::Constant(ref func), ref destination, cleanup, ..
+        } => {
+            match func.ty.sty {
+                ty::TyFnDef(callee, substs, _) if callee == def_id => {
+                    (substs, destination, cleanup)
+                }
+                _ => return None
+            }
+        }
+        _ => return None
+    };
+
+    // The regions have been erased by now, and don't matter anyway.
+    if !substs.types().eq(fn_substs.types()) {
+        return Some(Err("it is called with different type arguments"));
+    }
+
+    match *destination {
+        Some((Lvalue::Local(result), target)) => Some(returns_result(mir, target, result)),
+        Some(_) => Some(Err("its result is not returned")),
+        // A call which diverges is as good as one in tail position, unless values are still
+        // alive when it is made: they are only dropped if it unwinds.
+        None if cleanup.map_or(false, |cleanup| drops_on_unwind(mir, cleanup)) => {
+            Some(Err("some values are dropped if it unwinds"))
+        }
+        None => Some(Ok(vec![])),
+    }
+}
+
+/// Whether anything is dropped on the way from the cleanup block `cleanup` out of the function.
+fn drops_on_unwind<'tcx>(mir: &Mir<'tcx>, cleanup: BasicBlock) -> bool {
+    let mut visited = vec![];
+    let mut pending = vec![cleanup];
+    while let Some(block) = pending.pop() {
+        if visited.contains(&block) {
+            continue
+        }
+        visited.push(block);
+
+        let terminator = mir[block].terminator();
+        match terminator.kind {
+            TerminatorKind::Drop { .. } |
+            TerminatorKind::DropAndReplace { .. } => return true,
+            _ => pending.extend(terminator.successors().iter().cloned()),
+        }
+    }
+    false
+}
+
+/// Checks that `result` is returned right after reaching `block`, only freeing storage on the
+/// way, and gathers the `StorageDead`s.
+fn returns_result<'tcx>(mir: &Mir<'tcx>,
+                        mut block: BasicBlock,
+                        result: Local)
+                        -> Result<Vec<Statement<'tcx>>, &'static str> {
+    let mut storage_deads = vec![];
+    let mut returned = result == RETURN_POINTER;
+    let mut visited = vec![];
+    loop {
+        if visited.contains(&block) {
+            return Err("it is followed by an infinite loop");
+        }
+        visited.push(block);
+
+        let data = &mir[block];
+        for statement in &data.statements {
+            match statement.kind {
+                StatementKind::StorageDead(_) => storage_deads.push(statement.clone()),
+                StatementKind::Nop => {}
+                StatementKind::Assign(Lvalue::Local(dest),
+                                      Rvalue::Use(Operand::Consume(Lvalue::Local(src))))
+                    if dest == RETURN_POINTER && src == result && !returned => {
+                    returned = true;
+                }
+                _ => return Err("some code runs after it returns"),
+            }
+        }
+
+        match data.terminator().kind {
+            TerminatorKind::Goto { target } => block = target,
+            TerminatorKind::Return if returned => return Ok(storage_deads),
+            TerminatorKind::Return => return Err("its result is not returned"),
+            TerminatorKind::Drop { .. } |
+            TerminatorKind::DropAndReplace { .. } => {
+                return Err("some values are dropped after it returns");
+            }
+            _ => return Err("some code runs after it returns"),
+        }
+    }
+}
+
+/// Replaces the call terminating `block` by the assignment of its arguments to the parameters
+/// and a jump to the start of the function.
+fn eliminate_call<'tcx>(mir: &mut Mir<'tcx>,
+                        block: BasicBlock,
+                        storage_deads: Vec<Statement<'tcx>>) {
+    let (args, source_info) = match mir[block].terminator().kind {
+        TerminatorKind::Call { ref args, .. } => {
+            (args.clone(), mir[block].terminator().source_info)
+        }
+        _ => bug!("eliminate_call: {:?} doesn't end with a call", block)
+    };
+
+    // Passing a parameter in its own position doesn't change it.
+    let moves: Vec<(Local, Operand<'tcx>)> = mir.args_iter().zip(args).filter(|&(arg, ref op)| {
+        match *op {
+            Operand::Consume(Lvalue::Local(local)) => local != arg,
+            _ => true,
+        }
+    }).collect();
+
+    // The arguments which read parameters are evaluated into temporaries first, so that they
+    // don't see the parameters assigned before them.
+    let mut statements = vec![];
+    let mut assignments = vec![];
+    for (arg, operand) in moves {
+        if reads_args(mir, &operand) {
+            let temp = mir.local_decls.push(LocalDecl::new_temp(mir.local_decls[arg].ty));
+            statements.push(Statement {
+                source_info: source_info,
+                kind: StatementKind::Assign(Lvalue::Local(temp), Rvalue::Use(operand)),
+            });
+            assignments.push((arg, Operand::Consume(Lvalue::Local(temp))));
+        } else {
+            assignments.push((arg, operand));
+        }
+    }
+    for (arg, operand) in assignments {
+        statements.push(Statement {
+            source_info: source_info,
+            kind: StatementKind::Assign(Lvalue::Local(arg), Rvalue::Use(operand)),
+        });
+    }
+    statements.extend(storage_deads);
+
+    debug!("eliminate_call: replacing the call in {:?} by {:?}", block, statements);
+    let data = &mut mir[block];
+    data.statements.extend(statements);
+    data.terminator_mut().kind = TerminatorKind::Goto { target: START_BLOCK };
+}
+
+fn borrows_locals<'tcx>(mir: &Mir<'tcx>) -> bool {
+    mir.basic_blocks().iter().any(|data| {
+        data.statements.iter().any(|statement| {
+            match statement.kind {
+                StatementKind::Assign(_, Rvalue::Ref(_, _, ref lvalue)) => is_in_frame(lvalue),
+                _ => false,
+            }
+        })
+    })
+}
+
+/// Whether `lvalue` is stored in the stack frame of the function.
+fn is_in_frame(lvalue: &Lvalue) -> bool {
+    match *lvalue {
+        Lvalue::Local(_) => true,
+        Lvalue::Static(_) => false,
+        Lvalue::Projection(ref projection) => {
+            match projection.elem {
+                ProjectionElem::Deref => false,
+                _ => is_in_frame(&projection.base),
+            }
+        }
+    }
+}
+
+fn reads_args<'tcx>(mir: &Mir<'tcx>, operand: &Operand<'tcx>) -> bool {
+    let mut finder = ArgFinder { mir: mir, found: false };
+    finder.visit_operand(operand, Location { block: START_BLOCK, statement_index: 0 });
+    finder.found
+}
+
+struct ArgFinder<'a, 'tcx: 'a> {
+    mir: &'a Mir<'tcx>,
+    found: bool,
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for ArgFinder<'a, 'tcx> {
+    fn visit_lvalue(&mut self,
+                    lvalue: &Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        if let Lvalue::Local(local) = *lvalue {
+            if self.mir.local_kind(local) == LocalKind::Arg {
+                self.found = true;
+            }
+        }
+        self.super_lvalue(lvalue, context, location);
+    }
+}
@@ -24,6 +24,7 @@ use {resolve_error, resolve_struct_error, ResolutionError};
 use rustc::middle::cstore::{DepKind, LoadedMacro};
 use rustc::hir::def::*;
 use rustc::hir::def_id::{CrateNum, CRATE_DEF_INDEX, DefId};
+use rustc::hir::map::DefPathData;
 use rustc::ty;
 
 use std::cell::Cell;
@@ -40,6 +41,7 @@ use syntax::ext::base::SyntaxExtension;
 use syntax::ext::expand::mark_tts;
 use syntax::ext::hygiene::Mark;
 use syntax::ext::tt::macro_rules;
+use syntax::feature_gate::{emit_feature_err, GateIssue};
 use syntax::parse::token::keywords;
 use syntax::visit::{self, Visitor};
 
@@ -71,6 +73,7 @@ impl<'b> Resolver<'b> {
         where T: ToNameBinding<'b>,
     {
         let binding = def.to_name_binding();
+        self.macro_imports_stale = true;
         if let Err(old_binding) = self.try_define(parent, name, ns, binding.clone()) {
             self.report_conflict(parent, name, ns, old_binding, &binding);
         }
@@ -332,6 +335,35 @@ impl<'b> Resolver<'b> {
                 self.define(parent, name, TypeNS, (module, sp, vis));
                 self.current_module = module;
             }
+
+            // `macro` items live in the macro namespace.
+            ItemKind::MacroDef(ref tts) => {
+                if !self.session.features.borrow().decl_macro {
+                    emit_feature_err(&self.session.parse_sess, "decl_macro", item.span,
+                                     GateIssue::Language, "`macro` is experimental");
+                }
+
+                let def_id = self.definitions.local_def_id(item.id);
+                let def = ast::MacroDef {
+                    ident: item.ident,
+                    attrs: item.attrs.clone(),
+                    id: item.id,
+                    span: item.span,
+                    imported_from: None,
+                    allow_internal_unstable: attr::contains_name(&item.attrs,
+                                                                 "allow_internal_unstable"),
+                    body: tts.clone(),
+                    legacy: false,
+                };
+                let ext = self.compile_decl_macro(&def, parent, true);
+                self.macro_map.insert(def_id, ext);
+                self.macro_names.insert(name);
+                self.define(parent, name, MacroNS, (Def::Macro(def_id), sp, vis));
+                if vis == ty::Visibility::Public {
+                    self.exported_macros.push(def);
+                }
+            }
+
             ItemKind::Mac(_) => panic!("unexpanded macro in resolve!"),
         }
     }
@@ -493,6 +525,13 @@ impl<'b> Resolver<'b> {
             LoadedMacro::ProcMacro(ext) => return ext,
         };
 
+        if !macro_rules.legacy {
+            let module = self.get_extern_macro_scope(def_id);
+            let ext = self.compile_decl_macro(&macro_rules, module, false);
+            self.macro_map.insert(def_id, ext.clone());
+            return ext;
+        }
+
         let mark = Mark::fresh();
         let invocation = self.arenas.alloc_invocation_data(InvocationData {
             module: Cell::new(self.get_extern_crate_root(def_id.krate)),
@@ -503,11 +542,62 @@ impl<'b> Resolver<'b> {
         });
         self.invocations.insert(mark, invocation);
         macro_rules.body = mark_tts(&macro_rules.body, mark);
-        let ext = Rc::new(macro_rules::compile(&self.session.parse_sess, &macro_rules));
+        let ext = Rc::new(macro_rules::compile(&self.session.parse_sess, None, &macro_rules));
         self.macro_map.insert(def_id, ext.clone());
         ext
     }
 
+    /// Compiles the `macro` item `def`, which is defined in `module`, in this crate if
+    /// `local`. The body is given a mark of its own, so that identifiers from it can be
+    /// resolved in `module`.
+    fn compile_decl_macro(&mut self, def: &ast::MacroDef, module: Module<'b>, local: bool)
+                          -> Rc<SyntaxExtension> {
+        let mark = Mark::fresh();
+        let invocation = self.arenas.alloc_invocation_data(InvocationData {
+            module: Cell::new(module),
+            def_index: CRATE_DEF_INDEX,
+            const_integer: false,
+            legacy_scope: Cell::new(LegacyScope::Empty),
+            expansion: Cell::new(LegacyScope::Empty),
+        });
+        self.invocations.insert(mark, invocation);
+        self.macro_def_scopes.insert(mark, module);
+
+        let def = ast::MacroDef { body: mark_tts(&def.body, mark), ..def.clone() };
+        let features = self.session.features.borrow();
+        let features = if local { Some(&*features) } else { None };
+        Rc::new(macro_rules::compile(&self.session.parse_sess, features, &def))
+    }
+
+    /// Finds the module of an external crate that contains the `macro` item `def_id`,
+    /// falling back to the crate root if it is not reachable through named modules.
+    fn get_extern_macro_scope(&mut self, def_id: DefId) -> Module<'b> {
+        let mut path = Vec::new();
+        let mut key = self.session.cstore.def_key(def_id);
+        while let Some(index) = key.parent {
+            key = self.session.cstore.def_key(DefId { krate: def_id.krate, index: index });
+            path.push(key.disambiguated_data.data.clone());
+        }
+
+        let root = self.get_extern_crate_root(def_id.krate);
+        let mut module = root;
+        for data in path.into_iter().rev() {
+            let name = match data {
+                DefPathData::CrateRoot => continue,
+                DefPathData::Module(name) => token::intern(&name),
+                _ => return root,
+            };
+            match self.resolve_name_in_module(module, name, TypeNS, false, None) {
+                Success(binding) => match binding.module() {
+                    Ok(child) => module = child,
+                    Err(_) => return root,
+                },
+                _ => return root,
+            }
+        }
+        module
+    }
+
     /// Ensures that the reduced graph rooted at the given external module
     /// is built, building it if it is not.
     pub fn populate_module_if_necessary(&mut self, module: Module<'b>) {
@@ -57,7 +57,8 @@ impl<'a, 'b> UnusedImportCheckVisitor<'a, 'b> {
     // used now. If an import is not used at all, we signal a lint error.
     fn check_import(&mut self, item_id: ast::NodeId, id: ast::NodeId, span: Span) {
         if !self.used_imports.contains(&(id, TypeNS)) &&
-           !self.used_imports.contains(&(id, ValueNS)) {
+           !self.used_imports.contains(&(id, ValueNS)) &&
+           !self.used_imports.contains(&(id, MacroNS)) {
             if self.maybe_unused_trait_imports.contains(&id) {
                 // Check later.
                 return;
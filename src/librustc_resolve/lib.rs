@@ -1119,6 +1119,14 @@ pub struct Resolver<'a> {
     // Maps the `Mark` of an expansion to its containing module or block.
     invocations: FxHashMap<Mark, &'a InvocationData<'a>>,
 
+    // Maps the `Mark` given to the body of each `macro` item to the module containing
+    // the item, which is where identifiers from the body are resolved.
+    macro_def_scopes: FxHashMap<Mark, Module<'a>>,
+
+    // True if items or imports have been added since imports were last resolved in the
+    // macro namespace, c.f. `resolve_macro_imports`.
+    macro_imports_stale: bool,
+
     // Avoid duplicated errors for "name already defined".
     name_already_seen: FxHashMap<Name, Span>,
 }
@@ -1313,6 +1321,8 @@ impl<'a> Resolver<'a> {
             macro_map: FxHashMap(),
             macro_exports: Vec::new(),
             invocations: invocations,
+            macro_def_scopes: FxHashMap(),
+            macro_imports_stale: false,
             name_already_seen: FxHashMap(),
         }
     }
@@ -1565,6 +1575,7 @@ impl<'a> Resolver<'a> {
                                       ns: Namespace,
                                       record_used: Option<Span>)
                                       -> Option<LexicalScopeBinding<'a>> {
+        let def_site_module = self.macro_def_scope(ident.ctxt);
         if ns == TypeNS {
             ident = Ident::with_empty_ctxt(ident.name);
         }
@@ -1581,6 +1592,13 @@ impl<'a> Resolver<'a> {
 
             if let ModuleRibKind(module) = self.get_ribs(ns)[i].kind {
                 let name = ident.name;
+                if let (&ModuleKind::Def(..), Some(def_site_module)) =
+                       (&module.kind, def_site_module) {
+                    // `ident` comes from the body of a `macro` item, so it refers to an
+                    // item in scope where the macro is defined rather than where it is used.
+                    return self.resolve_name_at_def_site(def_site_module, name, ns, record_used);
+                }
+
                 let item = self.resolve_name_in_module(module, name, ns, true, record_used);
                 if let Success(binding) = item {
                     // The ident resolves to an item.
@@ -1610,6 +1628,51 @@ impl<'a> Resolver<'a> {
         None
     }
 
+    /// If `ctxt` belongs to an identifier from the body of a `macro` item, returns the
+    /// module in which that macro is defined.
+    fn macro_def_scope(&self, mut ctxt: SyntaxContext) -> Option<Module<'a>> {
+        while ctxt != SyntaxContext::empty() {
+            let (source_ctxt, source_macro) = ctxt.source();
+            if let Some(&module) = self.macro_def_scopes.get(&source_macro) {
+                return Some(module);
+            }
+            ctxt = source_ctxt;
+        }
+        None
+    }
+
+    /// Resolves `name` as an item in scope in `module`, which contains the definition of
+    /// a `macro`. Privacy is checked from `module` rather than from the macro invocation.
+    fn resolve_name_at_def_site(&mut self,
+                                mut module: Module<'a>,
+                                name: Name,
+                                ns: Namespace,
+                                record_used: Option<Span>)
+                                -> Option<LexicalScopeBinding<'a>> {
+        let orig_module = replace(&mut self.current_module, module);
+        let mut result = None;
+        loop {
+            let item = self.resolve_name_in_module(module, name, ns, true, record_used);
+            if let Success(binding) = item {
+                result = Some(binding);
+                break
+            }
+
+            match module.kind {
+                ModuleKind::Block(..) => module = module.parent.unwrap(),
+                _ if module.no_implicit_prelude => break,
+                _ => {
+                    result = self.prelude.and_then(|prelude| {
+                        self.resolve_name_in_module(prelude, name, ns, false, None).success()
+                    });
+                    break
+                }
+            }
+        }
+        self.current_module = orig_module;
+        result.map(LexicalScopeBinding::Item)
+    }
+
     /// Resolves a "module prefix". A module prefix is one or both of (a) `self::`;
     /// (b) some chain of `super::`.
     /// grammar: (SELF MOD_SEP ) ? (SUPER MOD_SEP) *
@@ -1830,7 +1893,7 @@ impl<'a> Resolver<'a> {
                 }
             }
 
-            ItemKind::ExternCrate(_) => {
+            ItemKind::ExternCrate(_) | ItemKind::MacroDef(..) => {
                 // do nothing, these are just around to be encoded
             }
 
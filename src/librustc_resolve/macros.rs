@@ -8,12 +8,16 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use {Module, ModuleKind, Resolver};
+use {Module, ModuleKind, Resolver, ResolveResult};
+use Namespace::{MacroNS, TypeNS};
+use ResolveResult::{Success, Indeterminate, Failed};
+use UseLexicalScopeFlag::DontUseLexicalScope;
 use build_reduced_graph::BuildReducedGraphVisitor;
 use rustc::hir::def_id::{DefId, BUILTIN_MACROS_CRATE, CRATE_DEF_INDEX, DefIndex};
 use rustc::hir::def::{Def, Export};
 use rustc::hir::map::{self, DefCollector};
 use std::cell::Cell;
+use std::mem::replace;
 use std::rc::Rc;
 use syntax::ast;
 use syntax::errors::DiagnosticBuilder;
@@ -149,7 +153,9 @@ impl<'a> base::Resolver for Resolver<'a> {
         let binding = self.arenas.alloc_legacy_binding(LegacyBinding {
             parent: invocation.legacy_scope.get(),
             name: def.ident.name,
-            ext: Rc::new(macro_rules::compile(&self.session.parse_sess, &def)),
+            ext: Rc::new(macro_rules::compile(&self.session.parse_sess,
+                                              Some(&*self.session.features.borrow()),
+                                              &def)),
             span: def.span,
         });
         invocation.legacy_scope.set(LegacyScope::Binding(binding));
@@ -169,8 +175,11 @@ impl<'a> base::Resolver for Resolver<'a> {
     }
 
     fn add_ext(&mut self, ident: ast::Ident, ext: Rc<SyntaxExtension>) {
-        if let NormalTT(..) = *ext {
-            self.macro_names.insert(ident.name);
+        match *ext {
+            NormalTT(..) | SyntaxExtension::ProcMacro(..) => {
+                self.macro_names.insert(ident.name);
+            }
+            _ => {}
         }
         let def_id = DefId {
             krate: BUILTIN_MACROS_CRATE,
@@ -202,27 +211,38 @@ impl<'a> base::Resolver for Resolver<'a> {
 
     fn resolve_macro(&mut self, scope: Mark, path: &ast::Path, force: bool)
                      -> Result<Rc<SyntaxExtension>, Determinacy> {
-        if path.segments.len() > 1 || path.global || !path.segments[0].parameters.is_empty() {
-            self.session.span_err(path.span, "expected macro name without module separators");
+        let ast::Path { ref segments, global, span } = *path;
+        if segments.iter().any(|segment| !segment.parameters.is_empty()) {
+            self.session.span_err(span, "type parameters are not allowed on macros");
             return Err(Determinacy::Determined);
         }
-        let name = path.segments[0].identifier.name;
+        if segments.len() > 1 || global {
+            if !self.session.features.borrow().decl_macro {
+                self.session.span_err(span, "expected macro name without module separators");
+                return Err(Determinacy::Determined);
+            }
+            return self.resolve_macro_path(scope, path, force);
+        }
+        let name = segments[0].identifier.name;
 
         let invocation = self.invocations[&scope];
         if let LegacyScope::Expansion(parent) = invocation.legacy_scope.get() {
             invocation.legacy_scope.set(LegacyScope::simplify_expansion(parent));
         }
-        self.resolve_macro_name(invocation.legacy_scope.get(), name).ok_or_else(|| {
-            if force {
-                let msg = format!("macro undefined: '{}!'", name);
-                let mut err = self.session.struct_span_err(path.span, &msg);
-                self.suggest_macro_name(&name.as_str(), &mut err);
-                err.emit();
-                Determinacy::Determined
-            } else {
-                Determinacy::Undetermined
-            }
-        })
+        if let Some(ext) = self.resolve_macro_name(invocation.legacy_scope.get(), name) {
+            return Ok(ext);
+        }
+
+        // Fall back to the `macro` items and imported macros in scope.
+        match self.resolve_macro_path(scope, path, false) {
+            Err(Determinacy::Undetermined) if force => {}
+            result => return result,
+        }
+        let msg = format!("macro undefined: '{}!'", name);
+        let mut err = self.session.struct_span_err(span, &msg);
+        self.suggest_macro_name(&name.as_str(), &mut err);
+        err.emit();
+        Err(Determinacy::Determined)
     }
 }
 
@@ -270,6 +290,96 @@ impl<'a> Resolver<'a> {
         self.builtin_macros.get(&name).cloned().map(|def_id| self.get_macro(Def::Macro(def_id)))
     }
 
+    // Resolves `path` in the macro namespace, following the usual scoping and privacy
+    // rules, as for a `macro` item. Ribs are not set up during expansion, so the
+    // first segment of a relative path is looked up in the invocation's module and
+    // any blocks around it. Imports are only resolved after expansion, so those that
+    // can define macros are resolved on demand here.
+    fn resolve_macro_path(&mut self, scope: Mark, path: &ast::Path, force: bool)
+                          -> Result<Rc<SyntaxExtension>, Determinacy> {
+        let idents: Vec<_> = path.segments.iter().map(|segment| segment.identifier).collect();
+        let (ident, module_path) = idents.split_last().unwrap();
+        self.resolve_macro_imports();
+
+        // Paths in the body of a `macro` item are resolved where the macro is defined.
+        let invocation = self.invocations[&scope];
+        let module = self.macro_def_scope(ident.ctxt).unwrap_or_else(|| invocation.module.get());
+        let orig_module = replace(&mut self.current_module, module);
+
+        let module = match module_path.first() {
+            _ if path.global => {
+                self.resolve_module_path(module_path, DontUseLexicalScope, Some(path.span))
+            }
+            None => Success(self.current_module),
+            Some(first) => match &*first.name.as_str() {
+                "self" | "super" | "$crate" => {
+                    self.resolve_module_path(module_path, DontUseLexicalScope, Some(path.span))
+                }
+                _ => self.resolve_macro_module_path(module_path, path.span),
+            },
+        };
+
+        let binding = match module {
+            Success(module) => {
+                let record_use = match self.resolve_name_in_module(module, ident.name, MacroNS,
+                                                                   false, None) {
+                    Success(_) => true,
+                    // The macro may just be inaccessible, in which case recording the use
+                    // reports it as a privacy error.
+                    Failed(None) => force,
+                    _ => false,
+                };
+                let record_used = if record_use { Some(path.span) } else { None };
+                self.resolve_name_in_module(module, ident.name, MacroNS, false, record_used)
+            }
+            result => result,
+        };
+        self.current_module = orig_module;
+
+        match binding {
+            Success(binding) if binding.def() == Def::Err => Err(Determinacy::Determined),
+            Success(binding) => Ok(self.get_macro(binding.def())),
+            Indeterminate | Failed(_) if !force => Err(Determinacy::Undetermined),
+            Indeterminate | Failed(None) => {
+                let msg = format!("cannot find macro `{}`", path);
+                self.session.span_err(path.span, &msg);
+                Err(Determinacy::Determined)
+            }
+            Failed(Some((span, msg))) => {
+                self.session.span_err(span, &msg);
+                Err(Determinacy::Determined)
+            }
+        }
+    }
+
+    fn resolve_macro_module_path(&mut self, module_path: &[ast::Ident], span: Span)
+                                 -> ResolveResult<Module<'a>> {
+        let name = module_path[0].name;
+        let mut module = self.current_module;
+        loop {
+            match self.resolve_name_in_module(module, name, TypeNS, false, None) {
+                Success(binding) => {
+                    return match self.expect_module(name, binding, Some(span)) {
+                        Success(module) => {
+                            self.resolve_module_path_from_root(module, module_path, 1, Some(span))
+                        }
+                        result => result,
+                    };
+                }
+                Indeterminate => return Indeterminate,
+                Failed(_) => {}
+            }
+
+            match module.kind {
+                ModuleKind::Block(..) => module = module.parent.unwrap(),
+                _ => {
+                    let msg = format!("Use of undeclared type or module `{}`", name);
+                    return Failed(Some((span, msg)));
+                }
+            }
+        }
+    }
+
     fn suggest_macro_name(&mut self, name: &str, err: &mut DiagnosticBuilder<'a>) {
         if let Some(suggestion) = find_best_match_for_name(self.macro_names.iter(), name, None) {
             if suggestion != name {
@@ -11,7 +11,7 @@
 use self::ImportDirectiveSubclass::*;
 
 use Module;
-use Namespace::{self, TypeNS, ValueNS};
+use Namespace::{self, TypeNS, ValueNS, MacroNS};
 use {NameBinding, NameBindingKind, PrivacyError, ToNameBinding};
 use ResolveResult;
 use ResolveResult::*;
@@ -36,6 +36,16 @@ impl<'a> Resolver<'a> {
     pub fn resolve_imports(&mut self) {
         ImportResolver { resolver: self }.resolve_imports();
     }
+
+    /// Resolves what imports it can in the macro namespace, so that imported `macro`s
+    /// can be invoked during expansion, before the other namespaces are resolved.
+    /// This is a no-op unless items or imports have been added since the last call.
+    pub fn resolve_macro_imports(&mut self) {
+        if self.macro_imports_stale {
+            self.macro_imports_stale = false;
+            ImportResolver { resolver: self }.resolve_macro_imports();
+        }
+    }
 }
 
 /// Contains data for specific types of import directives.
@@ -46,6 +56,7 @@ pub enum ImportDirectiveSubclass<'a> {
         source: Name,
         value_result: Cell<Result<&'a NameBinding<'a>, Determinacy>>,
         type_result: Cell<Result<&'a NameBinding<'a>, Determinacy>>,
+        macro_result: Cell<Result<&'a NameBinding<'a>, Determinacy>>,
     },
     GlobImport {
         is_prelude: bool,
@@ -62,6 +73,7 @@ impl<'a> ImportDirectiveSubclass<'a> {
             source: source,
             type_result: Cell::new(Err(Undetermined)),
             value_result: Cell::new(Err(Undetermined)),
+            macro_result: Cell::new(Err(Undetermined)),
         }
     }
 }
@@ -272,9 +284,10 @@ impl<'a> Resolver<'a> {
         });
 
         self.indeterminate_imports.push(directive);
+        self.macro_imports_stale = true;
         match directive.subclass {
             SingleImport { target, .. } => {
-                for &ns in &[ValueNS, TypeNS] {
+                for &ns in &[ValueNS, TypeNS, MacroNS] {
                     let mut resolution = self.resolution(current_module, target, ns).borrow_mut();
                     resolution.single_imports.add_directive(directive);
                 }
@@ -484,6 +497,64 @@ impl<'a, 'b:'a> ImportResolver<'a, 'b> {
         }
     }
 
+    fn resolve_macro_imports(&mut self) {
+        let orig_module = self.current_module;
+        let mut progress = true;
+        while progress {
+            progress = false;
+            for import in self.indeterminate_imports.clone() {
+                progress |= self.resolve_macro_import(import);
+            }
+        }
+        self.current_module = orig_module;
+    }
+
+    /// Attempts to resolve the given import in the macro namespace only, returning
+    /// whether it resolved. Failures are not recorded since the name may still be
+    /// defined by a macro that has not been expanded yet; `resolve_import` will
+    /// determine them once expansion is complete.
+    fn resolve_macro_import(&mut self, directive: &'b ImportDirective<'b>) -> bool {
+        let (source, target, macro_result) = match directive.subclass {
+            SingleImport { source, target, ref macro_result, .. } => (source, target, macro_result),
+            GlobImport { .. } => return false,
+            _ => unreachable!(),
+        };
+        if let Ok(_) = macro_result.get() {
+            return false;
+        }
+
+        self.current_module = directive.parent;
+        let module = match directive.imported_module.get() {
+            Some(module) => module,
+            None => {
+                let vis = directive.vis.get();
+                directive.vis.set(ty::Visibility::PrivateExternal);
+                let result =
+                    self.resolve_module_path(&directive.module_path, DontUseLexicalScope, None);
+                directive.vis.set(vis);
+
+                match result {
+                    Success(module) => module,
+                    Indeterminate | Failed(_) => return false,
+                }
+            }
+        };
+        directive.imported_module.set(Some(module));
+
+        let binding = match self.resolve_name_in_module(module, source, MacroNS, false, None) {
+            Success(binding) => binding,
+            Indeterminate | Failed(_) => return false,
+        };
+        macro_result.set(Ok(binding));
+        let imported_binding = self.import(binding, directive);
+        if let Err(old_binding) = self.try_define(directive.parent, target, MacroNS,
+                                                  imported_binding) {
+            let binding = &self.import(binding, directive);
+            self.report_conflict(directive.parent, target, MacroNS, binding, old_binding);
+        }
+        true
+    }
+
     // Define a "dummy" resolution containing a Def::Err as a placeholder for a
     // failed resolution
     fn import_dummy_binding(&mut self, directive: &'b ImportDirective<'b>) {
@@ -491,7 +562,8 @@ impl<'a, 'b:'a> ImportResolver<'a, 'b> {
             let dummy_binding = self.dummy_binding;
             let dummy_binding = self.import(dummy_binding, directive);
             let _ = self.try_define(directive.parent, target, ValueNS, dummy_binding.clone());
-            let _ = self.try_define(directive.parent, target, TypeNS, dummy_binding);
+            let _ = self.try_define(directive.parent, target, TypeNS, dummy_binding.clone());
+            let _ = self.try_define(directive.parent, target, MacroNS, dummy_binding);
         }
     }
 
@@ -526,9 +598,9 @@ impl<'a, 'b:'a> ImportResolver<'a, 'b> {
         };
 
         directive.imported_module.set(Some(module));
-        let (source, target, value_result, type_result) = match directive.subclass {
-            SingleImport { source, target, ref value_result, ref type_result } =>
-                (source, target, value_result, type_result),
+        let (source, target, value_result, type_result, macro_result) = match directive.subclass {
+            SingleImport { source, target, ref value_result, ref type_result, ref macro_result } =>
+                (source, target, value_result, type_result, macro_result),
             GlobImport { .. } => {
                 self.resolve_glob_import(directive);
                 return Success(());
@@ -537,7 +609,8 @@ impl<'a, 'b:'a> ImportResolver<'a, 'b> {
         };
 
         let mut indeterminate = false;
-        for &(ns, result) in &[(ValueNS, value_result), (TypeNS, type_result)] {
+        let results = [(ValueNS, value_result), (TypeNS, type_result), (MacroNS, macro_result)];
+        for &(ns, result) in &results {
             if let Err(Undetermined) = result.get() {
                 result.set({
                     match self.resolve_name_in_module(module, source, ns, false, None) {
@@ -604,9 +677,9 @@ impl<'a, 'b:'a> ImportResolver<'a, 'b> {
             },
         };
 
-        let (name, value_result, type_result) = match directive.subclass {
-            SingleImport { source, ref value_result, ref type_result, .. } =>
-                (source, value_result.get(), type_result.get()),
+        let (name, value_result, type_result, macro_result) = match directive.subclass {
+            SingleImport { source, ref value_result, ref type_result, ref macro_result, .. } =>
+                (source, value_result.get(), type_result.get(), macro_result.get()),
             GlobImport { .. } if module.def_id() == directive.parent.def_id() => {
                 // Importing a module into itself is not allowed.
                 let msg = "Cannot glob-import a module into itself.".into();
@@ -624,7 +697,8 @@ impl<'a, 'b:'a> ImportResolver<'a, 'b> {
             _ => unreachable!(),
         };
 
-        for &(ns, result) in &[(ValueNS, value_result), (TypeNS, type_result)] {
+        let results = [(ValueNS, value_result), (TypeNS, type_result), (MacroNS, macro_result)];
+        for &(ns, result) in &results {
             if let Ok(binding) = result {
                 if self.record_use(name, ns, binding, directive.span) {
                     self.resolution(module, name, ns).borrow_mut().binding =
@@ -633,12 +707,14 @@ impl<'a, 'b:'a> ImportResolver<'a, 'b> {
             }
         }
 
-        if value_result.is_err() && type_result.is_err() {
-            let (value_result, type_result);
+        if value_result.is_err() && type_result.is_err() && macro_result.is_err() {
+            let (value_result, type_result, macro_result);
             value_result = self.resolve_name_in_module(module, name, ValueNS, false, Some(span));
             type_result = self.resolve_name_in_module(module, name, TypeNS, false, Some(span));
+            macro_result = self.resolve_name_in_module(module, name, MacroNS, false, Some(span));
 
-            return if let (Failed(_), Failed(_)) = (value_result, type_result) {
+            return if let (Failed(_), Failed(_), Failed(_)) =
+                          (value_result, type_result, macro_result) {
                 let resolutions = module.resolutions.borrow();
                 let names = resolutions.iter().filter_map(|(&(ref n, _), resolution)| {
                     if *n == name { return None; } // Never suggest the same name
@@ -715,12 +791,18 @@ impl<'a, 'b:'a> ImportResolver<'a, 'b> {
             _ => {}
         }
 
+        if let (Err(_), Err(_), Ok(binding)) = (value_result, type_result, macro_result) {
+            if !binding.pseudo_vis().is_at_least(directive.vis.get(), self) {
+                reexport_error();
+            }
+        }
+
         // Record what this import resolves to for later uses in documentation,
-        // this may resolve to either a value or a type, but for documentation
-        // purposes it's good enough to just favor one over the other.
+        // this may resolve to either a value, a type or a macro, but for documentation
+        // purposes it's good enough to just favor one over the others.
         let def = match type_result.ok().map(NameBinding::def) {
             Some(def) => def,
-            None => value_result.ok().map(NameBinding::def).unwrap(),
+            None => value_result.or(macro_result).ok().map(NameBinding::def).unwrap(),
         };
         let path_resolution = PathResolution::new(def);
         self.def_map.insert(directive.id, path_resolution);
@@ -798,8 +880,9 @@ impl<'a, 'b:'a> ImportResolver<'a, 'b> {
                 }
             }
 
+            // Public `macro` items are not otherwise encoded as children of their module.
             if binding.vis == ty::Visibility::Public &&
-               (binding.is_import() || binding.is_extern_crate()) {
+               (binding.is_import() || binding.is_extern_crate() || ns == MacroNS) {
                 let def = binding.def();
                 if def != Def::Err {
                     reexports.push(Export { name: name, def: def });
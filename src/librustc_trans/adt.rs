@@ -45,7 +45,7 @@ use super::Disr;
 
 use std;
 
-use llvm::{ValueRef, True, IntEQ, IntNE};
+use llvm::{ValueRef, True, IntEQ, IntNE, IntULE};
 use rustc::ty::layout;
 use rustc::ty::{self, Ty, AdtKind};
 use build::*;
@@ -150,15 +150,18 @@ pub fn finish_type_of<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
         layout::CEnum { .. } | layout::General { .. }
         | layout::UntaggedUnion { .. } | layout::RawNullablePointer { .. } => { }
         layout::Univariant { ..}
-        | layout::StructWrappedNullablePointer { .. } => {
-            let (nonnull_variant, packed) = match *l {
-                layout::Univariant { ref variant, .. } => (0, variant.packed),
+        | layout::StructWrappedNullablePointer { .. }
+        | layout::NicheFilling { .. } => {
+            let (nonnull_variant_index, nonnull_variant, packed) = match *l {
+                layout::Univariant { ref variant, .. } => (0, variant, variant.packed),
                 layout::StructWrappedNullablePointer { nndiscr, ref nonnull, .. } =>
-                    (nndiscr, nonnull.packed),
+                    (nndiscr, nonnull, nonnull.packed),
+                layout::NicheFilling { dataful_variant, ref dataful, .. } =>
+                    (dataful_variant, dataful, dataful.packed),
                 _ => unreachable!()
             };
-            let fields = compute_fields(cx, t, nonnull_variant as usize, true);
-            llty.set_struct_body(&struct_llfields(cx, &fields, false, false),
+            let fields = compute_fields(cx, t, nonnull_variant_index as usize, true);
+            llty.set_struct_body(&struct_llfields(cx, &fields, nonnull_variant, false, false),
                                  packed)
         },
         _ => bug!("This function cannot handle {} with layout {:#?}", t, l)
@@ -184,11 +187,12 @@ fn generic_type_of<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
                 &def.variants[nndiscr as usize].fields[0]);
             type_of::sizing_type_of(cx, nnty)
         }
-        layout::StructWrappedNullablePointer { nndiscr, ref nonnull, .. } => {
+        layout::StructWrappedNullablePointer { nndiscr, ref nonnull, .. } |
+        layout::NicheFilling { dataful_variant: nndiscr, dataful: ref nonnull, .. } => {
             let fields = compute_fields(cx, t, nndiscr as usize, false);
             match name {
                 None => {
-                    Type::struct_(cx, &struct_llfields(cx, &fields, sizing, dst),
+                    Type::struct_(cx, &struct_llfields(cx, &fields, nonnull, sizing, dst),
                                   nonnull.packed)
                 }
                 Some(name) => {
@@ -203,7 +207,7 @@ fn generic_type_of<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
             let fields = compute_fields(cx, t, 0, true);
             match name {
                 None => {
-                    let fields = struct_llfields(cx, &fields, sizing, dst);
+                    let fields = struct_llfields(cx, &fields, variant, sizing, dst);
                     Type::struct_(cx, &fields, variant.packed)
                 }
                 Some(name) => {
@@ -289,13 +293,16 @@ fn union_fill(cx: &CrateContext, size: u64, align: u64) -> Type {
 }
 
 
+/// The LLVM types of the fields of `variant`, in memory order.
 fn struct_llfields<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>, fields: &Vec<Ty<'tcx>>,
+                             variant: &layout::Struct,
                              sizing: bool, dst: bool) -> Vec<Type> {
+    let fields = variant.field_index_by_increasing_offset().map(|i| fields[i]);
     if sizing {
-        fields.iter().filter(|&ty| !dst || type_is_sized(cx.tcx(), *ty))
-            .map(|&ty| type_of::sizing_type_of(cx, ty)).collect()
+        fields.filter(|ty| !dst || type_is_sized(cx.tcx(), *ty))
+            .map(|ty| type_of::sizing_type_of(cx, ty)).collect()
     } else {
-        fields.iter().map(|&ty| type_of::in_memory_type_of(cx, ty)).collect()
+        fields.map(|ty| type_of::in_memory_type_of(cx, ty)).collect()
     }
 }
 
@@ -309,7 +316,8 @@ pub fn trans_switch<'blk, 'tcx>(bcx: Block<'blk, 'tcx>,
     let l = bcx.ccx().layout_of(t);
     match *l {
         layout::CEnum { .. } | layout::General { .. } |
-        layout::RawNullablePointer { .. } | layout::StructWrappedNullablePointer { .. } => {
+        layout::RawNullablePointer { .. } | layout::StructWrappedNullablePointer { .. } |
+        layout::NicheFilling { .. } => {
             (BranchKind::Switch, Some(trans_get_discr(bcx, t, scrutinee, None, range_assert)))
         }
         layout::Univariant { .. } | layout::UntaggedUnion { .. } => {
@@ -360,6 +368,17 @@ pub fn trans_get_discr<'blk, 'tcx>(bcx: Block<'blk, 'tcx>, t: Ty<'tcx>,
         layout::StructWrappedNullablePointer { nndiscr, ref discrfield, .. } => {
             struct_wrapped_nullable_bitdiscr(bcx, nndiscr, discrfield, scrutinee)
         },
+        layout::NicheFilling { dataful_variant, niche_variants, ref niche, niche_start, .. } => {
+            let llty = Type::from_integer(bcx.ccx(), niche.value);
+            let val = Load(bcx, niche_ptr(bcx, niche, scrutinee));
+            // The variants stored in the niche map to consecutive values.
+            let relative = Sub(bcx, val, C_integral(llty, niche_start, false), DebugLoc::None);
+            let max = C_integral(llty, niche_variants.1 - niche_variants.0, false);
+            let in_niche = ICmp(bcx, IntULE, relative, max, DebugLoc::None);
+            let discr = Add(bcx, relative, C_integral(llty, niche_variants.0, false),
+                            DebugLoc::None);
+            Select(bcx, in_niche, discr, C_integral(llty, dataful_variant, false))
+        }
         _ => bug!("{} is not an enum", t)
     };
     match cast_to {
@@ -377,6 +396,20 @@ fn struct_wrapped_nullable_bitdiscr(bcx: Block, nndiscr: u64, discrfield: &layou
     ICmp(bcx, cmp, llptr, C_null(val_ty(llptr)), DebugLoc::None)
 }
 
+/// Get a pointer to the niche of a niche-filled enum.
+fn niche_ptr(bcx: Block, niche: &layout::Niche, val: ValueRef) -> ValueRef {
+    let llptr = PointerCast(bcx, val, Type::i8p(bcx.ccx()));
+    let llptr = InBoundsGEP(bcx, llptr, &[C_uint(bcx.ccx(), niche.offset.bytes())]);
+    PointerCast(bcx, llptr, Type::from_integer(bcx.ccx(), niche.value).ptr_to())
+}
+
+/// The value of the niche representing the variant `discr`, which must
+/// be in `niche_variants`.
+fn niche_value(niche_variants: (u64, u64), niche_start: u64, discr: Disr) -> u64 {
+    assert!(niche_variants.0 <= discr.0 && discr.0 <= niche_variants.1);
+    (discr.0 - niche_variants.0).wrapping_add(niche_start)
+}
+
 /// Helper for cases where the discriminant is simply loaded.
 fn load_discr(bcx: Block, ity: layout::Integer, ptr: ValueRef, min: u64, max: u64,
               range_assert: bool)
@@ -419,6 +452,9 @@ pub fn trans_case<'blk, 'tcx>(bcx: Block<'blk, 'tcx>, t: Ty<'tcx>, value: Disr)
             assert!(value == Disr(0) || value == Disr(1));
             C_bool(bcx.ccx(), value != Disr(0))
         }
+        layout::NicheFilling { ref niche, .. } => {
+            C_integral(Type::from_integer(bcx.ccx(), niche.value), value.0, false)
+        }
         _ => {
             bug!("{} does not have a discriminant. Represented as {:#?}", t, l);
         }
@@ -472,6 +508,13 @@ pub fn trans_set_discr<'blk, 'tcx>(bcx: Block<'blk, 'tcx>, t: Ty<'tcx>,
                 }
             }
         }
+        layout::NicheFilling { dataful_variant, niche_variants, ref niche, niche_start, .. } => {
+            if to.0 != dataful_variant {
+                let llty = Type::from_integer(bcx.ccx(), niche.value);
+                let value = niche_value(niche_variants, niche_start, to);
+                Store(bcx, C_integral(llty, value, false), niche_ptr(bcx, niche, val));
+            }
+        }
         _ => bug!("Cannot handle {} represented as {:#?}", t, l)
     }
 }
@@ -556,6 +599,19 @@ pub fn trans_field_ptr_builder<'blk, 'tcx>(bcx: &BlockAndBuilder<'blk, 'tcx>,
              &compute_fields(bcx.ccx(), t, discr.0 as usize, false),
              val, ix, false)
         }
+        layout::NicheFilling { dataful_variant, .. } if discr.0 != dataful_variant => {
+            // All the fields of the variants in the niche are zero-sized.
+            let fields = compute_fields(bcx.ccx(), t, discr.0 as usize, false);
+            let ty = type_of::type_of(bcx.ccx(), fields[ix]);
+            assert_eq!(machine::llsize_of_alloc(bcx.ccx(), ty), 0);
+            if bcx.is_unreachable() { return C_undef(ty.ptr_to()); }
+            bcx.pointercast(val.value, ty.ptr_to())
+        }
+        layout::NicheFilling { ref dataful, .. } => {
+            struct_field_ptr(bcx, &dataful,
+             &compute_fields(bcx.ccx(), t, discr.0 as usize, false),
+             val, ix, false)
+        }
         _ => bug!("element access in type without elements: {} represented as {:#?}", t, l)
     }
 }
@@ -571,8 +627,8 @@ fn struct_field_ptr<'blk, 'tcx>(bcx: &BlockAndBuilder<'blk, 'tcx>,
     }
 
     let ptr_val = if needs_cast {
-        let fields = fields.iter().map(|&ty| {
-            type_of::in_memory_type_of(ccx, ty)
+        let fields = st.field_index_by_increasing_offset().map(|i| {
+            type_of::in_memory_type_of(ccx, fields[i])
         }).collect::<Vec<_>>();
         let real_ty = Type::struct_(ccx, &fields[..], st.packed);
         bcx.pointercast(val.value, real_ty.ptr_to())
@@ -580,19 +636,23 @@ fn struct_field_ptr<'blk, 'tcx>(bcx: &BlockAndBuilder<'blk, 'tcx>,
         val.value
     };
 
+    // The index of the field in the LLVM struct, which is in memory order.
+    let llix = st.memory_index[ix] as usize;
+
     // Simple case - we can just GEP the field
     //   * First field - Always aligned properly
     //   * Packed struct - There is no alignment padding
     //   * Field is sized - pointer is properly aligned already
-    if ix == 0 || st.packed || type_is_sized(bcx.tcx(), fty) {
-        return bcx.struct_gep(ptr_val, ix);
+    if st.offsets[ix] == layout::Size::from_bytes(0) || st.packed ||
+       type_is_sized(bcx.tcx(), fty) {
+        return bcx.struct_gep(ptr_val, llix);
     }
 
     // If the type of the last field is [T] or str, then we don't need to do
     // any adjusments
     match fty.sty {
         ty::TySlice(..) | ty::TyStr => {
-            return bcx.struct_gep(ptr_val, ix);
+            return bcx.struct_gep(ptr_val, llix);
         }
         _ => ()
     }
@@ -601,7 +661,7 @@ fn struct_field_ptr<'blk, 'tcx>(bcx: &BlockAndBuilder<'blk, 'tcx>,
     if !val.has_meta() {
         debug!("Unsized field `{}`, of `{:?}` has no metadata for adjustment",
                ix, Value(ptr_val));
-        return bcx.struct_gep(ptr_val, ix);
+        return bcx.struct_gep(ptr_val, llix);
     }
 
     let dbloc = DebugLoc::None;
@@ -733,6 +793,27 @@ pub fn trans_const<'a, 'tcx>(ccx: &CrateContext<'a, 'tcx>, t: Ty<'tcx>, discr: D
                          false)
             }
         }
+        layout::NicheFilling { dataful_variant, niche_variants, ref niche, niche_start,
+                               ref dataful } => {
+            if discr.0 == dataful_variant {
+                C_struct(ccx, &build_const_struct(ccx, &dataful, vals), false)
+            } else {
+                // Only the niche is meaningful, leave the rest undefined.
+                let llty = Type::from_integer(ccx, niche.value);
+                let value = niche_value(niche_variants, niche_start, discr);
+                let offset = niche.offset.bytes();
+                let end = offset + niche.value.size().bytes();
+                let mut contents = vec![];
+                if offset > 0 {
+                    contents.push(padding(ccx, offset));
+                }
+                contents.push(C_integral(llty, value, false));
+                if end < dataful.stride().bytes() {
+                    contents.push(padding(ccx, dataful.stride().bytes() - end));
+                }
+                C_struct(ccx, &contents[..], true)
+            }
+        }
         _ => bug!("trans_const: cannot handle type {} repreented as {:#?}", t, l)
     }
 }
@@ -758,8 +839,10 @@ fn build_const_struct<'a, 'tcx>(ccx: &CrateContext<'a, 'tcx>,
     // offset of current value
     let mut offset = 0;
     let mut cfields = Vec::new();
-    let offsets = st.offsets.iter().map(|i| i.bytes());
-    for (&val, target_offset) in vals.iter().zip(offsets) {
+    let parts = st.field_index_by_increasing_offset().map(|i| {
+        (vals[i], st.offsets[i].bytes())
+    });
+    for (val, target_offset) in parts {
         if offset < target_offset {
             cfields.push(padding(ccx, target_offset - offset));
             offset = target_offset;
@@ -805,19 +888,35 @@ fn roundup(x: u64, a: u32) -> u64 { let a = a as u64; ((x + (a - 1)) / a) * a }
 /// (Not to be confused with `common::const_get_elt`, which operates on
 /// raw LLVM-level structs and arrays.)
 pub fn const_get_field<'a, 'tcx>(ccx: &CrateContext<'a, 'tcx>, t: Ty<'tcx>,
-                       val: ValueRef, _discr: Disr,
+                       val: ValueRef, discr: Disr,
                        ix: usize) -> ValueRef {
     let l = ccx.layout_of(t);
     match *l {
         layout::CEnum { .. } => bug!("element access in C-like enum const"),
-        layout::Univariant { .. } | layout::Vector { .. } => const_struct_field(val, ix),
+        layout::Univariant { ref variant, .. } => {
+            const_struct_field(val, variant.memory_index[ix] as usize)
+        }
+        layout::Vector { .. } => const_struct_field(val, ix),
         layout::UntaggedUnion { .. } => const_struct_field(val, 0),
-        layout::General { .. } => const_struct_field(val, ix + 1),
+        layout::General { ref variants, .. } => {
+            const_struct_field(val, variants[discr.0 as usize].memory_index[ix + 1] as usize)
+        }
         layout::RawNullablePointer { .. } => {
             assert_eq!(ix, 0);
             val
         },
-        layout::StructWrappedNullablePointer{ .. } => const_struct_field(val, ix),
+        layout::StructWrappedNullablePointer{ ref nonnull, .. } => {
+            const_struct_field(val, nonnull.memory_index[ix] as usize)
+        }
+        layout::NicheFilling { dataful_variant, ref dataful, .. } => {
+            if discr.0 == dataful_variant {
+                const_struct_field(val, dataful.memory_index[ix] as usize)
+            } else {
+                // The fields of the other variants are all zero-sized.
+                let fields = compute_fields(ccx, t, discr.0 as usize, false);
+                C_undef(type_of::type_of(ccx, fields[ix]))
+            }
+        }
         _ => bug!("{} does not have fields.", t)
     }
 }
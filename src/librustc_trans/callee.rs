@@ -407,8 +407,12 @@ fn get_fn<'a, 'tcx>(ccx: &CrateContext<'a, 'tcx>,
         return (llfn, fn_ty);
     }
 
+    // With `-Z polymorphize`, the instance may share its code with others,
+    // whose types differ from `fn_ty`. The pointer is then cast below.
+    let shared_instance = Instance::new(def_id,
+                                        monomorphize::polymorphize(ccx.shared(), def_id, substs));
     let sym = ccx.symbol_map().get_or_compute(ccx.shared(),
-                                              TransItem::Fn(instance));
+                                              TransItem::Fn(shared_instance));
     debug!("get_fn({:?}: {:?}) => {}", instance, fn_ty, sym);
 
     // This is subtle and surprising, but sometimes we have to bitcast
@@ -461,7 +465,7 @@ fn get_fn<'a, 'tcx>(ccx: &CrateContext<'a, 'tcx>,
         attributes::from_fn_attrs(ccx, &attrs, llfn);
 
         let is_local_def = ccx.shared().translation_items().borrow()
-                              .contains(&TransItem::Fn(instance));
+                              .contains(&TransItem::Fn(shared_instance));
         if is_local_def {
             // FIXME(eddyb) Doubt all extern fn should allow unwinding.
             attributes::unwind(llfn, true);
@@ -1009,6 +1009,7 @@ fn create_fn_trans_item<'a, 'tcx>(scx: &SharedCrateContext<'a, 'tcx>,
     assert!(concrete_substs.is_normalized_for_trans(),
             "concrete_substs not normalized for trans: {:?}",
             concrete_substs);
+    let concrete_substs = monomorphize::polymorphize(scx, def_id, concrete_substs);
     TransItem::Fn(Instance::new(def_id, concrete_substs))
 }
 
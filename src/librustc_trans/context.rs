@@ -21,7 +21,7 @@ use common::BuilderRef_res;
 use debuginfo;
 use declare;
 use glue::DropGlueKind;
-use monomorphize::Instance;
+use monomorphize::{Instance, ParamUsage};
 
 use partitioning::CodegenUnit;
 use trans_item::TransItem;
@@ -77,6 +77,7 @@ pub struct SharedCrateContext<'a, 'tcx: 'a> {
     translation_items: RefCell<FxHashSet<TransItem<'tcx>>>,
     trait_cache: RefCell<DepTrackingMap<TraitSelectionCache<'tcx>>>,
     project_cache: RefCell<DepTrackingMap<ProjectionCache<'tcx>>>,
+    param_usage: RefCell<DefIdMap<Rc<Vec<ParamUsage>>>>,
 }
 
 /// The local portion of a `CrateContext`.  There is one `LocalCrateContext`
@@ -510,6 +511,7 @@ impl<'b, 'tcx> SharedCrateContext<'b, 'tcx> {
             translation_items: RefCell::new(FxHashSet()),
             trait_cache: RefCell::new(DepTrackingMap::new(tcx.dep_graph.clone())),
             project_cache: RefCell::new(DepTrackingMap::new(tcx.dep_graph.clone())),
+            param_usage: RefCell::new(DefIdMap()),
         }
     }
 
@@ -537,6 +539,10 @@ impl<'b, 'tcx> SharedCrateContext<'b, 'tcx> {
         &self.project_cache
     }
 
+    pub fn param_usage(&self) -> &RefCell<DefIdMap<Rc<Vec<ParamUsage>>>> {
+        &self.param_usage
+    }
+
     pub fn link_meta<'a>(&'a self) -> &'a LinkMeta {
         &self.link_meta
     }
@@ -885,6 +885,7 @@ impl<'tcx> MemberDescriptionFactory<'tcx> {
 
 // Creates MemberDescriptions for the fields of a struct
 struct StructMemberDescriptionFactory<'tcx> {
+    ty: Ty<'tcx>,
     variant: ty::VariantDef<'tcx>,
     substs: &'tcx Substs<'tcx>,
     is_simd: bool,
@@ -906,6 +907,14 @@ impl<'tcx> StructMemberDescriptionFactory<'tcx> {
             None
         };
 
+        // Fields aren't necessarily laid out in source order, so their
+        // offsets come from the layout rather than from the LLVM type.
+        let offsets = match *cx.layout_of(self.ty) {
+            layout::Univariant { ref variant, .. } => Some(&variant.offsets),
+            layout::Vector { .. } => None,
+            ref l => bug!("{} is not a struct: {:#?}", self.ty, l)
+        };
+
         self.variant.fields.iter().enumerate().map(|(i, f)| {
             let name = if self.variant.ctor_kind == CtorKind::Fn {
                 format!("__{}", i)
@@ -914,10 +923,9 @@ impl<'tcx> StructMemberDescriptionFactory<'tcx> {
             };
             let fty = monomorphize::field_ty(cx.tcx(), self.substs, f);
 
-            let offset = if self.is_simd {
-                FixedMemberOffset { bytes: i * field_size.unwrap() }
-            } else {
-                ComputedMemberOffset
+            let offset = match offsets {
+                Some(offsets) => FixedMemberOffset { bytes: offsets[i].bytes() as usize },
+                None => FixedMemberOffset { bytes: i * field_size.unwrap() }
             };
 
             MemberDescription {
@@ -960,6 +968,7 @@ fn prepare_struct_metadata<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
         struct_metadata_stub,
         struct_llvm_type,
         StructMDF(StructMemberDescriptionFactory {
+            ty: struct_type,
             variant: variant,
             substs: substs,
             is_simd: struct_type.is_simd(),
@@ -974,6 +983,7 @@ fn prepare_struct_metadata<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
 
 // Creates MemberDescriptions for the fields of a tuple
 struct TupleMemberDescriptionFactory<'tcx> {
+    ty: Ty<'tcx>,
     component_types: Vec<Ty<'tcx>>,
     span: Span,
 }
@@ -981,6 +991,11 @@ struct TupleMemberDescriptionFactory<'tcx> {
 impl<'tcx> TupleMemberDescriptionFactory<'tcx> {
     fn create_member_descriptions<'a>(&self, cx: &CrateContext<'a, 'tcx>)
                                       -> Vec<MemberDescription> {
+        let offsets = match *cx.layout_of(self.ty) {
+            layout::Univariant { ref variant, .. } => &variant.offsets,
+            ref l => bug!("{} is not a tuple: {:#?}", self.ty, l)
+        };
+
         self.component_types
             .iter()
             .enumerate()
@@ -989,7 +1004,7 @@ impl<'tcx> TupleMemberDescriptionFactory<'tcx> {
                 name: format!("__{}", i),
                 llvm_type: type_of::type_of(cx, component_type),
                 type_metadata: type_metadata(cx, component_type, self.span),
-                offset: ComputedMemberOffset,
+                offset: FixedMemberOffset { bytes: offsets[i].bytes() as usize },
                 flags: FLAGS_NONE,
             }
         }).collect()
@@ -1016,6 +1031,7 @@ fn prepare_tuple_metadata<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
                            NO_SCOPE_METADATA),
         tuple_llvm_type,
         TupleMDF(TupleMemberDescriptionFactory {
+            ty: tuple_type,
             component_types: component_types.to_vec(),
             span: span,
         })
@@ -1254,7 +1270,7 @@ impl<'tcx> EnumMemberDescriptionFactory<'tcx> {
             },
             layout::StructWrappedNullablePointer { nonnull: ref struct_def,
                                                 nndiscr,
-                                                ref discrfield, ..} => {
+                                                ref discrfield_source, ..} => {
                 // Create a description of the non-null variant
                 let (variant_type_metadata, variant_llvm_type, member_description_factory) =
                     describe_enum_variant(cx,
@@ -1277,12 +1293,11 @@ impl<'tcx> EnumMemberDescriptionFactory<'tcx> {
                 // member's name.
                 let null_variant_index = (1 - nndiscr) as usize;
                 let null_variant_name = adt.variants[null_variant_index].name;
-                let discrfield = discrfield.iter()
-                                           .skip(1)
+                let discrfield_source = discrfield_source.iter()
                                            .map(|x| x.to_string())
                                            .collect::<Vec<_>>().join("$");
                 let union_member_name = format!("RUST$ENCODED$ENUM${}${}",
-                                                discrfield,
+                                                discrfield_source,
                                                 null_variant_name);
 
                 // Create the (singleton) list of descriptions of union members.
@@ -1296,14 +1311,119 @@ impl<'tcx> EnumMemberDescriptionFactory<'tcx> {
                     }
                 ]
             },
+            layout::NicheFilling { ref dataful, dataful_variant, niche, .. } => {
+                // Every variant is described along with a discriminant field
+                // overlapping the niche, whose type names the variant each
+                // value of the niche stands for.
+                let discriminant_info =
+                    NicheDiscriminant(self.niche_discriminant_type_metadata(cx), niche);
+                let dl = &cx.tcx().data_layout;
+                adt.variants.iter().enumerate().map(|(i, variant)| {
+                    // All the other variants are zero-sized.
+                    let zero_sized;
+                    let struct_def = if i as u64 == dataful_variant {
+                        dataful
+                    } else {
+                        let fields = variant.fields.iter().map(|f| {
+                            cx.layout_of(monomorphize::field_ty(cx.tcx(), substs, f))
+                        }).collect();
+                        zero_sized = layout::Struct::new(dl, &fields, &[],
+                            layout::StructKind::AlwaysSizedUnivariant, self.enum_type)
+                            .unwrap_or_else(|e| bug!("{} variant layout: {}", self.enum_type, e));
+                        &zero_sized
+                    };
+                    let (variant_type_metadata, variant_llvm_type, member_desc_factory) =
+                        describe_enum_variant(cx,
+                                              self.enum_type,
+                                              struct_def,
+                                              variant,
+                                              discriminant_info,
+                                              self.containing_scope,
+                                              self.span);
+
+                    let member_descriptions = member_desc_factory
+                        .create_member_descriptions(cx);
+
+                    set_members_of_composite_type(cx,
+                                                  variant_type_metadata,
+                                                  variant_llvm_type,
+                                                  &member_descriptions);
+                    MemberDescription {
+                        name: "".to_string(),
+                        llvm_type: variant_llvm_type,
+                        type_metadata: variant_type_metadata,
+                        offset: FixedMemberOffset { bytes: 0 },
+                        flags: FLAGS_NONE
+                    }
+                }).collect()
+            },
             layout::CEnum { .. } => span_bug!(self.span, "This should be unreachable."),
             ref l @ _ => bug!("Not an enum layout: {:#?}", l)
         }
     }
+
+    /// Creates the type of the discriminant of a niche-filled enum, an
+    /// enumeration of the values its niche holds for each variant. The
+    /// values of the dataful variant are only listed if there are few
+    /// enough of them, e.g. for a `bool`, so the name of the type encodes
+    /// the dataful variant as well, for the pretty printers in `src/etc`.
+    fn niche_discriminant_type_metadata<'a>(&self, cx: &CrateContext<'a, 'tcx>) -> DIType {
+        const MAX_DATAFUL_VALUES: u64 = 256;
+
+        let adt = self.enum_type.ty_adt_def().unwrap();
+        let (dataful_variant, niche_variants, niche, niche_start) = match *self.type_rep {
+            layout::NicheFilling { dataful_variant, niche_variants, niche, niche_start, .. } => {
+                (dataful_variant, niche_variants, niche, niche_start)
+            }
+            ref l @ _ => bug!("Not a niche-filling layout: {:#?}", l)
+        };
+        let mask = !0 >> (64 - niche.value.size().bits());
+        let enumerator = |variant: u64, value: u64| {
+            let name = CString::new(adt.variants[variant as usize].name.as_str().as_bytes())
+                .unwrap();
+            unsafe {
+                llvm::LLVMRustDIBuilderCreateEnumerator(DIB(cx), name.as_ptr(), value & mask)
+            }
+        };
+
+        let mut enumerators_metadata: Vec<DIDescriptor> =
+            (niche_variants.0..niche_variants.1 + 1).filter(|&v| v != dataful_variant).map(|v| {
+                enumerator(v, (v - niche_variants.0).wrapping_add(niche_start))
+            }).collect();
+        // The valid values end right before the ones taken by this enum.
+        let dataful_values = niche_start.wrapping_sub(niche.valid_start) & mask;
+        if dataful_values <= MAX_DATAFUL_VALUES {
+            enumerators_metadata.extend((0..dataful_values).map(|i| {
+                enumerator(dataful_variant, niche.valid_start.wrapping_add(i))
+            }));
+        }
+
+        let discriminant_llvm_type = Type::from_integer(cx, niche.value);
+        let (discriminant_size, discriminant_align) =
+            size_and_align_of(cx, discriminant_llvm_type);
+        let discriminant_base_type_metadata =
+            type_metadata(cx, niche.value.to_ty(&cx.tcx(), false), syntax_pos::DUMMY_SP);
+        let name = format!("RUST$NICHE$ENUM${}", adt.variants[dataful_variant as usize].name);
+        let name = CString::new(name).unwrap();
+        unsafe {
+            llvm::LLVMRustDIBuilderCreateEnumerationType(
+                DIB(cx),
+                self.containing_scope,
+                name.as_ptr(),
+                self.file_metadata,
+                UNKNOWN_LINE_NUMBER,
+                bytes_to_bits(discriminant_size),
+                bytes_to_bits(discriminant_align),
+                create_DIArray(DIB(cx), &enumerators_metadata),
+                discriminant_base_type_metadata)
+        }
+    }
 }
 
 // Creates MemberDescriptions for the fields of a single enum variant.
 struct VariantMemberDescriptionFactory<'tcx> {
+    // Cloned from the layout::Struct describing the variant.
+    offsets: Vec<layout::Size>,
     args: Vec<(String, Ty<'tcx>)>,
     discriminant_type_metadata: Option<DIType>,
     span: Span,
@@ -1320,7 +1440,7 @@ impl<'tcx> VariantMemberDescriptionFactory<'tcx> {
                     Some(metadata) if i == 0 => metadata,
                     _ => type_metadata(cx, ty, self.span)
                 },
-                offset: ComputedMemberOffset,
+                offset: FixedMemberOffset { bytes: self.offsets[i].bytes() as usize },
                 flags: FLAGS_NONE
             }
         }).collect()
@@ -1330,6 +1450,9 @@ impl<'tcx> VariantMemberDescriptionFactory<'tcx> {
 #[derive(Copy, Clone)]
 enum EnumDiscriminantInfo {
     RegularDiscriminant(DIType),
+    /// The discriminant of a niche-filled enum is stored in the niche,
+    /// which is not one of the fields of each variant.
+    NicheDiscriminant(DIType, layout::Niche),
     OptimizedDiscriminant,
     NoDiscriminant
 }
@@ -1356,7 +1479,8 @@ fn describe_enum_variant<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
         layout::General{discr, ..} => Some((discr, false)),
         layout::Univariant { .. }
         | layout::RawNullablePointer { .. }
-        | layout::StructWrappedNullablePointer { .. } => None,
+        | layout::StructWrappedNullablePointer { .. }
+        | layout::NicheFilling { .. } => None,
         ref l @ _ => bug!("This should be unreachable. Type is {:#?} layout is {:#?}", enum_type, l)
     };
 
@@ -1370,11 +1494,9 @@ fn describe_enum_variant<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
 
 
     let variant_llvm_type =
-        Type::struct_(cx, &field_tys
-                                    .iter()
-                                    .map(|t| type_of::type_of(cx, t))
-                                    .collect::<Vec<_>>()
-                                    ,
+        Type::struct_(cx, &struct_def.field_index_by_increasing_offset()
+                                    .map(|i| type_of::type_of(cx, field_tys[i]))
+                                    .collect::<Vec<_>>(),
                       struct_def.packed);
     // Could do some consistency checks here: size, align, field count, discr type
 
@@ -1411,8 +1533,14 @@ fn describe_enum_variant<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
     };
 
     // If this is not a univariant enum, there is also the discriminant field.
+    let mut offsets = struct_def.offsets.clone();
     match discriminant_info {
         RegularDiscriminant(_) => arg_names.insert(0, "RUST$ENUM$DISR".to_string()),
+        NicheDiscriminant(_, niche) => {
+            arg_names.insert(0, "RUST$ENUM$DISR".to_string());
+            field_tys.insert(0, niche.value.to_ty(&cx.tcx(), false));
+            offsets.insert(0, niche.offset);
+        }
         _ => { /* do nothing */ }
     };
 
@@ -1424,9 +1552,11 @@ fn describe_enum_variant<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
 
     let member_description_factory =
         VariantMDF(VariantMemberDescriptionFactory {
+            offsets: offsets,
             args: args,
             discriminant_type_metadata: match discriminant_info {
-                RegularDiscriminant(discriminant_type_metadata) => {
+                RegularDiscriminant(discriminant_type_metadata) |
+                NicheDiscriminant(discriminant_type_metadata, _) => {
                     Some(discriminant_type_metadata)
                 }
                 _ => None
@@ -1517,6 +1647,7 @@ fn prepare_enum_metadata<'a, 'tcx>(cx: &CrateContext<'a, 'tcx>,
         },
         layout::RawNullablePointer { .. }           |
         layout::StructWrappedNullablePointer { .. } |
+        layout::NicheFilling { .. }                 |
         layout::Univariant { .. }                      => None,
         layout::General { discr, .. } => Some(discriminant_type_metadata(discr, false)),
         ref l @ _ => bug!("Not an enum layout: {:#?}", l)
@@ -67,12 +67,20 @@ impl<'mir, 'bcx, 'tcx> LocalAnalyzer<'mir, 'bcx, 'tcx> {
     fn new(mir: &'mir mir::Mir<'tcx>,
            bcx: &'mir BlockAndBuilder<'bcx, 'tcx>)
            -> LocalAnalyzer<'mir, 'bcx, 'tcx> {
-        LocalAnalyzer {
+        let mut analyzer = LocalAnalyzer {
             mir: mir,
             bcx: bcx,
             lvalue_locals: BitVector::new(mir.local_decls.len()),
             seen_assigned: BitVector::new(mir.local_decls.len())
+        };
+
+        // Arguments get assigned on entry, so assigning them again in the body,
+        // as tail recursion elimination does, requires an alloca.
+        for arg in mir.args_iter() {
+            analyzer.seen_assigned.insert(arg.index());
         }
+
+        analyzer
     }
 
     fn mark_as_lvalue(&mut self, local: mir::Local) {
@@ -11,6 +11,7 @@
 use llvm::{self, ValueRef};
 use rustc::middle::const_val::ConstVal;
 use rustc_const_eval::{ErrKind, ConstEvalErr, report_const_eval_err};
+use rustc_const_eval::interpret::{self, EvalErrorKind};
 use rustc_const_math::ConstInt::*;
 use rustc_const_math::ConstFloat::*;
 use rustc_const_math::{ConstInt, ConstIsize, ConstUsize, ConstMathErr};
@@ -260,6 +261,30 @@ impl<'a, 'tcx> MirConstContext<'a, 'tcx> {
         MirConstContext::new(ccx, &mir, instance.substs, args).trans()
     }
 
+    /// Evaluates the constant or static `instance` of type `ty` with the
+    /// MIR interpreter, or translates its MIR directly if the interpreter
+    /// can't represent its value, e.g. because it is an aggregate.
+    fn trans_item(ccx: &'a CrateContext<'a, 'tcx>,
+                  instance: Instance<'tcx>,
+                  ty: Ty<'tcx>)
+                  -> Result<Const<'tcx>, ConstEvalErr> {
+        let tcx = ccx.tcx();
+        match interpret::eval_const(tcx, instance.def, instance.substs) {
+            None |
+            Some(Ok(ConstVal::Function(_))) |
+            Some(Err(interpret::EvalError { kind: EvalErrorKind::Unimplemented(_), .. })) => {
+                MirConstContext::trans_def(ccx, instance, IndexVec::new())
+            }
+            Some(Ok(value)) => Ok(Const::from_constval(ccx, value, ty)),
+            Some(Err(err)) => {
+                let span = tcx.map.span_if_local(instance.def).unwrap_or(DUMMY_SP);
+                let err = ConstEvalErr::from_interpreter(err, span);
+                report_const_eval_err(tcx, &err, err.span, "expression").emit();
+                Err(err)
+            }
+        }
+    }
+
     fn monomorphize<T>(&self, value: &T) -> T
         where T: TransNormalize<'tcx>
     {
@@ -276,7 +301,22 @@ impl<'a, 'tcx> MirConstContext<'a, 'tcx> {
         // report as many errors as we possibly can.
         let mut failure = Ok(());
 
+        // `const fn`s with control flow may loop, so give up eventually,
+        // like the interpreter does.
+        let mut steps = 0;
+
         loop {
+            steps += 1;
+            if steps > interpret::STEP_LIMIT {
+                let span = self.mir[bb].terminator().source_info.span;
+                let err = ConstEvalErr {
+                    span: span,
+                    kind: ErrKind::Interpreter(EvalErrorKind::ExecutionTimeLimitReached)
+                };
+                report_const_eval_err(tcx, &err, span, "expression").emit();
+                return Err(err);
+            }
+
             let data = &self.mir[bb];
             for statement in &data.statements {
                 let span = statement.source_info.span;
@@ -310,6 +350,23 @@ impl<'a, 'tcx> MirConstContext<'a, 'tcx> {
                     }));
                 }
 
+                mir::TerminatorKind::If { ref cond, targets: (then, else_) } => {
+                    let cond = self.const_operand(cond, span)?;
+                    if common::const_to_uint(cond.llval) != 0 { then } else { else_ }
+                }
+
+                mir::TerminatorKind::SwitchInt {
+                    ref discr, switch_ty, ref values, ref targets
+                } => {
+                    let discr = self.const_lvalue(discr, span)?.to_const(span);
+                    let discr = common::const_to_uint(discr.llval);
+                    let switch_ty = self.monomorphize(&switch_ty);
+                    values.iter().position(|value| {
+                        let value = Const::from_constval(self.ccx, value.clone(), switch_ty);
+                        common::const_to_uint(value.llval) == discr
+                    }).map_or(*targets.last().unwrap(), |i| targets[i])
+                }
+
                 mir::TerminatorKind::Assert { ref cond, expected, ref msg, target, .. } => {
                     let cond = self.const_operand(cond, span)?;
                     let cond_bool = common::const_to_uint(cond.llval) != 0;
@@ -492,7 +549,7 @@ impl<'a, 'tcx> MirConstContext<'a, 'tcx> {
 
                         let substs = self.monomorphize(&substs);
                         let instance = Instance::new(def_id, substs);
-                        MirConstContext::trans_def(self.ccx, instance, IndexVec::new())
+                        MirConstContext::trans_item(self.ccx, instance, ty)
                     }
                     mir::Literal::Promoted { index } => {
                         let mir = &self.mir.promoted[index];
@@ -972,7 +1029,7 @@ impl<'bcx, 'tcx> MirContext<'bcx, 'tcx> {
 
                 let substs = bcx.monomorphize(&substs);
                 let instance = Instance::new(def_id, substs);
-                MirConstContext::trans_def(bcx.ccx(), instance, IndexVec::new())
+                MirConstContext::trans_item(bcx.ccx(), instance, ty)
             }
             mir::Literal::Promoted { index } => {
                 let mir = &self.mir.promoted[index];
@@ -999,5 +1056,8 @@ impl<'bcx, 'tcx> MirContext<'bcx, 'tcx> {
 pub fn trans_static_initializer(ccx: &CrateContext, def_id: DefId)
                                 -> Result<ValueRef, ConstEvalErr> {
     let instance = Instance::mono(ccx.shared(), def_id);
-    MirConstContext::trans_def(ccx, instance, IndexVec::new()).map(|c| c.llval)
+    let ty = monomorphize::apply_param_substs(ccx.shared(),
+                                              instance.substs,
+                                              &ccx.tcx().item_type(def_id));
+    MirConstContext::trans_item(ccx, instance, ty).map(|c| c.llval)
 }
@@ -11,12 +11,15 @@
 use common::*;
 use rustc::hir::def_id::DefId;
 use rustc::infer::TransNormalize;
+use rustc::mir::{self, Location};
+use rustc::mir::visit::{LvalueContext, Visitor as MirVisitor};
 use rustc::ty::fold::{TypeFolder, TypeFoldable};
-use rustc::ty::subst::{Subst, Substs};
+use rustc::ty::subst::{Kind, Subst, Substs};
 use rustc::ty::{self, Ty, TyCtxt};
 use rustc::util::ppaux;
 use rustc::util::common::MemoizationMap;
 use std::fmt;
+use std::rc::Rc;
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Instance<'tcx> {
@@ -57,6 +60,201 @@ pub fn apply_param_substs<'a, 'tcx, T>(scx: &SharedCrateContext<'a, 'tcx>,
 }
 
 
+/// How the code of a generic function depends on one of its type parameters.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ParamUsage {
+    /// The parameter isn't mentioned at all.
+    Unused,
+    /// The parameter only appears as the pointee of pointers which are never
+    /// dereferenced, other than to be reborrowed, and which are only nested in
+    /// tuples, arrays, slices and function pointers. Such pointers look the
+    /// same whatever they point to, as long as it is sized.
+    BehindPointer,
+    /// The code depends on the parameter.
+    Used,
+}
+
+/// Replaces the type parameters the code of `def_id` doesn't depend on with
+/// `()`, so that instantiations which only differ in those parameters share a
+/// single copy of the code. Callers get the function through a pointer cast
+/// to the type they expect, see `callee::get_fn`.
+///
+/// This is only done with `-Z polymorphize`.
+pub fn polymorphize<'a, 'tcx>(scx: &SharedCrateContext<'a, 'tcx>,
+                              def_id: DefId,
+                              substs: &'tcx Substs<'tcx>)
+                              -> &'tcx Substs<'tcx> {
+    let tcx = scx.tcx();
+    if !tcx.sess.opts.debugging_opts.polymorphize || substs.types().next().is_none() {
+        return substs;
+    }
+
+    let usage = param_usage(scx, def_id);
+    let nil = tcx.mk_nil();
+    let mut changed = false;
+    let params: Vec<Kind<'tcx>> = substs.params().iter().enumerate().map(|(i, &kind)| {
+        let ty = match kind.as_type() {
+            Some(ty) if ty != nil => ty,
+            _ => return kind,
+        };
+        let collapse = match usage.get(i) {
+            Some(&ParamUsage::Unused) => true,
+            Some(&ParamUsage::BehindPointer) => type_is_sized(tcx, ty),
+            Some(&ParamUsage::Used) | None => false,
+        };
+        if collapse {
+            changed = true;
+            Kind::from(nil)
+        } else {
+            kind
+        }
+    }).collect();
+
+    if changed {
+        debug!("polymorphize({:?}, {:?}) = {:?}", def_id, substs, params);
+        tcx.intern_substs(&params)
+    } else {
+        substs
+    }
+}
+
+/// Finds out how the MIR of `def_id` depends on each of its type parameters,
+/// including those of its parents, indexed like its substs.
+pub fn param_usage<'a, 'tcx>(scx: &SharedCrateContext<'a, 'tcx>,
+                             def_id: DefId)
+                             -> Rc<Vec<ParamUsage>> {
+    if let Some(usage) = scx.param_usage().borrow().get(&def_id) {
+        return usage.clone();
+    }
+
+    let tcx = scx.tcx();
+    let has_mir = match tcx.item_type(def_id).sty {
+        ty::TyFnDef(..) => {
+            if def_id.is_local() {
+                tcx.mir_map.borrow().contains_key(&def_id)
+            } else {
+                tcx.sess.cstore.is_item_mir_available(def_id)
+            }
+        }
+        // Closures are instantiated along with the function they're
+        // defined in, which uses all of their type parameters.
+        _ => false,
+    };
+
+    let usage = if has_mir {
+        let mir = tcx.item_mir(def_id);
+        let mut visitor = ParamUsageVisitor {
+            tcx: tcx,
+            mir: &mir,
+            usage: vec![ParamUsage::Unused; tcx.item_generics(def_id).count()],
+        };
+        visitor.visit_mir(&mir);
+        for promoted in &mir.promoted {
+            visitor.mir = promoted;
+            visitor.visit_mir(promoted);
+        }
+        visitor.usage
+    } else {
+        // Parameters without a usage are all considered used.
+        vec![]
+    };
+    debug!("param_usage({:?}) = {:?}", def_id, usage);
+
+    let usage = Rc::new(usage);
+    scx.param_usage().borrow_mut().insert(def_id, usage.clone());
+    usage
+}
+
+struct ParamUsageVisitor<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    mir: &'a mir::Mir<'tcx>,
+    usage: Vec<ParamUsage>,
+}
+
+impl<'a, 'tcx> ParamUsageVisitor<'a, 'tcx> {
+    fn record(&mut self, ty: Ty<'tcx>, behind_pointer: bool) {
+        match ty.sty {
+            ty::TyParam(p) => {
+                if let Some(usage) = self.usage.get_mut(p.idx as usize) {
+                    *usage = match (*usage, behind_pointer) {
+                        (ParamUsage::Unused, true) => ParamUsage::BehindPointer,
+                        (usage, true) => usage,
+                        (_, false) => ParamUsage::Used,
+                    };
+                }
+            }
+            ty::TyRef(_, mt) | ty::TyRawPtr(mt) => {
+                let behind_pointer = match mt.ty.sty {
+                    ty::TyParam(_) => true,
+                    _ => false,
+                };
+                self.record(mt.ty, behind_pointer);
+            }
+            ty::TyTuple(..) | ty::TyArray(..) | ty::TySlice(_) | ty::TyFnPtr(_) => {
+                for inner in ty.walk_shallow() {
+                    self.record(inner, false);
+                }
+            }
+            // The parameters of anything that trait selection may look at,
+            // including the drop glue of ADTs, matter in full.
+            _ => self.record_all(ty),
+        }
+    }
+
+    fn record_all(&mut self, ty: Ty<'tcx>) {
+        for inner in ty.walk() {
+            if let ty::TyParam(_) = inner.sty {
+                self.record(inner, false);
+            }
+        }
+    }
+}
+
+impl<'a, 'tcx> MirVisitor<'tcx> for ParamUsageVisitor<'a, 'tcx> {
+    fn visit_ty(&mut self, ty: &Ty<'tcx>) {
+        self.record(ty, false);
+    }
+
+    fn visit_substs(&mut self, substs: &&'tcx Substs<'tcx>) {
+        for ty in substs.types() {
+            self.record_all(ty);
+        }
+    }
+
+    fn visit_closure_substs(&mut self, substs: &ty::ClosureSubsts<'tcx>) {
+        for ty in substs.func_substs.types() {
+            self.record_all(ty);
+        }
+    }
+
+    fn visit_lvalue(&mut self,
+                    lvalue: &mir::Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        if let mir::Lvalue::Projection(ref projection) = *lvalue {
+            match (&projection.elem, context) {
+                (&mir::ProjectionElem::Deref, LvalueContext::Borrow { .. }) => {}
+                (&mir::ProjectionElem::Deref, _) => {
+                    // Reading or writing the pointee needs its layout.
+                    let ty = lvalue.ty(self.mir, self.tcx).to_ty(self.tcx);
+                    self.record_all(ty);
+                }
+                _ => {}
+            }
+        }
+        self.super_lvalue(lvalue, context, location);
+    }
+
+    fn visit_rvalue(&mut self, rvalue: &mir::Rvalue<'tcx>, location: Location) {
+        if let mir::Rvalue::Cast(mir::CastKind::Unsize, ref operand, _) = *rvalue {
+            // The vtable or length of the source's pointee is needed.
+            let ty = operand.ty(self.mir, self.tcx);
+            self.record_all(ty);
+        }
+        self.super_rvalue(rvalue, location);
+    }
+}
+
 /// Returns the normalized type of a struct field
 pub fn field_ty<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                           param_substs: &Substs<'tcx>,
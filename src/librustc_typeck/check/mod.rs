@@ -2457,14 +2457,12 @@ impl<'a, 'gcx, 'tcx> FnCtxt<'a, 'gcx, 'tcx> {
         fn parameter_count_error<'tcx>(sess: &Session, sp: Span, fn_inputs: &[Ty<'tcx>],
                                        expected_count: usize, arg_count: usize, error_code: &str,
                                        variadic: bool) {
-            let mut err = sess.struct_span_err_with_code(sp,
-                &format!("this function takes {}{} parameter{} but {} parameter{} supplied",
-                    if variadic {"at least "} else {""},
-                    expected_count,
-                    if expected_count == 1 {""} else {"s"},
-                    arg_count,
-                    if arg_count == 1 {" was"} else {"s were"}),
-                error_code);
+            let msg = sess.diagnostic_message("typeck-arg-count-mismatch", &[
+                ("variadic", &variadic),
+                ("expected", &expected_count),
+                ("supplied", &arg_count),
+            ]);
+            let mut err = sess.struct_span_err_with_code(sp, &msg, error_code);
 
             let input_types = fn_inputs.iter().map(|i| format!("{:?}", i)).collect::<Vec<String>>();
             if input_types.len() > 1 {
@@ -2973,8 +2971,8 @@ impl<'a, 'gcx, 'tcx> FnCtxt<'a, 'gcx, 'tcx> {
             self.tcx().types.err
         } else {
             let mut err = self.type_error_struct(field.span, |actual| {
-                format!("no field `{}` on type `{}`",
-                        field.node, actual)
+                self.tcx().sess.diagnostic_message("typeck-no-field",
+                                                   &[("field", &field.node), ("ty", &actual)])
             }, expr_t);
             match expr_t.sty {
                 ty::TyAdt(def, _) if !def.is_enum() => {
@@ -1957,6 +1957,11 @@ pub enum ItemKind {
     ///
     /// E.g. `macro_rules! foo { .. }` or `foo!(..)`
     Mac(Mac),
+    /// A macro definition with definition-site hygiene.
+    ///
+    /// E.g. `macro foo($x:expr) { .. }`. The token trees are the macro's rules,
+    /// written as in `macro_rules!`.
+    MacroDef(Vec<TokenTree>),
 }
 
 impl ItemKind {
@@ -1974,6 +1979,7 @@ impl ItemKind {
             ItemKind::Struct(..) => "struct",
             ItemKind::Union(..) => "union",
             ItemKind::Trait(..) => "trait",
+            ItemKind::MacroDef(..) => "macro",
             ItemKind::Mac(..) |
             ItemKind::Impl(..) |
             ItemKind::DefaultImpl(..) => "item"
@@ -2022,6 +2028,9 @@ pub struct MacroDef {
     pub imported_from: Option<Ident>,
     pub allow_internal_unstable: bool,
     pub body: Vec<TokenTree>,
+    /// Whether this is a `macro_rules!` macro, rather than a `macro` item
+    /// with definition-site hygiene.
+    pub legacy: bool,
 }
 
 #[cfg(test)]
@@ -66,6 +66,18 @@ macro_rules! struct_span_err {
     })
 }
 
+/// Like `struct_span_err!`, but takes the message from the session's message
+/// catalog, with the given named arguments interpolated into it.
+#[macro_export]
+macro_rules! struct_span_err_message {
+    ($session:expr, $span:expr, $code:ident, $id:expr $(, $name:ident = $value:expr)*) => ({
+        __diagnostic_used!($code);
+        let message = $session.diagnostic_message(
+            $id, &[$((stringify!($name), &$value as &::std::fmt::Display)),*]);
+        $session.struct_span_err_with_code($span, &message, stringify!($code))
+    })
+}
+
 #[macro_export]
 macro_rules! struct_span_warn {
     ($session:expr, $span:expr, $code:ident, $($message:tt)*) => ({
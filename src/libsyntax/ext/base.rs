@@ -509,7 +509,10 @@ pub enum SyntaxExtension {
     ///
     IdentTT(Box<IdentMacroExpander>, Option<Span>, bool),
 
-    CustomDerive(Box<MultiItemModifier>),
+    /// A custom `#[derive]` mode, along with the names of the helper attributes
+    /// it declares and whether they stay on the item once all of the item's
+    /// derives have run.
+    CustomDerive(Box<MultiItemModifier>, Vec<InternedString>, bool),
 }
 
 pub type NamedSyntaxExtension = (Name, SyntaxExtension);
@@ -95,6 +95,12 @@ impl<'a, 'b> PlaceholderExpander<'a, 'b> {
     fn remove(&mut self, id: ast::NodeId) -> Expansion {
         self.expansions.remove(&id).unwrap()
     }
+
+    // `macro` items have already been defined by the resolver, so they are removed
+    // like `macro_rules!` definitions unless they are kept for pretty-printing.
+    fn keep_macro_defs(&self) -> bool {
+        !self.monotonic || self.cx.ecfg.keep_macs
+    }
 }
 
 impl<'a, 'b> Folder for PlaceholderExpander<'a, 'b> {
@@ -165,6 +171,7 @@ impl<'a, 'b> Folder for PlaceholderExpander<'a, 'b> {
     }
 
     fn fold_block(&mut self, block: P<ast::Block>) -> P<ast::Block> {
+        let keep_macro_defs = self.keep_macro_defs();
         noop_fold_block(block, self).map(|mut block| {
             let mut macros = Vec::new();
             let mut remaining_stmts = block.stmts.len();
@@ -174,9 +181,13 @@ impl<'a, 'b> Folder for PlaceholderExpander<'a, 'b> {
 
                 // Scope placeholder
                 if let ast::StmtKind::Item(ref item) = stmt.node {
-                    if let ast::ItemKind::Mac(..) = item.node {
-                        macros.push(item.ident.ctxt.data().outer_mark);
-                        return None;
+                    match item.node {
+                        ast::ItemKind::Mac(..) => {
+                            macros.push(item.ident.ctxt.data().outer_mark);
+                            return None;
+                        }
+                        ast::ItemKind::MacroDef(..) if !keep_macro_defs => return None,
+                        _ => {}
                     }
                 }
 
@@ -206,9 +217,11 @@ impl<'a, 'b> Folder for PlaceholderExpander<'a, 'b> {
     }
 
     fn fold_mod(&mut self, module: ast::Mod) -> ast::Mod {
+        let keep_macro_defs = self.keep_macro_defs();
         let mut module = noop_fold_mod(module, self);
         module.items = module.items.move_flat_map(|item| match item.node {
             ast::ItemKind::Mac(_) => None, // remove scope placeholders from modules
+            ast::ItemKind::MacroDef(..) if !keep_macro_defs => None,
             _ => Some(item),
         });
         module
@@ -782,6 +782,7 @@ fn statements_mk_tt(cx: &ExtCtxt, tt: &TokenTree, matcher: bool) -> Vec<ast::Stm
             let e_op = match seq.op {
                 tokenstream::KleeneOp::ZeroOrMore => "ZeroOrMore",
                 tokenstream::KleeneOp::OneOrMore => "OneOrMore",
+                tokenstream::KleeneOp::ZeroOrOne => "ZeroOrOne",
             };
             let e_op_idents = vec![
                 id_ext("syntax"),
@@ -78,7 +78,7 @@ pub use self::NamedMatch::*;
 pub use self::ParseResult::*;
 use self::TokenTreeOrTokenTreeVec::*;
 
-use ast::Ident;
+use ast::{self, Ident};
 use syntax_pos::{self, BytePos, mk_sp, Span};
 use codemap::Spanned;
 use errors::FatalError;
@@ -89,7 +89,7 @@ use parse::token::{DocComment, MatchNt, SubstNt};
 use parse::token::{Token, Nonterminal};
 use parse::token;
 use print::pprust;
-use tokenstream::{self, TokenTree};
+use tokenstream::{self, KleeneOp, TokenTree};
 use util::small_vector::SmallVector;
 
 use std::mem;
@@ -134,6 +134,8 @@ pub struct MatcherPos {
     stack: Vec<MatcherTtFrame>,
     top_elts: TokenTreeOrTokenTreeVec,
     sep: Option<Token>,
+    /// The repetition operator of the sequence being matched, if any
+    seq_op: Option<KleeneOp>,
     idx: usize,
     up: Option<Box<MatcherPos>>,
     matches: Vec<Vec<Rc<NamedMatch>>>,
@@ -168,6 +170,7 @@ pub fn initial_matcher_pos(ms: Vec<TokenTree>, sep: Option<Token>, lo: BytePos)
         stack: vec![],
         top_elts: TtSeq(ms),
         sep: sep,
+        seq_op: None,
         idx: 0,
         up: None,
         matches: matches,
@@ -347,6 +350,11 @@ pub fn parse(sess: &ParseSess, rdr: TtReader, ms: &[TokenTree]) -> NamedParseRes
 
                     // can we go around again?
 
+                    // a `?` sequence matches at most once
+                    if ei.seq_op == Some(KleeneOp::ZeroOrOne) {
+                        continue
+                    }
+
                     // the *_t vars are workarounds for the lack of unary move
                     match ei.sep {
                         Some(ref t) if idx == len => { // we need a separator
@@ -374,7 +382,7 @@ pub fn parse(sess: &ParseSess, rdr: TtReader, ms: &[TokenTree]) -> NamedParseRes
                 match ei.top_elts.get_tt(idx) {
                     /* need to descend into sequence */
                     TokenTree::Sequence(sp, seq) => {
-                        if seq.op == tokenstream::KleeneOp::ZeroOrMore {
+                        if seq.op == KleeneOp::ZeroOrMore || seq.op == KleeneOp::ZeroOrOne {
                             let mut new_ei = ei.clone();
                             new_ei.match_cur += seq.num_captures;
                             new_ei.idx += 1;
@@ -392,6 +400,7 @@ pub fn parse(sess: &ParseSess, rdr: TtReader, ms: &[TokenTree]) -> NamedParseRes
                         cur_eis.push(Box::new(MatcherPos {
                             stack: vec![],
                             sep: seq.separator.clone(),
+                            seq_op: Some(seq.op),
                             idx: 0,
                             matches: matches,
                             match_lo: ei_t.match_cur,
@@ -402,11 +411,24 @@ pub fn parse(sess: &ParseSess, rdr: TtReader, ms: &[TokenTree]) -> NamedParseRes
                             top_elts: Tt(TokenTree::Sequence(sp, seq)),
                         }));
                     }
-                    TokenTree::Token(_, MatchNt(..)) => {
+                    TokenTree::Token(_, MatchNt(_, name)) => {
+                        // A `vis` fragment which doesn't start with `pub` is
+                        // empty, so match it without consuming anything.
+                        if &*name.name.as_str() == "vis" && !may_begin_with("vis", &tok) {
+                            let match_cur = ei.match_cur;
+                            let vis = token::NtVis(ast::Visibility::Inherited);
+                            (&mut ei.matches[match_cur])
+                                .push(Rc::new(MatchedNonterminal(Rc::new(vis))));
+                            ei.idx += 1;
+                            ei.match_cur += 1;
+                            cur_eis.push(ei);
+                            continue
+                        }
                         // Built-in nonterminals never start with these tokens,
                         // so we can eliminate them from consideration.
                         match tok {
                             token::CloseDelim(_) => {},
+                            _ if !may_begin_with(&name.name.as_str(), &tok) => {},
                             _ => bb_eis.push(ei),
                         }
                     }
@@ -492,6 +514,34 @@ pub fn parse(sess: &ParseSess, rdr: TtReader, ms: &[TokenTree]) -> NamedParseRes
     }
 }
 
+/// Whether a fragment of kind `name` could start with the token `tok`. This
+/// only rules out tokens for fragments which are a single token or so; the
+/// rest are left for the parser to accept or reject.
+fn may_begin_with(name: &str, tok: &Token) -> bool {
+    match name {
+        "lifetime" => match *tok {
+            token::Lifetime(..) => true,
+            _ => false,
+        },
+        "literal" => match *tok {
+            token::Literal(..) | token::BinOp(token::Minus) => true,
+            token::Interpolated(ref nt) => match **nt {
+                token::NtLiteral(..) => true,
+                _ => false,
+            },
+            _ => tok.is_keyword(token::keywords::True) || tok.is_keyword(token::keywords::False),
+        },
+        "vis" => match *tok {
+            token::Interpolated(ref nt) => match **nt {
+                token::NtVis(..) => true,
+                _ => false,
+            },
+            _ => tok.is_keyword(token::keywords::Pub),
+        },
+        _ => true,
+    }
+}
+
 pub fn parse_nt<'a>(p: &mut Parser<'a>, sp: Span, name: &str) -> Nonterminal {
     match name {
         "tt" => {
@@ -550,6 +600,9 @@ pub fn parse_nt<'a>(p: &mut Parser<'a>, sp: Span, name: &str) -> Nonterminal {
             token::NtPath(panictry!(p.parse_path(PathStyle::Type)))
         },
         "meta" => token::NtMeta(panictry!(p.parse_meta_item())),
+        "vis" => token::NtVis(panictry!(p.parse_visibility(true))),
+        "lifetime" => token::NtLifetime(panictry!(p.parse_lifetime())),
+        "literal" => token::NtLiteral(panictry!(p.parse_pat_literal_maybe_minus())),
         // this is not supposed to happen, since it has been checked
         // when compiling the macro.
         _ => p.span_bug(sp, "invalid fragment specifier")
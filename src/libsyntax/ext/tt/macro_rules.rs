@@ -17,6 +17,7 @@ use ext::placeholders;
 use ext::tt::macro_parser::{Success, Error, Failure};
 use ext::tt::macro_parser::{MatchedSeq, MatchedNonterminal};
 use ext::tt::macro_parser::{parse, parse_failure_msg};
+use feature_gate::{emit_feature_err, Features, GateIssue};
 use parse::ParseSess;
 use parse::lexer::new_tt_reader;
 use parse::parser::{Parser, Restrictions};
@@ -166,6 +167,7 @@ impl IdentMacroExpander for MacroRulesExpander {
             body: tts,
             allow_internal_unstable: attr::contains_name(&attrs, "allow_internal_unstable"),
             attrs: attrs,
+            legacy: true,
         };
 
         // If keep_macs is true, expands to a MacEager::items instead.
@@ -186,7 +188,14 @@ impl IdentMacroExpander for MacroRulesExpander {
 // Holy self-referential!
 
 /// Converts a `macro_rules!` invocation into a syntax extension.
-pub fn compile(sess: &ParseSess, def: &ast::MacroDef) -> SyntaxExtension {
+///
+/// `features` are those of the crate defining the macro, which gate the
+/// unstable parts of the macro syntax. They are `None` for macros loaded from
+/// other crates, which were checked when those crates were compiled.
+pub fn compile(sess: &ParseSess,
+               features: Option<&Features>,
+               def: &ast::MacroDef)
+               -> SyntaxExtension {
     let lhs_nm =  gensym_ident("lhs");
     let rhs_nm =  gensym_ident("rhs");
 
@@ -272,6 +281,12 @@ pub fn compile(sess: &ParseSess, def: &ast::MacroDef) -> SyntaxExtension {
         valid &= check_lhs_no_empty_seq(sess, &[lhs.clone()])
     }
 
+    if let Some(features) = features {
+        for tt in lhses.iter().chain(&rhses) {
+            check_features(sess, features, tt);
+        }
+    }
+
     let exp: Box<_> = Box::new(MacroRulesMacroExpander {
         name: def.ident,
         lhses: lhses,
@@ -282,6 +297,40 @@ pub fn compile(sess: &ParseSess, def: &ast::MacroDef) -> SyntaxExtension {
     NormalTT(exp, Some(def.span), def.allow_internal_unstable)
 }
 
+/// Reports the unstable macro syntax used in `tt` which isn't enabled by
+/// `features`.
+fn check_features(sess: &ParseSess, features: &Features, tt: &TokenTree) {
+    match *tt {
+        TokenTree::Token(sp, MatchNt(_, frag)) => {
+            let (enabled, feature) = match &*frag.name.as_str() {
+                "vis" => (features.macro_vis_matcher, "macro_vis_matcher"),
+                "lifetime" => (features.macro_lifetime_matcher, "macro_lifetime_matcher"),
+                "literal" => (features.macro_literal_matcher, "macro_literal_matcher"),
+                _ => return,
+            };
+            if !enabled {
+                let explain = format!("the `{}` fragment specifier is experimental", frag);
+                emit_feature_err(sess, feature, sp, GateIssue::Language, &explain);
+            }
+        }
+        TokenTree::Token(..) => {}
+        TokenTree::Delimited(_, ref delimited) => {
+            for tt in &delimited.tts {
+                check_features(sess, features, tt);
+            }
+        }
+        TokenTree::Sequence(sp, ref seq) => {
+            if seq.op == tokenstream::KleeneOp::ZeroOrOne && !features.macro_at_most_once_rep {
+                emit_feature_err(sess, "macro_at_most_once_rep", sp, GateIssue::Language,
+                                 "the `?` macro repetition operator is experimental");
+            }
+            for tt in &seq.tts {
+                check_features(sess, features, tt);
+            }
+        }
+    }
+}
+
 fn check_lhs_nt_follows(sess: &ParseSess, lhs: &TokenTree) -> bool {
     // lhs is going to be like TokenTree::Delimited(...), where the
     // entire lhs is those tts. Or, it can be a "bare sequence", not wrapped in parens.
@@ -311,7 +360,8 @@ fn check_lhs_no_empty_seq(sess: &ParseSess, tts: &[TokenTree]) -> bool {
                     if seq.tts.iter().all(|seq_tt| {
                         match *seq_tt {
                             TokenTree::Sequence(_, ref sub_seq) =>
-                                sub_seq.op == tokenstream::KleeneOp::ZeroOrMore,
+                                sub_seq.op == tokenstream::KleeneOp::ZeroOrMore ||
+                                sub_seq.op == tokenstream::KleeneOp::ZeroOrOne,
                             _ => false,
                         }
                     }) {
@@ -415,7 +465,9 @@ impl FirstSets {
                         }
 
                         // Reverse scan: Sequence comes before `first`.
-                        if subfirst.maybe_empty || seq_rep.op == tokenstream::KleeneOp::ZeroOrMore {
+                        if subfirst.maybe_empty ||
+                           seq_rep.op == tokenstream::KleeneOp::ZeroOrMore ||
+                           seq_rep.op == tokenstream::KleeneOp::ZeroOrOne {
                             // If sequence is potentially empty, then
                             // union them (preserving first emptiness).
                             first.add_all(&TokenSet { maybe_empty: true, ..subfirst });
@@ -463,7 +515,8 @@ impl FirstSets {
                             assert!(first.maybe_empty);
                             first.add_all(subfirst);
                             if subfirst.maybe_empty ||
-                               seq_rep.op == tokenstream::KleeneOp::ZeroOrMore {
+                               seq_rep.op == tokenstream::KleeneOp::ZeroOrMore ||
+                               seq_rep.op == tokenstream::KleeneOp::ZeroOrOne {
                                 // continue scanning for more first
                                 // tokens, but also make sure we
                                 // restore empty-tracking state
@@ -744,11 +797,13 @@ fn token_can_be_followed_by_any(tok: &Token) -> bool {
 /// ANYTHING without fear of future compatibility hazards).
 fn frag_can_be_followed_by_any(frag: &str) -> bool {
     match frag {
-        "item"  | // always terminated by `}` or `;`
-        "block" | // exactly one token tree
-        "ident" | // exactly one token tree
-        "meta"  | // exactly one token tree
-        "tt" =>   // exactly one token tree
+        "item"     | // always terminated by `}` or `;`
+        "block"    | // exactly one token tree
+        "ident"    | // exactly one token tree
+        "meta"     | // exactly one token tree
+        "lifetime" | // exactly one token tree
+        "literal"  | // a token, or a `-` and a token
+        "tt" =>      // exactly one token tree
             true,
 
         _ =>
@@ -804,10 +859,32 @@ fn is_in_follow(tok: &Token, frag: &str) -> Result<bool, (String, &'static str)>
                     _ => Ok(false)
                 }
             },
-            "ident" => {
-                // being a single token, idents are harmless
+            "ident" | "lifetime" => {
+                // being a single token, idents and lifetimes are harmless
+                Ok(true)
+            },
+            "literal" => {
+                // literals are at most two tokens, the first of which can
+                // only be `-`, so they are harmless too
                 Ok(true)
             },
+            "vis" => {
+                // a visibility can be empty, so it must be followed by
+                // something which can't continue one: a `,`, an identifier
+                // other than `priv`, or the start of a type
+                match *tok {
+                    Comma | OpenDelim(token::DelimToken::Paren) |
+                    OpenDelim(token::DelimToken::Bracket) | Not | BinOp(token::Star) |
+                    BinOp(token::And) | AndAnd | Question | Lifetime(_) | Lt |
+                    BinOp(token::Shl) | ModSep | Underscore => Ok(true),
+                    Ident(i) => Ok(i.name.as_str() != "priv"),
+                    MatchNt(_, ref frag) => {
+                        let frag = frag.name.as_str();
+                        Ok(&*frag == "ident" || &*frag == "ty" || &*frag == "path")
+                    }
+                    _ => Ok(false)
+                }
+            },
             "meta" | "tt" => {
                 // being either a single token or a delimited sequence, tt is
                 // harmless
@@ -815,8 +892,8 @@ fn is_in_follow(tok: &Token, frag: &str) -> Result<bool, (String, &'static str)>
             },
             _ => Err((format!("invalid fragment specifier `{}`", frag),
                      "valid fragment specifiers are `ident`, `block`, \
-                      `stmt`, `expr`, `pat`, `ty`, `path`, `meta`, `tt` \
-                      and `item`"))
+                      `stmt`, `expr`, `pat`, `ty`, `path`, `meta`, `tt`, \
+                      `item`, `vis`, `lifetime` and `literal`"))
         }
     }
 }
@@ -835,7 +912,8 @@ fn has_legal_fragment_specifier(tok: &Token) -> Result<(), String> {
 fn is_legal_fragment_specifier(frag: &str) -> bool {
     match frag {
         "item" | "block" | "stmt" | "expr" | "pat" |
-        "path" | "ty" | "ident" | "meta" | "tt" => true,
+        "path" | "ty" | "ident" | "meta" | "tt" |
+        "vis" | "lifetime" | "literal" => true,
         _ => false,
     }
 }
@@ -12,7 +12,7 @@ use self::LockstepIterSize::*;
 use ast::Ident;
 use errors::{Handler, DiagnosticBuilder};
 use ext::tt::macro_parser::{NamedMatch, MatchedSeq, MatchedNonterminal};
-use parse::token::{self, MatchNt, SubstNt, Token, NtIdent};
+use parse::token::{self, MatchNt, SubstNt, Token, NtIdent, NtLifetime};
 use parse::lexer::TokenAndSpan;
 use syntax_pos::{Span, DUMMY_SP};
 use tokenstream::{self, TokenTree};
@@ -233,6 +233,11 @@ pub fn tt_next_token(r: &mut TtReader) -> TokenAndSpan {
                             r.stack.last_mut().unwrap().idx += 1;
                             return tt_next_token(r);
                         }
+                        if len > 1 && seq.op == tokenstream::KleeneOp::ZeroOrOne {
+                            // FIXME #2887 blame invoker
+                            panic!(r.sp_diag.span_fatal(sp.clone(),
+                                                 "this must repeat at most once"));
+                        }
                         r.repeat_len.push(len);
                         r.repeat_idx.push(0);
                         r.stack.push(TtFrame {
@@ -264,6 +269,12 @@ pub fn tt_next_token(r: &mut TtReader) -> TokenAndSpan {
                                 r.cur_tok = token::Ident(sn.node);
                                 return ret_val;
                             }
+                            // lifetimes are single tokens too
+                            NtLifetime(ref lifetime) => {
+                                r.cur_span = lifetime.span;
+                                r.cur_tok = token::Lifetime(Ident::with_empty_ctxt(lifetime.name));
+                                return ret_val;
+                            }
                             _ => {
                                 // FIXME(pcwalton): Bad copy.
                                 r.cur_span = sp;
@@ -311,6 +311,25 @@ declare_features! (
 
     // Allows using `Self` and associated types in struct expressions and patterns.
     (active, more_struct_aliases, "1.14.0", Some(37544)),
+
+    // Allows `macro` items, which follow the usual privacy and import rules
+    // and are hygienic at the definition site.
+    (active, decl_macro, "1.15.0", None),
+
+    // Allows `#[tail_recursive]` on functions.
+    (active, tail_recursion, "1.15.0", None),
+
+    // Allows `let` bindings, assignments, branches and loops in `const fn`
+    // bodies, which are evaluated by interpreting their MIR.
+    (active, const_fn_control_flow, "1.15.0", None),
+
+    // Allows the `vis`, `lifetime` and `literal` macro fragment specifiers.
+    (active, macro_vis_matcher, "1.15.0", None),
+    (active, macro_lifetime_matcher, "1.15.0", None),
+    (active, macro_literal_matcher, "1.15.0", None),
+
+    // Allows the `?` zero-or-one macro repetition operator.
+    (active, macro_at_most_once_rep, "1.15.0", None),
 );
 
 declare_features! (
@@ -642,6 +661,18 @@ pub const BUILTIN_ATTRIBUTES: &'static [(&'static str, AttributeType, AttributeG
                                          is an experimental feature",
                                         cfg_fn!(proc_macro))),
 
+    ("proc_macro_attribute", Normal, Gated(Stability::Unstable,
+                                           "proc_macro",
+                                           "the `#[proc_macro_attribute]` attribute \
+                                            is an experimental feature",
+                                           cfg_fn!(proc_macro))),
+
+    ("proc_macro", Normal, Gated(Stability::Unstable,
+                                 "proc_macro",
+                                 "the `#[proc_macro]` attribute \
+                                  is an experimental feature",
+                                 cfg_fn!(proc_macro))),
+
     ("rustc_copy_clone_marker", Whitelisted, Gated(Stability::Unstable,
                                                    "rustc_attrs",
                                                    "internal implementation detail",
@@ -658,6 +689,11 @@ pub const BUILTIN_ATTRIBUTES: &'static [(&'static str, AttributeType, AttributeG
                                  "the `#[naked]` attribute \
                                   is an experimental feature",
                                  cfg_fn!(naked_functions))),
+    ("tail_recursive", Whitelisted, Gated(Stability::Unstable,
+                                          "tail_recursion",
+                                          "the `#[tail_recursive]` attribute \
+                                           is an experimental feature",
+                                          cfg_fn!(tail_recursion))),
     ("export_name", Whitelisted, Ungated),
     ("inline", Whitelisted, Ungated),
     ("link", Whitelisted, Ungated),
@@ -23,7 +23,7 @@ use codemap::CodeMap;
 use syntax_pos::{self, MacroBacktrace, Span, SpanLabel, MultiSpan};
 use errors::registry::Registry;
 use errors::{DiagnosticBuilder, SubDiagnostic, RenderSpan, CodeSuggestion, CodeMapper};
-use errors::emitter::Emitter;
+use errors::emitter::{self, Emitter};
 
 use std::rc::Rc;
 use std::io::{self, Write};
@@ -35,6 +35,7 @@ pub struct JsonEmitter {
     dst: Box<Write + Send>,
     registry: Option<Registry>,
     cm: Rc<CodeMapper + 'static>,
+    macro_backtrace: bool,
 }
 
 impl JsonEmitter {
@@ -44,6 +45,7 @@ impl JsonEmitter {
             dst: Box::new(io::stderr()),
             registry: registry,
             cm: code_map,
+            macro_backtrace: false,
         }
     }
 
@@ -58,13 +60,26 @@ impl JsonEmitter {
             dst: dst,
             registry: registry,
             cm: code_map,
+            macro_backtrace: false,
         }
     }
+
+    /// Whether to add the full macro expansion backtrace of each diagnostic to its
+    /// children, as it is shown by the human-readable emitter.
+    pub fn macro_backtrace(mut self, macro_backtrace: bool) -> JsonEmitter {
+        self.macro_backtrace = macro_backtrace;
+        self
+    }
 }
 
 impl Emitter for JsonEmitter {
     fn emit(&mut self, db: &DiagnosticBuilder) {
-        let data = Diagnostic::from_diagnostic_builder(db, self);
+        let notes = if self.macro_backtrace {
+            emitter::macro_backtrace_notes(&*self.cm, &db.span)
+        } else {
+            vec![]
+        };
+        let data = Diagnostic::from_diagnostic_builder(db, &notes, self);
         if let Err(e) = writeln!(&mut self.dst, "{}", as_json(&data)) {
             panic!("failed to print diagnostics: {:?}", e);
         }
@@ -150,6 +165,7 @@ struct DiagnosticCode {
 
 impl<'a> Diagnostic<'a> {
     fn from_diagnostic_builder<'c>(db: &'c DiagnosticBuilder,
+                                   notes: &'c [SubDiagnostic],
                                    je: &JsonEmitter)
                                    -> Diagnostic<'c> {
         Diagnostic {
@@ -157,7 +173,7 @@ impl<'a> Diagnostic<'a> {
             code: DiagnosticCode::map_opt_string(db.code.clone(), je),
             level: db.level.to_str(),
             spans: DiagnosticSpan::from_multispan(&db.span, je),
-            children: db.children.iter().map(|c| {
+            children: db.children.iter().chain(notes).map(|c| {
                 Diagnostic::from_sub_diagnostic(c, je)
             }).collect(),
             rendered: None,
@@ -296,7 +312,7 @@ impl DiagnosticSpanLine {
                          h_end: usize)
                          -> DiagnosticSpanLine {
         DiagnosticSpanLine {
-            text: fm.get_line(index).unwrap().to_owned(),
+            text: fm.get_line(index).unwrap_or("").to_owned(),
             highlight_start: h_start,
             highlight_end: h_end,
         }
@@ -305,10 +321,16 @@ impl DiagnosticSpanLine {
     /// Create a list of DiagnosticSpanLines from span - each line with any part
     /// of `span` gets a DiagnosticSpanLine, with the highlight indicating the
     /// `span` within the line.
+    ///
+    /// Spans in files from other crates, whose source is not available, have
+    /// no lines.
     fn from_span(span: Span, je: &JsonEmitter) -> Vec<DiagnosticSpanLine> {
         je.cm.span_to_lines(span)
              .map(|lines| {
                  let fm = &*lines.file;
+                 if fm.is_imported() {
+                     return vec![];
+                 }
                  lines.lines
                       .iter()
                       .map(|line| {
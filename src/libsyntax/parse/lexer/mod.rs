@@ -13,6 +13,7 @@ use syntax_pos::{self, BytePos, CharPos, Pos, Span};
 use codemap::CodeMap;
 use errors::{FatalError, Handler, DiagnosticBuilder};
 use ext::tt::transcribe::tt_next_token;
+use parse::ParseSess;
 use parse::token::{self, keywords, str_to_ident};
 use str::char_at;
 use rustc_unicode::property::Pattern_White_Space;
@@ -93,9 +94,9 @@ pub struct StringReader<'a> {
     pub filemap: Rc<syntax_pos::FileMap>,
     /// If Some, stop reading the source at this position (inclusive).
     pub terminator: Option<BytePos>,
-    /// Whether to record new-lines in filemap. This is only necessary the first
-    /// time a filemap is lexed. If part of a filemap is being re-lexed, this
-    /// should be set to false.
+    /// Whether to record new-lines and multibyte characters in filemap. This is
+    /// only necessary the first time a filemap is lexed. If part of a filemap
+    /// is being re-lexed, this should be set to false.
     pub save_new_lines: bool,
     // cached:
     pub peek_tok: token::Token,
@@ -227,6 +228,29 @@ impl<'a> StringReader<'a> {
         sr
     }
 
+    /// A reader for the source code covered by `span`, which has been lexed
+    /// before. The tokens keep their positions in the original filemap.
+    pub fn retokenize<'b>(sess: &'b ParseSess, mut span: Span) -> StringReader<'b> {
+        let begin = sess.codemap().lookup_byte_offset(span.lo);
+        let end = sess.codemap().lookup_byte_offset(span.hi);
+
+        // Read nothing if the span doesn't lie within a single filemap.
+        if span.lo > span.hi || begin.fm.start_pos != end.fm.start_pos {
+            span.hi = span.lo;
+        }
+
+        let mut sr = StringReader::new_raw_internal(&sess.span_diagnostic, begin.fm);
+        sr.save_new_lines = false;
+        sr.next_pos = span.lo;
+        sr.terminator = Some(span.hi);
+        sr.bump();
+        if let Err(_) = sr.advance_token() {
+            sr.emit_fatal_errors();
+            panic!(FatalError);
+        }
+        sr
+    }
+
     pub fn ch_is(&self, c: char) -> bool {
         self.ch == Some(c)
     }
@@ -438,7 +462,7 @@ impl<'a> StringReader<'a> {
             } else {
                 self.col = self.col + CharPos(1);
             }
-            if new_ch_len > 1 {
+            if new_ch_len > 1 && self.save_new_lines {
                 self.filemap.record_multibyte_char(self.pos, new_ch_len);
             }
         } else {
@@ -212,6 +212,14 @@ pub fn filemap_to_tts(sess: &ParseSess, filemap: Rc<FileMap>)
     panictry!(p1.parse_all_token_trees())
 }
 
+/// Lexes the source code covered by `span` again, giving the token trees the
+/// spans they have in the original source.
+pub fn span_to_tts(sess: &ParseSess, span: Span) -> Vec<tokenstream::TokenTree> {
+    let srdr = lexer::StringReader::retokenize(sess, span);
+    let mut p1 = Parser::new(sess, Box::new(srdr));
+    panictry!(p1.parse_all_token_trees())
+}
+
 /// Given tts and the ParseSess, produce a parser
 pub fn tts_to_parser<'a>(sess: &'a ParseSess, tts: Vec<tokenstream::TokenTree>) -> Parser<'a> {
     let trdr = lexer::new_tt_reader(&sess.span_diagnostic, None, tts);
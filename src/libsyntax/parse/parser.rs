@@ -110,7 +110,7 @@ macro_rules! maybe_whole_expr {
     ($p:expr) => {
         if let token::Interpolated(nt) = $p.token.clone() {
             match *nt {
-                token::NtExpr(ref e) => {
+                token::NtExpr(ref e) | token::NtLiteral(ref e) => {
                     $p.bump();
                     return Ok((*e).clone());
                 }
@@ -1508,7 +1508,7 @@ impl<'a> Parser<'a> {
     pub fn parse_lit_token(&mut self) -> PResult<'a, LitKind> {
         let out = match self.token {
             token::Interpolated(ref nt) => match **nt {
-                token::NtExpr(ref v) => match v.node {
+                token::NtExpr(ref v) | token::NtLiteral(ref v) => match v.node {
                     ExprKind::Lit(ref lit) => { lit.node.clone() }
                     _ => { return self.unexpected_last(&self.token); }
                 },
@@ -1583,6 +1583,8 @@ impl<'a> Parser<'a> {
 
     /// matches '-' lit | lit
     pub fn parse_pat_literal_maybe_minus(&mut self) -> PResult<'a, P<Expr>> {
+        maybe_whole!(self, NtLiteral, |x| x);
+
         let minus_lo = self.span.lo;
         let minus_present = self.eat(&token::BinOp(token::Minus));
         let lo = self.span.lo;
@@ -2669,7 +2671,7 @@ impl<'a> Parser<'a> {
     }
 
     /// Parse an optional separator followed by a Kleene-style
-    /// repetition token (+ or *).
+    /// repetition token (+, * or ?).
     pub fn parse_sep_and_kleene_op(&mut self)
                                    -> PResult<'a, (Option<token::Token>, tokenstream::KleeneOp)> {
         fn parse_kleene_op<'a>(parser: &mut Parser<'a>) ->
@@ -2683,6 +2685,13 @@ impl<'a> Parser<'a> {
                     parser.bump();
                     Ok(Some(tokenstream::KleeneOp::OneOrMore))
                 },
+                // `$(...)?*` and `$(...)?+` use `?` as a separator
+                token::Question if !parser.look_ahead(1, |t| {
+                    *t == token::BinOp(token::Star) || *t == token::BinOp(token::Plus)
+                }) => {
+                    parser.bump();
+                    Ok(Some(tokenstream::KleeneOp::ZeroOrOne))
+                },
                 _ => Ok(None)
             }
         };
@@ -2693,8 +2702,11 @@ impl<'a> Parser<'a> {
 
         let separator = self.bump_and_get();
         match parse_kleene_op(self)? {
+            Some(tokenstream::KleeneOp::ZeroOrOne) => {
+                Err(self.fatal("the `?` macro repetition operator does not take a separator"))
+            }
             Some(zerok) => Ok((Some(separator), zerok)),
-            None => return Err(self.fatal("expected `*` or `+`"))
+            None => return Err(self.fatal("expected one of: `*`, `+`, or `?`"))
         }
     }
 
@@ -5188,7 +5200,9 @@ impl<'a> Parser<'a> {
     }
 
     // If `allow_path` is false, just parse the `pub` in `pub(path)` (but still parse `pub(crate)`)
-    fn parse_visibility(&mut self, allow_path: bool) -> PResult<'a, Visibility> {
+    pub fn parse_visibility(&mut self, allow_path: bool) -> PResult<'a, Visibility> {
+        maybe_whole!(self, NtVis, |x| x);
+
         let pub_crate = |this: &mut Self| {
             let span = this.prev_span;
             this.expect(&token::CloseDelim(token::Paren))?;
@@ -5484,6 +5498,34 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// Parse a `macro` item, after the `macro` keyword. It has either a single
+    /// rule, `macro foo($x:expr) { .. }`, or several separated by commas,
+    /// `macro foo { ($x:expr) => { .. }, () => { .. } }`. Either way, the
+    /// rules are stored as they would be written in `macro_rules!`.
+    fn parse_item_macro_def(&mut self) -> PResult<'a, ItemInfo> {
+        let ident = self.parse_ident()?;
+        let tts = if self.check(&token::OpenDelim(token::Paren)) {
+            let lhs = self.parse_token_tree()?;
+            if !self.check(&token::OpenDelim(token::Brace)) {
+                let token_str = self.this_token_to_string();
+                return Err(self.fatal(&format!("expected `{{`, found `{}`", token_str)));
+            }
+            let rhs = self.parse_token_tree()?;
+            let arrow_span = mk_sp(lhs.span().hi, rhs.span().lo);
+            vec![lhs, TokenTree::Token(arrow_span, token::FatArrow), rhs]
+        } else {
+            self.expect(&token::OpenDelim(token::Brace))?;
+            let rules = self.parse_seq_to_end(&token::CloseDelim(token::Brace),
+                                              SeqSep::none(),
+                                              |p| p.parse_token_tree())?;
+            rules.into_iter().map(|tt| match tt {
+                TokenTree::Token(sp, token::Comma) => TokenTree::Token(sp, token::Semi),
+                tt => tt,
+            }).collect()
+        };
+        Ok((ident, ItemKind::MacroDef(tts), None))
+    }
+
     /// Parse extern crate links
     ///
     /// # Examples
@@ -5689,6 +5731,19 @@ impl<'a> Parser<'a> {
             return Ok(Some(item));
         }
 
+        if self.eat_keyword(keywords::Macro) {
+            // MACRO DEFINITION ITEM
+            let (ident, item_, extra_attrs) = self.parse_item_macro_def()?;
+            let prev_span = self.prev_span;
+            let item = self.mk_item(lo,
+                                    prev_span.hi,
+                                    ident,
+                                    item_,
+                                    visibility,
+                                    maybe_append(attrs, extra_attrs));
+            return Ok(Some(item));
+        }
+
         if self.eat_keyword(keywords::Extern) {
             if self.eat_keyword(keywords::Crate) {
                 return Ok(Some(self.parse_item_extern_crate(lo, visibility, attrs)?));
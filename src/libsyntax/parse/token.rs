@@ -175,6 +175,7 @@ impl Token {
             Pound                       => true, // for expression attributes
             Interpolated(ref nt) => match **nt {
                 NtExpr(..) => true,
+                NtLiteral(..) => true,
                 NtIdent(..) => true,
                 NtBlock(..) => true,
                 NtPath(..) => true,
@@ -304,6 +305,10 @@ pub enum Nonterminal {
     NtMeta(P<ast::MetaItem>),
     NtPath(ast::Path),
     NtTT(tokenstream::TokenTree),
+    NtVis(ast::Visibility),
+    NtLifetime(ast::Lifetime),
+    /// A literal expression, optionally negated
+    NtLiteral(P<ast::Expr>),
     // These are not exposed to macros, but are used by quasiquote.
     NtArm(ast::Arm),
     NtImplItem(ast::ImplItem),
@@ -326,6 +331,9 @@ impl fmt::Debug for Nonterminal {
             NtMeta(..) => f.pad("NtMeta(..)"),
             NtPath(..) => f.pad("NtPath(..)"),
             NtTT(..) => f.pad("NtTT(..)"),
+            NtVis(..) => f.pad("NtVis(..)"),
+            NtLifetime(..) => f.pad("NtLifetime(..)"),
+            NtLiteral(..) => f.pad("NtLiteral(..)"),
             NtArm(..) => f.pad("NtArm(..)"),
             NtImplItem(..) => f.pad("NtImplItem(..)"),
             NtTraitItem(..) => f.pad("NtTraitItem(..)"),
@@ -296,6 +296,9 @@ pub fn token_to_string(tok: &Token) -> String {
             token::NtPat(ref e)         => pat_to_string(&e),
             token::NtIdent(ref e)       => ident_to_string(e.node),
             token::NtTT(ref e)          => tt_to_string(&e),
+            token::NtVis(ref e)         => vis_to_string(&e),
+            token::NtLifetime(ref e)    => lifetime_to_string(&e),
+            token::NtLiteral(ref e)     => expr_to_string(&e),
             token::NtArm(ref e)         => arm_to_string(&e),
             token::NtImplItem(ref e)    => impl_item_to_string(&e),
             token::NtTraitItem(ref e)   => trait_item_to_string(&e),
@@ -330,6 +333,10 @@ pub fn lifetime_to_string(e: &ast::Lifetime) -> String {
     to_string(|s| s.print_lifetime(e))
 }
 
+pub fn vis_to_string(v: &ast::Visibility) -> String {
+    to_string(|s| s.print_visibility(v))
+}
+
 pub fn tt_to_string(tt: &tokenstream::TokenTree) -> String {
     to_string(|s| s.print_tt(tt))
 }
@@ -1342,6 +1349,14 @@ impl<'a> State<'a> {
                 try!(word(&mut self.s, ";"));
                 try!(self.end());
             }
+            ast::ItemKind::MacroDef(ref tts) => {
+                try!(self.head(&visibility_qualified(&item.vis, "macro")));
+                try!(self.print_ident(item.ident));
+                try!(self.nbsp());
+                try!(self.bopen());
+                try!(self.print_tts(&tts[..]));
+                try!(self.bclose(item.span));
+            }
         }
         self.ann.post(self, NodeItem(item))
     }
@@ -1497,6 +1512,7 @@ impl<'a> State<'a> {
                 match seq.op {
                     tokenstream::KleeneOp::ZeroOrMore => word(&mut self.s, "*"),
                     tokenstream::KleeneOp::OneOrMore => word(&mut self.s, "+"),
+                    tokenstream::KleeneOp::ZeroOrOne => word(&mut self.s, "?"),
                 }
             }
         }
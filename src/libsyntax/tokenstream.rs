@@ -87,7 +87,8 @@ pub struct SequenceRepetition {
     pub tts: Vec<TokenTree>,
     /// The optional separator
     pub separator: Option<token::Token>,
-    /// Whether the sequence can be repeated zero (*), or one or more times (+)
+    /// Whether the sequence can be repeated zero or more times (*), one or more
+    /// times (+), or zero or one times (?)
     pub op: KleeneOp,
     /// The number of `MatchNt`s that appear in the sequence (and subsequences)
     pub num_captures: usize,
@@ -99,6 +100,7 @@ pub struct SequenceRepetition {
 pub enum KleeneOp {
     ZeroOrMore,
     OneOrMore,
+    ZeroOrOne,
 }
 
 /// When the main rust parser encounters a syntax-extension invocation, it
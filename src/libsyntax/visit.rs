@@ -290,6 +290,7 @@ pub fn walk_item<V: Visitor>(visitor: &mut V, item: &Item) {
             walk_list!(visitor, visit_trait_item, methods);
         }
         ItemKind::Mac(ref mac) => visitor.visit_mac(mac),
+        ItemKind::MacroDef(..) => {}
     }
     walk_list!(visitor, visit_attribute, &item.attrs);
 }
@@ -10,13 +10,14 @@
 
 use std::panic;
 
-use errors::FatalError;
 use proc_macro::{TokenStream, __internal};
+use proc_macro_impl::report_panic;
 use syntax::ast::{self, ItemKind, Attribute};
 use syntax::attr::{mark_used, mark_known};
 use syntax::codemap::Span;
 use syntax::ext::base::*;
-use syntax::fold::Folder;
+use syntax::fold::{self, Folder};
+use syntax::parse::{self, token};
 use syntax::parse::token::InternedString;
 use syntax::visit::Visitor;
 
@@ -31,6 +32,23 @@ impl<'a> Visitor for MarkAttrs<'a> {
     }
 }
 
+/// Removes the attributes named in the list from an item and its fields.
+pub struct StripAttrs<'a>(pub &'a [InternedString]);
+
+impl<'a> Folder for StripAttrs<'a> {
+    fn fold_attribute(&mut self, attr: Attribute) -> Option<Attribute> {
+        if self.0.contains(&attr.name()) {
+            None
+        } else {
+            Some(attr)
+        }
+    }
+
+    fn fold_mac(&mut self, mac: ast::Mac) -> ast::Mac {
+        fold::noop_fold_mac(mac, self)
+    }
+}
+
 pub struct CustomDerive {
     inner: fn(TokenStream) -> TokenStream,
     attrs: Vec<InternedString>,
@@ -73,35 +91,42 @@ impl MultiItemModifier for CustomDerive {
         // Mark attributes as known, and used.
         MarkAttrs(&self.attrs).visit_item(&item);
 
-        let input = __internal::new_token_stream(ecx.resolver.eliminate_crate_var(item.clone()));
-        let res = __internal::set_parse_sess(&ecx.parse_sess, || {
+        let item_for_input = ecx.resolver.eliminate_crate_var(item.clone());
+        let res = __internal::set_sess(&ecx.parse_sess, span, || {
+            let input = __internal::new_token_stream(item_for_input);
             let inner = self.inner;
             panic::catch_unwind(panic::AssertUnwindSafe(|| inner(input)))
         });
-        let new_items = match res {
-            Ok(stream) => __internal::token_stream_items(stream),
-            Err(e) => {
-                let msg = "custom derive attribute panicked";
-                let mut err = ecx.struct_span_fatal(span, msg);
-                if let Some(s) = e.downcast_ref::<String>() {
-                    err.help(&format!("message: {}", s));
-                }
-                if let Some(s) = e.downcast_ref::<&'static str>() {
-                    err.help(&format!("message: {}", s));
-                }
-
-                err.emit();
-                panic!(FatalError);
-            }
+        let stream = match res {
+            Ok(stream) => __internal::token_stream_inner(stream),
+            Err(e) => report_panic(ecx, span, "custom derive attribute panicked", e),
         };
 
+        // The tokens keep the spans they were given by the derive: those
+        // copied from the input point at the item, and the rest at the
+        // `#[derive]` which invoked it.
         let mut res = vec![Annotatable::Item(item)];
-        // Reassign spans of all expanded items to the input `item`
-        // for better errors here.
-        res.extend(new_items.into_iter().flat_map(|item| {
-            ChangeSpan { span: span }.fold_item(item)
-        }).map(Annotatable::Item));
+        let mut parser = parse::new_parser_from_ts(ecx.parse_sess, stream);
+        loop {
+            match parser.parse_item() {
+                Ok(Some(item)) => res.push(Annotatable::Item(item)),
+                Ok(None) => {
+                    if parser.token != token::Eof {
+                        let msg = format!("expected item, found `{}`",
+                                          parser.this_token_to_string());
+                        ecx.struct_span_err(parser.span, &msg)
+                           .span_note(span, "in this custom derive output")
+                           .emit();
+                    }
+                    break
+                }
+                Err(mut err) => {
+                    err.span_note(span, "in this custom derive output");
+                    err.emit();
+                    break
+                }
+            }
+        }
         res
     }
 }
-
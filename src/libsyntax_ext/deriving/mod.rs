@@ -16,6 +16,7 @@ use syntax::codemap;
 use syntax::ext::base::{Annotatable, ExtCtxt, SyntaxExtension};
 use syntax::ext::build::AstBuilder;
 use syntax::feature_gate::{self, emit_feature_err};
+use syntax::fold::Folder;
 use syntax::parse::token::{intern, intern_and_get_ident};
 use syntax::ptr::P;
 use syntax_pos::Span;
@@ -162,7 +163,7 @@ pub fn expand_derive(cx: &mut ExtCtxt,
             let derive_mode =
                 ast::Path::from_ident(titem.span, ast::Ident::with_empty_ctxt(intern(&tname)));
             cx.resolver.resolve_macro(cx.current_expansion.mark, &derive_mode, false).map(|ext| {
-                if let SyntaxExtension::CustomDerive(_) = *ext { true } else { false }
+                if let SyntaxExtension::CustomDerive(..) = *ext { true } else { false }
             }).unwrap_or(false)
         } {
             return true;
@@ -198,39 +199,51 @@ pub fn expand_derive(cx: &mut ExtCtxt,
 
     // Now check for macros-1.1 style custom #[derive].
     //
-    // Expand each of them in order given, but *before* we expand any built-in
-    // derive modes. The logic here is to:
-    //
-    // 1. Collect the remaining `#[derive]` annotations into a list. If
-    //    there are any left, attach a `#[derive]` attribute to the item
-    //    that we're currently expanding with the remaining derive modes.
-    // 2. Manufacture a `#[derive(Foo)]` attribute to pass to the expander.
-    // 3. Expand the current item we're expanding, getting back a list of
-    //    items that replace it.
-    // 4. Extend the returned list with the current list of items we've
-    //    collected so far.
-    // 5. Return everything!
-    //
-    // If custom derive extensions end up threading through the `#[derive]`
-    // attribute, we'll get called again later on to continue expanding
-    // those modes.
-    let macros_11_derive = traits.iter()
-                                 .cloned()
-                                 .enumerate()
-                                 .filter(|&(_, ref name)| !is_builtin_trait(&name.name().unwrap()))
-                                 .next();
-    if let Some((i, titem)) = macros_11_derive {
+    // Expand each of them in the order given, but *before* we expand any
+    // built-in derive modes. Every custom derive sees the item with all of
+    // its helper attributes; only once they have all run are the helpers
+    // stripped, except those a derive asked to keep for later derives and
+    // lints. Any built-in derives left are then attached to the item as a
+    // `#[derive]` attribute, so we'll get called again to expand them.
+    let (custom_traits, builtin_traits): (Vec<_>, Vec<_>) = traits.into_iter().partition(|t| {
+        !is_builtin_trait(&t.name().unwrap())
+    });
+    traits = builtin_traits;
+    if !custom_traits.is_empty() {
         if !cx.ecfg.features.unwrap().proc_macro {
             let issue = feature_gate::GateIssue::Language;
             let msg = "custom derive macros are experimentally supported";
-            emit_feature_err(cx.parse_sess, "proc_macro", titem.span, issue, msg);
+            emit_feature_err(cx.parse_sess, "proc_macro", custom_traits[0].span, issue, msg);
         }
 
-        let tname = ast::Ident::with_empty_ctxt(intern(&titem.name().unwrap()));
-        let path = ast::Path::from_ident(titem.span, tname);
-        let ext = cx.resolver.resolve_macro(cx.current_expansion.mark, &path, false).unwrap();
+        let (mut stripped, mut kept) = (Vec::new(), Vec::new());
+        let mut derived = Vec::new();
+        for titem in custom_traits {
+            let tname = ast::Ident::with_empty_ctxt(intern(&titem.name().unwrap()));
+            let path = ast::Path::from_ident(titem.span, tname);
+            let ext = cx.resolver.resolve_macro(cx.current_expansion.mark, &path, false).unwrap();
+
+            let titem = cx.meta_list_item_word(titem.span, titem.name().unwrap());
+            let mitem = cx.meta_list(titem.span,
+                                     intern_and_get_ident("derive"),
+                                     vec![titem]);
+            let mut res = if let SyntaxExtension::CustomDerive(ref ext, ref attrs, keep) = *ext {
+                let helpers = if keep { &mut kept } else { &mut stripped };
+                helpers.extend(attrs.iter().cloned());
+                ext.expand(cx, mitem.span, &mitem, Annotatable::Item(item))
+            } else {
+                unreachable!()
+            };
+            if res.is_empty() {
+                // The derive rejected the item, and has reported why.
+                return res
+            }
+            item = res.remove(0).expect_item();
+            derived.extend(res);
+        }
 
-        traits.remove(i);
+        stripped.retain(|attr| !kept.contains(attr));
+        item = item.map(|i| custom::StripAttrs(&stripped).fold_item_simple(i));
         if traits.len() > 0 {
             item = item.map(|mut i| {
                 let list = cx.meta_list(mitem.span,
@@ -240,16 +253,8 @@ pub fn expand_derive(cx: &mut ExtCtxt,
                 i
             });
         }
-        let titem = cx.meta_list_item_word(titem.span, titem.name().unwrap());
-        let mitem = cx.meta_list(titem.span,
-                                 intern_and_get_ident("derive"),
-                                 vec![titem]);
-        let item = Annotatable::Item(item);
-        if let SyntaxExtension::CustomDerive(ref ext) = *ext {
-            return ext.expand(cx, mitem.span, &mitem, item);
-        } else {
-            unreachable!()
-        }
+        derived.insert(0, Annotatable::Item(item));
+        return derived
     }
 
     // Ok, at this point we know that there are no old-style `#[derive_Foo]` nor
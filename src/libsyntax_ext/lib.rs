@@ -45,6 +45,7 @@ mod log_syntax;
 mod trace_macros;
 
 pub mod proc_macro_registrar;
+pub mod proc_macro_impl;
 
 // for custom_derive
 pub mod deriving;
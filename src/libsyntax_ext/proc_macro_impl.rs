@@ -0,0 +1,83 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::any::Any;
+use std::panic;
+
+use errors::FatalError;
+use proc_macro::{TokenStream, __internal};
+use syntax::codemap::Span;
+use syntax::ext::base::{self, ExtCtxt};
+use syntax::tokenstream;
+
+pub struct AttrProcMacro {
+    pub inner: fn(TokenStream, TokenStream) -> TokenStream,
+}
+
+impl base::AttrProcMacro for AttrProcMacro {
+    fn expand<'cx>(&self,
+                   ecx: &'cx mut ExtCtxt,
+                   span: Span,
+                   annotation: tokenstream::TokenStream,
+                   annotated: tokenstream::TokenStream)
+                   -> tokenstream::TokenStream {
+        let annotation = __internal::token_stream_wrap(annotation);
+        let annotated = __internal::token_stream_wrap(annotated);
+
+        let res = __internal::set_sess(&ecx.parse_sess, span, || {
+            let inner = self.inner;
+            panic::catch_unwind(panic::AssertUnwindSafe(|| inner(annotation, annotated)))
+        });
+
+        match res {
+            Ok(stream) => __internal::token_stream_inner(stream),
+            Err(e) => report_panic(ecx, span, "custom attribute panicked", e),
+        }
+    }
+}
+
+pub struct BangProcMacro {
+    pub inner: fn(TokenStream) -> TokenStream,
+}
+
+impl base::ProcMacro for BangProcMacro {
+    fn expand<'cx>(&self,
+                   ecx: &'cx mut ExtCtxt,
+                   span: Span,
+                   input: tokenstream::TokenStream)
+                   -> tokenstream::TokenStream {
+        let input = __internal::token_stream_wrap(input);
+
+        let res = __internal::set_sess(&ecx.parse_sess, span, || {
+            let inner = self.inner;
+            panic::catch_unwind(panic::AssertUnwindSafe(|| inner(input)))
+        });
+
+        match res {
+            Ok(stream) => __internal::token_stream_inner(stream),
+            Err(e) => report_panic(ecx, span, "proc macro panicked", e),
+        }
+    }
+}
+
+/// Reports that a procedural macro invoked at `span` panicked with the payload
+/// `e`, and aborts.
+pub fn report_panic(ecx: &ExtCtxt, span: Span, msg: &str, e: Box<Any + Send>) -> ! {
+    let mut err = ecx.struct_span_fatal(span, msg);
+    if let Some(s) = e.downcast_ref::<String>() {
+        err.help(&format!("message: {}", s));
+    }
+    if let Some(s) = e.downcast_ref::<&'static str>() {
+        err.help(&format!("message: {}", s));
+    }
+
+    err.emit();
+    panic!(FatalError);
+}
@@ -31,10 +31,18 @@ struct CustomDerive {
     function_name: Ident,
     span: Span,
     attrs: Vec<InternedString>,
+    keep_attrs: bool,
 }
 
-struct CollectCustomDerives<'a> {
+struct ProcMacroDef {
+    function_name: Ident,
+    span: Span,
+}
+
+struct CollectProcMacros<'a> {
     derives: Vec<CustomDerive>,
+    attr_macros: Vec<ProcMacroDef>,
+    bang_macros: Vec<ProcMacroDef>,
     in_root: bool,
     handler: &'a errors::Handler,
     is_proc_macro_crate: bool,
@@ -50,8 +58,10 @@ pub fn modify(sess: &ParseSess,
     let ecfg = ExpansionConfig::default("proc_macro".to_string());
     let mut cx = ExtCtxt::new(sess, ecfg, resolver);
 
-    let mut collect = CollectCustomDerives {
+    let mut collect = CollectProcMacros {
         derives: Vec::new(),
+        attr_macros: Vec::new(),
+        bang_macros: Vec::new(),
         in_root: true,
         handler: handler,
         is_proc_macro_crate: is_proc_macro_crate,
@@ -72,7 +82,10 @@ pub fn modify(sess: &ParseSess,
         handler.err("cannot mix `proc-macro` crate type with others");
     }
 
-    krate.module.items.push(mk_registrar(&mut cx, &collect.derives));
+    krate.module.items.push(mk_registrar(&mut cx,
+                                         &collect.derives,
+                                         &collect.attr_macros,
+                                         &collect.bang_macros));
 
     if krate.exported_macros.len() > 0 {
         handler.err("cannot export macro_rules! macros from a `proc-macro` \
@@ -82,7 +95,14 @@ pub fn modify(sess: &ParseSess,
     return krate
 }
 
-impl<'a> CollectCustomDerives<'a> {
+fn is_proc_macro_attr(attr: &ast::Attribute) -> bool {
+    PROC_MACRO_KINDS.iter().any(|kind| attr.check_name(kind))
+}
+
+const PROC_MACRO_KINDS: [&'static str; 3] =
+    ["proc_macro_derive", "proc_macro_attribute", "proc_macro"];
+
+impl<'a> CollectProcMacros<'a> {
     fn check_not_pub_in_root(&self, vis: &ast::Visibility, sp: Span) {
         if self.is_proc_macro_crate &&
            self.in_root &&
@@ -90,63 +110,17 @@ impl<'a> CollectCustomDerives<'a> {
             self.handler.span_err(sp,
                                   "`proc-macro` crate types cannot \
                                    export any items other than functions \
-                                   tagged with `#[proc_macro_derive]` \
-                                   currently");
+                                   tagged with `#[proc_macro_derive]`, \
+                                   `#[proc_macro_attribute]` or \
+                                   `#[proc_macro]` currently");
         }
     }
-}
-
-impl<'a> Visitor for CollectCustomDerives<'a> {
-    fn visit_item(&mut self, item: &ast::Item) {
-        // First up, make sure we're checking a bare function. If we're not then
-        // we're just not interested in this item.
-        //
-        // If we find one, try to locate a `#[proc_macro_derive]` attribute on
-        // it.
-        match item.node {
-            ast::ItemKind::Fn(..) => {}
-            _ => {
-                // Check for invalid use of proc_macro_derive
-                let attr = item.attrs.iter()
-                    .filter(|a| a.check_name("proc_macro_derive"))
-                    .next();
-                if let Some(attr) = attr {
-                    self.handler.span_err(attr.span(),
-                                          "the `#[proc_macro_derive]` \
-                                          attribute may only be used \
-                                          on bare functions");
-                    return;
-                }
-                self.check_not_pub_in_root(&item.vis, item.span);
-                return visit::walk_item(self, item)
-            }
-        }
-
-        let mut attrs = item.attrs.iter()
-                            .filter(|a| a.check_name("proc_macro_derive"));
-        let attr = match attrs.next() {
-            Some(attr) => attr,
-            None => {
-                self.check_not_pub_in_root(&item.vis, item.span);
-                return visit::walk_item(self, item)
-            }
-        };
-
-        if let Some(a) = attrs.next() {
-            self.handler.span_err(a.span(), "multiple `#[proc_macro_derive]` \
-                                             attributes found");
-        }
-
-        if !self.is_proc_macro_crate {
-            self.handler.span_err(attr.span(),
-                                  "the `#[proc_macro_derive]` attribute is \
-                                   only usable with crates of the `proc-macro` \
-                                   crate type");
-        }
 
+    fn collect_custom_derive(&mut self, item: &ast::Item, attr: &ast::Attribute) {
         // Once we've located the `#[proc_macro_derive]` attribute, verify
-        // that it's of the form `#[proc_macro_derive(Foo)]` or
-        // `#[proc_macro_derive(Foo, attributes(A, ..))]`
+        // that it's of the form `#[proc_macro_derive(Foo)]`,
+        // `#[proc_macro_derive(Foo, attributes(A, ..))]` or
+        // `#[proc_macro_derive(Foo, attributes(A, ..), keep_attributes)]`
         let list = match attr.meta_item_list() {
             Some(list) => list,
             None => {
@@ -156,13 +130,14 @@ impl<'a> Visitor for CollectCustomDerives<'a> {
                 return
             }
         };
-        if list.len() != 1 && list.len() != 2 {
+        if list.is_empty() || list.len() > 3 {
             self.handler.span_err(attr.span(),
-                                  "attribute must have either one or two arguments");
+                                  "attribute must have between one and three arguments");
             return
         }
         let trait_attr = &list[0];
         let attributes_attr = list.get(1);
+        let keep_attr = list.get(2);
         let trait_name = match trait_attr.name() {
             Some(name) => name,
             _ => {
@@ -213,18 +188,114 @@ impl<'a> Visitor for CollectCustomDerives<'a> {
             Vec::new()
         };
 
+        // Helper attributes are stripped from the item once the derive has
+        // run, unless it asks for them to be kept for later derives and lints.
+        let keep_attrs = match keep_attr {
+            Some(attr) if attr.check_name("keep_attributes") && attr.is_word() => true,
+            Some(attr) => {
+                self.handler.span_err(attr.span(),
+                                      "third argument must be `keep_attributes`");
+                false
+            }
+            None => false,
+        };
+
         if self.in_root {
             self.derives.push(CustomDerive {
                 span: item.span,
                 trait_name: trait_name,
                 function_name: item.ident,
                 attrs: proc_attrs,
+                keep_attrs: keep_attrs,
             });
         } else {
             let msg = "functions tagged with `#[proc_macro_derive]` must \
                        currently reside in the root of the crate";
             self.handler.span_err(item.span, msg);
         }
+    }
+
+    /// Collects a `#[proc_macro_attribute]` or `#[proc_macro]` function,
+    /// which defines a macro named after the function itself.
+    fn collect_proc_macro(&mut self, item: &ast::Item, attr: &ast::Attribute) {
+        let kind = attr.name();
+        if !attr.is_word() {
+            self.handler.span_err(attr.span(),
+                                  &format!("`#[{}]` attribute does not take any \
+                                            arguments", kind));
+        }
+
+        if !self.in_root {
+            let msg = format!("functions tagged with `#[{}]` must currently reside \
+                               in the root of the crate", kind);
+            self.handler.span_err(item.span, &msg);
+            return
+        }
+
+        let def = ProcMacroDef {
+            function_name: item.ident,
+            span: item.span,
+        };
+        if &*kind == "proc_macro_attribute" {
+            self.attr_macros.push(def);
+        } else {
+            self.bang_macros.push(def);
+        }
+    }
+}
+
+impl<'a> Visitor for CollectProcMacros<'a> {
+    fn visit_item(&mut self, item: &ast::Item) {
+        // First up, try to locate a `#[proc_macro_derive]`,
+        // `#[proc_macro_attribute]` or `#[proc_macro]` attribute on this
+        // item, of which there may only be one.
+        let mut found_attr: Option<&ast::Attribute> = None;
+        for attr in item.attrs.iter().filter(|a| is_proc_macro_attr(a)) {
+            if let Some(prev_attr) = found_attr {
+                let msg = if attr.name() == prev_attr.name() {
+                    format!("multiple `#[{}]` attributes found", attr.name())
+                } else {
+                    format!("`#[{}]` and `#[{}]` attributes cannot both be applied \
+                             to the same function", prev_attr.name(), attr.name())
+                };
+                self.handler.span_err(attr.span(), &msg);
+                return
+            }
+            found_attr = Some(attr);
+        }
+
+        let attr = match found_attr {
+            Some(attr) => attr,
+            None => {
+                self.check_not_pub_in_root(&item.vis, item.span);
+                return visit::walk_item(self, item)
+            }
+        };
+
+        // Make sure we're checking a bare function. If we're not then the
+        // attribute is misplaced.
+        match item.node {
+            ast::ItemKind::Fn(..) => {}
+            _ => {
+                self.handler.span_err(attr.span(),
+                                      &format!("the `#[{}]` attribute may only be \
+                                                used on bare functions", attr.name()));
+                return
+            }
+        }
+
+        if !self.is_proc_macro_crate {
+            self.handler.span_err(attr.span(),
+                                  &format!("the `#[{}]` attribute is only usable \
+                                            with crates of the `proc-macro` crate \
+                                            type", attr.name()));
+        }
+
+        if attr.check_name("proc_macro_derive") {
+            self.collect_custom_derive(item, attr);
+        } else {
+            self.collect_proc_macro(item, attr);
+        }
 
         visit::walk_item(self, item);
     }
@@ -252,13 +323,20 @@ impl<'a> Visitor for CollectCustomDerives<'a> {
 //
 //          #[plugin_registrar]
 //          fn registrar(registrar: &mut Registry) {
-//              registrar.register_custom_derive($name_trait1, ::$name1, &[]);
-//              registrar.register_custom_derive($name_trait2, ::$name2, &["attribute_name"]);
+//              registrar.register_custom_derive($name_trait1, ::$name1, &[], false);
+//              registrar.register_custom_derive($name_trait2, ::$name2, &["attribute_name"],
+//                                               true);
+//              // ...
+//              registrar.register_attr_proc_macro($name3, ::$name3);
+//              // ...
+//              registrar.register_bang_proc_macro($name4, ::$name4);
 //              // ...
 //          }
 //      }
 fn mk_registrar(cx: &mut ExtCtxt,
-                custom_derives: &[CustomDerive]) -> P<ast::Item> {
+                custom_derives: &[CustomDerive],
+                custom_attrs: &[ProcMacroDef],
+                custom_macros: &[ProcMacroDef]) -> P<ast::Item> {
     let eid = cx.codemap().record_expansion(ExpnInfo {
         call_site: DUMMY_SP,
         callee: NameAndSpan {
@@ -279,25 +357,40 @@ fn mk_registrar(cx: &mut ExtCtxt,
     let registry = token::str_to_ident("Registry");
     let registrar = token::str_to_ident("registrar");
     let register_custom_derive = token::str_to_ident("register_custom_derive");
-    let stmts = custom_derives.iter().map(|cd| {
+    let register_attr_proc_macro = token::str_to_ident("register_attr_proc_macro");
+    let register_bang_proc_macro = token::str_to_ident("register_bang_proc_macro");
+
+    let mut stmts = custom_derives.iter().map(|cd| {
         let path = cx.path_global(cd.span, vec![cd.function_name]);
         let trait_name = cx.expr_str(cd.span, cd.trait_name.clone());
         let attrs = cx.expr_vec_slice(
             span,
             cd.attrs.iter().map(|s| cx.expr_str(cd.span, s.clone())).collect::<Vec<_>>()
         );
-        (path, trait_name, attrs)
-    }).map(|(path, trait_name, attrs)| {
+        let keep_attrs = cx.expr_bool(span, cd.keep_attrs);
         let registrar = cx.expr_ident(span, registrar);
         let ufcs_path = cx.path(span, vec![proc_macro, __internal, registry,
                                            register_custom_derive]);
         cx.expr_call(span,
                      cx.expr_path(ufcs_path),
-                     vec![registrar, trait_name, cx.expr_path(path), attrs])
+                     vec![registrar, trait_name, cx.expr_path(path), attrs, keep_attrs])
     }).map(|expr| {
         cx.stmt_expr(expr)
     }).collect::<Vec<_>>();
 
+    let attrs = custom_attrs.iter().map(|def| (def, register_attr_proc_macro));
+    let macros = custom_macros.iter().map(|def| (def, register_bang_proc_macro));
+    stmts.extend(attrs.chain(macros).map(|(def, register)| {
+        let path = cx.path_global(def.span, vec![def.function_name]);
+        let name = cx.expr_str(def.span, def.function_name.name.as_str());
+        let registrar = cx.expr_ident(span, registrar);
+        let ufcs_path = cx.path(span, vec![proc_macro, __internal, registry, register]);
+        let expr = cx.expr_call(span,
+                                cx.expr_path(ufcs_path),
+                                vec![registrar, name, cx.expr_path(path)]);
+        cx.stmt_expr(expr)
+    }));
+
     let path = cx.path(span, vec![proc_macro, __internal, registry]);
     let registrar_path = cx.ty_path(path);
     let arg_ty = cx.ty_rptr(span, registrar_path, None, ast::Mutability::Mutable);
@@ -0,0 +1,61 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-tidy-linelength
+// compile-flags:-Zprint-trans-items=eager -Zpolymorphize
+
+#![deny(dead_code)]
+
+fn unused<T>() -> u32 {
+    1
+}
+
+fn partially_unused<T1, T2>(a: T1) -> T1 {
+    a
+}
+
+fn behind_pointer<T>(a: &T, b: &T) -> bool {
+    a as *const T == b as *const T
+}
+
+fn unsized_pointee<T: ?Sized>(a: &T) -> &T {
+    a
+}
+
+fn dereferenced<T: Copy>(a: &T) -> T {
+    *a
+}
+
+//~ TRANS_ITEM fn polymorphized_functions::main[0]
+fn main() {
+    //~ TRANS_ITEM fn polymorphized_functions::unused[0]<()>
+    let _ = unused::<i32>();
+    let _ = unused::<&str>();
+
+    //~ TRANS_ITEM fn polymorphized_functions::partially_unused[0]<i32, ()>
+    let _ = partially_unused::<i32, char>(2);
+    let _ = partially_unused::<i32, u8>(2);
+    //~ TRANS_ITEM fn polymorphized_functions::partially_unused[0]<char, ()>
+    let _ = partially_unused::<char, i64>('v');
+
+    //~ TRANS_ITEM fn polymorphized_functions::behind_pointer[0]<()>
+    let _ = behind_pointer(&1i32, &2i32);
+    let _ = behind_pointer(&'a', &'b');
+
+    //~ TRANS_ITEM fn polymorphized_functions::unsized_pointee[0]<()>
+    let _ = unsized_pointee(&1u8);
+    //~ TRANS_ITEM fn polymorphized_functions::unsized_pointee[0]<str>
+    let _ = unsized_pointee("abc");
+
+    //~ TRANS_ITEM fn polymorphized_functions::dereferenced[0]<i32>
+    let _ = dereferenced(&1i32);
+    //~ TRANS_ITEM fn polymorphized_functions::dereferenced[0]<char>
+    let _ = dereferenced(&'a');
+}
@@ -33,8 +33,8 @@ pub fn foo3(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     input
 }
 
-#[proc_macro_derive(b, c, d)]
-//~^ ERROR: attribute must have either one or two arguments
+#[proc_macro_derive(b, attributes(c), keep_attributes, d)]
+//~^ ERROR: attribute must have between one and three arguments
 pub fn foo4(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     input
 }
@@ -58,7 +58,7 @@ pub fn foo7(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 }
 
 #[proc_macro_derive(l, attributes(m), n)]
-//~^ ERROR: attribute must have either one or two arguments
+//~^ ERROR: third argument must be `keep_attributes`
 pub fn foo8(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     input
 }
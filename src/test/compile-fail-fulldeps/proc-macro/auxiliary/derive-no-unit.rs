@@ -0,0 +1,46 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// force-host
+// no-prefer-dynamic
+
+#![feature(proc_macro)]
+#![feature(proc_macro_lib)]
+#![crate_type = "proc-macro"]
+
+extern crate proc_macro;
+
+use proc_macro::{TokenStream, TokenNode, Delimiter, Spacing};
+
+#[proc_macro_derive(NoUnit)]
+pub fn derive_no_unit(input: TokenStream) -> TokenStream {
+    for tree in input {
+        if let TokenNode::Group(Delimiter::Brace, fields) = tree.kind {
+            let mut after_colon = false;
+            for field in fields {
+                match field.kind {
+                    TokenNode::Group(Delimiter::Parenthesis, ref ty) if after_colon => {
+                        if ty.is_empty() {
+                            field.span.error("unit fields are not supported")
+                                .help("remove the field")
+                                .emit();
+                        }
+                    }
+                    _ => {}
+                }
+                after_colon = match field.kind {
+                    TokenNode::Op(':', Spacing::Alone) => true,
+                    _ => false,
+                };
+            }
+        }
+    }
+    TokenStream::empty()
+}
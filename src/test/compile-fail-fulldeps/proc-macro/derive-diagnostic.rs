@@ -0,0 +1,41 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// aux-build:derive-no-unit.rs
+
+#![feature(proc_macro)]
+#![allow(warnings)]
+
+#[macro_use]
+extern crate derive_no_unit;
+
+#[derive(NoUnit)]
+struct A {
+    a: u32,
+    b: (),
+    //~^ ERROR: unit fields are not supported
+    c: (u32, u32),
+    d: (),
+    //~^ ERROR: unit fields are not supported
+}
+
+/// The other attributes don't stop the tokens of the item from keeping their
+/// own spans.
+#[derive(Debug, NoUnit)]
+#[allow(dead_code)]
+struct B {
+    /// A documented field.
+    a: u32,
+    b: (),
+    //~^ ERROR: unit fields are not supported
+}
+
+fn main() {
+}
@@ -0,0 +1,32 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// aux-build:derive-b.rs
+
+#![feature(proc_macro)]
+#![deny(unused_attributes)]
+
+#[macro_use]
+extern crate derive_b;
+
+#[derive(B)]
+#[B]
+struct A {
+    #[B]
+    a: u8,
+}
+
+#[B] //~ ERROR unused attribute
+struct C {
+    #[B] //~ ERROR unused attribute
+    c: u8,
+}
+
+fn main() {}
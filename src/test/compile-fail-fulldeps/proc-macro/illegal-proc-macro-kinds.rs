@@ -0,0 +1,33 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "proc-macro"]
+#![feature(proc_macro)]
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+
+#[proc_macro_attribute]
+//~^ ERROR: the `#[proc_macro_attribute]` attribute may only be used on bare functions
+pub struct Foo;
+
+#[proc_macro(foo)]
+//~^ ERROR: `#[proc_macro]` attribute does not take any arguments
+pub fn foo(input: TokenStream) -> TokenStream {
+    input
+}
+
+#[proc_macro]
+#[proc_macro_attribute]
+//~^ ERROR: `#[proc_macro]` and `#[proc_macro_attribute]` attributes cannot both be applied
+pub fn bar(input: TokenStream) -> TokenStream {
+    input
+}
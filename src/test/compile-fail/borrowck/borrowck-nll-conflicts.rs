@@ -0,0 +1,60 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z nll
+
+// Accesses which conflict with a borrow are still errors under `-Z nll` as
+// long as the reference it created is used afterwards.
+
+fn mut_borrow_twice(x: &mut Vec<u32>) {
+    let a = &mut *x;
+    let b = &mut *x; //~ ERROR cannot borrow `*x` as mutable more than once at a time
+    a.push(1);
+    b.push(2);
+}
+
+fn mut_borrow_while_shared(v: &mut Vec<u32>) {
+    let first = &v[0];
+    v.push(1); //~ ERROR cannot borrow `*v` as mutable because it is also borrowed as immutable
+    println!("{}", first);
+}
+
+fn use_while_mutably_borrowed() {
+    let mut x = 0;
+    let r = &mut x;
+    let y = x; //~ ERROR cannot use `x` because it was mutably borrowed
+    *r += y;
+}
+
+fn move_while_borrowed() {
+    let s = String::new();
+    let r = &s;
+    let t = s; //~ ERROR cannot move out of `s` because it is borrowed
+    r.len();
+    drop(t);
+}
+
+fn assign_while_borrowed() {
+    let mut x = 0;
+    let r = &x;
+    x = 1; //~ ERROR cannot assign to `x` because it is borrowed
+    drop(r);
+}
+
+fn reference_outlives_referent() {
+    let r;
+    {
+        let x = 0;
+        r = &x; //~ ERROR `x` does not live long enough
+    }
+    drop(r);
+}
+
+fn main() {}
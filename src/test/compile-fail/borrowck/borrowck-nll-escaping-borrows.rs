@@ -0,0 +1,68 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z nll
+
+// Under `-Z nll`, a borrow stays live as long as anything it was stored into
+// is used, including memory reached through another reference and the
+// environment of a closure.
+
+fn stored_through_reference() {
+    let mut v = Vec::new();
+    {
+        let x = 0;
+        v.push(&x); //~ ERROR `x` does not live long enough
+    }
+    v.len();
+}
+
+fn store<'a>(v: &mut Vec<&'a u32>, x: &'a u32) {
+    v.push(x);
+}
+
+fn stored_through_call() {
+    let mut v = Vec::new();
+    {
+        let x = 0;
+        store(&mut v, &x); //~ ERROR `x` does not live long enough
+    }
+    v.len();
+}
+
+fn stored_through_reborrow() {
+    let mut v = Vec::new();
+    {
+        let r = &mut v;
+        let x = 0;
+        r.push(&x); //~ ERROR `x` does not live long enough
+    }
+    v.len();
+}
+
+fn captured_by_closure() {
+    let f;
+    {
+        let x = 0;
+        f = || x + 1; //~ ERROR `x` does not live long enough
+    }
+    f();
+}
+
+fn captured_by_mutable_closure() {
+    let mut v = Vec::new();
+    {
+        let x = 0;
+        let mut push = || v.push(&x); //~ ERROR `x` does not live long enough
+        push();
+    }
+    v.len();
+}
+
+fn main() {}
@@ -10,8 +10,8 @@
 
 // compile-flags: -Zforce-overflow-checks=on
 
-// these errors are not actually "const_err", they occur in trans/consts
-// and are unconditional warnings that can't be denied or allowed
+// the warnings in `main` are not "const_err", but the `unconditional_panic`
+// lint, which MIR constant propagation reports before trans
 
 #![allow(exceeding_bitshifts)]
 #![allow(const_err)]
@@ -0,0 +1,53 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Errors while interpreting a `const fn` point at the expression in its
+// body that failed, even inside of branches and loops.
+
+#![feature(const_fn, const_fn_control_flow)]
+
+const fn sum_to(n: u8) -> u8 {
+    let mut sum = 0;
+    let mut i = 0;
+    while i < n {
+        i += 1;
+        sum += i;
+        //~^ ERROR constant evaluation error
+        //~| attempt to add with overflow
+    }
+    sum
+}
+
+const fn nth_prime(n: usize) -> u32 {
+    let primes = [2, 3, 5];
+    primes[n]
+    //~^ ERROR constant evaluation error
+    //~| index out of bounds: the len is 3 but the index is 5
+}
+
+const fn ratio(a: u32, b: u32) -> u32 {
+    if a > b {
+        a / b
+        //~^ ERROR constant evaluation error
+        //~| attempt to divide by zero
+    } else {
+        0
+    }
+}
+
+const SUM: u8 = sum_to(30);
+const PRIME: u32 = nth_prime(5);
+const RATIO: u32 = ratio(1, 0);
+
+fn main() {
+    let _sum = SUM;
+    let _prime = PRIME;
+    let _ratio = RATIO;
+}
@@ -0,0 +1,19 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[tail_recursive]
+//~^ ERROR the `#[tail_recursive]` attribute is an experimental feature
+fn count(n: u32) -> u32 {
+    if n == 0 { 0 } else { count(n - 1) }
+}
+
+fn main() {
+    count(3);
+}
@@ -0,0 +1,28 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check the follow set of the `vis` fragment specifier.
+
+#![feature(macro_vis_matcher)]
+
+macro_rules! follow_vis {
+    ($v:vis $e:expr) => {};  //~ERROR `$v:vis` is followed by `$e:expr`
+    ($v:vis $p:pat) => {};   //~ERROR `$v:vis` is followed by `$p:pat`
+    ($v:vis {}) => {};       //~ERROR `$v:vis` is followed by `{`
+    ($v:vis ;) => {};        //~ERROR `$v:vis` is followed by `;`
+    ($v:vis priv) => {};     //~ERROR `$v:vis` is followed by `priv`
+    ($v:vis ,) => {};
+    ($v:vis struct) => {};
+    ($v:vis $i:ident) => {};
+    ($v:vis $t:ty) => {};
+    ($v:vis $p:path) => {};
+}
+
+fn main() {}
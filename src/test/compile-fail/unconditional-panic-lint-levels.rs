@@ -0,0 +1,73 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Zforce-overflow-checks=on
+
+// Checks which lint attributes apply to `unconditional_panic`, which is
+// reported for whole function and closure bodies.
+
+#![allow(const_err)]
+
+fn black_box<T>(_: T) {
+    unimplemented!()
+}
+
+#[deny(unconditional_panic)]
+fn denied() {
+    black_box(200u8 + 100);
+    //~^ ERROR this expression will panic at run-time
+    //~| attempt to add with overflow
+}
+
+fn inner_items() {
+    #[deny(unconditional_panic)]
+    fn inner_denied() {
+        black_box(1 / 0);
+        //~^ ERROR this expression will panic at run-time
+        //~| attempt to divide by zero
+    }
+
+    #[allow(unconditional_panic)]
+    fn inner_allowed() {
+        black_box(1 / 0);
+    }
+
+    black_box(inner_denied);
+    black_box(inner_allowed);
+}
+
+fn closures() {
+    #[deny(unconditional_panic)]
+    let denied = || black_box(-std::i32::MIN);
+    //~^ ERROR this expression will panic at run-time
+    //~| attempt to negate with overflow
+
+    #[allow(unconditional_panic)]
+    let allowed = || black_box(-std::i32::MIN);
+
+    black_box(denied);
+    black_box(allowed);
+}
+
+fn statements() {
+    // The lint belongs to the whole body, so this attribute has no effect.
+    #[allow(unconditional_panic)]
+    let x = [0u8; 3][3];
+    //~^ WARN this expression will panic at run-time
+    //~| index out of bounds: the len is 3 but the index is 3
+    black_box(x);
+}
+
+fn main() {
+    black_box(denied);
+    black_box(inner_items);
+    black_box(closures);
+    black_box(statements);
+}
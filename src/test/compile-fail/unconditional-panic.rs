@@ -0,0 +1,67 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Zforce-overflow-checks=on
+
+#![deny(unconditional_panic)]
+#![allow(const_err)]
+
+fn black_box<T>(_: T) {
+    unimplemented!()
+}
+
+fn through_locals() {
+    let x = 100u8;
+    let y = x * 2;
+    let z = y + x;
+    //~^ ERROR this expression will panic at run-time
+    //~| attempt to add with overflow
+    black_box(z);
+}
+
+fn through_branches(flag: bool) {
+    let mut i = 3;
+    if flag {
+        i = 4;
+    }
+    let array = [0u8; 3];
+    black_box(array[i]);
+
+    let j = if false { 0 } else { 3 };
+    black_box(array[j]);
+    //~^ ERROR this expression will panic at run-time
+    //~| index out of bounds: the len is 3 but the index is 3
+}
+
+fn through_loops() {
+    let mut n = 0u32;
+    let mut i = 0;
+    while i < 10 {
+        n = n + 1;
+        i = i + 1;
+    }
+    black_box(n);
+    black_box(10 / (i - i));
+}
+
+#[allow(unconditional_panic)]
+fn allowed() {
+    black_box(1 / 0);
+}
+
+fn main() {
+    black_box(-std::i32::MIN);
+    //~^ ERROR this expression will panic at run-time
+    //~| attempt to negate with overflow
+    black_box(through_locals);
+    black_box(through_branches);
+    black_box(through_loops);
+    black_box(allowed);
+}
@@ -0,0 +1,66 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![feature(tail_recursion)]
+#![deny(uneliminated_tail_calls)]
+
+#[tail_recursive]
+fn tail(n: u64, acc: u64) -> u64 {
+    if n == 0 { acc } else { tail(n - 1, acc + n) }
+}
+
+#[tail_recursive]
+fn not_tail(n: u64) -> u64 {
+    if n == 0 { 1 } else { n * not_tail(n - 1) }
+    //~^ ERROR recursive call not turned into a jump
+}
+
+#[tail_recursive]
+fn drops_after(n: u64, v: Vec<u64>) -> u64 {
+    let w = v.clone();
+    if n == 0 { w.len() as u64 } else { drops_after(n - 1, v) }
+    //~^ ERROR recursive call not turned into a jump
+}
+
+#[tail_recursive]
+fn borrows_local(n: u64, x: &u64) -> u64 {
+    let y = n + *x;
+    if n == 0 { *x } else { borrows_local(n - 1, &y) }
+    //~^ ERROR recursive call not turned into a jump
+}
+
+#[tail_recursive]
+fn polymorphic<T>(n: u64) -> u64 {
+    if n == 0 { 0 } else { polymorphic::<u8>(n - 1) }
+    //~^ ERROR recursive call not turned into a jump
+}
+
+#[tail_recursive]
+fn diverges_with_live_values(_s: String, n: u64) -> ! {
+    let _v = vec![n];
+    diverges_with_live_values(String::new(), n - 1)
+    //~^ ERROR recursive call not turned into a jump
+}
+
+#[tail_recursive]
+#[allow(uneliminated_tail_calls)]
+fn allowed(n: u64) -> u64 {
+    if n == 0 { 1 } else { 1 + allowed(n - 1) }
+}
+
+fn main() {
+    tail(3, 0);
+    not_tail(3);
+    drops_after(3, vec![]);
+    borrows_local(3, &1);
+    polymorphic::<()>(3);
+    allowed(3);
+    diverges_with_live_values(String::new(), 3);
+}
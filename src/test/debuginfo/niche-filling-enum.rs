@@ -0,0 +1,91 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-tidy-linelength
+// min-lldb-version: 310
+
+// compile-flags:-g
+
+// === GDB TESTS ===================================================================================
+
+// gdb-command:run
+
+// gdb-command:print some
+// gdbr-check:$1 = core::option::Option<bool>::Some(true)
+
+// gdb-command:print none
+// gdbr-check:$2 = core::option::Option<bool>::None
+
+// gdb-command:print point
+// gdbr-check:$3 = niche_filling_enum::Shape::Point
+
+// gdb-command:print circle
+// gdbr-check:$4 = niche_filling_enum::Shape::Circle(false)
+
+// gdb-command:print line
+// gdbr-check:$5 = niche_filling_enum::Shape::Line
+
+// gdb-command:continue
+
+
+// === LLDB TESTS ==================================================================================
+
+// lldb-command:run
+
+// lldb-command:print some
+// lldb-check:[...]$0 = Some(true)
+
+// lldb-command:print none
+// lldb-check:[...]$1 = None
+
+// lldb-command:print point
+// lldb-check:[...]$2 = Point
+
+// lldb-command:print circle
+// lldb-check:[...]$3 = Circle(false)
+
+// lldb-command:print line
+// lldb-check:[...]$4 = Line
+
+// lldb-command:print some_char
+// lldb-check:[...]$5 = Some('x')
+
+// lldb-command:print none_char
+// lldb-check:[...]$6 = None
+
+#![allow(unused_variables)]
+#![feature(omit_gdb_pretty_printer_section)]
+#![omit_gdb_pretty_printer_section]
+
+// The discriminants of these enums are stored in the invalid values of
+// a `bool` or a `char`, so they must not be mistaken for the variant
+// holding the `bool` or `char`.
+
+enum Shape {
+    Point,
+    Circle(bool),
+    Line,
+}
+
+fn main() {
+    let some = Some(true);
+    let none: Option<bool> = None;
+
+    let point = Shape::Point;
+    let circle = Shape::Circle(false);
+    let line = Shape::Line;
+
+    let some_char = Some('x');
+    let none_char: Option<char> = None;
+
+    zzz(); // #break
+}
+
+fn zzz() {()}
@@ -0,0 +1,42 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Calls to trait methods are inlined once the impl is known.
+
+fn main() {
+    let x = 20u32.with_flag();
+}
+
+trait Flag {
+    fn with_flag(self) -> Self;
+}
+
+impl Flag for u32 {
+    fn with_flag(self) -> u32 {
+        self | 1
+    }
+}
+
+// END RUST SOURCE
+// START rustc.node4.Inline.before.mir
+// bb0: {
+//     _1 = const <u32 as Flag>::with_flag(const 20u32) -> bb1;
+// }
+// END rustc.node4.Inline.before.mir
+// START rustc.node4.Inline.after.mir
+// bb0: {
+//     _2 = const 20u32;
+//     goto -> bb2;
+// }
+// bb2: {
+//     _1 = BitOr(_4, const 1u32);
+//     goto -> bb1;
+// }
+// END rustc.node4.Inline.after.mir
@@ -12,7 +12,7 @@
 
 macro_rules! foo {
     { $+ } => { //~ ERROR expected identifier, found `+`
-        $(x)(y) //~ ERROR expected `*` or `+`
+        $(x)(y) //~ ERROR expected one of: `*`, `+`, or `?`
                 //~^ ERROR no rules expected the token `y`
     }
 }
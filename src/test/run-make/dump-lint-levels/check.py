@@ -0,0 +1,55 @@
+# Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+# file at the top-level directory of this distribution and at
+# http://rust-lang.org/COPYRIGHT.
+#
+# Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+# http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+# <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+# option. This file may not be copied, modified, or distributed
+# except according to those terms.
+
+import json
+import sys
+
+path, case = sys.argv[1:]
+with open(path) as f:
+    reports = dict((report['pass'], report['items'])
+                   for report in map(json.loads, f.read().splitlines()))
+assert sorted(reports) == ['early', 'late'], reports.keys()
+
+
+def lint_at(items, item, lint):
+    for entry in items:
+        if entry['item'] == item:
+            assert entry['span'].startswith('foo.rs:'), entry['span']
+            for level in entry['lints']:
+                if level['lint'] == lint:
+                    return level
+    raise AssertionError('no level for `{}` at `{}`'.format(lint, item))
+
+
+for items in reports.values():
+    unused = lint_at(items, 'bar', 'unused_variables')
+    assert unused['level'] == 'allow', unused
+    assert unused['source'] == 'attribute', unused
+    assert unused['set_by'] == 'unused', unused
+    assert unused['span'].startswith('foo.rs:13'), unused
+
+late = reports['late']
+if case == 'warned':
+    dead = lint_at(late, 'bar', 'dead_code')
+    assert dead['level'] == 'warn', dead
+    assert dead['source'] == 'command-line', dead
+    assert dead['set_by'] == 'dead_code', dead
+elif case == 'allowed':
+    dead = lint_at(late, 'bar', 'dead_code')
+    assert dead['level'] == 'allow', dead
+    assert dead['source'] == 'command-line', dead
+elif case == 'capped':
+    snake = lint_at(late, 'bar', 'non_snake_case')
+    assert snake['level'] == 'allow', snake
+    assert snake['source'] == 'default', snake
+    assert snake['set_by'] is None and snake['span'] is None, snake
+    assert snake['capped_from'] == 'warn', snake
+else:
+    raise AssertionError('unknown case `{}`'.format(case))
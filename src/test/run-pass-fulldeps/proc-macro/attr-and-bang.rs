@@ -0,0 +1,45 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// aux-build:attr-and-bang.rs
+// ignore-stage1
+
+#![feature(proc_macro)]
+
+#[macro_use]
+extern crate attr_and_bang;
+
+#[rename(two)]
+fn one() -> u32 { 2 }
+
+make_answer!(40 + two());
+
+// Only the tokens after the name of the attribute are passed, without the
+// delimiters around them.
+#[count_args]
+fn no_args() -> usize { 100 }
+
+#[count_args()]
+fn empty_args() -> usize { 100 }
+
+#[count_args(a, b = "c")]
+fn list_args() -> usize { 100 }
+
+#[count_args = "value"]
+fn value_arg() -> usize { 100 }
+
+fn main() {
+    assert_eq!(two(), 2);
+    assert_eq!(answer(), 42);
+    assert_eq!(no_args(), 0);
+    assert_eq!(empty_args(), 0);
+    assert_eq!(list_args(), 5);
+    assert_eq!(value_arg(), 2);
+}
@@ -0,0 +1,68 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// no-prefer-dynamic
+
+#![crate_type = "proc-macro"]
+#![feature(proc_macro)]
+#![feature(proc_macro_lib)]
+
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, Literal, TokenStream, TokenNode, TokenTree};
+
+/// Renames the function it is applied to: `#[rename(bar)] fn foo() {}`
+/// defines `bar`.
+#[proc_macro_attribute]
+pub fn rename(args: TokenStream, input: TokenStream) -> TokenStream {
+    let new_name = match args.into_iter().next().map(|tree| tree.kind) {
+        Some(TokenNode::Term(term)) => term,
+        _ => panic!("expected a new name"),
+    };
+    let mut after_fn = false;
+    input.into_iter().map(|tree| {
+        let is_fn = match tree.kind {
+            TokenNode::Term(term) => term.to_string() == "fn",
+            _ => false,
+        };
+        let tree = if after_fn {
+            TokenTree { span: tree.span, kind: TokenNode::Term(new_name) }
+        } else {
+            tree
+        };
+        after_fn = is_fn;
+        tree
+    }).collect()
+}
+
+/// Replaces the body of the function it is applied to with the number of
+/// token trees it is given as arguments.
+#[proc_macro_attribute]
+pub fn count_args(args: TokenStream, input: TokenStream) -> TokenStream {
+    let count = args.into_iter().count() as u64;
+    input.into_iter().map(|tree| {
+        match tree.kind {
+            TokenNode::Group(Delimiter::Brace, _) => {
+                let body = TokenTree::from(TokenNode::Literal(Literal::integer(count)));
+                TokenTree {
+                    span: tree.span,
+                    kind: TokenNode::Group(Delimiter::Brace, body.into()),
+                }
+            }
+            _ => tree,
+        }
+    }).collect()
+}
+
+/// Defines a function `answer` returning the expression it is given.
+#[proc_macro]
+pub fn make_answer(input: TokenStream) -> TokenStream {
+    format!("fn answer() -> u32 {{ {} }}", input).parse().unwrap()
+}
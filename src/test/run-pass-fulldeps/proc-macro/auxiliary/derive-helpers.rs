@@ -0,0 +1,46 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// no-prefer-dynamic
+
+#![crate_type = "proc-macro"]
+#![feature(proc_macro)]
+#![feature(proc_macro_lib)]
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+
+#[proc_macro_derive(Keep, attributes(kept), keep_attributes)]
+pub fn derive_keep(input: TokenStream) -> TokenStream {
+    assert!(input.to_string().contains("#[kept]"));
+    "".parse().unwrap()
+}
+
+#[proc_macro_derive(Strip, attributes(stripped))]
+pub fn derive_strip(input: TokenStream) -> TokenStream {
+    assert_eq!(input.to_string().matches("#[stripped]").count(), 2);
+    "".parse().unwrap()
+}
+
+// Shares its helper with `Strip`, so it must still see it after `Strip` ran.
+#[proc_macro_derive(AlsoStrip, attributes(stripped))]
+pub fn derive_also_strip(input: TokenStream) -> TokenStream {
+    assert_eq!(input.to_string().matches("#[stripped]").count(), 2);
+    "".parse().unwrap()
+}
+
+#[proc_macro_derive(Check)]
+pub fn derive_check(input: TokenStream) -> TokenStream {
+    let input = input.to_string();
+    assert_eq!(input.matches("#[kept]").count(), 2);
+    assert_eq!(input.matches("#[stripped]").count(), 2);
+    "".parse().unwrap()
+}
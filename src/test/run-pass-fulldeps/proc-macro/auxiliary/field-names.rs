@@ -0,0 +1,72 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// no-prefer-dynamic
+
+#![crate_type = "proc-macro"]
+#![feature(proc_macro)]
+#![feature(proc_macro_lib)]
+
+extern crate proc_macro;
+
+use proc_macro::{TokenStream, TokenNode, TokenTree, Delimiter, Literal, Spacing,
+                 Term};
+
+#[proc_macro_derive(FieldNames)]
+pub fn derive(input: TokenStream) -> TokenStream {
+    let mut name = None;
+    let mut fields = None;
+    let mut after_struct = false;
+    for tree in input {
+        match tree.kind {
+            TokenNode::Term(term) if after_struct => {
+                name = Some(term);
+                after_struct = false;
+            }
+            TokenNode::Term(term) => after_struct = term.to_string() == "struct",
+            TokenNode::Group(Delimiter::Brace, body) => fields = Some(body),
+            _ => {}
+        }
+    }
+
+    // Field names are the identifiers which come directly before a `:`, as
+    // long as that `:` isn't part of a `::`.
+    let mut names = Vec::new();
+    let mut prev = None;
+    for tree in fields.unwrap() {
+        match tree.kind {
+            TokenNode::Op(':', Spacing::Alone) => {
+                if let Some(TokenNode::Term(term)) = prev.take() {
+                    names.push(term.to_string());
+                }
+            }
+            kind => prev = Some(kind),
+        }
+    }
+
+    let names = names.iter().flat_map(|name| {
+        vec![TokenNode::Literal(Literal::string(name)), TokenNode::Op(',', Spacing::Alone)]
+    }).map(TokenTree::from).collect::<TokenStream>();
+    let body = vec![
+        TokenNode::Op('&', Spacing::Alone),
+        TokenNode::Group(Delimiter::Bracket, names),
+    ].into_iter().map(TokenTree::from).collect::<TokenStream>();
+
+    let signature = "fn field_names() -> &'static [&'static str]";
+    let method = signature.parse::<TokenStream>().unwrap().into_iter()
+        .chain(Some(TokenTree::from(TokenNode::Group(Delimiter::Brace, body))))
+        .collect::<TokenStream>();
+
+    vec![
+        TokenNode::Term(Term::intern("impl")),
+        TokenNode::Term(name.unwrap()),
+        TokenNode::Group(Delimiter::Brace, method),
+    ].into_iter().map(TokenTree::from).collect()
+}
@@ -0,0 +1,46 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// no-prefer-dynamic
+
+#![crate_type = "proc-macro"]
+#![feature(proc_macro)]
+#![feature(proc_macro_lib)]
+
+#[macro_use]
+extern crate proc_macro;
+
+use proc_macro::{Literal, TokenNode, TokenStream, TokenTree};
+
+/// `make_adder!(add a b c)` defines `fn add(a: u32, b: u32, c: u32) -> u32`,
+/// which returns the sum of its arguments.
+#[proc_macro]
+pub fn make_adder(input: TokenStream) -> TokenStream {
+    let mut trees = input.into_iter();
+    let name = trees.next().expect("expected a function name");
+    let args = trees.collect::<Vec<TokenTree>>();
+    let params = args.clone();
+    let arity = TokenNode::Literal(Literal::integer(args.len() as u64));
+    quote! {
+        #[inline]
+        fn #name(#(#params: u32),*) -> u32 {
+            0 #(+ #args)*
+        }
+
+        const ARITY: usize = #arity;
+
+        /// Constants spelled with each kind of literal.
+        const NAME: &'static str = r#"adder"#;
+        const SHIFTED: u32 = { let mut x = 0x40u32; x >>= 2; x };
+        const SCALE: f64 = 1.5e1f64;
+        const QUOTE: char = '\'';
+        const BYTES: &'static [u8] = b"\x01\x02";
+    }
+}
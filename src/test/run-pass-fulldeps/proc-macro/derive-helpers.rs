@@ -0,0 +1,33 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// aux-build:derive-helpers.rs
+// ignore-stage1
+
+#![feature(proc_macro)]
+#![deny(unused_attributes)]
+
+#[macro_use]
+extern crate derive_helpers;
+
+#[derive(Keep, Strip, AlsoStrip, Check)]
+#[kept]
+#[stripped]
+struct A {
+    #[kept]
+    a: u8,
+    #[stripped]
+    b: u8,
+}
+
+fn main() {
+    let a = A { a: 1, b: 2 };
+    assert_eq!(a.a + a.b, 3);
+}
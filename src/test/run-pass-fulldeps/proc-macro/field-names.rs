@@ -0,0 +1,30 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// aux-build:field-names.rs
+// ignore-stage1
+
+#![feature(proc_macro)]
+
+#[macro_use]
+extern crate field_names;
+
+#[derive(FieldNames)]
+#[allow(dead_code)]
+struct A {
+    /// The first field.
+    a: u32,
+    b: ::std::string::String,
+    c_d: Option<Vec<u8>>,
+}
+
+fn main() {
+    assert_eq!(A::field_names(), &["a", "b", "c_d"]);
+}
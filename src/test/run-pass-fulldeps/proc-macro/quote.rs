@@ -0,0 +1,29 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// aux-build:quote.rs
+// ignore-stage1
+
+#![feature(proc_macro)]
+
+#[macro_use]
+extern crate quote;
+
+make_adder!(add a b c);
+
+fn main() {
+    assert_eq!(add(1, 2, 3), 6);
+    assert_eq!(ARITY, 3);
+    assert_eq!(NAME, "adder");
+    assert_eq!(SHIFTED, 16);
+    assert_eq!(SCALE, 15.0);
+    assert_eq!(QUOTE, '\'');
+    assert_eq!(BYTES, &[1, 2]);
+}
@@ -0,0 +1,36 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type="rlib"]
+#![feature(const_fn, const_fn_control_flow)]
+
+pub const fn square(x: u32) -> u32 { x * x }
+
+pub const fn low_byte(x: u32) -> u8 { (x + 256) as u8 }
+
+pub const fn diff(a: i16, b: i16) -> i16 { a - b }
+
+pub const fn factorial(n: u64) -> u64 {
+    let mut acc = 1;
+    let mut i = n;
+    while i > 1 {
+        acc *= i;
+        i -= 1;
+    }
+    acc
+}
+
+pub const SIXTEEN: u32 = square(4);
+
+pub const FACTORIAL_TEN: u64 = factorial(10);
+
+pub const SHIFTED: i64 = -1 << 40;
+
+pub const NAME: &'static str = "mir";
@@ -0,0 +1,69 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z nll
+
+// Programs rejected by the lexical borrow checker which are accepted once a
+// borrow only lasts until the last use of the reference it creates.
+
+use std::collections::HashMap;
+
+fn push_after_last_use(v: &mut Vec<u32>) {
+    let first = &v[0];
+    let x = *first;
+    v.push(x);
+}
+
+fn append_or_insert(map: &mut HashMap<u32, String>, key: u32) {
+    match map.get_mut(&key) {
+        Some(value) => value.push_str("!"),
+        None => {
+            map.insert(key, String::new());
+        }
+    }
+}
+
+fn get_default<'r>(map: &'r mut HashMap<u32, String>, key: u32) -> &'r mut String {
+    match map.get_mut(&key) {
+        Some(value) => value,
+        None => {
+            map.insert(key, String::from("default"));
+            map.get_mut(&key).unwrap()
+        }
+    }
+}
+
+fn referent_dies_after_last_use() -> u32 {
+    let r;
+    let y;
+    {
+        let x = 1;
+        r = &x;
+        y = *r;
+    }
+    y
+}
+
+fn main() {
+    let mut v = vec![1];
+    push_after_last_use(&mut v);
+    assert_eq!(v, [1, 1]);
+
+    let mut map = HashMap::new();
+    append_or_insert(&mut map, 1);
+    append_or_insert(&mut map, 1);
+    assert_eq!(map[&1], "!");
+
+    assert_eq!(get_default(&mut map, 2), "default");
+    get_default(&mut map, 1).push_str("?");
+    assert_eq!(map[&1], "!?");
+
+    assert_eq!(referent_dies_after_last_use(), 1);
+}
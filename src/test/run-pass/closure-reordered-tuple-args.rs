@@ -0,0 +1,36 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that the arguments of closures and "rust-call" functions are passed
+// in the right order when their tuple has its fields reordered in memory,
+// including when the tuple is small enough to be an immediate.
+
+#![feature(fn_traits, unboxed_closures)]
+
+fn call<F: Fn(u8, u16, u8) -> (u8, u16, u8)>(f: F, a: u8, b: u16, c: u8) -> (u8, u16, u8) {
+    f(a, b, c)
+}
+
+fn call_tupled<F: Fn<(u8, u16, u8), Output=(u8, u16, u8)>>(f: F, args: (u8, u16, u8))
+                                                           -> (u8, u16, u8) {
+    f.call(args)
+}
+
+fn main() {
+    let f = |a: u8, b: u16, c: u8| {
+        assert_eq!(a, 1);
+        assert_eq!(b, 0x0203);
+        assert_eq!(c, 4);
+        (a, b, c)
+    };
+    assert_eq!(call(&f, 1, 0x0203, 4), (1, 0x0203, 4));
+    assert_eq!(call_tupled(&f, (1, 0x0203, 4)), (1, 0x0203, 4));
+    assert_eq!(f(1, 0x0203, 4), (1, 0x0203, 4));
+}
@@ -0,0 +1,66 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// aux-build:const_eval_mir_lib.rs
+
+// Constants and `const fn`s from another crate are evaluated by
+// interpreting their MIR when used in patterns, including ones whose
+// bodies loop.
+
+#![feature(const_fn)]
+
+extern crate const_eval_mir_lib;
+
+use const_eval_mir_lib::{FACTORIAL_TEN, NAME, SHIFTED, SIXTEEN};
+use const_eval_mir_lib::{diff, factorial, low_byte, square};
+
+const NINE: u32 = square(3);
+const BYTE: u8 = low_byte(300);
+const DIFF: i16 = diff(-3, 4);
+const FACTORIAL_FIVE: u64 = factorial(5);
+
+fn classify(x: u32) -> &'static str {
+    match x {
+        NINE => "nine",
+        SIXTEEN => "sixteen",
+        _ => "other",
+    }
+}
+
+fn main() {
+    assert_eq!(classify(9), "nine");
+    assert_eq!(classify(16), "sixteen");
+    assert_eq!(classify(25), "other");
+
+    match 44u8 {
+        BYTE => {}
+        _ => panic!("expected {}", BYTE),
+    }
+    match -7i16 {
+        DIFF => {}
+        _ => panic!("expected {}", DIFF),
+    }
+    match -1i64 << 40 {
+        SHIFTED => {}
+        _ => panic!("expected {}", SHIFTED),
+    }
+    match 120u64 {
+        FACTORIAL_FIVE => {}
+        _ => panic!("expected {}", FACTORIAL_FIVE),
+    }
+    match 3628800u64 {
+        FACTORIAL_TEN => {}
+        _ => panic!("expected {}", FACTORIAL_TEN),
+    }
+    match "mir" {
+        NAME => {}
+        _ => panic!("expected {:?}", NAME),
+    }
+}
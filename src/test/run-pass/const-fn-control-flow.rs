@@ -0,0 +1,68 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// `const fn`s with `let` bindings, branches and loops are evaluated by
+// interpreting their MIR, and agree with calling them at runtime.
+
+#![feature(const_fn, const_fn_control_flow)]
+
+const fn triangle(n: u32) -> u32 {
+    let mut sum = 0;
+    let mut i = 1;
+    while i <= n {
+        sum += i;
+        i += 1;
+    }
+    sum
+}
+
+const fn abs_diff(a: i32, b: i32) -> i32 {
+    let d = a - b;
+    if d < 0 { -d } else { d }
+}
+
+const fn digit_name_len(d: u8) -> usize {
+    match d {
+        0 => 4,
+        1 | 2 | 6 | 10 => 3,
+        3 | 7 | 8 => 5,
+        _ => 4,
+    }
+}
+
+const fn collatz_steps(start: u64) -> u32 {
+    let mut n = start;
+    let mut steps = 0;
+    loop {
+        if n == 1 {
+            return steps;
+        }
+        n = if n % 2 == 0 { n / 2 } else { 3 * n + 1 };
+        steps += 1;
+    }
+}
+
+const TRIANGLE: u32 = triangle(100);
+const DIFF: i32 = abs_diff(3, 10);
+const NAME_LEN: usize = digit_name_len(7);
+const STEPS: u32 = collatz_steps(27);
+static STATIC_TRIANGLE: u32 = triangle(10) + TRIANGLE;
+
+fn main() {
+    assert_eq!(TRIANGLE, 5050);
+    assert_eq!(TRIANGLE, triangle(100));
+    assert_eq!(DIFF, 7);
+    assert_eq!(DIFF, abs_diff(3, 10));
+    assert_eq!(NAME_LEN, 5);
+    assert_eq!(NAME_LEN, digit_name_len(7));
+    assert_eq!(STEPS, 111);
+    assert_eq!(STEPS, collatz_steps(27));
+    assert_eq!(STATIC_TRIANGLE, 5105);
+}
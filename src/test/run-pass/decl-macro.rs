@@ -0,0 +1,46 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check `macro` items: module scoping, imports and definition-site name resolution.
+
+#![feature(decl_macro)]
+
+mod foo {
+    fn private() -> u32 { 1 }
+
+    pub macro bar($e:expr) {
+        private() + $e
+    }
+
+    pub macro baz {
+        () => { 2 },
+        ($e:expr) => { $e * 2 },
+    }
+
+    pub mod inner {
+        pub macro twice($e:expr) { ($e, $e) }
+    }
+}
+
+mod quux {
+    pub use foo::baz;
+}
+
+use foo::inner::twice;
+
+fn private() -> u32 { 10 }
+
+fn main() {
+    assert_eq!(foo::bar!(1), 2);
+    assert_eq!(::foo::bar!(private()), 11);
+    assert_eq!(quux::baz!(), 2);
+    assert_eq!(quux::baz!(3), 6);
+    assert_eq!(twice!(4), (4, 4));
+}
@@ -0,0 +1,88 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Test that enums with a single variant carrying data store their
+// discriminant in the invalid values of one of its fields.
+
+use std::cmp::Ordering;
+use std::mem::size_of;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum CLike { A, B, C }
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum ManyEmpty { First, Data(u32, bool), Second, Third }
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Wrapper { x: u16, c: char }
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum TwoData { A(bool), B(bool) }
+
+static NONE_BOOL: Option<bool> = None;
+static SOME_CHAR: Option<char> = Some('x');
+static NESTED: Option<Option<CLike>> = Some(None);
+
+fn roundtrip<T: Copy + PartialEq + ::std::fmt::Debug>(x: T) {
+    let y = x;
+    assert_eq!(x, y);
+}
+
+fn main() {
+    assert_eq!(size_of::<Option<bool>>(), 1);
+    assert_eq!(size_of::<Option<Option<bool>>>(), 1);
+    assert_eq!(size_of::<Option<char>>(), 4);
+    assert_eq!(size_of::<Option<Ordering>>(), 1);
+    assert_eq!(size_of::<Option<Option<Ordering>>>(), 1);
+    assert_eq!(size_of::<Option<CLike>>(), 1);
+    assert_eq!(size_of::<ManyEmpty>(), 8);
+    assert_eq!(size_of::<Option<Wrapper>>(), size_of::<Wrapper>());
+    assert_eq!(size_of::<Option<(u8, bool)>>(), 2);
+
+    // More than one variant carries data, so a separate tag is needed.
+    assert_eq!(size_of::<TwoData>(), 2);
+
+    for x in &[None, Some(false), Some(true)] {
+        roundtrip(*x);
+        roundtrip(Some(*x));
+    }
+    assert_eq!(NONE_BOOL, None);
+    assert_eq!(SOME_CHAR, Some('x'));
+    assert_eq!(NESTED, Some(None));
+
+    let values = [ManyEmpty::First, ManyEmpty::Data(7, true),
+                  ManyEmpty::Second, ManyEmpty::Third];
+    for (i, v) in values.iter().enumerate() {
+        let j = match *v {
+            ManyEmpty::First => 0,
+            ManyEmpty::Data(x, b) => {
+                assert_eq!((x, b), (7, true));
+                1
+            }
+            ManyEmpty::Second => 2,
+            ManyEmpty::Third => 3,
+        };
+        assert_eq!(i, j);
+    }
+
+    let mut w = Some(Wrapper { x: 1, c: 'a' });
+    if let Some(ref mut w) = w {
+        w.c = 'b';
+    }
+    assert_eq!(w, Some(Wrapper { x: 1, c: 'b' }));
+    w = None;
+    assert_eq!(w, None);
+
+    let nested = [None, Some(None), Some(Some(Ordering::Less)),
+                  Some(Some(Ordering::Equal)), Some(Some(Ordering::Greater))];
+    for x in &nested {
+        roundtrip(*x);
+    }
+}
@@ -0,0 +1,34 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check the `?` zero-or-one macro repetition operator.
+
+#![feature(macro_at_most_once_rep)]
+
+macro_rules! sum {
+    ($($x:expr),* $(,)?) => { 0 $(+ $x)* };
+}
+
+macro_rules! opt {
+    ($a:ident $(= $b:expr)?) => { { let $a = 1; $a $(+ $b)? } };
+}
+
+macro_rules! sep_question {
+    ($($x:ident)?*) => { [$(stringify!($x)),*] };
+}
+
+fn main() {
+    assert_eq!(sum!(), 0);
+    assert_eq!(sum!(1, 2), 3);
+    assert_eq!(sum!(1, 2,), 3);
+    assert_eq!(opt!(a), 1);
+    assert_eq!(opt!(a = 2), 3);
+    assert_eq!(sep_question!(a ? b ? c), ["a", "b", "c"]);
+}
@@ -0,0 +1,55 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check the `vis`, `lifetime` and `literal` fragment specifiers.
+
+#![feature(macro_vis_matcher, macro_lifetime_matcher, macro_literal_matcher)]
+
+macro_rules! make_struct {
+    ($v:vis struct $name:ident) => { $v struct $name(u32); }
+}
+
+macro_rules! first {
+    ($l:lifetime) => {
+        fn first<$l>(s: &$l str) -> &$l str { &s[..1] }
+    }
+}
+
+macro_rules! classify {
+    ($x:expr, $($lit:literal => $name:expr),*) => {
+        match $x { $($lit => $name,)* _ => "other" }
+    }
+}
+
+macro_rules! lit {
+    ($l:literal) => { $l };
+    ($i:ident) => { "ident" };
+}
+
+mod m {
+    make_struct!(pub struct Public);
+    make_struct!(struct Private);
+
+    pub fn private() -> u32 { Private(2).0 }
+}
+
+first!('a);
+
+fn main() {
+    assert_eq!(m::Public(1).0, 1);
+    assert_eq!(m::private(), 2);
+    assert_eq!(first("hello"), "h");
+    assert_eq!(classify!(-1, 0 => "zero", -1 => "minus one"), "minus one");
+    assert_eq!(classify!(7, 0 => "zero", -1 => "minus one"), "other");
+    assert_eq!(lit!(-1.5), -1.5);
+    assert_eq!(lit!("str"), "str");
+    assert_eq!(lit!(true), true);
+    assert_eq!(lit!(foo), "ident");
+}
@@ -0,0 +1,67 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z mir-opt-level=3
+
+fn arithmetic() -> u32 {
+    let a = 6;
+    let b = a * 7;
+    if b > 40 { b - 2 } else { b + 2 }
+}
+
+fn switch(c: char) -> u8 {
+    let x = 'b';
+    let y = match x {
+        'a' => 1,
+        'b' => 2,
+        _ => 3,
+    };
+    match c {
+        'a' => y,
+        _ => y * 10,
+    }
+}
+
+fn loops(n: u32) -> (u32, u32) {
+    let mut i = 0;
+    let mut same = 5;
+    while i < n {
+        same = 5;
+        i += 1;
+    }
+    (i, same)
+}
+
+fn borrowed() -> i32 {
+    let mut x = 1;
+    {
+        let r = &mut x;
+        *r = 2;
+    }
+    x
+}
+
+fn partial() -> (u8, bool) {
+    let mut t = (1u8, false);
+    t.0 = 3;
+    t.1 = t.0 == 3;
+    t
+}
+
+fn main() {
+    assert_eq!(arithmetic(), 40);
+    assert_eq!(switch('a'), 2);
+    assert_eq!(switch('z'), 20);
+    assert_eq!(loops(0), (0, 5));
+    assert_eq!(loops(7), (7, 5));
+    assert_eq!(borrowed(), 2);
+    assert_eq!(partial(), (3, true));
+    assert_eq!(std::u8::MAX as u32 + 1, 256);
+}
@@ -0,0 +1,71 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z mir-opt-level=3
+
+struct Big([u64; 16]);
+
+fn make(x: u64) -> Big {
+    Big([x; 16])
+}
+
+fn chain(x: u64) -> Big {
+    let a = make(x);
+    let b = a;
+    let c = b;
+    c
+}
+
+fn swap(mut a: u64, mut b: u64, n: u32) -> (u64, u64) {
+    for _ in 0..n {
+        let t = a;
+        a = b;
+        b = t;
+    }
+    (a, b)
+}
+
+fn overlapping(x: u64) -> u64 {
+    let a = x + 1;
+    let b = a;
+    let c = a + b;
+    b + c
+}
+
+fn branches(c: bool) -> Vec<u64> {
+    let v = if c { vec![1, 2] } else { vec![3] };
+    let w = v;
+    w
+}
+
+fn reassigned(x: u64) -> u64 {
+    let mut a = x;
+    let b = a;
+    a = 7;
+    a + b
+}
+
+fn borrowed(x: u64) -> u64 {
+    let a = x;
+    let r = &a;
+    let b = *r;
+    a + b
+}
+
+fn main() {
+    assert_eq!(chain(3).0[15], 3);
+    assert_eq!(swap(1, 2, 3), (2, 1));
+    assert_eq!(swap(1, 2, 4), (1, 2));
+    assert_eq!(overlapping(1), 6);
+    assert_eq!(branches(true), vec![1, 2]);
+    assert_eq!(branches(false), vec![3]);
+    assert_eq!(reassigned(5), 12);
+    assert_eq!(borrowed(4), 8);
+}
@@ -0,0 +1,56 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z mir-opt-level=2
+
+// Check that inlined calls keep their drops, both on the normal path and when
+// unwinding out of the inlined body through the cleanup of the caller.
+
+use std::cell::RefCell;
+use std::panic;
+
+struct Noisy<'a>(&'static str, &'a RefCell<Vec<&'static str>>);
+
+impl<'a> Drop for Noisy<'a> {
+    fn drop(&mut self) {
+        self.1.borrow_mut().push(self.0);
+    }
+}
+
+#[inline]
+fn make<'a>(name: &'static str, log: &'a RefCell<Vec<&'static str>>) -> Noisy<'a> {
+    Noisy(name, log)
+}
+
+#[inline]
+fn consume(n: Noisy, fail: bool) -> usize {
+    let _inner = make("inner", n.1);
+    if fail {
+        panic!("consume failed");
+    }
+    n.0.len()
+}
+
+fn main() {
+    let log = RefCell::new(Vec::new());
+    {
+        let _outer = make("outer", &log);
+        assert_eq!(consume(make("arg", &log), false), 3);
+    }
+    assert_eq!(*log.borrow(), ["inner", "arg", "outer"]);
+
+    log.borrow_mut().clear();
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let _outer = make("outer", &log);
+        consume(make("arg", &log), true)
+    }));
+    assert!(result.is_err());
+    assert_eq!(*log.borrow(), ["inner", "arg", "outer"]);
+}
@@ -0,0 +1,57 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z mir-opt-level=2
+
+// Check inlining of generic callees, of callees with promoted constants, and
+// of calls whose destination goes through a deref.
+
+#[inline]
+fn pick<T: Copy>(values: &[T], first: bool) -> T {
+    if first { values[0] } else { values[values.len() - 1] }
+}
+
+fn table() -> &'static [u32] {
+    &[1, 2, 3]
+}
+
+#[inline]
+fn bump(counter: &mut usize) -> usize {
+    *counter += 1;
+    *counter
+}
+
+struct Wrapper<T>(T);
+
+impl<T: Clone> Wrapper<T> {
+    fn get(&self) -> T {
+        self.0.clone()
+    }
+}
+
+fn main() {
+    assert_eq!(pick(&[1u8, 2, 3], true), 1);
+    assert_eq!(pick(&["a", "b"], false), "b");
+    assert_eq!(pick(table(), false), 3);
+
+    let mut counter = 0;
+    let mut total = 0;
+    {
+        let total = &mut total;
+        *total = bump(&mut counter);
+        *total += bump(&mut counter);
+    }
+    assert_eq!(total, 3);
+    assert_eq!(counter, 2);
+
+    let mut boxed = Box::new(0);
+    *boxed = Wrapper(String::from("hello")).get().len();
+    assert_eq!(*boxed, 5);
+}
@@ -0,0 +1,65 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z polymorphize
+
+use std::fmt::Debug;
+use std::mem;
+
+fn unused<T>(x: u32) -> u32 {
+    x + 1
+}
+
+fn same<T>(a: &T, b: &T) -> bool {
+    a as *const T == b as *const T
+}
+
+fn pick<'a, T>(c: bool, a: &'a T, b: &'a T) -> &'a T {
+    if c { a } else { b }
+}
+
+fn size<T>(_: &T) -> usize {
+    mem::size_of::<T>()
+}
+
+fn show<T: Debug>(x: &T) -> String {
+    format!("{:?}", x)
+}
+
+fn first<T: Copy>(xs: &[T]) -> T {
+    xs[0]
+}
+
+fn main() {
+    assert_eq!(unused::<u8>(1), 2);
+    assert_eq!(unused::<String>(2), 3);
+
+    let (a, b) = (1u64, 2u64);
+    assert!(same(&a, &a));
+    assert!(!same(&a, &b));
+    let (c, d) = ('c', 'd');
+    assert!(same(&c, &c));
+    assert!(!same(&c, &d));
+
+    assert_eq!(*pick(true, &a, &b), 1);
+    assert_eq!(*pick(false, &c, &d), 'd');
+
+    let f: fn(bool, &u64, &u64) -> &u64 = pick;
+    assert_eq!(*f(false, &a, &b), 2);
+
+    assert_eq!(size(&1u8), 1);
+    assert_eq!(size(&1u64), 8);
+
+    assert_eq!(show(&1u8), "1");
+    assert_eq!(show(&"x"), "\"x\"");
+
+    assert_eq!(first(&[3u16, 4]), 3);
+    assert_eq!(first(&['e', 'f']), 'e');
+}
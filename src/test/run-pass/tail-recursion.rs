@@ -0,0 +1,74 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Deep enough that these would overflow the stack if the recursive calls weren't turned into
+// jumps.
+
+#![feature(tail_recursion)]
+#![deny(uneliminated_tail_calls)]
+
+#[tail_recursive]
+fn count(n: u64, acc: u64) -> u64 {
+    if n == 0 { acc } else { count(n - 1, acc + 1) }
+}
+
+#[tail_recursive]
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[tail_recursive]
+fn alternate(a: u64, b: u64, n: u64) -> (u64, u64) {
+    if n == 0 { (a, b) } else { alternate(b, a, n - 1) }
+}
+
+#[tail_recursive]
+fn last<T: Copy>(xs: &[T], acc: T) -> T {
+    if xs.is_empty() { acc } else { last(&xs[1..], xs[0]) }
+}
+
+#[tail_recursive]
+fn forever(n: u64) -> ! {
+    if n == 0 { panic!("done") } else { forever(n - 1) }
+}
+
+struct Node {
+    value: u64,
+    next: Option<Box<Node>>,
+}
+
+#[tail_recursive]
+fn sum(node: &Node, acc: u64) -> u64 {
+    match node.next {
+        Some(ref next) => sum(next, acc + node.value),
+        None => acc + node.value,
+    }
+}
+
+fn main() {
+    assert_eq!(count(10_000_000, 0), 10_000_000);
+    assert_eq!(gcd(1_000_000_007, 998_244_353), 1);
+    assert_eq!(alternate(1, 2, 10_000_001), (2, 1));
+
+    let xs: Vec<u32> = (0..1_000_000).collect();
+    assert_eq!(last(&xs, 0), 999_999);
+
+    let mut list = None;
+    for value in 0..1_000_000 {
+        list = Some(Box::new(Node { value: value, next: list }));
+    }
+    assert_eq!(sum(list.as_ref().unwrap(), 0), 499_999_500_000);
+    // Dropping the list recursively would overflow the stack too.
+    while let Some(node) = list {
+        list = node.next;
+    }
+
+    assert!(std::thread::spawn(|| forever(10_000_000)).join().is_err());
+}
@@ -30,6 +30,23 @@ enum e3 {
     a([u16; 0], u8), b
 }
 
+// Test struct field reordering to make sure it actually reorders.
+struct WillOptimize1(u8, u16, u8);
+struct WillOptimize2 { a: u8, b: u16, c: u8}
+
+// Test that `#[repr(C)]` and `#[repr(packed)]` keep source order.
+#[repr(C)]
+struct ReorderReprC {a: u8, b: u64, c: u8}
+#[repr(packed)]
+struct ReorderPacked {a: u8, b: u64, c: u8}
+
+struct ReorderDefault {a: u8, b: u64, c: u8}
+
+// The discriminant stays first, the rest of the variant is sorted.
+enum EnumReorder {
+    A(u32, u8, u8), B
+}
+
 pub fn main() {
     assert_eq!(size_of::<u8>(), 1 as usize);
     assert_eq!(size_of::<u32>(), 4 as usize);
@@ -53,4 +70,22 @@ pub fn main() {
     assert_eq!(size_of::<e1>(), 8 as usize);
     assert_eq!(size_of::<e2>(), 8 as usize);
     assert_eq!(size_of::<e3>(), 4 as usize);
+
+    assert_eq!(size_of::<WillOptimize1>(), 4);
+    assert_eq!(size_of::<WillOptimize2>(), 4);
+
+    assert_eq!(size_of::<ReorderReprC>(), 24);
+    assert_eq!(size_of::<ReorderPacked>(), 10);
+    assert_eq!(size_of::<ReorderDefault>(), 16);
+    assert_eq!(size_of::<EnumReorder>(), 8);
+
+    // Reordering must not be visible through field access.
+    let s = WillOptimize1(1, 2, 3);
+    assert_eq!((s.0, s.1, s.2), (1, 2, 3));
+    let s = ReorderDefault { a: 4, b: 5, c: 6 };
+    assert_eq!((s.a, s.b, s.c), (4, 5, 6));
+    match EnumReorder::A(7, 8, 9) {
+        EnumReorder::A(x, y, z) => assert_eq!((x, y, z), (7, 8, 9)),
+        EnumReorder::B => panic!(),
+    }
 }
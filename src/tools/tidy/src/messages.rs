@@ -0,0 +1,109 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Tidy check to verify that every diagnostic message used by the compiler has
+//! built-in text in the message catalog.
+//!
+//! Messages are looked up by identifier when the diagnostic is emitted, so a
+//! misspelt identifier would otherwise only be noticed when the error it
+//! belongs to is reported.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+/// The ways of looking up a message, with the position of the argument holding
+/// its identifier.
+const LOOKUPS: &'static [(&'static str, usize)] = &[
+    ("struct_span_err_message!(", 3),
+    ("diagnostic_message(", 0),
+];
+
+pub fn check(path: &Path, bad: &mut bool) {
+    let mut contents = String::new();
+    let catalog = path.join("librustc_errors/catalog.rs");
+    t!(t!(File::open(&catalog), catalog).read_to_string(&mut contents));
+
+    // Built-in messages are listed one per entry, as `("id",` at the start of
+    // a line.
+    let builtin = contents.lines().filter_map(|line| {
+        let line = line.trim();
+        if line.starts_with("(\"") && line.ends_with("\",") {
+            Some(line[2..line.len() - 2].to_string())
+        } else {
+            None
+        }
+    }).collect::<HashSet<_>>();
+
+    let mut uses = 0;
+    super::walk(path,
+                &mut |path| super::filter_dirs(path) || path.ends_with("src/test"),
+                &mut |file| {
+        let filename = file.file_name().unwrap().to_string_lossy();
+        if !filename.ends_with(".rs") || file.ends_with("src/tools/tidy/src/messages.rs") {
+            return
+        }
+
+        contents.truncate(0);
+        t!(t!(File::open(file), file).read_to_string(&mut contents));
+
+        for &(lookup, position) in LOOKUPS {
+            for (start, _) in contents.match_indices(lookup) {
+                // Lookups which forward an identifier they were given, such as
+                // the definitions of these functions, are checked at their
+                // callers instead.
+                let arg = match argument(&contents[start + lookup.len()..], position) {
+                    Some(arg) if arg.len() > 1 && arg.starts_with('"') && arg.ends_with('"') => {
+                        arg
+                    }
+                    _ => continue,
+                };
+                let id = &arg[1..arg.len() - 1];
+                uses += 1;
+                if !builtin.contains(id) {
+                    let line = contents[..start].lines().count();
+                    println!("{}:{}: diagnostic message `{}` has no built-in text in {}",
+                             file.display(), line, id, catalog.display());
+                    *bad = true;
+                }
+            }
+        }
+    });
+
+    if !*bad {
+        println!("* {} diagnostic message lookups", uses);
+    }
+}
+
+/// Returns the argument at `position` of the call whose arguments start `args`.
+fn argument(args: &str, position: usize) -> Option<&str> {
+    let mut depth = 0;
+    let mut index = 0;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth > 0 => depth -= 1,
+            ',' | ')' if depth == 0 => {
+                if index == position {
+                    return Some(args[start..i].trim());
+                }
+                if c == ')' {
+                    return None;
+                }
+                index += 1;
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}